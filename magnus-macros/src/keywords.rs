@@ -0,0 +1,124 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    ext::IdentExt, spanned::Spanned, Data, DataStruct, DeriveInput, Error, Fields, FieldsNamed,
+    GenericArgument, LitStr, PathArguments, Type,
+};
+
+use crate::util;
+
+pub fn expand_derive_keywords(input: DeriveInput) -> Result<TokenStream, Error> {
+    let named = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(FieldsNamed { ref named, .. }),
+            ..
+        }) => named,
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "Keywords can only be derived for structs with named fields",
+            ))
+        }
+    };
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "Keywords can not be derived for generic types",
+        ));
+    }
+
+    let mut required = Vec::new();
+    let mut optional = Vec::new();
+    let mut rest = None;
+    for field in named {
+        let ident = field.ident.as_ref().unwrap();
+        let mut name = None;
+        let mut is_rest = false;
+        if let Some(attrs) = util::get_magnus_attrubute(&field.attrs)? {
+            attrs.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else if meta.path.is_ident("rest") {
+                    is_rest = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported attribute"))
+                }
+            })?;
+        }
+        if is_rest {
+            if rest.is_some() {
+                return Err(Error::new(field.span(), "duplicate `rest` field"));
+            }
+            rest = Some(ident);
+            continue;
+        }
+        let name = name.unwrap_or_else(|| ident.unraw().to_string());
+        if is_option(&field.ty) {
+            optional.push((ident, name, &field.ty));
+        } else {
+            required.push((ident, name, &field.ty));
+        }
+    }
+
+    if required.len() > 9 || optional.len() > 9 {
+        return Err(Error::new(
+            named.span(),
+            "too many keywords, the maximum is 9 required and 9 optional",
+        ));
+    }
+
+    let req_idents = required.iter().map(|(i, _, _)| i).collect::<Vec<_>>();
+    let req_names = required.iter().map(|(_, n, _)| n);
+    let req_tys = required.iter().map(|(_, _, t)| t);
+    let opt_idents = optional.iter().map(|(i, _, _)| i).collect::<Vec<_>>();
+    let opt_names = optional.iter().map(|(_, n, _)| n);
+    let opt_tys = optional.iter().map(|(_, _, t)| t);
+    let (splat_ty, rest_field) = match rest {
+        Some(ident) => (quote! { magnus::RHash }, quote! { #ident: args.splat, }),
+        None => (quote! { () }, quote! {}),
+    };
+
+    let ident = &input.ident;
+    Ok(quote! {
+        impl magnus::scan_args::Keywords for #ident {
+            fn from_kwargs(kw: magnus::RHash) -> Result<Self, magnus::Error> {
+                let args = magnus::scan_args::get_kwargs::<
+                    &str,
+                    (#(#req_tys,)*),
+                    (#(#opt_tys,)*),
+                    #splat_ty,
+                >(kw, &[#(#req_names,)*], &[#(#opt_names,)*])?;
+                let (#(#req_idents,)*) = args.required;
+                let (#(#opt_idents,)*) = args.optional;
+                Ok(Self {
+                    #(#req_idents,)*
+                    #(#opt_idents,)*
+                    #rest_field
+                })
+            }
+        }
+    })
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) if p.qself.is_none() => p
+            .path
+            .segments
+            .last()
+            .map(|s| {
+                s.ident == "Option"
+                    && matches!(
+                        &s.arguments,
+                        PathArguments::AngleBracketed(a)
+                            if a.args.len() == 1
+                                && matches!(a.args[0], GenericArgument::Type(_))
+                    )
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
@@ -20,6 +20,8 @@ use proc_macro::TokenStream;
 use syn::parse_macro_input;
 
 mod init;
+mod keywords;
+mod method;
 mod typed_data;
 mod util;
 
@@ -110,6 +112,177 @@ pub fn init(attrs: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Wrap a function as a Ruby method, inferring the arity from the function
+/// signature.
+///
+/// This is an alternative to the `method!` macro, where the arity does not
+/// need to be given.
+///
+/// This generates an additional function named after the annotated function
+/// with the suffix `_method` that returns the wrapped function, suitable for
+/// passing to functions that define Ruby methods such as
+/// `Module::define_method`. The annotated function itself is unchanged, and
+/// can still be called from Rust.
+///
+/// The function may optionally take a `&Ruby` as its first argument. This is
+/// followed by the `self` argument, either a `self` receiver (e.g. `&self`
+/// for a type wrapped with [`macro@wrap`]) or any type implementing
+/// `TryConvert`, such as `typed_data::Obj<T>` or `Value`. The remaining
+/// arguments determine the arity of the Ruby method. A single `&[Value]`
+/// argument after `self` accepts any number of arguments.
+///
+/// Functions taking a `self` receiver, or with `Self` in their signature, are
+/// assumed to be in an `impl` block and the generated function will be an
+/// associated function.
+///
+/// # Argument Attributes
+///
+/// The `#[magnus(...)]` attribute can be set on the last argument with the
+/// following values:
+///
+/// * `kwargs` - The argument receives the keyword arguments passed to the
+///   method. The argument type must be `RHash`, which will be empty if no
+///   keywords were passed, or a type implementing `scan_args::Keywords` (see
+///   [`macro@Keywords`]). See `scan_args::get_kwargs` for extracting values
+///   from a hash.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{prelude::*, rb_assert, scan_args::get_kwargs, Error, RHash, Ruby};
+///
+/// #[magnus::wrap(class = "Point")]
+/// struct Point {
+///     x: isize,
+///     y: isize,
+/// }
+///
+/// impl Point {
+///     #[magnus::wrap_function]
+///     fn new(x: isize, y: isize) -> Self {
+///         Self { x, y }
+///     }
+///
+///     #[magnus::wrap_method]
+///     fn x(&self) -> isize {
+///         self.x
+///     }
+///
+///     #[magnus::wrap_method]
+///     fn distance(&self, other: &Point) -> f64 {
+///         (((other.x - self.x).pow(2) + (other.y - self.y).pow(2)) as f64).sqrt()
+///     }
+///
+///     #[magnus::wrap_method]
+///     fn describe(&self, #[magnus(kwargs)] opts: RHash) -> Result<String, Error> {
+///         let kw = get_kwargs::<_, (), (Option<bool>,), ()>(opts, &[], &["verbose"])?;
+///         let (verbose,) = kw.optional;
+///         if verbose.unwrap_or(false) {
+///             Ok(format!("Point(x: {}, y: {})", self.x, self.y))
+///         } else {
+///             Ok(format!("({}, {})", self.x, self.y))
+///         }
+///     }
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let class = ruby.define_class("Point", ruby.class_object())?;
+///     class.define_singleton_method("new", Point::new_function())?;
+///     class.define_method("x", Point::x_method())?;
+///     class.define_method("distance", Point::distance_method())?;
+///     class.define_method("describe", Point::describe_method())?;
+///
+///     rb_assert!(ruby, "Point.new(1, 2).x == 1");
+///     rb_assert!(ruby, "Point.new(0, 0).distance(Point.new(3, 4)) == 5.0");
+///     rb_assert!(ruby, r#"Point.new(1, 2).describe == "(1, 2)""#);
+///     rb_assert!(ruby, r#"Point.new(1, 2).describe(verbose: true) == "Point(x: 1, y: 2)""#);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[proc_macro_attribute]
+pub fn wrap_method(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    expand_method(method::Kind::Method, attrs, item)
+}
+
+/// Wrap a function as a Ruby method ignoring `self`, inferring the arity from
+/// the function signature.
+///
+/// This is an alternative to the `function!` macro, where the arity does not
+/// need to be given.
+///
+/// This generates an additional function named after the annotated function
+/// with the suffix `_function` that returns the wrapped function, suitable
+/// for passing to functions that define Ruby methods such as
+/// `Module::define_method`. The annotated function itself is unchanged, and
+/// can still be called from Rust.
+///
+/// The function may optionally take a `&Ruby` as its first argument. The
+/// remaining arguments determine the arity of the Ruby method. A single
+/// `&[Value]` argument accepts any number of arguments.
+///
+/// Functions with `Self` in their signature are assumed to be in an `impl`
+/// block and the generated function will be an associated function.
+///
+/// # Argument Attributes
+///
+/// The `#[magnus(...)]` attribute can be set on the last argument with the
+/// following values:
+///
+/// * `kwargs` - The argument receives the keyword arguments passed to the
+///   method. The argument type must be `RHash`, which will be empty if no
+///   keywords were passed, or a type implementing `scan_args::Keywords` (see
+///   [`macro@Keywords`]). See `scan_args::get_kwargs` for extracting values
+///   from a hash.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{rb_assert, Error, Ruby};
+///
+/// #[magnus::wrap_function]
+/// fn distance(ruby: &Ruby, a: (f64, f64), b: (f64, f64)) -> Result<f64, Error> {
+///     if a.0.is_nan() || b.0.is_nan() {
+///         return Err(Error::new(ruby.exception_arg_error(), "NaN"));
+///     }
+///     Ok(((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt())
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.define_global_function("distance", distance_function());
+///
+///     rb_assert!(ruby, "distance([0.0, 0.0], [3.0, 4.0]) == 5.0");
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[proc_macro_attribute]
+pub fn wrap_function(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    expand_method(method::Kind::Function, attrs, item)
+}
+
+fn expand_method(kind: method::Kind, attrs: TokenStream, item: TokenStream) -> TokenStream {
+    if !attrs.is_empty() {
+        return syn::Error::new(
+            proc_macro2::TokenStream::from(attrs)
+                .into_iter()
+                .next()
+                .map(|tt| tt.span())
+                .unwrap_or_else(proc_macro2::Span::call_site),
+            "unsupported attribute",
+        )
+        .into_compile_error()
+        .into();
+    }
+    match method::expand(kind, parse_macro_input!(item)) {
+        Ok(tokens) => tokens,
+        Err(e) => e.into_compile_error(),
+    }
+    .into()
+}
+
 /// Allow a Rust type to be passed to Ruby, automatically wrapped as a Ruby
 /// object.
 ///
@@ -126,11 +299,33 @@ pub fn init(attrs: TokenStream, item: TokenStream) -> TokenStream {
 ///   implmentation does not call Ruby.
 /// * `size` - Report the [`std::mem::size_of_val`] of the type to Ruby, used
 ///   to aid in deciding when to run the garbage collector.
+/// * `ractor_safe` - Allow frozen instances of the Ruby class to be shared
+///   between Ractors. The Rust type must be [`Sync`].
+/// * `hash_eql` - Define Ruby `hash`, `eql?`, and `==` methods on the class
+///   delegating to the type's [`std::hash::Hash`] and [`Eq`] implementations,
+///   so that instances can be used as Hash keys and in Sets.
+/// * `inspect` - Define a Ruby `inspect` method on the class using the type's
+///   [`Debug`](std::fmt::Debug) implementation, prefixed with the class name.
+/// * `to_s` - Define a Ruby `to_s` method on the class using the type's
+///   [`Display`](std::fmt::Display) implementation.
+/// * `marshal` - Define Ruby `_dump` and `_load` methods using the type's
+///   serde `Serialize` and `Deserialize` implementations, so the type can be
+///   used with Ruby's `Marshal`. Requires magnus' `marshal` feature.
+/// * `dup` - Define Ruby `dup` and `clone` methods using the type's [`Clone`]
+///   implementation, so copies of the Ruby object wrap an independent copy of
+///   the Rust type.
+/// * `pretty_print` - Define a Ruby `pretty_print` method, used by `pp`, from
+///   the type's `typed_data::PrettyPrint` implementation.
 /// * `unsafe_generics` - The derived implementation of [`TypedData`] is not
 ///   guaranteed to be correct for types with generics. If you are sure it is
 ///   for your type this attribute can be used to override the compile time
 ///   error usually generated for types with generics.
 ///
+/// The methods requested by `hash_eql`, `inspect`, `to_s`, `marshal`, `dup`,
+/// and `pretty_print` are defined by `TypedData::setup`, which should be
+/// called once the Ruby class has been defined. Methods the class already
+/// defines itself are left in place.
+///
 /// # Variant Attributes
 ///
 /// The `#[magnus(...)]` attribute can be set on enum variants with the
@@ -242,11 +437,34 @@ pub fn derive_data_type_functions(input: TokenStream) -> TokenStream {
 /// * `compact` - Enable Ruby calling the `DataTypeFunctions::compact` function.
 /// * `wb_protected` - Enable the `wb_protected` flag.
 /// * `frozen_shareable` - Enable the `frozen_shareable` flag.
+/// * `ractor_safe` - Alias for `frozen_shareable`. Frozen instances of the
+///   Ruby class can be shared between Ractors, so the Rust type must be
+///   [`Sync`].
+/// * `hash_eql` - Define Ruby `hash`, `eql?`, and `==` methods on the class
+///   delegating to the type's [`std::hash::Hash`] and [`Eq`] implementations,
+///   so that instances can be used as Hash keys and in Sets.
+/// * `inspect` - Define a Ruby `inspect` method on the class using the type's
+///   [`Debug`](std::fmt::Debug) implementation, prefixed with the class name.
+/// * `to_s` - Define a Ruby `to_s` method on the class using the type's
+///   [`Display`](std::fmt::Display) implementation.
+/// * `marshal` - Define Ruby `_dump` and `_load` methods using the type's
+///   serde `Serialize` and `Deserialize` implementations, so the type can be
+///   used with Ruby's `Marshal`. Requires magnus' `marshal` feature.
+/// * `dup` - Define Ruby `dup` and `clone` methods using the type's [`Clone`]
+///   implementation, so copies of the Ruby object wrap an independent copy of
+///   the Rust type.
+/// * `pretty_print` - Define a Ruby `pretty_print` method, used by `pp`, from
+///   the type's `typed_data::PrettyPrint` implementation.
 /// * `unsafe_generics` - The derived implementation of [`TypedData`] is not
 ///   guaranteed to be correct for types with generics. If you are sure it is
 ///   for your type this attribute can be used to override the compile time
 ///   error usually generated for types with generics.
 ///
+/// The methods requested by `hash_eql`, `inspect`, `to_s`, `marshal`, `dup`,
+/// and `pretty_print` are defined by `TypedData::setup`, which should be
+/// called once the Ruby class has been defined. Methods the class already
+/// defines itself are left in place.
+///
 /// # Field Attributes
 ///
 /// The `#[magnus(...)]` attribute can be set on struct fields with the
@@ -399,3 +617,68 @@ pub fn derive_typed_data(input: TokenStream) -> TokenStream {
     }
     .into()
 }
+
+/// Derives `scan_args::Keywords`, allowing a struct to be extracted from a
+/// method's keyword arguments.
+///
+/// Each field is a keyword of the same name. Fields with a type of `Option<T>`
+/// are optional keywords, all other fields are required. Unknown keywords
+/// will raise an `ArgumentError`, unless there is a `rest` field.
+///
+/// Up to 9 required and 9 optional keywords are supported.
+///
+/// # Field Attributes
+///
+/// The `#[magnus(...)]` attribute can be set on fields with the following
+/// values:
+///
+/// * `name = "..."` - sets the keyword name, rather than using the field name.
+/// * `rest` - the field receives any remaining keywords not matching another
+///   field. The field type must be `RHash`.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{function, rb_assert, Error, RHash, Ruby};
+///
+/// #[derive(magnus::Keywords)]
+/// struct ConnectOpts {
+///     host: String,
+///     port: Option<u16>,
+///     #[magnus(name = "async")]
+///     nonblocking: Option<bool>,
+///     #[magnus(rest)]
+///     extra: RHash,
+/// }
+///
+/// fn connect(opts: ConnectOpts) -> String {
+///     format!(
+///         "{}:{} async={} extra={}",
+///         opts.host,
+///         opts.port.unwrap_or(80),
+///         opts.nonblocking.unwrap_or(false),
+///         opts.extra.len()
+///     )
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.define_global_function("connect", function!(connect, 0, kwargs));
+///
+///     rb_assert!(ruby, r#"connect(host: "example.com") == "example.com:80 async=false extra=0""#);
+///     rb_assert!(
+///         ruby,
+///         r#"connect(host: "localhost", port: 8080, async: true, retry: 3) == "localhost:8080 async=true extra=1""#
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[proc_macro_derive(Keywords, attributes(magnus))]
+pub fn derive_keywords(input: TokenStream) -> TokenStream {
+    match keywords::expand_derive_keywords(parse_macro_input!(input)) {
+        Ok(tokens) => tokens,
+        Err(e) => e.into_compile_error(),
+    }
+    .into()
+}
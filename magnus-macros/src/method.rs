@@ -0,0 +1,329 @@
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::{spanned::Spanned, Error, FnArg, ItemFn, Type};
+
+use crate::util;
+
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Method,
+    Function,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Method => "method",
+            Kind::Function => "function",
+        }
+    }
+}
+
+enum Args {
+    /// Fixed number of arguments.
+    Fixed(usize),
+    /// A slice of `Value`s, arity -1.
+    Slice,
+    /// Fixed number of arguments followed by keywords, also arity -1.
+    Kwargs(Vec<Type>, Type),
+}
+
+pub fn expand(kind: Kind, mut input: ItemFn) -> Result<TokenStream, Error> {
+    let ident = input.sig.ident.clone();
+    let vis = input.vis.clone();
+
+    if !input.sig.generics.params.is_empty() {
+        return Err(Error::new(
+            input.sig.generics.span(),
+            format!(
+                "generic functions can not be wrapped as a Ruby {}",
+                kind.name()
+            ),
+        ));
+    }
+
+    // functions taking `self` or mentioning `Self` must be in an impl block,
+    // and have to be referred to with a `Self::` prefix
+    let associated = input.sig.receiver().is_some() || mentions_self(input.sig.to_token_stream());
+    let path = if associated {
+        quote! { Self::#ident }
+    } else {
+        quote! { #ident }
+    };
+
+    let mut inputs = input.sig.inputs.iter_mut().peekable();
+
+    let with_ruby = match inputs.peek() {
+        Some(FnArg::Typed(arg)) if is_ruby(&arg.ty) => {
+            inputs.next();
+            true
+        }
+        _ => false,
+    };
+
+    let mut self_ty = None;
+    if let Kind::Method = kind {
+        match inputs.next() {
+            Some(FnArg::Receiver(recv)) => {
+                if recv.mutability.is_some() {
+                    return Err(Error::new(
+                        recv.span(),
+                        "`&mut self` is not supported, consider using `RefCell`",
+                    ));
+                }
+                self_ty = Some((*recv.ty).clone());
+            }
+            Some(FnArg::Typed(arg)) => self_ty = Some((*arg.ty).clone()),
+            None => {
+                return Err(Error::new(
+                    ident.span(),
+                    "methods must take a `self` argument",
+                ))
+            }
+        }
+    }
+
+    let mut types = Vec::new();
+    let mut kwargs = None;
+    for arg in inputs {
+        let arg = match arg {
+            FnArg::Typed(arg) => arg,
+            FnArg::Receiver(recv) => {
+                return Err(Error::new(recv.span(), "unexpected `self` argument"))
+            }
+        };
+        if kwargs.is_some() {
+            return Err(Error::new(
+                arg.span(),
+                "keyword arguments must be the last argument",
+            ));
+        }
+        if let Some(attr) = util::get_magnus_attrubute(&arg.attrs)? {
+            let mut is_kwargs = false;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("kwargs") {
+                    is_kwargs = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported attribute"))
+                }
+            })?;
+            arg.attrs.retain(|attr| !attr.path().is_ident("magnus"));
+            if is_kwargs {
+                kwargs = Some((*arg.ty).clone());
+                continue;
+            }
+        }
+        types.push((*arg.ty).clone());
+    }
+
+    let args = match kwargs {
+        Some(kw) => Args::Kwargs(types, kw),
+        None if types.len() == 1 && is_value_slice(&types[0]) => Args::Slice,
+        None if types.len() > 16 => {
+            return Err(Error::new(
+                input.sig.inputs.span(),
+                "too many arguments, the maximum is 16",
+            ))
+        }
+        None => Args::Fixed(types.len()),
+    };
+
+    let wrapper_name = format_ident!("__magnus_{}_{}", kind.name(), ident);
+    let accessor_name = format_ident!("{}_{}", ident, kind.name());
+    let wrapper_path = if associated {
+        quote! { Self::#wrapper_name }
+    } else {
+        quote! { #wrapper_name }
+    };
+
+    let (wrapper, ptr_ty) = match args {
+        Args::Fixed(n) => {
+            let arg_names = (0..n)
+                .map(|i| Ident::new(&format!("arg{}", i), Span::call_site()))
+                .collect::<Vec<_>>();
+            let (plain_trait, ruby_trait) = match kind {
+                Kind::Method => (
+                    format_ident!("Method{}", n),
+                    format_ident!("RubyMethod{}", n),
+                ),
+                Kind::Function => (
+                    format_ident!("Function{}", n),
+                    format_ident!("RubyFunction{}", n),
+                ),
+            };
+            let call = match kind {
+                Kind::Method => quote! { #path.call_handle_error(rb_self, #(#arg_names,)*) },
+                Kind::Function => quote! { #path.call_handle_error(#(#arg_names,)*) },
+            };
+            let value_tys = arg_names.iter().map(|_| quote! { magnus::Value });
+            (
+                quote! {
+                    #[doc(hidden)]
+                    #[allow(unused_variables)]
+                    #vis unsafe extern "C" fn #wrapper_name(
+                        rb_self: magnus::Value,
+                        #(#arg_names: magnus::Value,)*
+                    ) -> magnus::Value {
+                        use magnus::method::{#plain_trait, #ruby_trait};
+                        #call
+                    }
+                },
+                quote! { unsafe extern "C" fn(magnus::Value, #(#value_tys,)*) -> magnus::Value },
+            )
+        }
+        Args::Slice => {
+            let (plain_trait, ruby_trait, call) = match kind {
+                Kind::Method => (
+                    quote! { MethodCAry },
+                    quote! { RubyMethodCAry },
+                    quote! { #path.call_handle_error(argc, argv, rb_self) },
+                ),
+                Kind::Function => (
+                    quote! { FunctionCAry },
+                    quote! { RubyFunctionCAry },
+                    quote! { #path.call_handle_error(argc, argv) },
+                ),
+            };
+            (
+                quote! {
+                    #[doc(hidden)]
+                    #[allow(unused_variables)]
+                    #vis unsafe extern "C" fn #wrapper_name(
+                        argc: std::os::raw::c_int,
+                        argv: *const magnus::Value,
+                        rb_self: magnus::Value,
+                    ) -> magnus::Value {
+                        use magnus::method::{#plain_trait, #ruby_trait};
+                        #call
+                    }
+                },
+                quote! {
+                    unsafe extern "C" fn(
+                        std::os::raw::c_int,
+                        *const magnus::Value,
+                        magnus::Value,
+                    ) -> magnus::Value
+                },
+            )
+        }
+        Args::Kwargs(types, kw) => {
+            let arg_names = (0..types.len())
+                .map(|i| Ident::new(&format!("arg{}", i), Span::call_site()))
+                .collect::<Vec<_>>();
+            let ruby_param = if with_ruby {
+                quote! { ruby: &magnus::Ruby, }
+            } else {
+                quote! {}
+            };
+            let ruby_arg = if with_ruby {
+                quote! { ruby, }
+            } else {
+                quote! {}
+            };
+            let (self_param, self_arg) = match &self_ty {
+                Some(ty) => (quote! { rb_self: #ty, }, quote! { rb_self, }),
+                None => (quote! {}, quote! {}),
+            };
+            let (plain_trait, ruby_trait, call) = match kind {
+                Kind::Method => (
+                    quote! { MethodCAry },
+                    quote! { RubyMethodCAry },
+                    quote! { call_handle_error(argc, argv, rb_self) },
+                ),
+                Kind::Function => (
+                    quote! { FunctionCAry },
+                    quote! { RubyFunctionCAry },
+                    quote! { call_handle_error(argc, argv) },
+                ),
+            };
+            (
+                quote! {
+                    #[doc(hidden)]
+                    #[allow(unused_variables)]
+                    #vis unsafe extern "C" fn #wrapper_name(
+                        argc: std::os::raw::c_int,
+                        argv: *const magnus::Value,
+                        rb_self: magnus::Value,
+                    ) -> magnus::Value {
+                        use magnus::method::{#plain_trait, #ruby_trait};
+                        (|#ruby_param #self_param args: &[magnus::Value]| -> Result<magnus::Value, magnus::Error> {
+                            let args = magnus::scan_args::scan_args::<(#(#types,)*), (), (), (), #kw, ()>(args)?;
+                            let (#(#arg_names,)*) = args.required;
+                            magnus::method::into_return_value(
+                                #path(#ruby_arg #self_arg #(#arg_names,)* args.keywords)
+                            )
+                        }).#call
+                    }
+                },
+                quote! {
+                    unsafe extern "C" fn(
+                        std::os::raw::c_int,
+                        *const magnus::Value,
+                        magnus::Value,
+                    ) -> magnus::Value
+                },
+            )
+        }
+    };
+
+    let accessor_doc = format!(
+        "Returns [`{}`]({}) wrapped as a Ruby {}.",
+        ident,
+        path.to_string().replace(' ', ""),
+        kind.name()
+    );
+
+    Ok(quote! {
+        #input
+
+        #wrapper
+
+        #[doc = #accessor_doc]
+        #[allow(dead_code)]
+        #vis fn #accessor_name() -> #ptr_ty {
+            #wrapper_path
+        }
+    })
+}
+
+fn is_ruby(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(r) => match &*r.elem {
+            Type::Path(p) => p
+                .path
+                .segments
+                .last()
+                .map(|s| s.ident == "Ruby")
+                .unwrap_or(false),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_value_slice(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(r) => match &*r.elem {
+            Type::Slice(s) => match &*s.elem {
+                Type::Path(p) => p
+                    .path
+                    .segments
+                    .last()
+                    .map(|s| s.ident == "Value")
+                    .unwrap_or(false),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn mentions_self(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ident) => ident == "Self",
+        TokenTree::Group(group) => mentions_self(group.stream()),
+        _ => false,
+    })
+}
@@ -37,6 +37,12 @@ pub fn expand_derive_typed_data(input: DeriveInput) -> Result<TokenStream, Error
     let mut wb_protected = false;
     let mut frozen_shareable = false;
     let mut unsafe_generics = false;
+    let mut hash_eql = false;
+    let mut inspect = false;
+    let mut to_s = false;
+    let mut marshal = false;
+    let mut dup = false;
+    let mut pretty_print = false;
 
     attrs.parse_nested_meta(|meta| {
         if meta.path.is_ident("class") {
@@ -60,12 +66,30 @@ pub fn expand_derive_typed_data(input: DeriveInput) -> Result<TokenStream, Error
         } else if meta.path.is_ident("wb_protected") {
             wb_protected = true;
             Ok(())
-        } else if meta.path.is_ident("frozen_shareable") {
+        } else if meta.path.is_ident("frozen_shareable") || meta.path.is_ident("ractor_safe") {
             frozen_shareable = true;
             Ok(())
         } else if meta.path.is_ident("unsafe_generics") {
             unsafe_generics = true;
             Ok(())
+        } else if meta.path.is_ident("hash_eql") {
+            hash_eql = true;
+            Ok(())
+        } else if meta.path.is_ident("inspect") {
+            inspect = true;
+            Ok(())
+        } else if meta.path.is_ident("to_s") {
+            to_s = true;
+            Ok(())
+        } else if meta.path.is_ident("marshal") {
+            marshal = true;
+            Ok(())
+        } else if meta.path.is_ident("dup") {
+            dup = true;
+            Ok(())
+        } else if meta.path.is_ident("pretty_print") {
+            pretty_print = true;
+            Ok(())
         } else if meta.path.is_ident("free_immediatly") {
             Err(meta.error("unsupported attribute (use free_immediately)"))
         } else {
@@ -193,6 +217,43 @@ pub fn expand_derive_typed_data(input: DeriveInput) -> Result<TokenStream, Error
         quote! {}
     };
 
+    let mut define_methods = Vec::new();
+    if hash_eql {
+        define_methods.push(quote! {
+            magnus::typed_data::define_method_unless_defined(class, "hash", magnus::method!(<#ident #generics as magnus::typed_data::Hash>::hash, 0))?;
+            magnus::typed_data::define_method_unless_defined(class, "eql?", magnus::method!(<#ident #generics as magnus::typed_data::IsEql>::is_eql, 1))?;
+            magnus::typed_data::define_method_unless_defined(class, "==", magnus::method!(<#ident #generics as magnus::typed_data::IsEql>::is_eql, 1))?;
+        });
+    }
+    if inspect {
+        define_methods.push(quote! {
+            magnus::typed_data::define_method_unless_defined(class, "inspect", magnus::method!(magnus::typed_data::inspect_with_class::<#ident #generics>, 0))?;
+        });
+    }
+    if to_s {
+        define_methods.push(quote! {
+            magnus::typed_data::define_method_unless_defined(class, "to_s", magnus::method!(magnus::typed_data::to_s_display::<#ident #generics>, 0))?;
+        });
+    }
+    if dup {
+        define_methods.push(quote! {
+            magnus::typed_data::define_method_unless_defined(class, "dup", magnus::method!(<#ident #generics as magnus::typed_data::Dup>::dup, 0))?;
+            magnus::typed_data::define_method_unless_defined(class, "clone", magnus::method!(<#ident #generics as magnus::typed_data::Dup>::clone, -1))?;
+        });
+    }
+    if pretty_print {
+        define_methods.push(quote! {
+            magnus::typed_data::define_method_unless_defined(class, "pretty_print", magnus::method!(magnus::typed_data::pretty_print::<#ident #generics>, 1))?;
+        });
+    }
+    if marshal {
+        define_methods.push(quote! {
+            magnus::typed_data::define_method_unless_defined(class, "_dump", magnus::method!(magnus::typed_data::marshal_dump::<#ident #generics>, 1))?;
+            magnus::typed_data::define_singleton_method_unless_defined(class, "_load", magnus::function!(magnus::typed_data::marshal_load::<#ident #generics>, 1))?;
+        });
+    }
+    let define_methods = define_methods.into_iter().collect::<TokenStream>();
+
     let mut builder = Vec::new();
     builder.push(quote! { magnus::data_type_builder!(#ident, #name) });
     if mark {
@@ -229,6 +290,13 @@ pub fn expand_derive_typed_data(input: DeriveInput) -> Result<TokenStream, Error
                 ruby.get_inner(&CLASS)
             }
 
+            fn setup(ruby: &magnus::Ruby) -> Result<(), magnus::Error> {
+                let class = <Self as magnus::TypedData>::class(ruby);
+                magnus::typed_data::register::<Self>(class);
+                #define_methods
+                Ok(())
+            }
+
             fn data_type() -> &'static magnus::DataType {
                 static DATA_TYPE: magnus::DataType = #builder;
                 &DATA_TYPE
@@ -0,0 +1,410 @@
+//! Lightweight tracing of typed data and Ruby object allocations.
+//!
+//! When the `alloc-trace` feature is enabled magnus can record each time a
+//! Rust type is wrapped in a Ruby object, each time a wrapped value is
+//! unwrapped (converted back to a Rust reference), and each time a wrapped
+//! value is freed by Ruby's garbage collector. It also records the number of
+//! Ruby objects allocated of each class.
+//!
+//! Recording is off by default and must be switched on with
+//! [`Ruby::alloc_trace_enable`]. The counts collected can be queried at any
+//! time with [`stats`] and [`object_stats`], which can help identify
+//! allocation hot spots in an extension without reaching for a full Ruby
+//! profiler.
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{alloc_trace, Error, Ruby};
+//!
+//! #[magnus::wrap(class = "Point")]
+//! #[allow(dead_code)]
+//! struct Point {
+//!     x: isize,
+//!     y: isize,
+//! }
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     ruby.define_class("Point", ruby.class_object())?;
+//!
+//!     ruby.alloc_trace_enable();
+//!     for i in 0..10 {
+//!         ruby.wrap(Point { x: i, y: i });
+//!     }
+//!     let _: Vec<String> = ruby.eval(r#"3.times.map { |i| "#{i}" }"#)?;
+//!     ruby.alloc_trace_disable();
+//!
+//!     let stats = alloc_trace::stats_for::<Point>().unwrap();
+//!     assert_eq!(stats.wrapped, 10);
+//!     assert_eq!(stats.class, "Point");
+//!
+//!     let strings = alloc_trace::object_stats_for("String").unwrap();
+//!     assert!(strings.allocated >= 3);
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap();
+//! ```
+
+use std::{
+    any::type_name,
+    collections::HashMap,
+    ffi::c_void,
+    slice, str,
+    sync::{
+        atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use rb_sys::{
+    rb_class_path_cached, rb_event_flag_t, rb_gc_register_mark_object, rb_obj_class,
+    rb_tracearg_from_tracepoint, rb_tracearg_object, rb_tracepoint_disable, rb_tracepoint_enable,
+    rb_tracepoint_new, RUBY_INTERNAL_EVENT_NEWOBJ, VALUE,
+};
+
+use crate::{
+    class::{Class, RClass},
+    r_string::RString,
+    typed_data::TypedData,
+    value::Value,
+    Ruby,
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STATS: Mutex<Option<HashMap<&'static str, AllocStats>>> = Mutex::new(None);
+static OBJECT_STATS: Mutex<Option<HashMap<String, usize>>> = Mutex::new(None);
+static NEWOBJ_HOOK: Mutex<Option<VALUE>> = Mutex::new(None);
+
+/// Allocation counts for a single wrapped Rust type.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    /// The name of the Rust type.
+    pub type_name: &'static str,
+    /// The name of the Ruby class the type was most recently wrapped as.
+    pub class: String,
+    /// The number of times the type has been wrapped in a Ruby object.
+    pub wrapped: usize,
+    /// The number of times the type has been unwrapped from a Ruby object.
+    pub unwrapped: usize,
+    /// The number of wrapped values freed by the garbage collector.
+    pub freed: usize,
+}
+
+impl AllocStats {
+    /// The number of wrapped values recorded as allocated but not yet freed.
+    ///
+    /// As values wrapped before tracing was enabled may be freed while
+    /// tracing is enabled, this is only an approximation.
+    pub fn live(&self) -> usize {
+        self.wrapped.saturating_sub(self.freed)
+    }
+}
+
+/// Allocation counts for a single Ruby class.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObjectAllocStats {
+    /// The name of the class. Anonymous classes are counted together, with an
+    /// empty name.
+    pub class: String,
+    /// The number of objects of the class allocated.
+    pub allocated: usize,
+}
+
+fn lock() -> MutexGuard<'static, Option<HashMap<&'static str, AllocStats>>> {
+    // this is locked while wrapping and freeing values, including from the
+    // GC's free function, where a panic would abort. A poisoned lock can at
+    // worst leave one type's counts off by one, and `live` is already only
+    // an approximation
+    STATS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn lock_objects() -> MutexGuard<'static, Option<HashMap<String, usize>>> {
+    OBJECT_STATS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn record<T, F>(f: F)
+where
+    F: FnOnce(&mut AllocStats),
+{
+    let name = type_name::<T>();
+    let mut guard = lock();
+    let entry = guard
+        .get_or_insert_with(HashMap::new)
+        .entry(name)
+        .or_insert_with(|| AllocStats {
+            type_name: name,
+            ..Default::default()
+        });
+    f(entry)
+}
+
+// Counts of freed values are recorded from the garbage collector's free
+// function, so rather than taking a lock they are kept in a fixed size table
+// of atomics, keyed by type name.
+
+const FREED_SLOTS: usize = 512;
+
+const SLOT_EMPTY: u8 = 0;
+const SLOT_WRITING: u8 = 1;
+const SLOT_READY: u8 = 2;
+
+struct FreedSlot {
+    state: AtomicU8,
+    name_ptr: AtomicPtr<u8>,
+    name_len: AtomicUsize,
+    count: AtomicUsize,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: FreedSlot = FreedSlot {
+    state: AtomicU8::new(SLOT_EMPTY),
+    name_ptr: AtomicPtr::new(std::ptr::null_mut()),
+    name_len: AtomicUsize::new(0),
+    count: AtomicUsize::new(0),
+};
+
+impl FreedSlot {
+    fn name(&self) -> Option<&'static str> {
+        if self.state.load(Ordering::Acquire) != SLOT_READY {
+            return None;
+        }
+        let ptr = self.name_ptr.load(Ordering::Relaxed);
+        let len = self.name_len.load(Ordering::Relaxed);
+        // only ever set from a &'static str
+        Some(unsafe { str::from_utf8_unchecked(slice::from_raw_parts(ptr, len)) })
+    }
+}
+
+static FREED: [FreedSlot; FREED_SLOTS] = [EMPTY_SLOT; FREED_SLOTS];
+
+/// Find or claim the slot for `name`, returning `None` if the table is full.
+///
+/// If two threads race to claim a slot for the same name it may end up with
+/// two slots, readers must total the counts of all slots matching a name.
+fn freed_slot(name: &'static str) -> Option<&'static FreedSlot> {
+    // FNV-1a
+    let hash = name.bytes().fold(0xcbf29ce484222325_u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    let start = hash as usize % FREED_SLOTS;
+    for i in 0..FREED_SLOTS {
+        let slot = &FREED[(start + i) % FREED_SLOTS];
+        match slot.name() {
+            Some(n) if n == name => return Some(slot),
+            Some(_) => continue,
+            None => (),
+        }
+        if slot
+            .state
+            .compare_exchange(
+                SLOT_EMPTY,
+                SLOT_WRITING,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        {
+            slot.name_ptr
+                .store(name.as_ptr() as *mut u8, Ordering::Relaxed);
+            slot.name_len.store(name.len(), Ordering::Relaxed);
+            slot.state.store(SLOT_READY, Ordering::Release);
+            return Some(slot);
+        }
+    }
+    None
+}
+
+fn freed_counts() -> HashMap<&'static str, usize> {
+    let mut res = HashMap::new();
+    for slot in FREED.iter() {
+        if let Some(name) = slot.name() {
+            *res.entry(name).or_insert(0) += slot.count.load(Ordering::Relaxed);
+        }
+    }
+    res
+}
+
+/// # Allocation tracing
+///
+/// Functions for controlling allocation tracing.
+///
+/// See also the [`alloc_trace`](self) module.
+impl Ruby {
+    /// Start recording allocations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{alloc_trace, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.alloc_trace_enable();
+    ///     assert!(alloc_trace::is_enabled());
+    ///     ruby.alloc_trace_disable();
+    ///     assert!(!alloc_trace::is_enabled());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn alloc_trace_enable(&self) {
+        let mut hook = NEWOBJ_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+        let tracepoint = *hook.get_or_insert_with(|| unsafe {
+            let tracepoint = rb_tracepoint_new(
+                0,
+                RUBY_INTERNAL_EVENT_NEWOBJ as rb_event_flag_t,
+                Some(on_newobj),
+                std::ptr::null_mut(),
+            );
+            rb_gc_register_mark_object(tracepoint);
+            tracepoint
+        });
+        unsafe { rb_tracepoint_enable(tracepoint) };
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+
+    /// Stop recording allocations.
+    ///
+    /// Counts already recorded are kept, see [`reset`] to clear them.
+    pub fn alloc_trace_disable(&self) {
+        ENABLED.store(false, Ordering::Relaxed);
+        let hook = NEWOBJ_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(tracepoint) = *hook {
+            unsafe { rb_tracepoint_disable(tracepoint) };
+        }
+    }
+}
+
+/// Returns whether allocations are currently being recorded.
+#[inline]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Clear all recorded counts.
+pub fn reset() {
+    *lock() = None;
+    *lock_objects() = None;
+    for slot in FREED.iter() {
+        slot.count.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Returns the recorded counts for all types, sorted by the number of times
+/// each type was wrapped, most frequent first.
+pub fn stats() -> Vec<AllocStats> {
+    let mut freed = freed_counts();
+    let mut res = lock()
+        .as_ref()
+        .map(|map| map.values().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    for stats in res.iter_mut() {
+        stats.freed = freed.remove(stats.type_name).unwrap_or(0);
+    }
+    // types only freed while tracing was enabled
+    res.extend(
+        freed
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(type_name, freed)| AllocStats {
+                type_name,
+                freed,
+                ..Default::default()
+            }),
+    );
+    res.sort_by(|a, b| b.wrapped.cmp(&a.wrapped).then(a.type_name.cmp(b.type_name)));
+    res
+}
+
+/// Returns the recorded counts for the type `T`, or `None` if nothing has
+/// been recorded for `T`.
+pub fn stats_for<T>() -> Option<AllocStats> {
+    let name = type_name::<T>();
+    stats().into_iter().find(|stats| stats.type_name == name)
+}
+
+/// Returns the recorded counts of Ruby objects allocated for all classes,
+/// sorted by the number of objects allocated, most first.
+pub fn object_stats() -> Vec<ObjectAllocStats> {
+    let mut res = lock_objects()
+        .as_ref()
+        .map(|map| {
+            map.iter()
+                .map(|(class, allocated)| ObjectAllocStats {
+                    class: class.clone(),
+                    allocated: *allocated,
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    res.sort_by(|a, b| b.allocated.cmp(&a.allocated).then(a.class.cmp(&b.class)));
+    res
+}
+
+/// Returns the recorded count of Ruby objects allocated for the class named
+/// `class`, or `None` if nothing has been recorded for `class`.
+pub fn object_stats_for(class: &str) -> Option<ObjectAllocStats> {
+    lock_objects().as_ref().and_then(|map| {
+        map.get(class).map(|allocated| ObjectAllocStats {
+            class: class.to_owned(),
+            allocated: *allocated,
+        })
+    })
+}
+
+/// Called by Ruby for each new object. Ruby objects must not be allocated
+/// here.
+unsafe extern "C" fn on_newobj(tracepoint: VALUE, _data: *mut c_void) {
+    let obj = rb_tracearg_object(rb_tracearg_from_tracepoint(tracepoint));
+    let class = rb_obj_class(obj);
+    // hidden objects have no class
+    if class == 0 {
+        return;
+    }
+    // the cached path doesn't allocate, it's nil for anonymous classes
+    let path = RString::from_value(Value::new(rb_class_path_cached(class)));
+    let name = path
+        .as_ref()
+        .and_then(|s| str::from_utf8(s.as_slice()).ok())
+        .unwrap_or("");
+    let mut guard = lock_objects();
+    let map = guard.get_or_insert_with(HashMap::new);
+    match map.get_mut(name) {
+        Some(count) => *count += 1,
+        None => {
+            map.insert(name.to_owned(), 1);
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn record_wrap<T>(class: RClass)
+where
+    T: TypedData,
+{
+    if !is_enabled() {
+        return;
+    }
+    let class_name = unsafe { class.name() }.into_owned();
+    record::<T, _>(|stats| {
+        stats.wrapped += 1;
+        stats.class = class_name;
+    });
+}
+
+#[inline]
+pub(crate) fn record_unwrap<T>() {
+    if is_enabled() {
+        record::<T, _>(|stats| stats.unwrapped += 1);
+    }
+}
+
+#[inline]
+pub(crate) fn record_free<T>() {
+    if is_enabled() {
+        if let Some(slot) = freed_slot(type_name::<T>()) {
+            slot.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
@@ -45,11 +45,12 @@ impl RubyGvlState {
         RUBY_GVL_STATE.with(|ruby_gvl_state| {
             let x = *ruby_gvl_state.borrow();
             match x {
-                // assumed not to change because there's currently no api to
-                // unlock.
+                // kept up to date by `set_gvl_locked` when the GVL is
+                // released/re-acquired with the `nogvl` apis.
                 Some(Self::Locked) => Self::Locked,
                 None => Self::current(),
-                // Don't expect without an api to unlock, so skip cache
+                // may have been re-acquired without going through magnus,
+                // so skip cache
                 Some(Self::Unlocked) => Self::current(),
                 // assumed not to change
                 Some(Self::NonRubyThread) => Self::NonRubyThread,
@@ -66,6 +67,37 @@ impl RubyGvlState {
     }
 }
 
+/// Update the cached GVL state for the current thread, called when the GVL is
+/// released or re-acquired.
+pub(crate) fn set_gvl_locked(locked: bool) {
+    let state = if locked {
+        RubyGvlState::Locked
+    } else {
+        RubyGvlState::Unlocked
+    };
+    RUBY_GVL_STATE.with(|ruby_gvl_state| {
+        *ruby_gvl_state.borrow_mut() = Some(state);
+    });
+}
+
+/// Panics if the Ruby API is not available on the current thread.
+///
+/// Used when the `sanitize` feature is enabled to turn misuse of the Ruby API
+/// in to a panic with a clear message, rather than undefined behaviour.
+#[cfg(feature = "sanitize")]
+#[track_caller]
+pub(crate) fn sanitize_check() {
+    match RubyGvlState::current() {
+        RubyGvlState::Locked => (),
+        RubyGvlState::Unlocked => {
+            panic!("magnus sanitize: Ruby API called while the GVL is released")
+        }
+        RubyGvlState::NonRubyThread => {
+            panic!("magnus sanitize: Ruby API called from a non-Ruby thread")
+        }
+    }
+}
+
 /// A handle to access Ruby's API.
 ///
 /// Using Ruby's API requires the Ruby VM to be initalised and all access to be
@@ -106,6 +138,7 @@ impl RubyGvlState {
 /// * [`GC`](#gc) - Garbage Collection
 /// * [Globals](#globals) - global variables, etc, plus current VM state such
 ///   as calling the current `super` method.
+/// * [GVL](#gvl) - releasing the Global VM Lock
 /// * [`Id`](#id) - low-level Symbol representation
 /// * [`Integer`](#integer)
 /// * [`Mutex`](#mutex)
@@ -159,11 +192,18 @@ impl Ruby {
     ///
     /// Note that all Ruby values are [`Copy`], so this will not take ownership
     /// of the passed value.
+    ///
+    /// With the `sanitize` feature enabled this will panic if called from a
+    /// non-Ruby thread, or while the GVL is released, which could otherwise
+    /// happen if a Ruby value were incorrectly sent to another thread.
     #[allow(unused_variables)]
+    #[cfg_attr(feature = "sanitize", track_caller)]
     pub fn get_with<T>(value: T) -> Self
     where
         T: ReprValue,
     {
+        #[cfg(feature = "sanitize")]
+        sanitize_check();
         Self(PhantomData)
     }
 
@@ -174,8 +214,14 @@ impl Ruby {
     /// This must only be called from a Ruby thread - that is one created by
     /// Ruby, or the main thread after [`embed::init`](crate::embed::init) has
     /// been called - and without having released the GVL.
+    ///
+    /// With the `sanitize` feature enabled this will panic, rather than
+    /// result in undefined behaviour, if these requirements are not met.
     #[inline]
+    #[cfg_attr(feature = "sanitize", track_caller)]
     pub unsafe fn get_unchecked() -> Self {
+        #[cfg(feature = "sanitize")]
+        sanitize_check();
         Self(PhantomData)
     }
 }
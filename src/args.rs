@@ -0,0 +1,198 @@
+//! Helpers for validating method arguments.
+//!
+//! Each function checks a single argument, returning it if valid, or an
+//! `ArgumentError` with a consistent message naming the parameter if not.
+//!
+//! See also [`Ruby::check_arity`] and the [`scan_args`](crate::scan_args)
+//! module.
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{args, function, rb_assert, Error, Ruby, Symbol};
+//!
+//! fn pad(ruby: &Ruby, s: String, width: i64, side: Symbol) -> Result<String, Error> {
+//!     let width = args::require_non_negative(ruby, "width", width)? as usize;
+//!     Ok(match args::one_of(ruby, "side", side, &["left", "right"])? {
+//!         "left" => format!("{:>width$}", s, width = width),
+//!         _ => format!("{:<width$}", s, width = width),
+//!     })
+//! }
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     ruby.define_global_function("pad", function!(pad, 3));
+//!
+//!     rb_assert!(ruby, r#"pad("a", 3, :left) == "  a""#);
+//!     rb_assert!(
+//!         ruby,
+//!         r#"(pad("a", -1, :left) rescue $!.message) == "width must not be negative, got -1""#,
+//!     );
+//!     rb_assert!(
+//!         ruby,
+//!         r#"(pad("a", 3, :up) rescue $!.message) == "side must be one of :left, :right, got :up""#,
+//!     );
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use std::{
+    fmt::{Debug, Display},
+    ops::RangeBounds,
+};
+
+use crate::{error::Error, symbol::Symbol, Ruby};
+
+fn invalid(ruby: &Ruby, msg: String) -> Error {
+    Error::new(ruby.exception_arg_error(), msg)
+}
+
+/// Returns `val` if it is greater than zero, otherwise an `ArgumentError`.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{args, Error, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     assert_eq!(args::require_positive(ruby, "count", 3)?, 3);
+///     assert_eq!(
+///         args::require_positive(ruby, "count", 0)
+///             .unwrap_err()
+///             .to_string(),
+///         "ArgumentError: count must be positive, got 0"
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn require_positive<T>(ruby: &Ruby, name: &str, val: T) -> Result<T, Error>
+where
+    T: PartialOrd + Default + Display,
+{
+    if val > T::default() {
+        Ok(val)
+    } else {
+        Err(invalid(
+            ruby,
+            format!("{} must be positive, got {}", name, val),
+        ))
+    }
+}
+
+/// Returns `val` if it is zero or greater, otherwise an `ArgumentError`.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{args, Error, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     assert_eq!(args::require_non_negative(ruby, "offset", 0)?, 0);
+///     assert_eq!(
+///         args::require_non_negative(ruby, "offset", -2)
+///             .unwrap_err()
+///             .to_string(),
+///         "ArgumentError: offset must not be negative, got -2"
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn require_non_negative<T>(ruby: &Ruby, name: &str, val: T) -> Result<T, Error>
+where
+    T: PartialOrd + Default + Display,
+{
+    if val >= T::default() {
+        Ok(val)
+    } else {
+        Err(invalid(
+            ruby,
+            format!("{} must not be negative, got {}", name, val),
+        ))
+    }
+}
+
+/// Returns `val` if it is within `range`, otherwise an `ArgumentError`.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{args, Error, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     assert_eq!(args::require_in_range(ruby, "level", 5, 1..=9)?, 5);
+///     assert_eq!(
+///         args::require_in_range(ruby, "level", 10, 1..=9)
+///             .unwrap_err()
+///             .to_string(),
+///         "ArgumentError: level must be in 1..=9, got 10"
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn require_in_range<T, R>(ruby: &Ruby, name: &str, val: T, range: R) -> Result<T, Error>
+where
+    T: PartialOrd + Display,
+    R: RangeBounds<T> + Debug,
+{
+    if range.contains(&val) {
+        Ok(val)
+    } else {
+        Err(invalid(
+            ruby,
+            format!("{} must be in {:?}, got {}", name, range, val),
+        ))
+    }
+}
+
+/// Returns the entry of `allowed` matching the name of `sym`, otherwise an
+/// `ArgumentError`.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{args, Error, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let modes = ["read", "write"];
+///     assert_eq!(
+///         args::one_of(ruby, "mode", ruby.to_symbol("read"), &modes)?,
+///         "read"
+///     );
+///     assert_eq!(
+///         args::one_of(ruby, "mode", ruby.to_symbol("append"), &modes)
+///             .unwrap_err()
+///             .to_string(),
+///         "ArgumentError: mode must be one of :read, :write, got :append"
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn one_of<'a>(
+    ruby: &Ruby,
+    name: &str,
+    sym: Symbol,
+    allowed: &[&'a str],
+) -> Result<&'a str, Error> {
+    let sym_name = sym.name()?;
+    if let Some(found) = allowed.iter().find(|a| **a == sym_name) {
+        return Ok(found);
+    }
+    let expected = allowed
+        .iter()
+        .map(|a| format!(":{}", a))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(invalid(
+        ruby,
+        format!("{} must be one of {}, got :{}", name, expected, sym_name),
+    ))
+}
@@ -2,6 +2,8 @@
 //!
 //! See also [`Ruby`](Ruby#blocks) for more block related methods.
 
+pub(crate) mod callback;
+
 use std::{
     fmt,
     mem::{forget, size_of},
@@ -9,6 +11,7 @@ use std::{
     slice,
 };
 
+pub use callback::{Callback, CallbackError};
 use rb_sys::{
     rb_block_given_p, rb_block_proc, rb_data_typed_object_wrap, rb_obj_is_proc, rb_proc_arity,
     rb_proc_call_kw, rb_proc_lambda_p, rb_proc_new, rb_yield, rb_yield_splat, rb_yield_values_kw,
@@ -144,19 +147,54 @@ impl Ruby {
                 .as_rb_value()
         }
 
-        let (closure, keepalive) = wrap_closure(block);
-        let call_func =
-            call::<F, R> as unsafe extern "C" fn(VALUE, VALUE, c_int, *const VALUE, VALUE) -> VALUE;
+        closure_proc(block, call::<F, R>)
+    }
 
-        let proc = unsafe {
-            Proc::from_rb_value_unchecked(rb_proc_new(Some(call_func), closure as VALUE))
-        };
-        // ivar without @ prefix is invisible from Ruby
-        proc.ivar_set("__rust_closure", keepalive).unwrap();
-        proc
+    /// Create a new `Proc` from a closure that may be called re-entrantly,
+    /// e.g. when used as a method body that recurses.
+    pub(crate) fn proc_from_shared_fn<F, R>(&self, block: F) -> Proc
+    where
+        F: 'static + Send + Fn(&Ruby, &[Value], Option<Proc>) -> R,
+        R: BlockReturn,
+    {
+        unsafe extern "C" fn call<F, R>(
+            _yielded_arg: VALUE,
+            callback_arg: VALUE,
+            argc: c_int,
+            argv: *const VALUE,
+            blockarg: VALUE,
+        ) -> VALUE
+        where
+            F: Fn(&Ruby, &[Value], Option<Proc>) -> R,
+            R: BlockReturn,
+        {
+            // calls may overlap, so only ever take a shared reference
+            let closure = &*(callback_arg as *const F);
+            closure
+                .call_handle_error(argc, argv as *const Value, Value::new(blockarg))
+                .as_rb_value()
+        }
+
+        closure_proc(block, call::<F, R>)
     }
 }
 
+fn closure_proc<F, R>(
+    block: F,
+    call_func: unsafe extern "C" fn(VALUE, VALUE, c_int, *const VALUE, VALUE) -> VALUE,
+) -> Proc
+where
+    F: 'static + Send + FnMut(&Ruby, &[Value], Option<Proc>) -> R,
+    R: BlockReturn,
+{
+    let (closure, keepalive) = wrap_closure(block);
+    let proc =
+        unsafe { Proc::from_rb_value_unchecked(rb_proc_new(Some(call_func), closure as VALUE)) };
+    // ivar without @ prefix is invisible from Ruby
+    proc.ivar_set("__rust_closure", keepalive).unwrap();
+    proc
+}
+
 /// Wrapper type for a Value known to be an instance of Ruby’s Proc class.
 ///
 /// See the [`ReprValue`] and [`Object`] traits for additional methods
@@ -0,0 +1,280 @@
+use std::{
+    error::Error as StdError,
+    fmt,
+    marker::PhantomData,
+    mem::take,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{mpsc, Arc, Condvar, Mutex, MutexGuard},
+};
+
+use crate::{
+    block::Proc,
+    error::Error,
+    gc,
+    into_value::RArrayArgList,
+    try_convert::TryConvert,
+    value::{DeferDrop, Opaque},
+    Ruby,
+};
+
+pub(crate) type Job = Box<dyn FnOnce(&Ruby) + Send>;
+
+struct State {
+    jobs: Vec<Job>,
+    running: bool,
+    interrupted: bool,
+}
+
+// Jobs submitted from non-Ruby threads are run by a single Ruby thread, which
+// waits for work with the GVL released.
+static STATE: Mutex<State> = Mutex::new(State {
+    jobs: Vec::new(),
+    running: false,
+    interrupted: false,
+});
+static READY: Condvar = Condvar::new();
+
+fn lock() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn start_executor(ruby: &Ruby) {
+    {
+        let mut state = lock();
+        if state.running {
+            return;
+        }
+        state.running = true;
+    }
+    ruby.thread_create_from_fn(run_executor);
+}
+
+fn run_executor(ruby: &Ruby) -> Result<(), Error> {
+    let res = loop {
+        let jobs = match wait_for_jobs(ruby) {
+            Ok(jobs) => jobs,
+            Err(e) => break Err(e),
+        };
+        for job in jobs {
+            // a panicking job only loses its own result
+            let _ = catch_unwind(AssertUnwindSafe(|| job(ruby)));
+        }
+        if let Err(e) = ruby.thread_check_ints() {
+            break Err(e);
+        }
+    };
+    // the thread was interrupted (e.g. killed as Ruby shuts down), dropping
+    // any jobs not yet run lets their callers know they won't be
+    let abandoned = {
+        let mut state = lock();
+        state.running = false;
+        take(&mut state.jobs)
+    };
+    drop(abandoned);
+    res
+}
+
+fn wait_for_jobs(ruby: &Ruby) -> Result<Vec<Job>, Error> {
+    ruby.without_gvl_unblock(
+        || {
+            let mut state = lock();
+            while state.jobs.is_empty() && !state.interrupted {
+                state = READY.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+            state.interrupted = false;
+            take(&mut state.jobs)
+        },
+        || {
+            lock().interrupted = true;
+            READY.notify_all();
+        },
+    )
+}
+
+// Returns the job if the executor isn't running.
+pub(crate) fn submit(job: Job) -> Result<(), Job> {
+    let mut state = lock();
+    if !state.running {
+        return Err(job);
+    }
+    state.jobs.push(job);
+    READY.notify_one();
+    Ok(())
+}
+
+/// # Callbacks
+impl Proc {
+    /// Convert `self` into a [`Callback`] that can be retained and called
+    /// from any thread.
+    ///
+    /// This starts a Ruby thread to run callbacks called from non-Ruby
+    /// threads, if it is not already running.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{block::Proc, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let proc: Proc = ruby.eval("proc { |a, b| a + b }")?;
+    ///     let callback = proc.into_callback::<(i64, i64), i64>();
+    ///
+    ///     let handle = std::thread::spawn(move || callback.call((1, 2)));
+    ///     // let other Ruby threads, including the one running callbacks, run
+    ///     while !handle.is_finished() {
+    ///         ruby.thread_schedule();
+    ///     }
+    ///     assert_eq!(handle.join().unwrap().unwrap(), 3);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn into_callback<A, R>(self) -> Callback<A, R>
+    where
+        A: RArrayArgList,
+        R: TryConvert,
+    {
+        let ruby = Ruby::get_with(self);
+        Callback {
+            proc: Arc::new(DeferDrop::new(&ruby, ProcHandle::new(self))),
+            phantom: PhantomData,
+        }
+    }
+}
+
+// Keeps a Proc registered with the GC. Unlike `BoxValue` this is `Send`; the
+// Proc is only accessed, and unregistered, with the GVL via `DeferDrop`.
+struct ProcHandle(Box<Opaque<Proc>>);
+
+impl ProcHandle {
+    fn new(proc: Proc) -> Self {
+        let handle = Box::new(Opaque::from(proc));
+        gc::register_address(&*handle);
+        Self(handle)
+    }
+
+    fn get(&self, ruby: &Ruby) -> Proc {
+        ruby.get_inner(*self.0)
+    }
+}
+
+impl Drop for ProcHandle {
+    fn drop(&mut self) {
+        gc::unregister_address(&*self.0);
+    }
+}
+
+/// A Ruby [`Proc`] that can be sent between threads and called from any
+/// thread.
+///
+/// Calls made from a non-Ruby thread are run on a Ruby thread started by
+/// [`Proc::into_callback`], and block until that thread acquires the GVL and
+/// completes the call. A Ruby thread must not block on a non-Ruby thread
+/// calling a `Callback` without releasing the GVL, or it will deadlock.
+///
+/// The proc is protected from Ruby's garbage collector for as long as the
+/// `Callback` (or any clones of it) exist.
+pub struct Callback<A, R> {
+    proc: Arc<DeferDrop<ProcHandle>>,
+    phantom: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> Callback<A, R>
+where
+    A: RArrayArgList,
+    R: TryConvert,
+{
+    /// Returns the underlying `Proc`.
+    pub fn proc(&self, ruby: &Ruby) -> Proc {
+        self.proc.get(ruby).get(ruby)
+    }
+
+    /// Call the callback from a Ruby thread.
+    pub fn call_with(&self, ruby: &Ruby, args: A) -> Result<R, Error> {
+        self.proc(ruby).call(args)
+    }
+}
+
+impl<A, R> Callback<A, R>
+where
+    A: RArrayArgList + Send + 'static,
+    R: TryConvert + Send + 'static,
+{
+    /// Call the callback from any thread, blocking until it completes.
+    ///
+    /// If called from a Ruby thread the callback is run immediately,
+    /// otherwise it is run on the callback executor's Ruby thread.
+    pub fn call(&self, args: A) -> Result<R, CallbackError> {
+        if let Ok(ruby) = Ruby::get() {
+            return self.call_with(&ruby, args).map_err(CallbackError::from);
+        }
+        let (tx, rx) = mpsc::sync_channel(1);
+        let proc = self.proc.clone();
+        submit(Box::new(move |ruby| {
+            let _ = tx.send(
+                proc.get(ruby)
+                    .get(ruby)
+                    .call(args)
+                    .map_err(CallbackError::from),
+            );
+        }))
+        .map_err(|_| CallbackError::ExecutorStopped)?;
+        rx.recv().unwrap_or(Err(CallbackError::ExecutorStopped))
+    }
+
+    /// Queue a call to the callback to be run on the callback executor's
+    /// Ruby thread, without waiting for it to complete.
+    ///
+    /// Any result, or error raised by the callback, is discarded.
+    pub fn call_async(&self, args: A) -> Result<(), CallbackError> {
+        let proc = self.proc.clone();
+        submit(Box::new(move |ruby| {
+            let _ = proc.get(ruby).get(ruby).call::<A, R>(args);
+        }))
+        .map_err(|_| CallbackError::ExecutorStopped)
+    }
+}
+
+impl<A, R> Clone for Callback<A, R> {
+    fn clone(&self) -> Self {
+        Self {
+            proc: self.proc.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A, R> fmt::Debug for Callback<A, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Callback").finish_non_exhaustive()
+    }
+}
+
+/// An error returned when calling a [`Callback`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallbackError {
+    /// The callback raised an exception, or otherwise errored. Contains the
+    /// error's message.
+    Raised(String),
+    /// The callback executor's Ruby thread is not running, usually because
+    /// the Ruby VM is shutting down.
+    ExecutorStopped,
+}
+
+impl From<Error> for CallbackError {
+    fn from(e: Error) -> Self {
+        Self::Raised(e.to_string())
+    }
+}
+
+impl fmt::Display for CallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Raised(m) => m.fmt(f),
+            Self::ExecutorStopped => write!(f, "callback executor is not running"),
+        }
+    }
+}
+
+impl StdError for CallbackError {}
@@ -0,0 +1,249 @@
+//! Conversions between Ruby CSV rows and the [`csv`](::csv) crate's records.
+//!
+//! [`StringRecord`] and [`ByteRecord`] convert to and from Ruby Arrays of
+//! Strings, the same as the rows yielded by Ruby's `CSV.parse` without
+//! headers. When converting from Ruby `nil` fields become empty, and other
+//! non-String fields are converted with `to_s`, matching `CSV.generate_line`.
+//! `CSV::Row` objects can also be converted to records, taking their fields.
+//!
+//! [`Ruby::csv_row_hash`], [`Ruby::csv_row_new`], and [`record_from_hash`]
+//! map between records and Ruby Hashes or `CSV::Row`s keyed by a header
+//! record.
+//!
+//! # Examples
+//!
+//! ```
+//! use csv::StringRecord;
+//! use magnus::{rb_assert, Error, Ruby};
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     let mut reader = csv::Reader::from_reader("name,age\nAlice,30\n".as_bytes());
+//!     let headers = reader.headers().unwrap().clone();
+//!     let records = reader
+//!         .records()
+//!         .collect::<Result<Vec<StringRecord>, _>>()
+//!         .unwrap();
+//!
+//!     let row = ruby.csv_row_hash(&headers, &records[0]);
+//!     rb_assert!(ruby, r#"row == {"name" => "Alice", "age" => "30"}"#, row);
+//!
+//!     let rows = ruby.ary_from_vec(records);
+//!     rb_assert!(ruby, r#"rows == [["Alice", "30"]]"#, rows);
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use ::csv::{ByteRecord, StringRecord};
+
+use crate::{
+    class::{Class, RClass},
+    error::Error,
+    into_value::{IntoValue, IntoValueFromNative},
+    module::Module,
+    r_array::RArray,
+    r_hash::RHash,
+    r_string::RString,
+    try_convert::TryConvert,
+    value::{LazyId, ReprValue, Value},
+    Ruby,
+};
+
+/// # CSV
+///
+/// Functions for converting [`csv`](::csv) crate records to Ruby CSV rows.
+///
+/// See also the [`csv`](self) module.
+impl Ruby {
+    /// Create a Ruby Hash mapping each header in `headers` to the
+    /// corresponding field in `record`.
+    ///
+    /// If `record` has fewer fields than `headers` the remaining headers map
+    /// to `nil`. Fields beyond the number of headers are ignored. If a header
+    /// is repeated the first occurrence is used, as with `CSV::Row#to_h`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use csv::StringRecord;
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let headers = StringRecord::from(vec!["a", "b", "c"]);
+    ///     let record = StringRecord::from(vec!["1", "2"]);
+    ///
+    ///     let row = ruby.csv_row_hash(&headers, &record);
+    ///     rb_assert!(ruby, r#"row == {"a" => "1", "b" => "2", "c" => nil}"#, row);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn csv_row_hash(&self, headers: &StringRecord, record: &StringRecord) -> RHash {
+        let hash = self.hash_new_capa(headers.len());
+        for (i, header) in headers.iter().enumerate() {
+            let key = self.str_new(header);
+            if hash.get(key).is_some() {
+                continue;
+            }
+            // can't fail for a String key
+            hash.aset(key, record.get(i)).unwrap();
+        }
+        hash
+    }
+
+    /// Create a Ruby `CSV::Row` from `headers` and `record`.
+    ///
+    /// This will `require "csv"` if it has not already been loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use csv::StringRecord;
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let headers = StringRecord::from(vec!["a", "b"]);
+    ///     let record = StringRecord::from(vec!["1", "2"]);
+    ///
+    ///     let row = ruby.csv_row_new(&headers, &record)?;
+    ///     rb_assert!(ruby, r#"row["b"] == "2""#, row);
+    ///     rb_assert!(ruby, r#"row.to_csv == "1,2\n""#, row);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn csv_row_new(
+        &self,
+        headers: &StringRecord,
+        record: &StringRecord,
+    ) -> Result<Value, Error> {
+        let class = csv_row_class(self)?;
+        class.new_instance((headers.clone(), record.clone()))
+    }
+}
+
+/// Create a [`StringRecord`] with the fields from `hash` in the order given
+/// by `headers`.
+///
+/// Each header is looked up first as a String key, then as a Symbol key.
+/// Headers missing from `hash` result in an empty field.
+///
+/// # Examples
+///
+/// ```
+/// use csv::StringRecord;
+/// use magnus::{csv::record_from_hash, Error, RHash, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let headers = StringRecord::from(vec!["a", "b", "c"]);
+///     let hash: RHash = ruby.eval(r#"{"b" => 2, a: "1"}"#)?;
+///
+///     let record = record_from_hash(hash, &headers)?;
+///     assert_eq!(record, StringRecord::from(vec!["1", "2", ""]));
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn record_from_hash(hash: RHash, headers: &StringRecord) -> Result<StringRecord, Error> {
+    let ruby = Ruby::get_with(hash);
+    let mut record = StringRecord::with_capacity(0, headers.len());
+    for header in headers.iter() {
+        let val = hash
+            .get(ruby.str_new(header))
+            .or_else(|| hash.get(ruby.to_symbol(header)));
+        match val {
+            Some(val) => record.push_field(&field_to_string(val)?),
+            None => record.push_field(""),
+        }
+    }
+    Ok(record)
+}
+
+fn csv_row_class(ruby: &Ruby) -> Result<RClass, Error> {
+    ruby.require("csv")?;
+    ruby.class_object()
+        .const_get::<_, RClass>("CSV")?
+        .const_get("Row")
+}
+
+// Returns the fields of a CSV::Row, or the value unchanged if it is not one.
+fn row_fields(val: Value) -> Result<Value, Error> {
+    static FIELDS: LazyId = LazyId::new("fields");
+
+    let ruby = Ruby::get_with(val);
+    let row_class = ruby
+        .class_object()
+        .const_get::<_, RClass>("CSV")
+        .and_then(|csv| csv.const_get::<_, RClass>("Row"));
+    match row_class {
+        Ok(class) if val.is_kind_of(class) => val.funcall(*FIELDS, ()),
+        _ => Ok(val),
+    }
+}
+
+fn field_to_string(val: Value) -> Result<String, Error> {
+    if val.is_nil() {
+        return Ok(String::new());
+    }
+    match RString::from_value(val) {
+        Some(s) => s.to_string(),
+        None => val.to_r_string()?.to_string(),
+    }
+}
+
+fn field_to_bytes(val: Value) -> Result<Vec<u8>, Error> {
+    if val.is_nil() {
+        return Ok(Vec::new());
+    }
+    let s = match RString::from_value(val) {
+        Some(s) => s,
+        None => val.to_r_string()?,
+    };
+    Ok(unsafe { s.as_slice() }.to_vec())
+}
+
+impl IntoValue for StringRecord {
+    #[inline]
+    fn into_value_with(self, ruby: &Ruby) -> Value {
+        ruby.ary_from_iter(self.iter().map(|field| ruby.str_new(field)))
+            .as_value()
+    }
+}
+
+unsafe impl IntoValueFromNative for StringRecord {}
+
+impl IntoValue for ByteRecord {
+    #[inline]
+    fn into_value_with(self, ruby: &Ruby) -> Value {
+        ruby.ary_from_iter(self.iter().map(|field| ruby.str_from_slice(field)))
+            .as_value()
+    }
+}
+
+unsafe impl IntoValueFromNative for ByteRecord {}
+
+impl TryConvert for StringRecord {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        let ary = RArray::try_convert(row_fields(val)?)?;
+        let mut record = StringRecord::with_capacity(0, ary.len());
+        for field in ary.to_vec::<Value>()? {
+            record.push_field(&field_to_string(field)?);
+        }
+        Ok(record)
+    }
+}
+
+impl TryConvert for ByteRecord {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        let ary = RArray::try_convert(row_fields(val)?)?;
+        let mut record = ByteRecord::with_capacity(0, ary.len());
+        for field in ary.to_vec::<Value>()? {
+            record.push_field(&field_to_bytes(field)?);
+        }
+        Ok(record)
+    }
+}
@@ -5,6 +5,7 @@
 use std::{
     ffi::CString,
     ops::Deref,
+    path::{Path, PathBuf},
     sync::atomic::{AtomicBool, Ordering},
 };
 
@@ -17,11 +18,16 @@ use rb_sys::{
 
 use crate::{
     error::{protect, Error},
-    r_string::IntoRString,
-    value::private::ReprValue,
+    module::Module,
+    r_string::{IntoRString, RString},
+    try_convert::TryConvert,
+    value::{private::ReprValue as _, LazyId, ReprValue, Value},
     Ruby,
 };
 
+pub mod repl;
+pub mod sandbox;
+
 /// A guard value that will run the cleanup function for the Ruby VM when
 /// dropped.
 ///
@@ -152,6 +158,15 @@ unsafe fn init_options(opts: &[&str]) {
     };
 }
 
+/// Ruby code to be run with [`Ruby::eval_script`].
+#[derive(Clone, Copy, Debug)]
+pub enum Script<'a> {
+    /// Ruby source code.
+    Code(&'a str),
+    /// The path to a file of Ruby source code.
+    File(&'a Path),
+}
+
 /// # Embedding
 ///
 /// Functions relevant when embedding Ruby in Rust.
@@ -194,6 +209,108 @@ impl Ruby {
         func(unsafe { &init() }).map_err(|e| e.to_string())
     }
 
+    /// Run a Ruby script, converting the value of its final expression to
+    /// `T`.
+    ///
+    /// The script is evaluated at the top level, as if run with `ruby`, so
+    /// may define methods, classes, and constants, and top level local
+    /// variables are shared between scripts.
+    ///
+    /// Errors raised by the script are returned as exceptions including the
+    /// full Ruby backtrace, which can be displayed by formatting the
+    /// exception with `{:#?}`. Code from a [`Script::File`] is reported with
+    /// its path in backtraces, and code from a [`Script::Code`] as
+    /// `(script)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{embed::Script, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let res: Vec<i64> = ruby.eval_script(Script::Code(
+    ///         r#"
+    ///             def double(x)
+    ///               x * 2
+    ///             end
+    ///
+    ///             [1, 2, 3].map { |x| double(x) }
+    ///         "#,
+    ///     ))?;
+    ///     assert_eq!(res, [2, 4, 6]);
+    ///
+    ///     let err = ruby
+    ///         .eval_script::<Vec<i64>>(Script::Code("raise 'bang'"))
+    ///         .unwrap_err();
+    ///     if let magnus::error::ErrorType::Exception(ex) = err.error_type() {
+    ///         assert!(format!("{:#?}", ex).contains("(script):1"));
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn eval_script<T>(&self, script: Script) -> Result<T, Error>
+    where
+        T: TryConvert,
+    {
+        static EVAL: LazyId = LazyId::new("eval");
+        static READ: LazyId = LazyId::new("read");
+
+        let (code, file) = match script {
+            Script::Code(code) => (self.str_new(code), self.str_new("(script)")),
+            Script::File(path) => {
+                let file = self.str_new(&path.to_string_lossy());
+                let code: RString = self.class_file().funcall(*READ, (file,))?;
+                (code, file)
+            }
+        };
+        let binding: Value = self.class_object().const_get("TOPLEVEL_BINDING")?;
+        binding.funcall(*EVAL, (code, file, 1))
+    }
+
+    /// Load a Ruby fixture file, resolving relative paths from the root of
+    /// the crate being tested.
+    ///
+    /// The crate root is taken from the `CARGO_MANIFEST_DIR` environment
+    /// variable, set by `cargo test`, falling back to the current directory.
+    ///
+    /// Files are loaded with `require` semantics, so each file is only run
+    /// once per process no matter how many tests load it. Returns `true` if
+    /// the file was run, or `false` if it had already been loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert!(ruby.load_fixture("tests/fixtures/point.rb")?);
+    ///     assert!(!ruby.load_fixture("tests/fixtures/point.rb")?);
+    ///
+    ///     rb_assert!(ruby, "FixturePoint.new(1, 2).x == 1");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn load_fixture<P>(&self, path: P) -> Result<bool, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::var_os("CARGO_MANIFEST_DIR")
+                .map(PathBuf::from)
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_default()
+                .join(path)
+        };
+        self.require(path.to_string_lossy().as_ref())
+    }
+
     /// Sets the current script name.
     pub fn script<T>(&self, name: T)
     where
@@ -0,0 +1,142 @@
+//! Utilities for building an interactive Ruby console.
+//!
+//! [`Repl`] evaluates lines of Ruby code against a persistent binding, so
+//! local variables defined by one line are available to the next, much like
+//! `irb` or `rails console`.
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{embed::repl::Repl, Error, Ruby};
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     let mut repl = Repl::new(ruby)?;
+//!
+//!     let mut buffer = String::new();
+//!     for line in ["a = [1, 2,", "3]", "a.sum"] {
+//!         buffer.push_str(line);
+//!         buffer.push('\n');
+//!         if !repl.is_complete(&buffer)? {
+//!             continue;
+//!         }
+//!         let output = repl.eval_inspect(&buffer)?;
+//!         buffer.clear();
+//!         println!("=> {}", output);
+//!     }
+//!     assert_eq!(repl.eval_inspect("a")?, "[1, 2, 3]");
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use crate::{
+    error::Error,
+    module::Module,
+    value::{BoxValue, LazyId, ReprValue, Value},
+    RClass, Ruby,
+};
+
+/// An evaluation context for a Ruby console.
+///
+/// See the [module level documentation](self) for more details.
+pub struct Repl {
+    binding: BoxValue<Value>,
+    file: String,
+    line: usize,
+}
+
+impl Repl {
+    /// Create a new `Repl` with a fresh top level binding.
+    ///
+    /// Code is reported as being from the file `(repl)` in backtraces, see
+    /// [`Repl::with_file`] to change this.
+    pub fn new(ruby: &Ruby) -> Result<Self, Error> {
+        Self::with_file(ruby, "(repl)")
+    }
+
+    /// Create a new `Repl` with a fresh top level binding, reporting code as
+    /// being from `file` in backtraces.
+    pub fn with_file(ruby: &Ruby, file: &str) -> Result<Self, Error> {
+        let binding: Value = ruby.eval("binding")?;
+        Ok(Self {
+            binding: BoxValue::new(binding),
+            file: file.to_owned(),
+            line: 1,
+        })
+    }
+
+    /// Returns the binding code is evaluated against.
+    pub fn binding(&self) -> Value {
+        *self.binding
+    }
+
+    /// Returns the line number the next evaluated code will start at.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Evaluate `code` against the binding, returning the result.
+    ///
+    /// Local variables assigned in `code` are available to following calls.
+    ///
+    /// The line number used for backtraces is advanced by the number of
+    /// lines in `code`, whether or not evaluation succeeds.
+    pub fn eval(&mut self, code: &str) -> Result<Value, Error> {
+        static EVAL: LazyId = LazyId::new("eval");
+
+        let line = self.line;
+        self.line += code.lines().count().max(1);
+        self.binding
+            .funcall(*EVAL, (code, self.file.as_str(), line))
+    }
+
+    /// Evaluate `code` against the binding, returning the result formatted
+    /// with Ruby's `#inspect`.
+    pub fn eval_inspect(&mut self, code: &str) -> Result<String, Error> {
+        self.eval(code).map(|val| val.inspect())
+    }
+
+    /// Check if `code` is a complete Ruby expression, or if more input is
+    /// required.
+    ///
+    /// Returns `Ok(false)` if `code` ends part way through an expression, for
+    /// example with an unclosed `do`/`end` block, bracket, or string. Returns
+    /// `Ok(true)` for complete code, and for code with syntax errors that
+    /// further input could not fix, so that the error can be reported by
+    /// [`Repl::eval`].
+    ///
+    /// This uses `RubyVM::InstructionSequence` to compile, but not run,
+    /// `code`, and so is only supported on CRuby.
+    pub fn is_complete(&self, code: &str) -> Result<bool, Error> {
+        static COMPILE: LazyId = LazyId::new("compile");
+
+        let ruby = Ruby::get_with(*self.binding);
+        let iseq = ruby
+            .class_object()
+            .const_get::<_, RClass>("RubyVM")?
+            .const_get::<_, Value>("InstructionSequence")?;
+        let err = match iseq.funcall::<_, _, Value>(*COMPILE, (code,)) {
+            Ok(_) => return Ok(true),
+            Err(e) => e,
+        };
+        if !err.is_kind_of(ruby.exception_syntax_error()) {
+            return Err(err);
+        }
+        let message = err.to_string();
+        Ok(!INCOMPLETE_MESSAGES.iter().any(|m| message.contains(m)))
+    }
+}
+
+// Fragments of the syntax error messages produced by the parse.y and prism
+// parsers when the input ends before an expression is complete.
+const INCOMPLETE_MESSAGES: &[&str] = &[
+    "unexpected end-of-input",
+    "unexpected end of file",
+    "unterminated",
+    "expected a matching",
+    "expected an `end`",
+    "expected a `}`",
+    "expected a `]`",
+    "expected a `)`",
+];
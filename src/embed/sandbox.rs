@@ -0,0 +1,166 @@
+//! Evaluate Ruby code with restricted access to builtins.
+//!
+//! [`Sandbox`] evaluates code within a 'cleanroom' module, where selected
+//! constants (such as `File` and `IO`) and methods (such as `system` and
+//! `require`) are replaced with stand-ins that raise `SecurityError` when
+//! used.
+//!
+//! **This is not a security boundary.** Ruby provides many ways to reach the
+//! original constants and methods (for example `::File`, `Kernel.system`, or
+//! `Object.const_get`), so a sandbox can only guard against accidental use of
+//! dangerous functionality in lightly-trusted code, such as simple
+//! expressions entered by users of an application. Untrusted code should be
+//! run in a separate, isolated, process.
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{embed::sandbox::Sandbox, Error, Ruby};
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     let sandbox = Sandbox::new(ruby)?;
+//!     sandbox.set_const("PRICE", 10)?;
+//!
+//!     let total: i64 = sandbox.eval("PRICE * 3")?;
+//!     assert_eq!(total, 30);
+//!
+//!     let err = sandbox.eval::<String>("File.read('/etc/passwd')").unwrap_err();
+//!     assert!(err.is_kind_of(ruby.exception_security_error()));
+//!
+//!     let err = sandbox.eval::<bool>("system('ls')").unwrap_err();
+//!     assert!(err.is_kind_of(ruby.exception_security_error()));
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use crate::{
+    class::Class,
+    error::Error,
+    into_value::IntoValue,
+    module::{Module, RModule},
+    try_convert::TryConvert,
+    value::{BoxValue, LazyId, ReprValue, Value},
+    Ruby,
+};
+
+/// Constants blocked by [`Sandbox::new`].
+pub const DEFAULT_BLOCKED_CONSTS: &[&str] = &[
+    "Dir",
+    "File",
+    "FileTest",
+    "IO",
+    "ObjectSpace",
+    "Process",
+    "Signal",
+    "Thread",
+];
+
+/// Methods blocked by [`Sandbox::new`].
+pub const DEFAULT_BLOCKED_METHODS: &[&str] = &[
+    "`",
+    "abort",
+    "autoload",
+    "binding",
+    "eval",
+    "exec",
+    "exit",
+    "exit!",
+    "fork",
+    "load",
+    "open",
+    "require",
+    "require_relative",
+    "spawn",
+    "syscall",
+    "system",
+    "trap",
+];
+
+/// A cleanroom for evaluating lightly-trusted Ruby code.
+///
+/// See the [module level documentation](self) for more details.
+pub struct Sandbox {
+    module: BoxValue<RModule>,
+}
+
+impl Sandbox {
+    /// Create a new sandbox with [`DEFAULT_BLOCKED_CONSTS`] and
+    /// [`DEFAULT_BLOCKED_METHODS`] blocked.
+    pub fn new(ruby: &Ruby) -> Result<Self, Error> {
+        let sandbox = Self::empty(ruby);
+        for name in DEFAULT_BLOCKED_CONSTS {
+            sandbox.block_const(name)?;
+        }
+        for name in DEFAULT_BLOCKED_METHODS {
+            sandbox.block_method(name)?;
+        }
+        Ok(sandbox)
+    }
+
+    /// Create a new sandbox with nothing blocked.
+    pub fn empty(ruby: &Ruby) -> Self {
+        Self {
+            module: BoxValue::new(ruby.module_new()),
+        }
+    }
+
+    /// Returns the cleanroom module code is evaluated within.
+    pub fn module(&self) -> RModule {
+        *self.module
+    }
+
+    /// Replace the constant `name` with an object that raises
+    /// `SecurityError` when any method is called on it.
+    pub fn block_const(&self, name: &str) -> Result<(), Error> {
+        let ruby = Ruby::get_with(*self.module);
+        let blocker = ruby.class_object().new_instance(())?;
+        let message = format!("{} is not available in the sandbox", name);
+        define_singleton_method_raising(&ruby, blocker, "method_missing".to_owned(), message)?;
+        self.module.const_set(name, blocker)
+    }
+
+    /// Replace the method `name` with one that raises `SecurityError` when
+    /// called without an explicit receiver.
+    pub fn block_method(&self, name: &str) -> Result<(), Error> {
+        let ruby = Ruby::get_with(*self.module);
+        let message = format!("`{}' is not available in the sandbox", name);
+        define_singleton_method_raising(&ruby, self.module.as_value(), name.to_owned(), message)
+    }
+
+    /// Set the constant `name` within the sandbox to `value`, replacing any
+    /// existing constant of the same name visible to evaluated code.
+    pub fn set_const<T>(&self, name: &str, value: T) -> Result<(), Error>
+    where
+        T: IntoValue,
+    {
+        self.module.const_set(name, value)
+    }
+
+    /// Evaluate `code` within the sandbox, converting the result to `T`.
+    pub fn eval<T>(&self, code: &str) -> Result<T, Error>
+    where
+        T: TryConvert,
+    {
+        static MODULE_EVAL: LazyId = LazyId::new("module_eval");
+
+        self.module.funcall(*MODULE_EVAL, (code, "(sandbox)", 1))
+    }
+}
+
+fn define_singleton_method_raising(
+    ruby: &Ruby,
+    obj: Value,
+    name: String,
+    message: String,
+) -> Result<(), Error> {
+    static DEFINE_SINGLETON_METHOD: LazyId = LazyId::new("define_singleton_method");
+
+    let block = ruby.proc_from_fn(move |ruby, _args, _block| {
+        Err::<Value, _>(Error::new(ruby.exception_security_error(), message.clone()))
+    });
+    let name = ruby.to_symbol(name);
+    let _: Value = obj.funcall_with_block(*DEFINE_SINGLETON_METHOD, (name,), block)?;
+    Ok(())
+}
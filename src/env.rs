@@ -0,0 +1,214 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crate::{
+    error::Error,
+    into_value::IntoValue,
+    module::Module,
+    object::Object,
+    r_string::RString,
+    value::{
+        private::{self, ReprValue as _},
+        LazyId, NonZeroValue, ReprValue, Value,
+    },
+    Ruby,
+};
+
+static AREF: LazyId = LazyId::new("[]");
+static ASET: LazyId = LazyId::new("[]=");
+static DELETE: LazyId = LazyId::new("delete");
+static KEY_P: LazyId = LazyId::new("key?");
+static TO_H: LazyId = LazyId::new("to_h");
+
+/// # `ENV`
+///
+/// Functions for accessing Ruby's `ENV`.
+///
+/// See also the [`Env`] type.
+impl Ruby {
+    /// Return Ruby's `ENV` object.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `ENV` constant has been removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let env = ruby.env();
+    ///     env.set("MAGNUS_EXAMPLE", Some("1"))?;
+    ///     rb_assert!(ruby, r#"ENV["MAGNUS_EXAMPLE"] == "1""#);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn env(&self) -> Env {
+        let env: Value = self.class_object().const_get("ENV").unwrap();
+        // ENV is an Object, so never a zero value such as false
+        Env(unsafe { NonZeroValue::new_unchecked(env) })
+    }
+}
+
+/// A wrapper around Ruby's `ENV` object.
+///
+/// Reading and writing the environment through `Env`, rather than
+/// [`std::env`], ensures Rust and Ruby code agree on the environment. Ruby
+/// may keep its own copy of the environment on some platforms, and on others
+/// `ENV` may be replaced with a different object (for example in tests).
+///
+/// See the [`ReprValue`] and [`Object`] traits for additional methods
+/// available on this type. See [`Ruby::env`] to get an `Env`.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct Env(NonZeroValue);
+
+impl Env {
+    /// Returns the value of the environment variable `name`, or `None` if it
+    /// is not set.
+    ///
+    /// Returns `Err` if the value can not be converted to UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let _: bool = ruby.eval(r#"ENV["MAGNUS_GET"] = "hello"; true"#)?;
+    ///
+    ///     assert_eq!(ruby.env().get("MAGNUS_GET")?, Some(String::from("hello")));
+    ///     assert_eq!(ruby.env().get("MAGNUS_UNSET")?, None);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn get(self, name: &str) -> Result<Option<String>, Error> {
+        self.get_r_string(name)?.map(RString::to_string).transpose()
+    }
+
+    /// Returns the value of the environment variable `name` as a Ruby
+    /// string, or `None` if it is not set.
+    ///
+    /// The returned string is frozen, as with `ENV[name]` in Ruby, so must
+    /// be duplicated before modifying.
+    pub fn get_r_string(self, name: &str) -> Result<Option<RString>, Error> {
+        self.funcall(*AREF, (name,))
+    }
+
+    /// Returns the value of the environment variable `name` parsed as `T`,
+    /// or `None` if it is not set.
+    ///
+    /// Returns `Err` if the value can not be parsed as `T`, or is not valid
+    /// UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let env = ruby.env();
+    ///     env.set("MAGNUS_THREADS", Some("4"))?;
+    ///     assert_eq!(env.get_as::<usize>("MAGNUS_THREADS")?, Some(4));
+    ///
+    ///     env.set("MAGNUS_THREADS", Some("many"))?;
+    ///     assert!(env.get_as::<usize>("MAGNUS_THREADS").is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn get_as<T>(self, name: &str) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = match self.get(name)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        value.trim().parse().map(Some).map_err(|e| {
+            Error::new(
+                Ruby::get_with(self).exception_arg_error(),
+                format!("invalid value {:?} for ENV[{:?}]: {}", value, name, e),
+            )
+        })
+    }
+
+    /// Returns the value of the environment variable `name` interpreted as
+    /// a boolean flag, or `None` if it is not set.
+    ///
+    /// `1`, `true`, `yes`, and `on` are `true`; `0`, `false`, `no`, `off`,
+    /// and the empty string are `false`, all ignoring case. Any other value
+    /// returns `Err`.
+    pub fn get_flag(self, name: &str) -> Result<Option<bool>, Error> {
+        let value = match self.get(name)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(Some(true)),
+            "0" | "false" | "no" | "off" | "" => Ok(Some(false)),
+            _ => Err(Error::new(
+                Ruby::get_with(self).exception_arg_error(),
+                format!(
+                    "invalid value {:?} for ENV[{:?}]: expected a flag",
+                    value, name
+                ),
+            )),
+        }
+    }
+
+    /// Set the environment variable `name` to `value`, or remove it if
+    /// `value` is `None`.
+    pub fn set(self, name: &str, value: Option<&str>) -> Result<(), Error> {
+        let _: Value = self.funcall(*ASET, (name, value))?;
+        Ok(())
+    }
+
+    /// Remove the environment variable `name`, returning its previous value.
+    pub fn remove(self, name: &str) -> Result<Option<String>, Error> {
+        self.funcall::<_, _, Option<RString>>(*DELETE, (name,))?
+            .map(RString::to_string)
+            .transpose()
+    }
+
+    /// Returns whether the environment variable `name` is set.
+    pub fn contains(self, name: &str) -> Result<bool, Error> {
+        self.funcall(*KEY_P, (name,))
+    }
+
+    /// Returns all environment variables as a `HashMap`.
+    pub fn to_hash_map(self) -> Result<HashMap<String, String>, Error> {
+        self.funcall(*TO_H, ())
+    }
+}
+
+impl fmt::Display for Env {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", unsafe { self.to_s_infallible() })
+    }
+}
+
+impl fmt::Debug for Env {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.inspect())
+    }
+}
+
+impl IntoValue for Env {
+    #[inline]
+    fn into_value_with(self, _: &Ruby) -> Value {
+        self.0.get()
+    }
+}
+
+impl Object for Env {}
+
+unsafe impl private::ReprValue for Env {}
+
+impl ReprValue for Env {}
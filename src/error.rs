@@ -5,8 +5,9 @@
 use std::{any::Any, borrow::Cow, ffi::CString, fmt, mem::transmute, os::raw::c_int};
 
 use rb_sys::{
-    rb_bug, rb_ensure, rb_errinfo, rb_exc_raise, rb_iter_break_value, rb_jump_tag, rb_protect,
-    rb_set_errinfo, rb_warning, ruby_special_consts, VALUE,
+    rb_bug, rb_ensure, rb_errinfo, rb_exc_raise, rb_iter_break_value, rb_ivar_get, rb_ivar_set,
+    rb_jump_tag, rb_make_backtrace, rb_protect, rb_set_errinfo, rb_warning, ruby_special_consts,
+    VALUE,
 };
 
 use crate::{
@@ -14,7 +15,9 @@ use crate::{
     exception::Exception,
     into_value::IntoValue,
     module::Module,
-    value::{private::ReprValue as _, ReprValue, Value},
+    r_array::RArray,
+    try_convert::TryConvert,
+    value::{private::ReprValue as _, IntoId, LazyId, ReprValue, Value},
     ExceptionClass, Ruby,
 };
 
@@ -93,6 +96,135 @@ impl Ruby {
 /// Shorthand for `std::result::Result<T, magnus::Error>`.
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Create an [`Error`] with a message built from a format string.
+///
+/// The first argument is the [`ExceptionClass`] of the error, the remaining
+/// arguments are as for [`format!`].
+///
+/// See also [`bail!`](macro@crate::bail).
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{error, Error, Ruby};
+///
+/// fn check_size(ruby: &Ruby, n: usize) -> Result<usize, Error> {
+///     if n > 10 {
+///         return Err(error!(ruby.exception_arg_error(), "bad size {n}"));
+///     }
+///     Ok(n)
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let err = check_size(ruby, 11).unwrap_err();
+///     assert!(err.is_kind_of(ruby.exception_arg_error()));
+///     assert_eq!(err.to_string(), "ArgumentError: bad size 11");
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[macro_export]
+macro_rules! error {
+    ($class:expr, $($arg:tt)+) => {
+        $crate::Error::new($class, ::std::format!($($arg)+))
+    };
+}
+
+/// Return early with an [`Error`] with a message built from a format string.
+///
+/// When used in a function exposed to Ruby this is the equivalent of Ruby's
+/// `raise`, the error will be raised as an exception once returned to Ruby.
+///
+/// The first argument is the [`ExceptionClass`] of the error, the remaining
+/// arguments are as for [`format!`]. The error is converted with [`Into`],
+/// so this can be used in functions returning any error type that
+/// implements `From<Error>`.
+///
+/// See also [`error!`](macro@crate::error) and [`raise!`](macro@crate::raise).
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{bail, function, rb_assert, Error, Ruby};
+///
+/// fn repeat(ruby: &Ruby, s: String, n: i64) -> Result<String, Error> {
+///     if n < 0 {
+///         bail!(ruby.exception_arg_error(), "negative count: {}", n);
+///     }
+///     Ok(s.repeat(n as usize))
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.define_global_function("repeat", function!(repeat, 2));
+///
+///     rb_assert!(ruby, r#"repeat("ab", 2) == "abab""#);
+///     rb_assert!(
+///         ruby,
+///         r#"(repeat("ab", -1) rescue $!.message) == "negative count: -1""#,
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($class:expr, $($arg:tt)+) => {
+        return ::std::result::Result::Err($crate::error!($class, $($arg)+).into())
+    };
+}
+
+/// Immediately raise a Ruby exception with a message built from a format
+/// string.
+///
+/// The first argument is the [`ExceptionClass`] of the exception, the
+/// remaining arguments are as for [`format!`].
+///
+/// Returning an [`Error`], for example with [`bail!`](macro@crate::bail), is
+/// almost always preferable. This macro is for the rare function exposed to
+/// Ruby that can't return a `Result`.
+///
+/// # Safety
+///
+/// The exception is raised with a `longjmp` back to Ruby, skipping over the
+/// Rust stack frames between Ruby and the call to `raise!`. Values in those
+/// frames will not be dropped, so none of them should own resources such as
+/// heap memory or locks. This macro must not be used from a thread without
+/// the GVL, or outside of a function called by Ruby.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{function, raise, rb_assert, Error, Ruby};
+///
+/// fn checked_size(ruby: &Ruby, n: i64) -> i64 {
+///     if n < 0 {
+///         unsafe { raise!(ruby.exception_arg_error(), "bad size {}", n) }
+///     }
+///     n
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.define_global_function("checked_size", function!(checked_size, 1));
+///
+///     rb_assert!(ruby, "checked_size(3) == 3");
+///     rb_assert!(
+///         ruby,
+///         r#"(checked_size(-1) rescue $!.message) == "bad size -1""#,
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[macro_export]
+macro_rules! raise {
+    ($class:expr, $($arg:tt)+) => {
+        $crate::error::raise_error($crate::error!($class, $($arg)+))
+    };
+}
+
 /// The possible types of [`Error`].
 #[derive(Debug, Clone)]
 pub enum ErrorType {
@@ -265,6 +397,254 @@ impl Error {
         }
     }
 
+    /// Attach `value` to the exception for `self` as the instance variable
+    /// `name`.
+    ///
+    /// This allows structured data, such as an error code, to be carried by
+    /// an error, rather than being encoded in the error message. Once raised
+    /// in Ruby the data can be read with `Exception#instance_variable_get`,
+    /// or an `attr_reader` on the exception class. Use [`Error::ivar_get`] to
+    /// read the data back from an `Error`.
+    ///
+    /// Note, the `@` is part of the name.
+    ///
+    /// If `self` is an interrupt such as `break` or `throw` it is returned
+    /// unchanged. If setting the instance variable fails the resulting error
+    /// is returned in place of `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{function, rb_assert, Error, Ruby};
+    ///
+    /// fn fetch(ruby: &Ruby) -> Result<(), Error> {
+    ///     Err(Error::new(ruby.exception_io_error(), "timed out")
+    ///         .with_ivar("@code", 504)
+    ///         .with_ivar("@retryable", true))
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.define_global_function("fetch", function!(fetch, 0));
+    ///
+    ///     rb_assert!(
+    ///         ruby,
+    ///         r#"
+    ///             begin
+    ///               fetch
+    ///             rescue IOError => e
+    ///               e.instance_variable_get(:@code) == 504
+    ///             end
+    ///         "#,
+    ///     );
+    ///
+    ///     let err = fetch(ruby).unwrap_err();
+    ///     assert_eq!(err.ivar_get::<_, i64>("@code")?, 504);
+    ///     assert!(err.ivar_get::<_, bool>("@retryable")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn with_ivar<T, U>(self, name: T, value: U) -> Self
+    where
+        T: IntoId,
+        U: IntoValue,
+    {
+        if let ErrorType::Jump(_) = self.0 {
+            return self;
+        }
+        let exception = self.exception();
+        let handle = Ruby::get_with(exception);
+        let id = name.into_id_with(&handle);
+        let value = value.into_value_with(&handle);
+        let res = protect(|| unsafe {
+            Value::new(rb_ivar_set(
+                exception.as_rb_value(),
+                id.as_rb_id(),
+                value.as_rb_value(),
+            ))
+        });
+        match res {
+            Ok(_) => exception.into(),
+            Err(e) => e,
+        }
+    }
+
+    /// Set the backtrace of the exception for `self` to the location this
+    /// method was called from in Rust, followed by the current Ruby
+    /// backtrace.
+    ///
+    /// Errors created in Rust otherwise only get a backtrace once raised in
+    /// Ruby, which will point to the Ruby code calling the Rust function, but
+    /// not where in the Rust code the error originated.
+    ///
+    /// If `self` is an interrupt such as `break` or `throw` it is returned
+    /// unchanged. If `self` is an exception that already has a backtrace the
+    /// Rust location is prepended to the existing backtrace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{function, rb_assert, Error, Ruby};
+    ///
+    /// fn broken(ruby: &Ruby) -> Result<(), Error> {
+    ///     Err(Error::new(ruby.exception_runtime_error(), "broken").with_backtrace())
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.define_global_function("broken", function!(broken, 0));
+    ///
+    ///     rb_assert!(
+    ///         ruby,
+    ///         r#"
+    ///             begin
+    ///               broken
+    ///             rescue => e
+    ///               e.backtrace.first.include?(".rs:")
+    ///             end
+    ///         "#,
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[track_caller]
+    pub fn with_backtrace(self) -> Self {
+        if let ErrorType::Jump(_) = self.0 {
+            return self;
+        }
+        let location = std::panic::Location::caller();
+        let exception = self.exception();
+        let handle = Ruby::get_with(exception);
+        let res = (|| {
+            let backtrace = match exception.funcall::<_, _, Option<RArray>>("backtrace", ())? {
+                Some(backtrace) => backtrace,
+                None => unsafe { RArray::from_rb_value_unchecked(rb_make_backtrace()) },
+            };
+            backtrace.unshift(handle.str_new(&format!(
+                "{}:{}:in `<rust>'",
+                location.file(),
+                location.line()
+            )))?;
+            exception.funcall::<_, _, Value>("set_backtrace", (backtrace,))
+        })();
+        match res {
+            Ok(_) => exception.into(),
+            Err(e) => e,
+        }
+    }
+
+    /// Create a new `Error` from a Rust [`std::error::Error`], preserving its
+    /// [`source`](std::error::Error::source) chain.
+    ///
+    /// `err` and each of its sources are converted to an instance of `class`
+    /// with the error's message, and each exception's `cause` is set to the
+    /// exception for the following source, so that the chain is available in
+    /// Ruby via `Exception#cause`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::{fmt, num::ParseIntError};
+    ///
+    /// use magnus::{function, rb_assert, Error, Ruby};
+    ///
+    /// #[derive(Debug)]
+    /// struct ConfigError(ParseIntError);
+    ///
+    /// impl fmt::Display for ConfigError {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         write!(f, "invalid config")
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for ConfigError {
+    ///     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    ///         Some(&self.0)
+    ///     }
+    /// }
+    ///
+    /// fn load(ruby: &Ruby, s: String) -> Result<i64, Error> {
+    ///     s.parse()
+    ///         .map_err(|e| Error::from_std_error(ruby.exception_arg_error(), &ConfigError(e)))
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.define_global_function("load", function!(load, 1));
+    ///
+    ///     rb_assert!(
+    ///         ruby,
+    ///         r#"
+    ///             begin
+    ///               load("x")
+    ///             rescue => e
+    ///               e.message == "invalid config" &&
+    ///                 e.cause.message == "invalid digit found in string" &&
+    ///                 e.cause.cause.nil?
+    ///             end
+    ///         "#,
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn from_std_error<E>(class: ExceptionClass, err: &E) -> Self
+    where
+        E: std::error::Error + ?Sized,
+    {
+        static CAUSE: LazyId = LazyId::new("cause");
+
+        let mut messages = vec![err.to_string()];
+        let mut source = err.source();
+        while let Some(e) = source {
+            messages.push(e.to_string());
+            source = e.source();
+        }
+        let mut cause = None;
+        for msg in messages.into_iter().rev() {
+            let exception = Error::new(class, msg).exception();
+            if let Some(cause) = cause {
+                // `cause` is a hidden instance variable (no `@` prefix) that
+                // Ruby won't overwrite if it is already set when raised
+                unsafe {
+                    rb_ivar_set(
+                        exception.as_rb_value(),
+                        CAUSE.as_rb_id(),
+                        cause.as_rb_value(),
+                    )
+                };
+            }
+            cause = Some(exception);
+        }
+        // there's always at least one message
+        cause.unwrap().into()
+    }
+
+    /// Get the value of the instance variable `name` from the exception for
+    /// `self`.
+    ///
+    /// Errors that do not yet have an exception instance, and interrupts such
+    /// as `break` or `throw`, are treated as having no instance variables
+    /// and will attempt to convert `nil` to `U`.
+    ///
+    /// See [`Error::with_ivar`] for an example.
+    pub fn ivar_get<T, U>(&self, name: T) -> Result<U, Error>
+    where
+        T: IntoId,
+        U: TryConvert,
+    {
+        match self.0 {
+            ErrorType::Exception(e) => {
+                let id = name.into_id_with(&Ruby::get_with(e));
+                protect(|| unsafe { Value::new(rb_ivar_get(e.as_rb_value(), id.as_rb_id())) })
+                    .and_then(TryConvert::try_convert)
+            }
+            _ => U::try_convert(unsafe { Ruby::get_unchecked() }.qnil().as_value()),
+        }
+    }
+
     /// Returns the [`ErrorType`] for self.
     pub fn error_type(&self) -> &ErrorType {
         &self.0
@@ -536,6 +916,11 @@ where
     unsafe { T::from_value_unchecked(Value::new(result)) }
 }
 
+#[doc(hidden)]
+pub unsafe fn raise_error(e: Error) -> ! {
+    raise(e)
+}
+
 pub(crate) fn raise(e: Error) -> ! {
     match e.0 {
         ErrorType::Jump(tag) => tag.resume(),
@@ -332,6 +332,13 @@ impl Fiber {
 
     /// Resume the execution of `self`.
     ///
+    /// Returns the values passed to [`Ruby::fiber_yield`] by `self`, or the
+    /// return value of the Fiber's function if it finishes.
+    ///
+    /// If the Fiber's function returns an error or panics the error is
+    /// returned here, as a Ruby exception, and the Fiber is dead. Resuming a
+    /// dead Fiber returns a `FiberError`.
+    ///
     /// # Examples
     ///
     /// ```
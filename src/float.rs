@@ -12,6 +12,7 @@ use crate::{
     into_value::IntoValue,
     numeric::Numeric,
     r_rational::RRational,
+    r_string::RString,
     try_convert::TryConvert,
     value::{
         private::{self, ReprValue as _},
@@ -58,6 +59,91 @@ impl Ruby {
             Float::from_rb_value_unchecked(val)
         }
     }
+
+    /// Format an `f64` as a Ruby string, exactly as Ruby's `Float#to_s` would.
+    ///
+    /// This uses the shortest representation that will round-trip back to
+    /// the same `f64`, switching to scientific notation for very large or
+    /// very small values, following the same rules as Ruby.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert_eq!(ruby.float_to_r_string(1.5).to_string()?, "1.5");
+    ///     assert_eq!(ruby.float_to_r_string(100.0).to_string()?, "100.0");
+    ///     assert_eq!(ruby.float_to_r_string(1e16).to_string()?, "1.0e+16");
+    ///     assert_eq!(ruby.float_to_r_string(0.00001).to_string()?, "1.0e-05");
+    ///     assert_eq!(ruby.float_to_r_string(f64::NAN).to_string()?, "NaN");
+    ///
+    ///     for f in [0.1, -0.0, 1e15, 123456.789, 1e-4, 2.5e-300, f64::MAX] {
+    ///         let expected: String = ruby.float_from_f64(f).funcall("to_s", ())?;
+    ///         assert_eq!(ruby.float_to_r_string(f).to_string()?, expected);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn float_to_r_string(&self, n: f64) -> RString {
+        self.str_new(&float_to_s(n))
+    }
+}
+
+/// Rust implementation of Ruby's `flo_to_s`.
+fn float_to_s(n: f64) -> String {
+    if n.is_nan() {
+        return String::from("NaN");
+    }
+    if n.is_infinite() {
+        return String::from(if n < 0.0 { "-Infinity" } else { "Infinity" });
+    }
+
+    let mut res = String::new();
+    if n.is_sign_negative() {
+        res.push('-');
+    }
+    if n == 0.0 {
+        res.push_str("0.0");
+        return res;
+    }
+
+    // Rust's `{:e}` formatting produces the shortest round-trip digits, e.g.
+    // `1.2345e6`, split this in to the digits and the decimal exponent
+    let sci = format!("{:e}", n.abs());
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    // position of the decimal point relative to the start of `digits`
+    let decpt = exp.parse::<i32>().unwrap() + 1;
+
+    if decpt > 0 && decpt <= f64::DIGITS as i32 + 1 {
+        let decpt = decpt as usize;
+        if digits.len() <= decpt {
+            res.push_str(&digits);
+            res.extend(std::iter::repeat('0').take(decpt - digits.len()));
+            res.push_str(".0");
+        } else {
+            res.push_str(&digits[..decpt]);
+            res.push('.');
+            res.push_str(&digits[decpt..]);
+        }
+    } else if decpt <= 0 && decpt > -4 {
+        res.push_str("0.");
+        res.extend(std::iter::repeat('0').take(-decpt as usize));
+        res.push_str(&digits);
+    } else {
+        res.push_str(&digits[..1]);
+        res.push('.');
+        if digits.len() > 1 {
+            res.push_str(&digits[1..]);
+        } else {
+            res.push('0');
+        }
+        res.push_str(&format!("e{:+03}", decpt - 1));
+    }
+    res
 }
 
 /// A type wrapping either a [`Flonum`](crate::value::Flonum) or an
@@ -2,19 +2,28 @@
 //!
 //! See also [`Ruby`](Ruby#gc) for more GC related methods.
 
-use std::{marker::PhantomData, ops::Range};
+use std::{
+    ffi::c_void,
+    marker::PhantomData,
+    ops::Range,
+    time::{Duration, Instant},
+};
 
 use rb_sys::{
-    rb_gc_adjust_memory_usage, rb_gc_count, rb_gc_disable, rb_gc_enable, rb_gc_location,
-    rb_gc_mark, rb_gc_mark_locations, rb_gc_mark_movable, rb_gc_register_address,
-    rb_gc_register_mark_object, rb_gc_start, rb_gc_stat, rb_gc_unregister_address, VALUE,
+    rb_event_flag_t, rb_gc_adjust_memory_usage, rb_gc_count, rb_gc_disable, rb_gc_enable,
+    rb_gc_location, rb_gc_mark, rb_gc_mark_locations, rb_gc_mark_movable, rb_gc_register_address,
+    rb_gc_register_mark_object, rb_gc_start, rb_gc_stat, rb_gc_unregister_address,
+    rb_tracearg_event_flag, rb_tracearg_from_tracepoint, rb_tracepoint_disable,
+    rb_tracepoint_enable, rb_tracepoint_new, RUBY_INTERNAL_EVENT_GC_END_SWEEP,
+    RUBY_INTERNAL_EVENT_GC_ENTER, RUBY_INTERNAL_EVENT_GC_EXIT, VALUE,
 };
 
 use crate::{
     error::{protect, Error},
+    module::{Module, RModule},
     r_hash::RHash,
     symbol::IntoSymbol,
-    value::{private::ReprValue as _, ReprValue, Value},
+    value::{private::ReprValue as _, BoxValue, LazyId, ReprValue, StaticSymbol, Value},
     Ruby,
 };
 
@@ -471,6 +480,446 @@ impl Ruby {
         unsafe { rb_gc_stat(res.as_rb_value()) };
         res
     }
+
+    /// Returns the current GC configuration as a Ruby Hash.
+    ///
+    /// This is equivalent to Ruby's `GC.config`, and is only available from
+    /// Ruby 3.4, returning `Err` on earlier versions.
+    ///
+    /// With earlier versions of Ruby, or for GC parameters not exposed by
+    /// `GC.config`, the `RUBY_GC_*` environment variables can be set (e.g.
+    /// with [`std::env::set_var`]) before initialising Ruby when embedding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     if let Ok(config) = ruby.gc_config() {
+    ///         assert!(config.len() > 0);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn gc_config(&self) -> Result<RHash, Error> {
+        static CONFIG: LazyId = LazyId::new("config");
+
+        self.module_gc().funcall(*CONFIG, ())
+    }
+
+    /// Update the GC configuration with the keys/values in `config`,
+    /// returning the new configuration.
+    ///
+    /// This is equivalent to Ruby's `GC.config(hash)`, and is only available
+    /// from Ruby 3.4, returning `Err` on earlier versions. See
+    /// [`Ruby::gc_config`].
+    pub fn gc_set_config(&self, config: RHash) -> Result<RHash, Error> {
+        static CONFIG: LazyId = LazyId::new("config");
+
+        self.module_gc().funcall(*CONFIG, (config,))
+    }
+
+    /// Register a function to be called at the end of every garbage
+    /// collection cycle, with statistics about the Ruby heap.
+    ///
+    /// `func` is called while the garbage collector is running, and so
+    /// **must not** call any Ruby API, or allocate Ruby objects. A typical
+    /// use is to record when a memory budget has been exceeded, so that the
+    /// embedding application can abort the running script once the garbage
+    /// collector has finished.
+    ///
+    /// The returned [`GcHook`] can be used to disable or re-enable the hook,
+    /// dropping it unregisters the hook.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{
+    ///     atomic::{AtomicUsize, Ordering},
+    ///     Arc,
+    /// };
+    ///
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let live_slots = Arc::new(AtomicUsize::new(0));
+    ///     let recorded = live_slots.clone();
+    ///     let hook = ruby.gc_on_cycle(move |stats| {
+    ///         recorded.store(stats.heap_live_slots, Ordering::Relaxed);
+    ///     });
+    ///
+    ///     ruby.gc_start();
+    ///     assert!(live_slots.load(Ordering::Relaxed) > 0);
+    ///     hook.disable();
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn gc_on_cycle<F>(&self, func: F) -> GcHook
+    where
+        F: FnMut(&GcStats) + Send + 'static,
+    {
+        unsafe extern "C" fn call(_tracepoint: VALUE, data: *mut c_void) {
+            let hook = &mut *(data as *mut GcHookData);
+            let stat = |key: Value| rb_gc_stat(key.as_rb_value()) as usize;
+            let stats = GcStats {
+                count: rb_gc_count() as usize,
+                heap_live_slots: stat(hook.keys[0]),
+                heap_free_slots: stat(hook.keys[1]),
+                heap_allocated_pages: stat(hook.keys[2]),
+                total_allocated_objects: stat(hook.keys[3]),
+                total_freed_objects: stat(hook.keys[4]),
+                malloc_increase_bytes: stat(hook.keys[5]),
+            };
+            abort_on_panic(|| (hook.func)(&stats));
+        }
+
+        static HEAP_LIVE_SLOTS: LazyId = LazyId::new("heap_live_slots");
+        static HEAP_FREE_SLOTS: LazyId = LazyId::new("heap_free_slots");
+        static HEAP_ALLOCATED_PAGES: LazyId = LazyId::new("heap_allocated_pages");
+        static TOTAL_ALLOCATED_OBJECTS: LazyId = LazyId::new("total_allocated_objects");
+        static TOTAL_FREED_OBJECTS: LazyId = LazyId::new("total_freed_objects");
+        static MALLOC_INCREASE_BYTES: LazyId = LazyId::new("malloc_increase_bytes");
+
+        // symbols are looked up here, as they can't be allocated during GC.
+        // They are static symbols, so are never collected.
+        let keys = [
+            &HEAP_LIVE_SLOTS,
+            &HEAP_FREE_SLOTS,
+            &HEAP_ALLOCATED_PAGES,
+            &TOTAL_ALLOCATED_OBJECTS,
+            &TOTAL_FREED_OBJECTS,
+            &MALLOC_INCREASE_BYTES,
+        ]
+        .map(|id| StaticSymbol::from(**id).as_value());
+        let data = GcHookData {
+            func: Box::new(func),
+            keys,
+        };
+        GcHook::new(
+            RUBY_INTERNAL_EVENT_GC_END_SWEEP as rb_event_flag_t,
+            call,
+            data,
+        )
+    }
+
+    /// Register a function to be called when the garbage collector pauses
+    /// Ruby execution, and again when execution resumes with the length of
+    /// the pause.
+    ///
+    /// With incremental marking and lazy sweeping a single garbage collection
+    /// cycle may be split over several shorter pauses, `func` is called for
+    /// each of them.
+    ///
+    /// `func` is called while the garbage collector is running, and so
+    /// **must not** call any Ruby API, or allocate Ruby objects. This can be
+    /// used by latency sensitive applications, such as games or audio
+    /// processing, to monitor pause times, or to pause work that can't be
+    /// interrupted.
+    ///
+    /// The returned [`GcHook`] can be used to disable or re-enable the hook,
+    /// dropping it unregisters the hook.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::{
+    ///     sync::{Arc, Mutex},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use magnus::{gc::GcPause, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let longest = Arc::new(Mutex::new(Duration::ZERO));
+    ///     let recorded = longest.clone();
+    ///     let hook = ruby.gc_on_pause(move |pause| {
+    ///         if let GcPause::End { duration } = pause {
+    ///             let mut longest = recorded.lock().unwrap();
+    ///             *longest = (*longest).max(duration);
+    ///         }
+    ///     });
+    ///
+    ///     ruby.gc_start();
+    ///     assert!(*longest.lock().unwrap() > Duration::ZERO);
+    ///     hook.disable();
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn gc_on_pause<F>(&self, func: F) -> GcHook
+    where
+        F: FnMut(GcPause) + Send + 'static,
+    {
+        unsafe extern "C" fn call(tracepoint: VALUE, data: *mut c_void) {
+            let hook = &mut *(data as *mut GcPauseData);
+            let event = rb_tracearg_event_flag(rb_tracearg_from_tracepoint(tracepoint));
+            let pause = if event == RUBY_INTERNAL_EVENT_GC_ENTER as rb_event_flag_t {
+                hook.started = Some(Instant::now());
+                GcPause::Start
+            } else {
+                match hook.started.take() {
+                    Some(started) => GcPause::End {
+                        duration: started.elapsed(),
+                    },
+                    // hook was enabled part way through a pause
+                    None => return,
+                }
+            };
+            abort_on_panic(|| (hook.func)(pause));
+        }
+
+        let data = GcPauseData {
+            func: Box::new(func),
+            started: None,
+        };
+        GcHook::new(
+            (RUBY_INTERNAL_EVENT_GC_ENTER | RUBY_INTERNAL_EVENT_GC_EXIT) as rb_event_flag_t,
+            call,
+            data,
+        )
+    }
+
+    fn module_gc_profiler(&self) -> Result<RModule, Error> {
+        self.module_gc().const_get("Profiler")
+    }
+
+    /// Start recording information about each garbage collection run.
+    ///
+    /// This is equivalent to Ruby's `GC::Profiler.enable`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.gc_profiler_enable()?;
+    ///     assert!(ruby.gc_profiler_is_enabled()?);
+    ///     ruby.gc_profiler_disable()?;
+    ///     assert!(!ruby.gc_profiler_is_enabled()?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn gc_profiler_enable(&self) -> Result<(), Error> {
+        static ENABLE: LazyId = LazyId::new("enable");
+
+        self.module_gc_profiler()?
+            .funcall::<_, _, Value>(*ENABLE, ())
+            .map(|_| ())
+    }
+
+    /// Stop recording information about garbage collection runs.
+    ///
+    /// Information already recorded is kept until
+    /// [`gc_profiler_clear`](Ruby::gc_profiler_clear) is called.
+    ///
+    /// This is equivalent to Ruby's `GC::Profiler.disable`.
+    pub fn gc_profiler_disable(&self) -> Result<(), Error> {
+        static DISABLE: LazyId = LazyId::new("disable");
+
+        self.module_gc_profiler()?
+            .funcall::<_, _, Value>(*DISABLE, ())
+            .map(|_| ())
+    }
+
+    /// Returns whether the GC profiler is currently recording.
+    ///
+    /// This is equivalent to Ruby's `GC::Profiler.enabled?`.
+    pub fn gc_profiler_is_enabled(&self) -> Result<bool, Error> {
+        static ENABLED: LazyId = LazyId::new("enabled?");
+
+        self.module_gc_profiler()?.funcall(*ENABLED, ())
+    }
+
+    /// Discard the information recorded by the GC profiler.
+    ///
+    /// This is equivalent to Ruby's `GC::Profiler.clear`.
+    pub fn gc_profiler_clear(&self) -> Result<(), Error> {
+        static CLEAR: LazyId = LazyId::new("clear");
+
+        self.module_gc_profiler()?
+            .funcall::<_, _, Value>(*CLEAR, ())
+            .map(|_| ())
+    }
+
+    /// Return a report of the garbage collection runs recorded by the GC
+    /// profiler, formatted as a table.
+    ///
+    /// This is equivalent to Ruby's `GC::Profiler.result`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.gc_profiler_enable()?;
+    ///     ruby.gc_start();
+    ///     let report = ruby.gc_profiler_report()?;
+    ///     assert!(report.contains("GC Time(ms)"));
+    ///     ruby.gc_profiler_disable()?;
+    ///     ruby.gc_profiler_clear()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn gc_profiler_report(&self) -> Result<String, Error> {
+        static RESULT: LazyId = LazyId::new("result");
+
+        self.module_gc_profiler()?.funcall(*RESULT, ())
+    }
+
+    /// Return the total time spent in garbage collection runs recorded by
+    /// the GC profiler.
+    ///
+    /// This is equivalent to Ruby's `GC::Profiler.total_time`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.gc_profiler_enable()?;
+    ///     ruby.gc_start();
+    ///     assert!(ruby.gc_profiler_total_time()? > Duration::ZERO);
+    ///     ruby.gc_profiler_disable()?;
+    ///     ruby.gc_profiler_clear()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn gc_profiler_total_time(&self) -> Result<Duration, Error> {
+        static TOTAL_TIME: LazyId = LazyId::new("total_time");
+
+        let secs: f64 = self.module_gc_profiler()?.funcall(*TOTAL_TIME, ())?;
+        Ok(Duration::from_secs_f64(secs))
+    }
+}
+
+struct GcHookData {
+    func: Box<dyn FnMut(&GcStats) + Send>,
+    keys: [Value; 6],
+}
+
+/// Statistics about the Ruby heap, passed to the function registered with
+/// [`Ruby::gc_on_cycle`].
+///
+/// See Ruby's `GC.stat` for more details on each value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// The number of garbage collections run since the start of the process.
+    pub count: usize,
+    /// The number of slots in the heap holding live objects.
+    pub heap_live_slots: usize,
+    /// The number of empty slots in the heap.
+    pub heap_free_slots: usize,
+    /// The number of pages allocated for the heap.
+    pub heap_allocated_pages: usize,
+    /// The number of objects allocated since the start of the process.
+    pub total_allocated_objects: usize,
+    /// The number of objects freed since the start of the process.
+    pub total_freed_objects: usize,
+    /// Bytes allocated with `malloc` since the last garbage collection.
+    pub malloc_increase_bytes: usize,
+}
+
+struct GcPauseData {
+    func: Box<dyn FnMut(GcPause) + Send>,
+    started: Option<Instant>,
+}
+
+/// Passed to the function registered with [`Ruby::gc_on_pause`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GcPause {
+    /// The garbage collector has paused Ruby execution.
+    Start,
+    /// The garbage collector has finished, and Ruby execution will resume.
+    End {
+        /// How long Ruby execution was paused for.
+        duration: Duration,
+    },
+}
+
+/// A function registered with [`Ruby::gc_on_cycle`] or
+/// [`Ruby::gc_on_pause`].
+///
+/// Dropping the `GcHook` unregisters the function and frees it. Use
+/// [`std::mem::forget`] to keep a function registered for the lifetime of
+/// the process.
+#[must_use = "dropping a GcHook unregisters the function"]
+pub struct GcHook {
+    tracepoint: BoxValue<Value>,
+    data: *mut c_void,
+    drop_data: unsafe fn(*mut c_void),
+}
+
+impl GcHook {
+    fn new<T>(
+        events: rb_event_flag_t,
+        func: unsafe extern "C" fn(VALUE, *mut c_void),
+        data: T,
+    ) -> Self {
+        unsafe fn drop_data<T>(data: *mut c_void) {
+            drop(Box::from_raw(data as *mut T));
+        }
+
+        let data = Box::into_raw(Box::new(data)) as *mut c_void;
+        unsafe {
+            let tracepoint = Value::new(rb_tracepoint_new(0, events, Some(func), data));
+            let hook = Self {
+                tracepoint: BoxValue::new(tracepoint),
+                data,
+                drop_data: drop_data::<T>,
+            };
+            rb_tracepoint_enable(tracepoint.as_rb_value());
+            hook
+        }
+    }
+
+    /// Stop calling the registered function.
+    pub fn disable(&self) {
+        unsafe { rb_tracepoint_disable(self.tracepoint.as_rb_value()) };
+    }
+
+    /// Resume calling the registered function after [`GcHook::disable`].
+    pub fn enable(&self) {
+        unsafe { rb_tracepoint_enable(self.tracepoint.as_rb_value()) };
+    }
+}
+
+impl Drop for GcHook {
+    fn drop(&mut self) {
+        self.disable();
+        // the tracepoint no longer calls the function, so it can be freed
+        unsafe { (self.drop_data)(self.data) };
+    }
+}
+
+/// Call a `GcHook`'s registered function from its tracepoint.
+///
+/// The tracepoint runs inside the garbage collector, which has no way to
+/// recover from a Rust panic unwinding through it, so abort the process
+/// instead.
+fn abort_on_panic<F>(func: F)
+where
+    F: FnOnce(),
+{
+    if std::panic::catch_unwind(std::panic::AssertUnwindSafe(func)).is_err() {
+        std::process::abort();
+    }
 }
 
 /// Disable automatic GC runs.
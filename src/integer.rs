@@ -1,9 +1,16 @@
+#[cfg(feature = "num-bigint")]
+use std::{ffi::c_void, os::raw::c_int, ptr};
 use std::{
     fmt,
     ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
     os::raw::c_long,
 };
 
+#[cfg(feature = "num-bigint")]
+use rb_sys::{
+    rb_absint_numwords, rb_integer_pack, rb_integer_unpack, INTEGER_PACK_LSWORD_FIRST,
+    INTEGER_PACK_NATIVE, INTEGER_PACK_NEGATIVE,
+};
 use rb_sys::{
     rb_big_cmp, rb_big_div, rb_big_eq, rb_big_minus, rb_big_mul, rb_big_norm, rb_big_plus,
     rb_int2big, rb_ll2inum, rb_to_int, rb_ull2inum, ruby_special_consts, ruby_value_type, Qtrue,
@@ -22,6 +29,8 @@ use crate::{
     },
     Ruby,
 };
+#[cfg(feature = "num-bigint")]
+use crate::{into_value::IntoValueFromNative, try_convert::TryConvertOwned};
 
 pub(crate) enum IntegerType {
     Fixnum(Fixnum),
@@ -97,6 +106,73 @@ impl Ruby {
             )
         }
     }
+
+    /// Create a new `Integer` from a [`num_bigint::BigInt`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    /// use num_bigint::BigInt;
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let n: BigInt = "-123456789012345678901234567890".parse().unwrap();
+    ///     rb_assert!(
+    ///         ruby,
+    ///         "i == -123456789012345678901234567890",
+    ///         i = ruby.integer_from_big_int(&n),
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg(feature = "num-bigint")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "num-bigint")))]
+    pub fn integer_from_big_int(&self, n: &num_bigint::BigInt) -> Integer {
+        let (sign, words) = n.to_u32_digits();
+        let flags = if sign == num_bigint::Sign::Minus {
+            INTEGER_PACK_NEGATIVE
+        } else {
+            0
+        };
+        integer_unpack(&words, flags)
+    }
+
+    /// Create a new `Integer` from a [`num_bigint::BigUint`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    /// use num_bigint::BigUint;
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let n = BigUint::from(2_u32).pow(100);
+    ///     rb_assert!(ruby, "i == 2**100", i = ruby.integer_from_big_uint(&n));
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg(feature = "num-bigint")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "num-bigint")))]
+    pub fn integer_from_big_uint(&self, n: &num_bigint::BigUint) -> Integer {
+        integer_unpack(&n.to_u32_digits(), 0)
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+fn integer_unpack(words: &[u32], flags: u32) -> Integer {
+    unsafe {
+        Integer::from_rb_value_unchecked(rb_integer_unpack(
+            words.as_ptr() as *const c_void,
+            words.len(),
+            4,
+            0,
+            (INTEGER_PACK_LSWORD_FIRST | INTEGER_PACK_NATIVE | flags) as c_int,
+        ))
+    }
 }
 
 /// A type wrapping either a [`Fixnum`] or a [`RBignum`].
@@ -490,6 +566,84 @@ impl Integer {
         }
     }
 
+    /// Convert `self` to a [`num_bigint::BigInt`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Integer, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let i: Integer = ruby.eval("-(2**100)")?;
+    ///     assert_eq!(i.to_big_int().to_string(), "-1267650600228229401496703205376");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg(feature = "num-bigint")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "num-bigint")))]
+    pub fn to_big_int(self) -> num_bigint::BigInt {
+        let (sign, words) = self.pack();
+        let sign = match sign {
+            s if s < 0 => num_bigint::Sign::Minus,
+            0 => num_bigint::Sign::NoSign,
+            _ => num_bigint::Sign::Plus,
+        };
+        num_bigint::BigInt::new(sign, words)
+    }
+
+    /// Convert `self` to a [`num_bigint::BigUint`]. Returns `Err` if `self` is
+    /// negative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Integer, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let i: Integer = ruby.eval("2**100")?;
+    ///     assert_eq!(i.to_big_uint()?.to_string(), "1267650600228229401496703205376");
+    ///
+    ///     let i: Integer = ruby.eval("-1")?;
+    ///     assert!(i.to_big_uint().is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg(feature = "num-bigint")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "num-bigint")))]
+    pub fn to_big_uint(self) -> Result<num_bigint::BigUint, Error> {
+        let (sign, words) = self.pack();
+        if sign < 0 {
+            return Err(Error::new(
+                Ruby::get_with(self).exception_range_error(),
+                "can't convert negative integer to unsigned",
+            ));
+        }
+        Ok(num_bigint::BigUint::new(words))
+    }
+
+    /// Returns the sign of `self`, and its absolute value as 32 bit words,
+    /// least significant first.
+    #[cfg(feature = "num-bigint")]
+    fn pack(self) -> (c_int, Vec<u32>) {
+        unsafe {
+            let len = rb_absint_numwords(self.as_rb_value(), 32, ptr::null_mut());
+            let mut words = vec![0_u32; len];
+            let sign = rb_integer_pack(
+                self.as_rb_value(),
+                words.as_mut_ptr() as *mut c_void,
+                words.len(),
+                4,
+                0,
+                (INTEGER_PACK_LSWORD_FIRST | INTEGER_PACK_NATIVE) as c_int,
+            );
+            (sign, words)
+        }
+    }
+
     /// Normalize `self`. If `self` is a `Fixnum`, returns `self`. If `self` is
     /// a `Bignum`, if it is small enough to fit in a `Fixnum`, returns a
     /// `Fixnum` with the same value. Otherwise, returns `self`.
@@ -558,6 +712,50 @@ impl TryConvert for Integer {
     }
 }
 
+#[cfg(feature = "num-bigint")]
+impl IntoValue for num_bigint::BigInt {
+    #[inline]
+    fn into_value_with(self, handle: &Ruby) -> Value {
+        handle.integer_from_big_int(&self).as_value()
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+unsafe impl IntoValueFromNative for num_bigint::BigInt {}
+
+#[cfg(feature = "num-bigint")]
+impl TryConvert for num_bigint::BigInt {
+    #[inline]
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        Integer::try_convert(val).map(Integer::to_big_int)
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+unsafe impl TryConvertOwned for num_bigint::BigInt {}
+
+#[cfg(feature = "num-bigint")]
+impl IntoValue for num_bigint::BigUint {
+    #[inline]
+    fn into_value_with(self, handle: &Ruby) -> Value {
+        handle.integer_from_big_uint(&self).as_value()
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+unsafe impl IntoValueFromNative for num_bigint::BigUint {}
+
+#[cfg(feature = "num-bigint")]
+impl TryConvert for num_bigint::BigUint {
+    #[inline]
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        Integer::try_convert(val)?.to_big_uint()
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+unsafe impl TryConvertOwned for num_bigint::BigUint {}
+
 impl PartialEq for Integer {
     fn eq(&self, other: &Self) -> bool {
         match self.integer_type() {
@@ -0,0 +1,151 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::{
+    class::{Class, RClass},
+    error::Error,
+    module::Module,
+    r_string::RString,
+    try_convert::{TryConvert, TryConvertOwned},
+    value::{LazyId, ReprValue, Value},
+    Ruby,
+};
+
+static PREFIX: LazyId = LazyId::new("prefix");
+
+/// # `IPAddr`
+///
+/// Functions for working with Ruby's `IPAddr` class.
+///
+/// Rust's [`IpAddr`], [`Ipv4Addr`], and [`Ipv6Addr`] can be converted from
+/// `IPAddr` objects or Strings, and [`Ruby::ip_addr_new`] creates an `IPAddr`
+/// from them.
+impl Ruby {
+    /// Return Ruby's `IPAddr` class, requiring `"ipaddr"` if it hasn't
+    /// already been loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net::IpAddr;
+    ///
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     rb_assert!(ruby, "klass == IPAddr", klass = ruby.class_ip_addr()?);
+    ///
+    ///     let addr: IpAddr = ruby.eval(r#"IPAddr.new("::1")"#)?;
+    ///     assert!(addr.is_loopback());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn class_ip_addr(&self) -> Result<RClass, Error> {
+        if let Ok(class) = self.class_object().const_get("IPAddr") {
+            return Ok(class);
+        }
+        self.require("ipaddr")?;
+        self.class_object().const_get("IPAddr")
+    }
+
+    /// Create a new Ruby `IPAddr` from `addr`, requiring `"ipaddr"` if it
+    /// hasn't already been loaded.
+    ///
+    /// Returns `Err` if the `ipaddr` library can't be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net::Ipv4Addr;
+    ///
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let addr = ruby.ip_addr_new(Ipv4Addr::new(192, 168, 0, 1))?;
+    ///     rb_assert!(ruby, r#"addr == IPAddr.new("192.168.0.1")"#, addr);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn ip_addr_new<T>(&self, addr: T) -> Result<Value, Error>
+    where
+        T: Into<IpAddr>,
+    {
+        self.class_ip_addr()?
+            .new_instance((addr.into().to_string(),))
+    }
+}
+
+impl TryConvert for IpAddr {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        let ruby = Ruby::get_with(val);
+        let s = match RString::from_value(val) {
+            Some(s) => s,
+            None => {
+                let is_ip_addr = ruby
+                    .class_object()
+                    .const_get::<_, RClass>("IPAddr")
+                    .map_or(false, |class| val.is_kind_of(class));
+                if !is_ip_addr {
+                    return Err(Error::new(
+                        ruby.exception_type_error(),
+                        format!("no implicit conversion of {} into IPAddr", unsafe {
+                            val.classname()
+                        }),
+                    ));
+                }
+                let prefix: u32 = val.funcall(*PREFIX, ())?;
+                let addr = parse(&ruby, val.to_r_string()?)?;
+                let max = if addr.is_ipv4() { 32 } else { 128 };
+                if prefix != max {
+                    return Err(Error::new(
+                        ruby.exception_arg_error(),
+                        format!("expected a single address, got {}/{}", addr, prefix),
+                    ));
+                }
+                return Ok(addr);
+            }
+        };
+        parse(&ruby, s)
+    }
+}
+
+unsafe impl TryConvertOwned for IpAddr {}
+
+fn parse(ruby: &Ruby, s: RString) -> Result<IpAddr, Error> {
+    s.to_string()?.parse().map_err(|e| {
+        Error::new(
+            ruby.exception_arg_error(),
+            format!("invalid address: {}", e),
+        )
+    })
+}
+
+impl TryConvert for Ipv4Addr {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        match IpAddr::try_convert(val)? {
+            IpAddr::V4(addr) => Ok(addr),
+            IpAddr::V6(_) => Err(Error::new(
+                Ruby::get_with(val).exception_arg_error(),
+                "expected an IPv4 address, got IPv6",
+            )),
+        }
+    }
+}
+
+unsafe impl TryConvertOwned for Ipv4Addr {}
+
+impl TryConvert for Ipv6Addr {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        match IpAddr::try_convert(val)? {
+            IpAddr::V6(addr) => Ok(addr),
+            IpAddr::V4(_) => Err(Error::new(
+                Ruby::get_with(val).exception_arg_error(),
+                "expected an IPv6 address, got IPv4",
+            )),
+        }
+    }
+}
+
+unsafe impl TryConvertOwned for Ipv6Addr {}
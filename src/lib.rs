@@ -216,7 +216,7 @@
 // * `RBIGNUM_SIGN`:
 //!
 //! ## `rb_a`-`rb_arx`
-// * `rb_absint_numwords`:
+//! * `rb_absint_numwords`: `Integer::to_big_int` and `Integer::to_big_uint`.
 // * `rb_absint_singlebit_p`:
 // * `rb_absint_size`:
 // * `rb_add_event_hook`:
@@ -240,7 +240,7 @@
 // * `rb_array_len`:
 //!
 //! ## `rb_ary`
-// * `rb_ary_aref`:
+//! * `rb_ary_aref`: [`RArray::aref`].
 //! * `rb_ary_assoc`: [`RArray::assoc`].
 //! * `rb_ary_cat`: [`RArray::cat`].
 //! * `rb_ary_clear`: [`RArray::clear`].
@@ -403,7 +403,7 @@
 // * `rb_cloexec_pipe`:
 // * `rb_close_before_exec`:
 // * `rb_cmperr`:
-// * `rb_cmpint`:
+//! * `rb_cmpint`: See [`ReprValue::between`] and [`ReprValue::clamp`].
 // * `rb_compile_error`:
 // * `rb_compile_error_append`:
 // * `rb_compile_error_with_enc`:
@@ -643,7 +643,8 @@
 //! ## `rb_en`-`rb_ez`
 // * `rb_ensure`:
 //! * `rb_enumeratorize`: See [`Value::enumeratorize`].
-//! * `rb_enumeratorize_with_size`: See [`Value::enumeratorize`].
+//! * `rb_enumeratorize_with_size`: See [`Value::enumeratorize`] &
+//!   [`Value::enumeratorize_with_size`].
 //! * `rb_enumeratorize_with_size_kw`: [`Value::enumeratorize`].
 // * `rb_enum_values_pack`:
 // * `rb_env_clear`:
@@ -651,7 +652,8 @@
 //! * `rb_eql`: [`Value::eql`].
 //! * `rb_equal`: [`Value::equal`].
 // * `rb_errinfo`:
-//! * `rb_error_arity`: [`scan_args::check_arity`].
+//! * `rb_error_arity`: See [`Ruby::arity_error`] &
+//!   [`Ruby::check_arity`].
 // * `rb_error_frozen`:
 // * `rb_error_frozen_object`:
 // * `rb_eval_cmd_kw`:
@@ -664,7 +666,7 @@
 // * `rb_exc_new_str`:
 //! * `rb_exc_raise`: Return [`Error`].
 // * `rb_exec_end_proc`:
-// * `rb_exec_recursive`:
+//! * `rb_exec_recursive`: [`Ruby::with_recursion_guard`].
 // * `rb_exec_recursive_outer`:
 // * `rb_exec_recursive_paired`:
 // * `rb_exec_recursive_paired_outer`:
@@ -675,7 +677,7 @@
 // * `rb_external_str_new_with_enc`:
 // * `rb_extract_keywords`:
 // * `RB_EXT_RACTOR_SAFE`:
-// * `rb_ext_ractor_safe`:
+//! * `rb_ext_ractor_safe`: [`Ruby::ext_ractor_safe`].
 //!
 //! ## `rb_f`
 // * `rb_fatal`:
@@ -827,7 +829,7 @@
 // * `rb_gc_mark_maybe`:
 //! * `rb_gc_mark_movable`: [`gc::Marker::mark_movable`].
 //! * `rb_gc_register_address`: [`gc::register_address`] or
-//!   [`BoxValue`](value::BoxValue).
+//!   [`BoxValue`](value::BoxValue) or [`ReprValue::pin`](value::ReprValue::pin).
 //! * `rb_gc_register_mark_object`: [`gc::register_mark_object`].
 //! * `rb_gc_start`: [`gc::start`].
 //! * `rb_gc_stat`: [`gc::stat`] or [`gc::all_stats`].
@@ -864,8 +866,8 @@
 //! * `rb_hash_clear`: [`RHash::clear`].
 //! * `rb_hash_delete`: [`RHash::delete`].
 // * `rb_hash_delete_if`:
-// * `rb_hash_dup`:
-// * `rb_hash_end`:
+//! * `rb_hash_dup`: See [`ReprValue::defensive_copy`].
+//! * `rb_hash_end`: [`RubyHasher`](typed_data::RubyHasher).
 //! * `rb_hash_fetch`: [`RHash::fetch`].
 //! * `rb_hash_foreach`: [`RHash::foreach`].
 // * `rb_hash_freeze`: See [`Value::freeze`].
@@ -878,9 +880,9 @@
 // * `rb_hash_set_ifnone`:
 //! * `rb_hash_size`: [`RHash::size`].
 //! * `rb_hash_size_num`: [`RHash::len`].
-// * `rb_hash_start`:
+//! * `rb_hash_start`: [`Ruby::hasher`].
 // * `rb_hash_tbl`:
-// * `rb_hash_uint`:
+//! * `rb_hash_uint`: [`RubyHasher`](typed_data::RubyHasher).
 // * `rb_hash_uint32`:
 //! * `rb_hash_update_by`: [`RHash::update`] (`update_func` arg not implemented).
 //!
@@ -900,9 +902,10 @@
 // * `RB_INT2NUM`:
 // * `rb_int2num_inline`:
 // * `rb_Integer`:
-// * `rb_integer_pack`:
+//! * `rb_integer_pack`: `Integer::to_big_int` and `Integer::to_big_uint`.
 // * `rb_integer_type_p`:
-// * `rb_integer_unpack`:
+//! * `rb_integer_unpack`: `Ruby::integer_from_big_int` and
+//!   `Ruby::integer_from_big_uint`.
 //! * `rb_intern`: [`std::convert::From`].
 //! * `rb_intern2`: [`std::convert::From`].
 //! * `rb_intern3`: [`std::convert::From`].
@@ -920,7 +923,7 @@
 // * `rb_io_addstr`:
 // * `rb_io_ascii8bit_binmode`:
 // * `rb_io_binmode`:
-// * `rb_io_bufwrite`:
+//! * `rb_io_bufwrite`: [`RFile::write_from`].
 // * `rb_io_check_byte_readable`:
 // * `rb_io_check_char_readable`:
 // * `rb_io_check_closed`:
@@ -934,12 +937,12 @@
 // * `rb_io_extract_encoding_option`:
 // * `rb_io_extract_modeenc`:
 // * `rb_io_fdopen`:
-// * `rb_io_flush`:
+//! * `rb_io_flush`: [`RFile::flush`].
 // * `rb_io_fptr_finalize`:
 // * `rb_io_getbyte`:
 // * `rb_io_gets`:
 // * `rb_io_get_io`:
-// * `rb_io_get_write_io`:
+//! * `rb_io_get_write_io`: See [`RFile::write_from`].
 // * `rb_io_make_open_file`:
 // * `rb_io_maybe_wait`:
 // * `rb_io_maybe_wait_readable`:
@@ -1027,7 +1030,7 @@
 // * `rb_long2num_inline`:
 //!
 //! ## `rb_m`
-// * `rb_make_backtrace`:
+//! * `rb_make_backtrace`: See [`Error::with_backtrace`].
 // * `rb_make_exception`:
 // * `rb_mark_hash`:
 // * `rb_mark_set`:
@@ -1039,7 +1042,7 @@
 // * `rb_match_busy`:
 // * `rb_memcicmp`:
 // * `rb_memerror`:
-// * `rb_memhash`:
+//! * `rb_memhash`: [`Ruby::memhash`].
 // * `rb_memory_id`:
 // * `rb_memory_view_available_p`:
 // * `rb_memory_view_extract_item_members`:
@@ -1237,14 +1240,14 @@
 // * `rb_provided`:
 //!
 //! ## `rb_r`
-// * `rb_ractor_local_storage_ptr`:
-// * `rb_ractor_local_storage_ptr_newkey`:
-// * `rb_ractor_local_storage_ptr_set`:
+//! * `rb_ractor_local_storage_ptr`: See [`ractor::RactorLocal`].
+//! * `rb_ractor_local_storage_ptr_newkey`: See [`ractor::RactorLocal`].
+//! * `rb_ractor_local_storage_ptr_set`: See [`ractor::RactorLocal`].
 // * `rb_ractor_local_storage_value`:
 // * `rb_ractor_local_storage_value_lookup`:
 // * `rb_ractor_local_storage_value_newkey`:
 // * `rb_ractor_local_storage_value_set`:
-// * `rb_ractor_make_shareable`:
+//! * `rb_ractor_make_shareable`: [`Ruby::ractor_make_shareable`].
 // * `rb_ractor_make_shareable_copy`:
 // * `rb_ractor_shareable_p`:
 // * `rb_ractor_stderr`:
@@ -1330,7 +1333,7 @@
 // * `rb_scan_args_length_mismatch`:
 // * `rb_set_class_path`:
 // * `rb_set_class_path_string`:
-// * `rb_set_end_proc`:
+//! * `rb_set_end_proc`: [`Ruby::at_cleanup`].
 // * `rb_set_errinfo`:
 //! * `rb_singleton_class`: [`Object::singleton_class`].
 // * `rb_singleton_class_attached`:
@@ -1407,9 +1410,9 @@
 // * `rb_str_inspect`:
 // * `rb_str_intern`:
 // * `rb_str_length`:
-// * `rb_str_locktmp`:
-// * `rb_str_modify`:
-// * `rb_str_modify_expand`:
+//! * `rb_str_locktmp`: [`RString::lock`].
+//! * `rb_str_modify`: [`RString::make_independent`].
+//! * `rb_str_modify_expand`: [`RString::reserve`].
 //! * `rb_str_new`: [`RString::from_slice`].
 // * `rb_str_new_cstr`:
 //! * `rb_str_new_frozen`: [`RString::new_frozen`].
@@ -1421,7 +1424,7 @@
 //! * `rb_str_offset`: [`RString::offset`].
 //! * `rb_str_plus`: [`RString::plus`].
 //! * `rb_str_replace`: [`RString::replace`].
-// * `rb_str_resize`:
+//! * `rb_str_resize`: [`RString::truncate`] and [`RString::clear`].
 // * `rb_str_resurrect`:
 //! * `rb_str_scrub`: [`RString::scrub`].
 // * `rb_str_setter`:
@@ -1440,7 +1443,7 @@
 //! * `rb_str_to_interned_str`: [`RString::to_interned_str`].
 // * `rb_str_to_inum`:
 //! * `rb_str_to_str`: [`TryConvert`] or [`Value::try_convert`].
-// * `rb_str_unlocktmp`:
+//! * `rb_str_unlocktmp`: [`RString::lock`].
 //! * `rb_str_update`: [`RString::update`].
 // * `rb_str_vcatf`:
 //!
@@ -1510,9 +1513,10 @@
 //! * `rb_thread_alone`: [`Ruby::thread_alone`].
 // * `rb_thread_atfork`:
 // * `rb_thread_atfork_before_exec`:
-// * `rb_thread_call_without_gvl`:
+//! * `rb_thread_call_without_gvl`: See [`Ruby::without_gvl`] &
+//!   [`Ruby::without_gvl_unblock`].
 // * `rb_thread_call_without_gvl2`:
-// * `rb_thread_call_with_gvl`:
+//! * `rb_thread_call_with_gvl`: [`nogvl::with_gvl`].
 //! * `rb_thread_check_ints`: [`Ruby::thread_check_ints`].
 //! * `rb_thread_create`: [`Ruby::thread_create`] & [`Ruby::thread_create_from_fn`].
 //! * `rb_thread_current`: [`Ruby::thread_current`].
@@ -1543,10 +1547,11 @@
 // * `rb_time_nano_new`:
 //! * `rb_time_new`: [`Ruby::time_new`].
 // * `rb_time_num_new`:
-// * `rb_time_timespec`:
-// * `rb_time_timespec_interval`:
-// * `rb_time_timespec_new`:
-//! * `rb_time_timeval`: [`TryConvert`].
+//! * `rb_time_timespec`: [`Time::timespec`] and [`TryConvert`].
+//! * `rb_time_timespec_interval`: [`TryConvert`].
+//! * `rb_time_timespec_new`: [`Ruby::time_nano_new`], [`Ruby::time_utc_new`], and
+//!   [`Ruby::time_new_with_offset`].
+// * `rb_time_timeval`:
 //! * `rb_time_utc_offset`: [`Time::utc_offset`].
 // * `rb_tolower`:
 // * `rb_toupper`:
@@ -1569,10 +1574,11 @@
 // * `rb_tracearg_raised_exception`:
 // * `rb_tracearg_return_value`:
 // * `rb_tracearg_self`:
-// * `rb_tracepoint_disable`:
-// * `rb_tracepoint_enable`:
+//! * `rb_tracepoint_disable`: See [`gc::GcHook::disable`].
+//! * `rb_tracepoint_enable`: See [`Ruby::gc_on_cycle`], [`Ruby::gc_on_pause`],
+//!   and [`gc::GcHook::enable`].
 // * `rb_tracepoint_enabled_p`:
-// * `rb_tracepoint_new`:
+//! * `rb_tracepoint_new`: See [`Ruby::gc_on_cycle`] and [`Ruby::gc_on_pause`].
 // * `rb_trap_exit`:
 // * `rb_type`:
 // * `rb_typeddata_inherited_p`:
@@ -1750,7 +1756,7 @@
 // * `ruby_signal_name`:
 // * `ruby_sig_finalize`:
 // * `ruby_snprintf`:
-// * `ruby_stack_check`:
+//! * `ruby_stack_check`: [`Ruby::stack_check`].
 // * `ruby_stack_length`:
 // * `ruby_stop`:
 // * `ruby_strdup`:
@@ -1791,14 +1797,22 @@
 #[macro_use]
 mod macros;
 
+#[cfg(feature = "alloc-trace")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc-trace")))]
+pub mod alloc_trace;
 mod api;
+pub mod args;
 pub mod block;
 pub mod class;
+#[cfg(feature = "csv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "csv")))]
+pub mod csv;
 #[cfg(feature = "embed")]
 #[cfg_attr(docsrs, doc(cfg(feature = "embed")))]
 pub mod embed;
 pub mod encoding;
 mod enumerator;
+mod env;
 pub mod error;
 pub mod exception;
 #[cfg(any(ruby_gte_3_1, docsrs))]
@@ -1808,12 +1822,27 @@ mod float;
 pub mod gc;
 mod integer;
 mod into_value;
+mod ip_addr;
 pub mod method;
 pub mod module;
+#[cfg(feature = "msgpack")]
+#[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+pub mod msgpack;
 mod mutex;
+mod native_frame;
+pub mod native_pool;
+#[cfg(feature = "ndarray")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ndarray")))]
+pub mod ndarray;
+pub mod nogvl;
 pub mod numeric;
 mod object;
+pub mod object_space;
 pub mod process;
+#[cfg(feature = "profile")]
+#[cfg_attr(docsrs, doc(cfg(feature = "profile")))]
+pub mod profile;
+pub mod ractor;
 /// Traits that commonly should be in scope.
 pub mod prelude {
     pub use crate::{
@@ -1839,22 +1868,45 @@ mod range;
 #[cfg(feature = "rb-sys")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rb-sys")))]
 pub mod rb_sys;
+mod recursion;
 pub mod scan_args;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod serde;
+mod socket;
 pub mod symbol;
+mod tempfile;
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
 mod thread;
 mod time;
 pub mod try_convert;
 pub mod typed_data;
+#[cfg(feature = "url")]
+mod uri;
 pub mod value;
 
-use std::{ffi::CString, mem::transmute, os::raw::c_int};
+use std::{
+    ffi::CString,
+    mem::transmute,
+    os::raw::c_int,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
 
 use ::rb_sys::{
     rb_alias_variable, rb_backref_get, rb_call_super_kw, rb_current_receiver, rb_define_class,
     rb_define_global_const, rb_define_global_function, rb_define_module, rb_define_variable,
-    rb_errinfo, rb_eval_string_protect, rb_require_string, rb_set_errinfo, VALUE,
+    rb_errinfo, rb_eval_string_protect, rb_require_string, rb_set_end_proc, rb_set_errinfo, VALUE,
+};
+pub use magnus_macros::{
+    init, wrap, wrap_function, wrap_method, DataTypeFunctions, Keywords, TypedData,
 };
-pub use magnus_macros::{init, wrap, DataTypeFunctions, TypedData};
 
 #[cfg(any(ruby_gte_3_1, docsrs))]
 #[cfg_attr(docsrs, doc(cfg(ruby_gte_3_1)))]
@@ -1865,6 +1917,7 @@ pub use crate::{
     api::Ruby,
     class::{Class, RClass},
     enumerator::Enumerator,
+    env::Env,
     error::Error,
     exception::{Exception, ExceptionClass},
     float::Float,
@@ -1877,7 +1930,7 @@ pub use crate::{
     r_array::RArray,
     r_bignum::RBignum,
     r_complex::RComplex,
-    r_file::RFile,
+    r_file::{RFile, RIo},
     r_float::RFloat,
     r_hash::RHash,
     r_match::RMatch,
@@ -1899,7 +1952,7 @@ use crate::{
     error::protect,
     method::Method,
     r_string::IntoRString,
-    value::{private::ReprValue as _, IntoId, ReprValue},
+    value::{private::ReprValue as _, IntoId, LazyId, ReprValue},
 };
 
 /// Evaluate a literal string of Ruby code with the given local variables.
@@ -1989,6 +2042,119 @@ macro_rules! bind {
     }};
 }
 
+/// Define frozen constants within a module or class from Rust values.
+///
+/// Each constant is given as `NAME = value`, or just `NAME` to use a Rust
+/// `const` (or local variable) with the same name as the constant. Any type
+/// that implements [`IntoValue`] can be used as a value. Values are frozen
+/// before being assigned.
+///
+/// Evaluates to `Result<(), Error>`, stopping at the first error.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{define_consts, rb_assert, Error, Ruby};
+///
+/// const VERSION: &str = "1.2.3";
+/// const MAX_SIZE: usize = 1024;
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let module = ruby.define_module("Example")?;
+///     define_consts!(module, VERSION, MAX_SIZE, RATIO = 0.5)?;
+///
+///     rb_assert!(ruby, r#"Example::VERSION == "1.2.3""#);
+///     rb_assert!(ruby, "Example::VERSION.frozen?");
+///     rb_assert!(ruby, "Example::MAX_SIZE == 1024");
+///     rb_assert!(ruby, "Example::RATIO == 0.5");
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[macro_export]
+macro_rules! define_consts {
+    ($module:expr, $($consts:tt)*) => {{
+        (|| -> ::std::result::Result<(), $crate::Error> {
+            let module = $module;
+            $crate::const_def!(module, $($consts)*);
+            Ok(())
+        })()
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! const_def {
+    ($module:ident $(,)?) => {};
+    ($module:ident, $k:ident = $v:expr $(, $($rest:tt)*)?) => {
+        {
+            use $crate::{module::Module, value::ReprValue};
+            let val = $crate::Ruby::get_with($module).into_value($v);
+            val.freeze();
+            $module.const_set(stringify!($k), val)?;
+        }
+        $crate::const_def!($module $(, $($rest)*)?);
+    };
+    ($module:ident, $k:ident $(, $($rest:tt)*)?) => {
+        $crate::const_def!($module, $k = $k $(, $($rest)*)?);
+    };
+}
+
+/// Run an extension's initialisation code only once.
+///
+/// Ruby may load an extension more than once, for example when the same
+/// library is required via two different paths. Each load calls the
+/// extension's [`init`](macro@init) function, redefining its classes,
+/// methods, and constants, which can result in "already initialized constant"
+/// warnings or errors.
+///
+/// This macro evaluates its body, which must evaluate to
+/// `Result<(), Error>`, on the first call only. Subsequent calls return
+/// `Ok(())` without evaluating the body. If the body returns an error it will
+/// be run again on the next call.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{define_consts, init_once, rb_assert, Error, Ruby};
+///
+/// #[magnus::init]
+/// fn init(ruby: &Ruby) -> Result<(), Error> {
+///     init_once! {
+///         let module = ruby.define_module("Example")?;
+///         define_consts!(module, VERSION = "1.0.0")?;
+///         Ok(())
+///     }
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     init(ruby)?;
+///     // no "already initialized constant" warning on second init
+///     init(ruby)?;
+///     rb_assert!(ruby, r#"Example::VERSION == "1.0.0""#);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[macro_export]
+macro_rules! init_once {
+    ($($body:tt)*) => {{
+        static INITIALIZED: ::std::sync::atomic::AtomicBool =
+            ::std::sync::atomic::AtomicBool::new(false);
+        if INITIALIZED.swap(true, ::std::sync::atomic::Ordering::SeqCst) {
+            ::std::result::Result::<(), $crate::Error>::Ok(())
+        } else {
+            let res = (|| -> ::std::result::Result<(), $crate::Error> { $($body)* })();
+            if res.is_err() {
+                INITIALIZED.store(false, ::std::sync::atomic::Ordering::SeqCst);
+            }
+            res
+        }
+    }};
+}
+
 /// Asserts a Ruby expression evaluates to a truthy value.
 ///
 /// This macro uses the Ruby
@@ -2068,6 +2234,95 @@ macro_rules! rb_assert {
     }};
 }
 
+/// Asserts a Ruby expression evaluates to a value equal to a Rust value.
+///
+/// The result of the Ruby expression is converted to the type of the
+/// expected value with [`TryConvert`], and compared with `==`.
+///
+/// # Panics
+///
+/// Panics if the expression raises, the conversion fails, or the values are
+/// not equal. Panics if called from a non-Ruby thread.
+///
+/// # Examples
+///
+/// ```
+/// # let _cleanup = unsafe { magnus::embed::init() };
+/// magnus::assert_ruby_eq!("1 + 2", 3);
+/// magnus::assert_ruby_eq!(r#""a" * 3"#, String::from("aaa"));
+/// ```
+///
+/// Passing [`Ruby`] to avoid the Ruby thread check, and setting local
+/// variables as with [`rb_assert`]:
+///
+/// ```
+/// # let _cleanup = unsafe { magnus::embed::init() };
+/// let ruby = magnus::Ruby::get().unwrap();
+/// magnus::assert_ruby_eq!(ruby, "[a, b].sum", 3, a = 1, b = 2);
+/// ```
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+#[macro_export]
+macro_rules! assert_ruby_eq {
+    ($expr:literal, $expected:expr) => {{
+        $crate::assert_ruby_eq!($crate::Ruby::get().unwrap(), $expr, $expected)
+    }};
+    ($expr:literal, $expected:expr, $($bindings:tt)*) => {{
+        $crate::assert_ruby_eq!($crate::Ruby::get().unwrap(), $expr, $expected, $($bindings)*)
+    }};
+    ($ruby:expr, $expr:literal, $expected:expr) => {{
+        $crate::assert_ruby_eq!($ruby, $expr, $expected,)
+    }};
+    ($ruby:expr, $expr:literal, $expected:expr, $($bindings:tt)*) => {{
+        let expected = $expected;
+        let actual = $crate::eval!($ruby, $expr, $($bindings)*)
+            .and_then(|val| $crate::testing::convert_like(&expected, val));
+        match actual {
+            Ok(actual) => assert_eq!(actual, expected, "{}", $expr),
+            Err(e) => panic!("{}\n{}", $expr, e),
+        }
+    }};
+}
+
+/// Asserts a closure returns an error that is an instance of the given
+/// exception class.
+///
+/// Optionally also asserts the error's message contains a string.
+///
+/// # Panics
+///
+/// Panics if the closure returns `Ok`, or an error of a different class or
+/// with a different message.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{assert_raises, Error, Ruby, Value};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     assert_raises!(ruby.exception_zero_div_error(), || {
+///         ruby.eval::<Value>("1 / 0")
+///     });
+///     assert_raises!(ruby.exception_arg_error(), "bad value", || {
+///         ruby.eval::<Value>("raise ArgumentError, 'bad value: 42'")
+///     });
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+#[macro_export]
+macro_rules! assert_raises {
+    ($class:expr, $func:expr) => {{
+        $crate::testing::assert_raises($class, None, $func)
+    }};
+    ($class:expr, $message:expr, $func:expr) => {{
+        $crate::testing::assert_raises($class, Some($message), $func)
+    }};
+}
+
 /// # Globals
 ///
 /// Functions for defining global variables, constants, etc, as well as
@@ -2459,6 +2714,114 @@ impl Ruby {
             other => Err(Error::from_tag(unsafe { transmute(other) })),
         }
     }
+
+    /// Evaluate a string of Ruby code, converting the result to a `T`,
+    /// raising `Timeout::Error` if evaluation takes longer than `timeout`.
+    ///
+    /// This uses Ruby's `Timeout` library, which interrupts the evaluating
+    /// thread with `Thread#raise`. Ruby only checks for interrupts when
+    /// running Ruby code or at blocking operations that release the GVL, so
+    /// code blocked in a native extension, or ensure blocks that don't
+    /// finish, can still exceed the timeout.
+    ///
+    /// Errors if `s` contains a null byte, the conversion fails, evaluation
+    /// does not finish within `timeout`, or on an uncaught Ruby exception.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use magnus::{prelude::*, Error, ExceptionClass, Ruby, Value};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let res: i64 = ruby.eval_with_timeout("1 + 2", Duration::from_secs(1))?;
+    ///     assert_eq!(res, 3);
+    ///
+    ///     let err = ruby
+    ///         .eval_with_timeout::<Value>("loop {}", Duration::from_millis(10))
+    ///         .unwrap_err();
+    ///     let timeout_error: ExceptionClass = ruby.eval("Timeout::Error")?;
+    ///     assert!(err.is_kind_of(timeout_error));
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn eval_with_timeout<T>(&self, s: &str, timeout: Duration) -> Result<T, Error>
+    where
+        T: TryConvert,
+    {
+        static TIMEOUT: LazyId = LazyId::new("timeout");
+
+        self.require("timeout")?;
+        let timeout_module: RModule = self.class_object().const_get("Timeout")?;
+        let code = s.to_owned();
+        let block = self.proc_from_fn(move |ruby, _args, _block| ruby.eval::<Value>(&code));
+        timeout_module.funcall_with_block(*TIMEOUT, (timeout.as_secs_f64(),), block)
+    }
+
+    /// Register a function to be called when the Ruby VM is shutting down.
+    ///
+    /// Registered functions are run alongside Ruby's `at_exit` handlers,
+    /// before the Ruby VM is cleaned up, so Ruby APIs may still be called.
+    /// This is intended for tearing down native resources such as background
+    /// threads or pools that hold Ruby values or call into Ruby.
+    ///
+    /// This works both when Rust is embedded in Ruby, e.g. in a Ruby Gem, and
+    /// when Ruby is embedded in Rust, where functions are run when the
+    /// [`Cleanup`](embed::Cleanup) guard is dropped.
+    ///
+    /// Functions are run in the reverse order of registration. A panic in a
+    /// function is caught, and does not prevent the remaining functions from
+    /// running.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.at_cleanup(|ruby| {
+    ///         let _: Result<magnus::Value, _> = ruby.eval("$stdout.flush");
+    ///     });
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn at_cleanup<F>(&self, func: F)
+    where
+        F: FnOnce(&Ruby) + Send + 'static,
+    {
+        let mut hooks = CLEANUP_HOOKS.lock().unwrap_or_else(|e| e.into_inner());
+        if !CLEANUP_HOOKS_SET.swap(true, Ordering::Relaxed) {
+            unsafe { rb_set_end_proc(Some(run_cleanup_hooks), self.qnil().as_rb_value()) };
+        }
+        hooks.push(Box::new(func));
+    }
+}
+
+type CleanupHook = Box<dyn FnOnce(&Ruby) + Send>;
+
+static CLEANUP_HOOKS: Mutex<Vec<CleanupHook>> = Mutex::new(Vec::new());
+static CLEANUP_HOOKS_SET: AtomicBool = AtomicBool::new(false);
+
+unsafe extern "C" fn run_cleanup_hooks(_: VALUE) {
+    let ruby = Ruby::get_unchecked();
+    // hooks may register further hooks, so keep going until none are left
+    loop {
+        let hook = CLEANUP_HOOKS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop();
+        match hook {
+            Some(hook) => {
+                let _ = catch_unwind(AssertUnwindSafe(|| hook(&ruby)));
+            }
+            None => break,
+        }
+    }
 }
 
 /// Define a class in the root scope.
@@ -10,7 +10,7 @@ macro_rules! debug_assert_value {
         // segfault in that case, which is kind of OK, as we're trying to panic
         // anyway.
         #[allow(unused_unsafe)]
-        #[cfg(debug_assertions)]
+        #[cfg(any(debug_assertions, feature = "sanitize"))]
         match unsafe { $crate::value::private::ReprValue::rb_type($value) } {
             ::rb_sys::ruby_value_type::RUBY_T_NONE | ::rb_sys::ruby_value_type::RUBY_T_ZOMBIE => {
                 panic!("Attempting to access garbage collected Object")
@@ -25,7 +25,8 @@ macro_rules! debug_assert_value {
 
 /// Get a `Ruby`.
 ///
-/// Panics in debug mode if not on a Ruby thread.
+/// Panics in debug mode, or with the `sanitize` feature, if not on a Ruby
+/// thread.
 /// Undefined behaviour in release if not on a Ruby thread.
 ///
 /// The check is currently not performed in release mode as the Ruby API
@@ -33,7 +34,7 @@ macro_rules! debug_assert_value {
 /// the check and the associated perfomance hit.
 macro_rules! get_ruby {
     () => {
-        if cfg!(debug_assertions) {
+        if cfg!(any(debug_assertions, feature = "sanitize")) {
             $crate::Ruby::get().unwrap()
         } else {
             #[allow(unused_unsafe)]
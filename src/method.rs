@@ -16,12 +16,13 @@ use crate::{
     error::{raise, Error, IntoError},
     into_value::{ArgList, IntoValue},
     r_array::RArray,
+    scan_args::{scan_args, ScanArgsKw},
     try_convert::TryConvert,
     value::{ReprValue, Value},
     Ruby,
 };
 
-mod private {
+pub(crate) mod private {
     use super::*;
 
     pub unsafe trait Method {
@@ -278,6 +279,19 @@ pub trait ReturnValue: private::ReturnValue {}
 
 impl<T> ReturnValue for T where T: private::ReturnValue {}
 
+/// Convert the return value of a wrapped function to a `Result<Value, Error>`.
+///
+/// Used by the [`wrap_method`](crate::wrap_method) and
+/// [`wrap_function`](crate::wrap_function) macros.
+#[doc(hidden)]
+#[inline]
+pub fn into_return_value<T>(val: T) -> Result<Value, Error>
+where
+    T: ReturnValue,
+{
+    val.into_return_value()
+}
+
 /// Trait marking types that can be returned to Ruby from a library
 /// [`init`](magnus_macros::init) function.
 ///
@@ -510,13 +524,7 @@ where
 
     #[inline]
     unsafe fn call_handle_error(self, rb_self: Value, args: RArray) -> Value {
-        let res = match std::panic::catch_unwind(AssertUnwindSafe(|| {
-            self.call_convert_value(rb_self, args)
-        })) {
-            Ok(v) => v,
-            Err(e) => Err(Error::from_panic(e)),
-        };
-        match res {
+        match catch_panic(|| self.call_convert_value(rb_self, args)) {
             Ok(v) => v,
             Err(e) => raise(e),
         }
@@ -557,13 +565,7 @@ where
 
     #[inline]
     unsafe fn call_handle_error(self, rb_self: Value, args: RArray) -> Value {
-        let res = match std::panic::catch_unwind(AssertUnwindSafe(|| {
-            self.call_convert_value(rb_self, args)
-        })) {
-            Ok(v) => v,
-            Err(e) => Err(Error::from_panic(e)),
-        };
-        match res {
+        match catch_panic(|| self.call_convert_value(rb_self, args)) {
             Ok(v) => v,
             Err(e) => raise(e),
         }
@@ -603,13 +605,7 @@ where
 
     #[inline]
     unsafe fn call_handle_error(self, argc: c_int, argv: *const Value, rb_self: Value) -> Value {
-        let res = match std::panic::catch_unwind(AssertUnwindSafe(|| {
-            self.call_convert_value(argc, argv, rb_self)
-        })) {
-            Ok(v) => v,
-            Err(e) => Err(Error::from_panic(e)),
-        };
-        match res {
+        match catch_panic(|| self.call_convert_value(argc, argv, rb_self)) {
             Ok(v) => v,
             Err(e) => raise(e),
         }
@@ -654,13 +650,7 @@ where
 
     #[inline]
     unsafe fn call_handle_error(self, argc: c_int, argv: *const Value, rb_self: Value) -> Value {
-        let res = match std::panic::catch_unwind(AssertUnwindSafe(|| {
-            self.call_convert_value(argc, argv, rb_self)
-        })) {
-            Ok(v) => v,
-            Err(e) => Err(Error::from_panic(e)),
-        };
-        match res {
+        match catch_panic(|| self.call_convert_value(argc, argv, rb_self)) {
             Ok(v) => v,
             Err(e) => raise(e),
         }
@@ -675,8 +665,256 @@ where
 {
 }
 
+/// Helper trait for wrapping a function as a Ruby method taking self, a slice
+/// of arguments, and an optional block, with type conversions and error
+/// handling.
+///
+/// See the [`method`](crate::method!) macro.
+#[doc(hidden)]
+pub trait MethodCAryBlock<RbSelf, Res>
+where
+    Self: Sized + Fn(RbSelf, &[Value], Option<Proc>) -> Res,
+    RbSelf: TryConvert,
+    Res: ReturnValue,
+{
+    #[inline]
+    unsafe fn call_convert_value(
+        self,
+        argc: c_int,
+        argv: *const Value,
+        rb_self: Value,
+    ) -> Result<Value, Error> {
+        let args = slice::from_raw_parts(argv, argc as usize);
+        (self)(
+            TryConvert::try_convert(rb_self)?,
+            args,
+            block_arg(&Ruby::get_with(rb_self))?,
+        )
+        .into_return_value()
+    }
+
+    #[inline]
+    unsafe fn call_handle_error(self, argc: c_int, argv: *const Value, rb_self: Value) -> Value {
+        match catch_panic(|| self.call_convert_value(argc, argv, rb_self)) {
+            Ok(v) => v,
+            Err(e) => raise(e),
+        }
+    }
+}
+
+impl<Func, RbSelf, Res> MethodCAryBlock<RbSelf, Res> for Func
+where
+    Func: Fn(RbSelf, &[Value], Option<Proc>) -> Res,
+    RbSelf: TryConvert,
+    Res: ReturnValue,
+{
+}
+
+/// Helper trait for wrapping a function as a Ruby method taking
+/// [`&Ruby`](Ruby), self, a slice of arguments, and an optional block, with
+/// type conversions and error handling.
+///
+/// See the [`method`](crate::method!) macro.
+#[doc(hidden)]
+pub trait RubyMethodCAryBlock<RbSelf, Res>
+where
+    Self: Sized + Fn(&Ruby, RbSelf, &[Value], Option<Proc>) -> Res,
+    RbSelf: TryConvert,
+    Res: ReturnValue,
+{
+    #[inline]
+    unsafe fn call_convert_value(
+        self,
+        argc: c_int,
+        argv: *const Value,
+        rb_self: Value,
+    ) -> Result<Value, Error> {
+        let args = slice::from_raw_parts(argv, argc as usize);
+        let ruby = Ruby::get_with(rb_self);
+        (self)(
+            &ruby,
+            TryConvert::try_convert(rb_self)?,
+            args,
+            block_arg(&ruby)?,
+        )
+        .into_return_value()
+    }
+
+    #[inline]
+    unsafe fn call_handle_error(self, argc: c_int, argv: *const Value, rb_self: Value) -> Value {
+        match catch_panic(|| self.call_convert_value(argc, argv, rb_self)) {
+            Ok(v) => v,
+            Err(e) => raise(e),
+        }
+    }
+}
+
+impl<Func, RbSelf, Res> RubyMethodCAryBlock<RbSelf, Res> for Func
+where
+    Func: Fn(&Ruby, RbSelf, &[Value], Option<Proc>) -> Res,
+    RbSelf: TryConvert,
+    Res: ReturnValue,
+{
+}
+
+/// Marker for the arity declared in the [`method`](crate::method!) and
+/// [`function`](crate::function!) macros.
+#[doc(hidden)]
+pub struct Arity<const N: usize>;
+
+/// Marker distinguishing functions that take a [`&Ruby`](Ruby) as their first
+/// argument when checking arity.
+#[doc(hidden)]
+pub struct WithRuby;
+
+/// Helper trait implemented for functions taking self and N arguments,
+/// regardless of the argument types.
+///
+/// Used to check the arity declared in [`method`](crate::method!) matches the
+/// function signature.
+#[doc(hidden)]
+pub trait MethodArity<const N: usize, Args> {}
+
+/// Helper trait implemented for functions taking N arguments, regardless of
+/// the argument types.
+///
+/// Used to check the arity declared in [`function`](crate::function!) matches
+/// the function signature.
+#[doc(hidden)]
+pub trait FunctionArity<const N: usize, Args> {}
+
+/// Helper trait implemented for functions taking self, N arguments, and an
+/// optional block, regardless of the argument types.
+///
+/// Used to check the arity declared in [`method`](crate::method!) matches the
+/// function signature.
+#[doc(hidden)]
+pub trait MethodBlockArity<const N: usize, Args> {}
+
+/// Helper trait implemented for functions taking N arguments and an optional
+/// block, regardless of the argument types.
+///
+/// Used to check the arity declared in [`function`](crate::function!) matches
+/// the function signature.
+#[doc(hidden)]
+pub trait FunctionBlockArity<const N: usize, Args> {}
+
+/// Helper trait implemented for functions taking self, N arguments, and
+/// keywords, regardless of the argument types.
+///
+/// Used to check the arity declared in [`method`](crate::method!) matches the
+/// function signature.
+#[doc(hidden)]
+pub trait MethodKwArity<const N: usize, Args> {}
+
+/// Helper trait implemented for functions taking N arguments and keywords,
+/// regardless of the argument types.
+///
+/// Used to check the arity declared in [`function`](crate::function!) matches
+/// the function signature.
+#[doc(hidden)]
+pub trait FunctionKwArity<const N: usize, Args> {}
+
+/// Compile time check that `func` takes self and `N` arguments.
+///
+/// With a mismatch between the declared arity and the function signature this
+/// fails to compile with an error such as "expected `1`, found `2`", rather
+/// than a less helpful error about an unimplemented trait.
+#[doc(hidden)]
+#[inline(always)]
+pub fn check_method_arity<Func, Args, const N: usize>(_func: &Func, _arity: Arity<N>)
+where
+    Func: MethodArity<N, Args>,
+{
+}
+
+/// Compile time check that `func` takes `N` arguments.
+///
+/// See [`check_method_arity`].
+#[doc(hidden)]
+#[inline(always)]
+pub fn check_function_arity<Func, Args, const N: usize>(_func: &Func, _arity: Arity<N>)
+where
+    Func: FunctionArity<N, Args>,
+{
+}
+
+/// Compile time check that `func` takes self, `N` arguments, and an optional
+/// block.
+///
+/// See [`check_method_arity`].
+#[doc(hidden)]
+#[inline(always)]
+pub fn check_method_block_arity<Func, Args, const N: usize>(_func: &Func, _arity: Arity<N>)
+where
+    Func: MethodBlockArity<N, Args>,
+{
+}
+
+/// Compile time check that `func` takes `N` arguments and an optional block.
+///
+/// See [`check_method_arity`].
+#[doc(hidden)]
+#[inline(always)]
+pub fn check_function_block_arity<Func, Args, const N: usize>(_func: &Func, _arity: Arity<N>)
+where
+    Func: FunctionBlockArity<N, Args>,
+{
+}
+
+/// Compile time check that `func` takes self, `N` arguments, and keywords.
+///
+/// See [`check_method_arity`].
+#[doc(hidden)]
+#[inline(always)]
+pub fn check_method_kw_arity<Func, Args, const N: usize>(_func: &Func, _arity: Arity<N>)
+where
+    Func: MethodKwArity<N, Args>,
+{
+}
+
+/// Compile time check that `func` takes `N` arguments and keywords.
+///
+/// See [`check_method_arity`].
+#[doc(hidden)]
+#[inline(always)]
+pub fn check_function_kw_arity<Func, Args, const N: usize>(_func: &Func, _arity: Arity<N>)
+where
+    Func: FunctionKwArity<N, Args>,
+{
+}
+
+/// Call `func`, converting a panic to an [`Error`].
+///
+/// With the `profile` feature enabled the call is also timed, see
+/// [`profile`](crate::profile).
+#[inline]
+fn catch_panic<F>(func: F) -> Result<Value, Error>
+where
+    F: FnOnce() -> Result<Value, Error>,
+{
+    #[cfg(feature = "profile")]
+    let start = crate::profile::start();
+    let res = match std::panic::catch_unwind(AssertUnwindSafe(func)) {
+        Ok(v) => v,
+        Err(e) => Err(Error::from_panic(e)),
+    };
+    #[cfg(feature = "profile")]
+    crate::profile::record(start);
+    res
+}
+
+/// The block passed to the current method, if any.
+fn block_arg(ruby: &Ruby) -> Result<Option<Proc>, Error> {
+    if ruby.block_given() {
+        ruby.block_proc().map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
 macro_rules! method_n {
-    ($name:ident, $ruby_name:ident, $n:literal) => {
+    ($name:ident, $ruby_name:ident, $block_name:ident, $ruby_block_name:ident, $n:literal) => {
         seq!(N in 0..$n {
             /// Helper trait for wrapping a function as a Ruby method taking
             /// self and N arguments, with type conversions and error handling.
@@ -700,14 +938,7 @@ macro_rules! method_n {
 
                 #[inline]
                 unsafe fn call_handle_error(self, rb_self: Value, #(arg~N: Value,)*) -> Value {
-                    let res =
-                        match std::panic::catch_unwind(AssertUnwindSafe(|| {
-                            self.call_convert_value(rb_self, #(arg~N,)*)
-                        })) {
-                            Ok(v) => v,
-                            Err(e) => Err(Error::from_panic(e)),
-                        };
-                    match res {
+                    match catch_panic(|| self.call_convert_value(rb_self, #(arg~N,)*)) {
                         Ok(v) => v,
                         Err(e) => raise(e),
                     }
@@ -746,14 +977,7 @@ macro_rules! method_n {
 
                 #[inline]
                 unsafe fn call_handle_error(self, rb_self: Value, #(arg~N: Value,)*) -> Value {
-                    let res =
-                        match std::panic::catch_unwind(AssertUnwindSafe(|| {
-                            self.call_convert_value(rb_self, #(arg~N,)*)
-                        })) {
-                            Ok(v) => v,
-                            Err(e) => Err(Error::from_panic(e)),
-                        };
-                    match res {
+                    match catch_panic(|| self.call_convert_value(rb_self, #(arg~N,)*)) {
                         Ok(v) => v,
                         Err(e) => raise(e),
                     }
@@ -767,22 +991,243 @@ macro_rules! method_n {
                 #(T~N: TryConvert,)*
                 Res: ReturnValue,
             {}
+
+            impl<Func, RbSelf, #(T~N,)* Res> MethodArity<$n, (RbSelf, #(T~N,)* Res)> for Func
+            where
+                Func: Fn(RbSelf, #(T~N,)*) -> Res,
+            {}
+
+            impl<Func, RbSelf, #(T~N,)* Res> MethodArity<$n, (WithRuby, RbSelf, #(T~N,)* Res)> for Func
+            where
+                Func: Fn(&Ruby, RbSelf, #(T~N,)*) -> Res,
+            {}
+
+            /// Helper trait for wrapping a function as a Ruby method taking
+            /// self, N arguments, and an optional block, with type
+            /// conversions and error handling.
+            ///
+            /// See the [`method`](crate::method!) macro.
+            #[doc(hidden)]
+            pub trait $block_name<RbSelf, #(T~N,)* Res>
+            where
+                Self: Sized + Fn(RbSelf, #(T~N,)* Option<Proc>) -> Res,
+                RbSelf: TryConvert,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {
+                #[inline]
+                fn call_convert_value(self, rb_self: Value, #(arg~N: Value,)*) -> Result<Value, Error> {
+                    (self)(
+                        TryConvert::try_convert(rb_self)?,
+                        #(TryConvert::try_convert(arg~N)?,)*
+                        block_arg(&Ruby::get_with(rb_self))?,
+                    ).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, rb_self: Value, #(arg~N: Value,)*) -> Value {
+                    match catch_panic(|| self.call_convert_value(rb_self, #(arg~N,)*)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, RbSelf, #(T~N,)* Res> $block_name<RbSelf, #(T~N,)* Res> for Func
+            where
+                Func: Fn(RbSelf, #(T~N,)* Option<Proc>) -> Res,
+                RbSelf: TryConvert,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {}
+
+            /// Helper trait for wrapping a function as a Ruby method taking
+            /// [`&Ruby`](Ruby), self, N arguments, and an optional block,
+            /// with type conversions and error handling.
+            ///
+            /// See the [`method`](crate::method!) macro.
+            #[doc(hidden)]
+            pub trait $ruby_block_name<RbSelf, #(T~N,)* Res>
+            where
+                Self: Sized + Fn(&Ruby, RbSelf, #(T~N,)* Option<Proc>) -> Res,
+                RbSelf: TryConvert,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {
+                #[inline]
+                fn call_convert_value(self, rb_self: Value, #(arg~N: Value,)*) -> Result<Value, Error> {
+                    let ruby = Ruby::get_with(rb_self);
+                    (self)(
+                        &ruby,
+                        TryConvert::try_convert(rb_self)?,
+                        #(TryConvert::try_convert(arg~N)?,)*
+                        block_arg(&ruby)?,
+                    ).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, rb_self: Value, #(arg~N: Value,)*) -> Value {
+                    match catch_panic(|| self.call_convert_value(rb_self, #(arg~N,)*)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, RbSelf, #(T~N,)* Res> $ruby_block_name<RbSelf, #(T~N,)* Res> for Func
+            where
+                Func: Fn(&Ruby, RbSelf, #(T~N,)* Option<Proc>) -> Res,
+                RbSelf: TryConvert,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {}
+
+            impl<Func, RbSelf, #(T~N,)* Res> MethodBlockArity<$n, (RbSelf, #(T~N,)* Res)> for Func
+            where
+                Func: Fn(RbSelf, #(T~N,)* Option<Proc>) -> Res,
+            {}
+
+            impl<Func, RbSelf, #(T~N,)* Res> MethodBlockArity<$n, (WithRuby, RbSelf, #(T~N,)* Res)> for Func
+            where
+                Func: Fn(&Ruby, RbSelf, #(T~N,)* Option<Proc>) -> Res,
+            {}
         });
     }
 }
 
 seq!(N in 0..=16 {
-    method_n!(Method~N, RubyMethod~N, N);
+    method_n!(Method~N, RubyMethod~N, MethodBlock~N, RubyMethodBlock~N, N);
 });
 
-/// Wrap a Rust function item with Ruby type conversion and error handling.
-///
-/// This macro wraps the given function and returns a function pointer
-/// implementing the [`Method`] trait, suitable for passing to functions that
-/// define Ruby methods such as
-/// [`define_method`](crate::module::Module::define_method).
-///
-/// Ruby code implicitly always has a `self` parameter available. In the
+macro_rules! method_kw_n {
+    ($name:ident, $ruby_name:ident, $n:literal) => {
+        seq!(N in 0..$n {
+            /// Helper trait for wrapping a function as a Ruby method taking
+            /// self, N arguments, and keywords, with type conversions and
+            /// error handling.
+            ///
+            /// See the [`method`](crate::method!) macro.
+            #[doc(hidden)]
+            pub trait $name<RbSelf, #(T~N,)* Kw, Res>
+            where
+                Self: Sized + Fn(RbSelf, #(T~N,)* Kw) -> Res,
+                RbSelf: TryConvert,
+                #(T~N: TryConvert,)*
+                Kw: ScanArgsKw,
+                Res: ReturnValue,
+            {
+                #[inline]
+                unsafe fn call_convert_value(
+                    self,
+                    argc: c_int,
+                    argv: *const Value,
+                    rb_self: Value,
+                ) -> Result<Value, Error> {
+                    let args = scan_args::<(#(T~N,)*), (), (), (), Kw, ()>(
+                        slice::from_raw_parts(argv, argc as usize),
+                    )?;
+                    let (#(arg~N,)*) = args.required;
+                    (self)(
+                        TryConvert::try_convert(rb_self)?,
+                        #(arg~N,)*
+                        args.keywords,
+                    ).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, argc: c_int, argv: *const Value, rb_self: Value) -> Value {
+                    match catch_panic(|| self.call_convert_value(argc, argv, rb_self)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, RbSelf, #(T~N,)* Kw, Res> $name<RbSelf, #(T~N,)* Kw, Res> for Func
+            where
+                Func: Fn(RbSelf, #(T~N,)* Kw) -> Res,
+                RbSelf: TryConvert,
+                #(T~N: TryConvert,)*
+                Kw: ScanArgsKw,
+                Res: ReturnValue,
+            {}
+
+            /// Helper trait for wrapping a function as a Ruby method taking
+            /// [`&Ruby`](Ruby), self, N arguments, and keywords, with type
+            /// conversions and error handling.
+            ///
+            /// See the [`method`](crate::method!) macro.
+            #[doc(hidden)]
+            pub trait $ruby_name<RbSelf, #(T~N,)* Kw, Res>
+            where
+                Self: Sized + Fn(&Ruby, RbSelf, #(T~N,)* Kw) -> Res,
+                RbSelf: TryConvert,
+                #(T~N: TryConvert,)*
+                Kw: ScanArgsKw,
+                Res: ReturnValue,
+            {
+                #[inline]
+                unsafe fn call_convert_value(
+                    self,
+                    argc: c_int,
+                    argv: *const Value,
+                    rb_self: Value,
+                ) -> Result<Value, Error> {
+                    let args = scan_args::<(#(T~N,)*), (), (), (), Kw, ()>(
+                        slice::from_raw_parts(argv, argc as usize),
+                    )?;
+                    let (#(arg~N,)*) = args.required;
+                    (self)(
+                        &Ruby::get_with(rb_self),
+                        TryConvert::try_convert(rb_self)?,
+                        #(arg~N,)*
+                        args.keywords,
+                    ).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, argc: c_int, argv: *const Value, rb_self: Value) -> Value {
+                    match catch_panic(|| self.call_convert_value(argc, argv, rb_self)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, RbSelf, #(T~N,)* Kw, Res> $ruby_name<RbSelf, #(T~N,)* Kw, Res> for Func
+            where
+                Func: Fn(&Ruby, RbSelf, #(T~N,)* Kw) -> Res,
+                RbSelf: TryConvert,
+                #(T~N: TryConvert,)*
+                Kw: ScanArgsKw,
+                Res: ReturnValue,
+            {}
+
+            impl<Func, RbSelf, #(T~N,)* Kw, Res> MethodKwArity<$n, (RbSelf, #(T~N,)* Kw, Res)> for Func
+            where
+                Func: Fn(RbSelf, #(T~N,)* Kw) -> Res,
+            {}
+
+            impl<Func, RbSelf, #(T~N,)* Kw, Res> MethodKwArity<$n, (WithRuby, RbSelf, #(T~N,)* Kw, Res)> for Func
+            where
+                Func: Fn(&Ruby, RbSelf, #(T~N,)* Kw) -> Res,
+            {}
+        });
+    }
+}
+
+seq!(N in 0..=9 {
+    method_kw_n!(MethodKw~N, RubyMethodKw~N, N);
+});
+
+/// Wrap a Rust function item with Ruby type conversion and error handling.
+///
+/// This macro wraps the given function and returns a function pointer
+/// implementing the [`Method`] trait, suitable for passing to functions that
+/// define Ruby methods such as
+/// [`define_method`](crate::module::Module::define_method).
+///
+/// Ruby code implicitly always has a `self` parameter available. In the
 /// extention API this is passed explicitly. As a result there is always an
 /// extra `self` argument before the arguments explitly passed in Ruby, and the
 /// number of Rust argument will be one more than the Ruby arity.
@@ -813,6 +1258,120 @@ seq!(N in 0..=16 {
 /// See the [`function`](crate::function!) macro for cases where there is no
 /// need to handle the `self` argument.
 ///
+/// Passing `kwargs` after the arity, e.g. `method!(name, 1, kwargs)`, wraps a
+/// function taking an additional final argument for the keyword arguments
+/// passed to the method. This argument can be a [`RHash`](crate::RHash),
+/// which will be empty if no keywords were given, or a type implementing
+/// [`Keywords`](crate::scan_args::Keywords). The keywords do not count
+/// towards the arity, which can be at most 9.
+///
+/// ```
+/// use magnus::{method, prelude::*, rb_assert, Error, Ruby};
+///
+/// #[derive(magnus::Keywords)]
+/// struct SplitOpts {
+///     limit: Option<usize>,
+/// }
+///
+/// fn rb_split_on(rb_self: String, sep: String, opts: SplitOpts) -> Vec<String> {
+///     match opts.limit {
+///         Some(n) => rb_self.splitn(n, &*sep).map(String::from).collect(),
+///         None => rb_self.split(&*sep).map(String::from).collect(),
+///     }
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let class = ruby.define_class("String", ruby.class_object())?;
+///     class.define_method("split_on", method!(rb_split_on, 1, kwargs))?;
+///
+///     rb_assert!(ruby, r#""a-b-c".split_on("-") == ["a", "b", "c"]"#);
+///     rb_assert!(ruby, r#""a-b-c".split_on("-", limit: 2) == ["a", "b-c"]"#);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+///
+/// Passing `block` after the arity, e.g. `method!(name, 1, block)`, wraps a
+/// function taking an additional final argument of `Option<Proc>`. This will
+/// be the block passed to the method, or `None` if no block was given. The
+/// block does not count towards the arity. With an arity of `-1` the
+/// function takes `rb_self`, the slice of arguments, and the block.
+///
+/// ```
+/// use magnus::{block::Proc, method, prelude::*, rb_assert, Error, Ruby, Value};
+///
+/// fn rb_each_word(rb_self: String, block: Option<Proc>) -> Result<usize, Error> {
+///     let mut count = 0;
+///     for word in rb_self.split_whitespace() {
+///         if let Some(block) = block {
+///             let _: Value = block.call((word,))?;
+///         }
+///         count += 1;
+///     }
+///     Ok(count)
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let class = ruby.define_class("String", ruby.class_object())?;
+///     class.define_method("each_word", method!(rb_each_word, 0, block))?;
+///
+///     rb_assert!(ruby, r#""hello world".each_word == 2"#);
+///     rb_assert!(
+///         ruby,
+///         r#"words = []; "hello world".each_word { |w| words << w }; words == ["hello", "world"]"#
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+///
+/// ```
+/// use magnus::{block::Proc, method, prelude::*, rb_assert, Error, Ruby, Value};
+///
+/// fn rb_tap_all(rb_self: Value, args: &[Value], block: Option<Proc>) -> Result<Value, Error> {
+///     if let Some(block) = block {
+///         for arg in args {
+///             let _: Value = block.call((rb_self, *arg))?;
+///         }
+///     }
+///     Ok(rb_self)
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.class_object()
+///         .define_method("tap_all", method!(rb_tap_all, -1, block))?;
+///
+///     rb_assert!(
+///         ruby,
+///         r#"seen = []; 1.tap_all(2, 3, 4) { |s, a| seen << s + a }; seen == [3, 4, 5]"#
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+///
+/// A mismatch between `arity` and the number of arguments the function takes
+/// is a compile error, reporting the expected and found arity.
+///
+/// ```compile_fail
+/// use magnus::{method, Error, Ruby};
+///
+/// fn rb_is_blank(rb_self: String) -> bool {
+///     rb_self.contains(|c: char| !c.is_whitespace())
+/// }
+///
+/// # #[allow(dead_code)]
+/// fn init(ruby: &Ruby) -> Result<(), Error> {
+///     let class = ruby.define_class("String", ruby.class_object())?;
+///     // error: expected `0`, found `1`
+///     class.define_method("blank?", method!(rb_is_blank, 1))?;
+///     Ok(())
+/// }
+/// ```
+///
 /// # Examples
 ///
 /// ```
@@ -858,6 +1417,7 @@ macro_rules! method {
     ($name:expr, 0) => {{
         unsafe extern "C" fn anon(rb_self: $crate::Value) -> $crate::Value {
             use $crate::method::{Method0, RubyMethod0};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<0>);
             $name.call_handle_error(rb_self)
         }
         anon as unsafe extern "C" fn($crate::Value) -> $crate::Value
@@ -865,6 +1425,7 @@ macro_rules! method {
     ($name:expr, 1) => {{
         unsafe extern "C" fn anon(rb_self: $crate::Value, a: $crate::Value) -> $crate::Value {
             use $crate::method::{Method1, RubyMethod1};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<1>);
             $name.call_handle_error(rb_self, a)
         }
         anon as unsafe extern "C" fn($crate::Value, $crate::Value) -> $crate::Value
@@ -876,6 +1437,7 @@ macro_rules! method {
             b: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method2, RubyMethod2};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<2>);
             $name.call_handle_error(rb_self, a, b)
         }
         anon as unsafe extern "C" fn($crate::Value, $crate::Value, $crate::Value) -> $crate::Value
@@ -888,6 +1450,7 @@ macro_rules! method {
             c: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method3, RubyMethod3};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<3>);
             $name.call_handle_error(rb_self, a, b, c)
         }
         anon as unsafe extern "C" fn(
@@ -906,6 +1469,7 @@ macro_rules! method {
             d: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method4, RubyMethod4};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<4>);
             $name.call_handle_error(rb_self, a, b, c, d)
         }
         anon as unsafe extern "C" fn(
@@ -926,6 +1490,7 @@ macro_rules! method {
             e: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method5, RubyMethod5};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<5>);
             $name.call_handle_error(rb_self, a, b, c, d, e)
         }
         anon as unsafe extern "C" fn(
@@ -948,6 +1513,7 @@ macro_rules! method {
             f: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method6, RubyMethod6};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<6>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f)
         }
         anon as unsafe extern "C" fn(
@@ -972,6 +1538,7 @@ macro_rules! method {
             g: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method7, RubyMethod7};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<7>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g)
         }
         anon as unsafe extern "C" fn(
@@ -998,6 +1565,7 @@ macro_rules! method {
             h: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method8, RubyMethod8};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<8>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h)
         }
         anon as unsafe extern "C" fn(
@@ -1026,6 +1594,7 @@ macro_rules! method {
             i: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method9, RubyMethod9};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<9>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i)
         }
         anon as unsafe extern "C" fn(
@@ -1056,6 +1625,7 @@ macro_rules! method {
             j: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method10, RubyMethod10};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<10>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j)
         }
         anon as unsafe extern "C" fn(
@@ -1088,6 +1658,7 @@ macro_rules! method {
             k: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method11, RubyMethod11};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<11>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k)
         }
         anon as unsafe extern "C" fn(
@@ -1122,6 +1693,7 @@ macro_rules! method {
             l: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method12, RubyMethod12};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<12>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l)
         }
         anon as unsafe extern "C" fn(
@@ -1158,6 +1730,7 @@ macro_rules! method {
             m: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method13, RubyMethod13};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<13>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l, m)
         }
         anon as unsafe extern "C" fn(
@@ -1196,6 +1769,7 @@ macro_rules! method {
             n: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method14, RubyMethod14};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<14>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l, m, n)
         }
         anon as unsafe extern "C" fn(
@@ -1236,6 +1810,7 @@ macro_rules! method {
             o: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method15, RubyMethod15};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<15>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l, m, n, o)
         }
         anon as unsafe extern "C" fn(
@@ -1278,6 +1853,7 @@ macro_rules! method {
             p: $crate::Value,
         ) -> $crate::Value {
             use $crate::method::{Method16, RubyMethod16};
+            $crate::method::check_method_arity(&$name, $crate::method::Arity::<16>);
             $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p)
         }
         anon as unsafe extern "C" fn(
@@ -1300,332 +1876,1687 @@ macro_rules! method {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, $arity:expr) => {
-        compile_error!("arity must be an integer literal between -2..=16")
-    };
-}
-
-/// Helper trait for wrapping a function as a Ruby method ignoring self and
-/// taking a Ruby array of arguments, with type conversions and error handling.
-///
-/// See the [`function`](crate::function!) macro.
-#[doc(hidden)]
-pub trait FunctionRbAry<Args, Res>
-where
-    Self: Sized + Fn(Args) -> Res,
-    Args: TryConvert,
-    Res: ReturnValue,
-{
-    #[inline]
-    fn call_convert_value(self, args: RArray) -> Result<Value, Error> {
-        (self)(TryConvert::try_convert(args.as_value())?).into_return_value()
-    }
-
-    #[inline]
-    unsafe fn call_handle_error(self, args: RArray) -> Value {
-        let res = match std::panic::catch_unwind(AssertUnwindSafe(|| self.call_convert_value(args)))
-        {
-            Ok(v) => v,
-            Err(e) => Err(Error::from_panic(e)),
-        };
-        match res {
-            Ok(v) => v,
-            Err(e) => raise(e),
+    ($name:expr, -1, block) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodCAryBlock, RubyMethodCAryBlock};
+            $name.call_handle_error(argc, argv, rb_self)
         }
-    }
-}
-
-impl<Func, Args, Res> FunctionRbAry<Args, Res> for Func
-where
-    Func: Fn(Args) -> Res,
-    Args: TryConvert,
-    Res: ReturnValue,
-{
-}
-
-/// Helper trait for wrapping a function as a Ruby method taking
-/// [`&Ruby`](Ruby), ignoring self, and taking a Ruby array of arguments, with
-/// type conversions and error handling.
-///
-/// See the [`function`](crate::function!) macro.
-#[doc(hidden)]
-pub trait RubyFunctionRbAry<Args, Res>
-where
-    Self: Sized + Fn(&Ruby, Args) -> Res,
-    Args: TryConvert,
-    Res: ReturnValue,
-{
-    #[inline]
-    fn call_convert_value(self, args: RArray) -> Result<Value, Error> {
-        (self)(
-            &Ruby::get_with(args),
-            TryConvert::try_convert(args.as_value())?,
-        )
-        .into_return_value()
-    }
-
-    #[inline]
-    unsafe fn call_handle_error(self, args: RArray) -> Value {
-        let res = match std::panic::catch_unwind(AssertUnwindSafe(|| self.call_convert_value(args)))
-        {
-            Ok(v) => v,
-            Err(e) => Err(Error::from_panic(e)),
-        };
-        match res {
-            Ok(v) => v,
-            Err(e) => raise(e),
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 0, block) => {{
+        unsafe extern "C" fn anon(rb_self: $crate::Value) -> $crate::Value {
+            use $crate::method::{MethodBlock0, RubyMethodBlock0};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<0>);
+            $name.call_handle_error(rb_self)
         }
-    }
-}
-
-impl<Func, Args, Res> RubyFunctionRbAry<Args, Res> for Func
-where
-    Func: Fn(&Ruby, Args) -> Res,
-    Args: TryConvert,
-    Res: ReturnValue,
-{
-}
-
-/// Helper trait for wrapping a function as a Ruby method ignoring self and
-/// taking a slice of arguments, with type conversions and error handling.
-///
-/// See the [`function`](crate::function!) macro.
-#[doc(hidden)]
-pub trait FunctionCAry<Res>
-where
-    Self: Sized + Fn(&[Value]) -> Res,
-    Res: ReturnValue,
-{
-    #[inline]
-    unsafe fn call_convert_value(self, argc: c_int, argv: *const Value) -> Result<Value, Error> {
-        let args = slice::from_raw_parts(argv, argc as usize);
-        (self)(args).into_return_value()
-    }
-
-    #[inline]
-    unsafe fn call_handle_error(self, argc: c_int, argv: *const Value) -> Value {
-        let res = match std::panic::catch_unwind(AssertUnwindSafe(|| {
-            self.call_convert_value(argc, argv)
-        })) {
-            Ok(v) => v,
-            Err(e) => Err(Error::from_panic(e)),
-        };
-        match res {
-            Ok(v) => v,
-            Err(e) => raise(e),
+        anon as unsafe extern "C" fn($crate::Value) -> $crate::Value
+    }};
+    ($name:expr, 1, block) => {{
+        unsafe extern "C" fn anon(rb_self: $crate::Value, a: $crate::Value) -> $crate::Value {
+            use $crate::method::{MethodBlock1, RubyMethodBlock1};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<1>);
+            $name.call_handle_error(rb_self, a)
         }
-    }
-}
-
-impl<Func, Res> FunctionCAry<Res> for Func
-where
-    Func: Fn(&[Value]) -> Res,
-    Res: ReturnValue,
+        anon as unsafe extern "C" fn($crate::Value, $crate::Value) -> $crate::Value
+    }};
+    ($name:expr, 2, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock2, RubyMethodBlock2};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<2>);
+            $name.call_handle_error(rb_self, a, b)
+        }
+        anon as unsafe extern "C" fn($crate::Value, $crate::Value, $crate::Value) -> $crate::Value
+    }};
+    ($name:expr, 3, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock3, RubyMethodBlock3};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<3>);
+            $name.call_handle_error(rb_self, a, b, c)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 4, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock4, RubyMethodBlock4};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<4>);
+            $name.call_handle_error(rb_self, a, b, c, d)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 5, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock5, RubyMethodBlock5};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<5>);
+            $name.call_handle_error(rb_self, a, b, c, d, e)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 6, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock6, RubyMethodBlock6};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<6>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 7, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock7, RubyMethodBlock7};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<7>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 8, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock8, RubyMethodBlock8};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<8>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 9, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock9, RubyMethodBlock9};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<9>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 10, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock10, RubyMethodBlock10};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<10>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 11, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock11, RubyMethodBlock11};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<11>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 12, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock12, RubyMethodBlock12};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<12>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 13, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+            m: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock13, RubyMethodBlock13};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<13>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l, m)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 14, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+            m: $crate::Value,
+            n: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock14, RubyMethodBlock14};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<14>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l, m, n)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 15, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+            m: $crate::Value,
+            n: $crate::Value,
+            o: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock15, RubyMethodBlock15};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<15>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l, m, n, o)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 16, block) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+            m: $crate::Value,
+            n: $crate::Value,
+            o: $crate::Value,
+            p: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodBlock16, RubyMethodBlock16};
+            $crate::method::check_method_block_arity(&$name, $crate::method::Arity::<16>);
+            $name.call_handle_error(rb_self, a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 0, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw0, RubyMethodKw0};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<0>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 1, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw1, RubyMethodKw1};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<1>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 2, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw2, RubyMethodKw2};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<2>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 3, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw3, RubyMethodKw3};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<3>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 4, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw4, RubyMethodKw4};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<4>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 5, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw5, RubyMethodKw5};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<5>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 6, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw6, RubyMethodKw6};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<6>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 7, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw7, RubyMethodKw7};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<7>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 8, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw8, RubyMethodKw8};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<8>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 9, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{MethodKw9, RubyMethodKw9};
+            $crate::method::check_method_kw_arity(&$name, $crate::method::Arity::<9>);
+            $name.call_handle_error(argc, argv, rb_self)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, $arity:expr) => {
+        compile_error!("arity must be an integer literal between -2..=16, use -1 to take a slice of any number of arguments")
+    };
+    ($name:expr, $arity:expr, block) => {
+        compile_error!("arity must be an integer literal between -1..=16 when taking a block")
+    };
+    ($name:expr, $arity:expr, kwargs) => {
+        compile_error!("arity must be an integer literal between 0..=9 when taking keywords")
+    };
+}
+
+/// Helper trait for wrapping a function as a Ruby method ignoring self and
+/// taking a Ruby array of arguments, with type conversions and error handling.
+///
+/// See the [`function`](crate::function!) macro.
+#[doc(hidden)]
+pub trait FunctionRbAry<Args, Res>
+where
+    Self: Sized + Fn(Args) -> Res,
+    Args: TryConvert,
+    Res: ReturnValue,
+{
+    #[inline]
+    fn call_convert_value(self, args: RArray) -> Result<Value, Error> {
+        (self)(TryConvert::try_convert(args.as_value())?).into_return_value()
+    }
+
+    #[inline]
+    unsafe fn call_handle_error(self, args: RArray) -> Value {
+        match catch_panic(|| self.call_convert_value(args)) {
+            Ok(v) => v,
+            Err(e) => raise(e),
+        }
+    }
+}
+
+impl<Func, Args, Res> FunctionRbAry<Args, Res> for Func
+where
+    Func: Fn(Args) -> Res,
+    Args: TryConvert,
+    Res: ReturnValue,
+{
+}
+
+/// Helper trait for wrapping a function as a Ruby method taking
+/// [`&Ruby`](Ruby), ignoring self, and taking a Ruby array of arguments, with
+/// type conversions and error handling.
+///
+/// See the [`function`](crate::function!) macro.
+#[doc(hidden)]
+pub trait RubyFunctionRbAry<Args, Res>
+where
+    Self: Sized + Fn(&Ruby, Args) -> Res,
+    Args: TryConvert,
+    Res: ReturnValue,
+{
+    #[inline]
+    fn call_convert_value(self, args: RArray) -> Result<Value, Error> {
+        (self)(
+            &Ruby::get_with(args),
+            TryConvert::try_convert(args.as_value())?,
+        )
+        .into_return_value()
+    }
+
+    #[inline]
+    unsafe fn call_handle_error(self, args: RArray) -> Value {
+        match catch_panic(|| self.call_convert_value(args)) {
+            Ok(v) => v,
+            Err(e) => raise(e),
+        }
+    }
+}
+
+impl<Func, Args, Res> RubyFunctionRbAry<Args, Res> for Func
+where
+    Func: Fn(&Ruby, Args) -> Res,
+    Args: TryConvert,
+    Res: ReturnValue,
+{
+}
+
+/// Helper trait for wrapping a function as a Ruby method ignoring self and
+/// taking a slice of arguments, with type conversions and error handling.
+///
+/// See the [`function`](crate::function!) macro.
+#[doc(hidden)]
+pub trait FunctionCAry<Res>
+where
+    Self: Sized + Fn(&[Value]) -> Res,
+    Res: ReturnValue,
+{
+    #[inline]
+    unsafe fn call_convert_value(self, argc: c_int, argv: *const Value) -> Result<Value, Error> {
+        let args = slice::from_raw_parts(argv, argc as usize);
+        (self)(args).into_return_value()
+    }
+
+    #[inline]
+    unsafe fn call_handle_error(self, argc: c_int, argv: *const Value) -> Value {
+        match catch_panic(|| self.call_convert_value(argc, argv)) {
+            Ok(v) => v,
+            Err(e) => raise(e),
+        }
+    }
+}
+
+impl<Func, Res> FunctionCAry<Res> for Func
+where
+    Func: Fn(&[Value]) -> Res,
+    Res: ReturnValue,
+{
+}
+
+/// Helper trait for wrapping a function as a Ruby method taking
+/// [`&Ruby`](Ruby), ignoring self, and taking a slice of arguments, with type
+/// conversions and error handling.
+///
+/// See the [`function`](crate::function!) macro.
+#[doc(hidden)]
+pub trait RubyFunctionCAry<Res>
+where
+    Self: Sized + Fn(&Ruby, &[Value]) -> Res,
+    Res: ReturnValue,
+{
+    #[inline]
+    unsafe fn call_convert_value(self, argc: c_int, argv: *const Value) -> Result<Value, Error> {
+        let args = slice::from_raw_parts(argv, argc as usize);
+        (self)(&Ruby::get_unchecked(), args).into_return_value()
+    }
+
+    #[inline]
+    unsafe fn call_handle_error(self, argc: c_int, argv: *const Value) -> Value {
+        match catch_panic(|| self.call_convert_value(argc, argv)) {
+            Ok(v) => v,
+            Err(e) => raise(e),
+        }
+    }
+}
+
+impl<Func, Res> RubyFunctionCAry<Res> for Func
+where
+    Func: Fn(&Ruby, &[Value]) -> Res,
+    Res: ReturnValue,
+{
+}
+
+/// Helper trait for wrapping a function as a Ruby method ignoring self and
+/// taking a slice of arguments and an optional block, with type conversions
+/// and error handling.
+///
+/// See the [`function`](crate::function!) macro.
+#[doc(hidden)]
+pub trait FunctionCAryBlock<Res>
+where
+    Self: Sized + Fn(&[Value], Option<Proc>) -> Res,
+    Res: ReturnValue,
+{
+    #[inline]
+    unsafe fn call_convert_value(self, argc: c_int, argv: *const Value) -> Result<Value, Error> {
+        let args = slice::from_raw_parts(argv, argc as usize);
+        (self)(args, block_arg(&Ruby::get_unchecked())?).into_return_value()
+    }
+
+    #[inline]
+    unsafe fn call_handle_error(self, argc: c_int, argv: *const Value) -> Value {
+        match catch_panic(|| self.call_convert_value(argc, argv)) {
+            Ok(v) => v,
+            Err(e) => raise(e),
+        }
+    }
+}
+
+impl<Func, Res> FunctionCAryBlock<Res> for Func
+where
+    Func: Fn(&[Value], Option<Proc>) -> Res,
+    Res: ReturnValue,
+{
+}
+
+/// Helper trait for wrapping a function as a Ruby method taking
+/// [`&Ruby`](Ruby), ignoring self, and taking a slice of arguments and an
+/// optional block, with type conversions and error handling.
+///
+/// See the [`function`](crate::function!) macro.
+#[doc(hidden)]
+pub trait RubyFunctionCAryBlock<Res>
+where
+    Self: Sized + Fn(&Ruby, &[Value], Option<Proc>) -> Res,
+    Res: ReturnValue,
+{
+    #[inline]
+    unsafe fn call_convert_value(self, argc: c_int, argv: *const Value) -> Result<Value, Error> {
+        let args = slice::from_raw_parts(argv, argc as usize);
+        let ruby = Ruby::get_unchecked();
+        (self)(&ruby, args, block_arg(&ruby)?).into_return_value()
+    }
+
+    #[inline]
+    unsafe fn call_handle_error(self, argc: c_int, argv: *const Value) -> Value {
+        match catch_panic(|| self.call_convert_value(argc, argv)) {
+            Ok(v) => v,
+            Err(e) => raise(e),
+        }
+    }
+}
+
+impl<Func, Res> RubyFunctionCAryBlock<Res> for Func
+where
+    Func: Fn(&Ruby, &[Value], Option<Proc>) -> Res,
+    Res: ReturnValue,
 {
 }
 
-/// Helper trait for wrapping a function as a Ruby method taking
-/// [`&Ruby`](Ruby), ignoring self, and taking a slice of arguments, with type
-/// conversions and error handling.
-///
-/// See the [`function`](crate::function!) macro.
-#[doc(hidden)]
-pub trait RubyFunctionCAry<Res>
-where
-    Self: Sized + Fn(&Ruby, &[Value]) -> Res,
-    Res: ReturnValue,
-{
-    #[inline]
-    unsafe fn call_convert_value(self, argc: c_int, argv: *const Value) -> Result<Value, Error> {
-        let args = slice::from_raw_parts(argv, argc as usize);
-        (self)(&Ruby::get_unchecked(), args).into_return_value()
+macro_rules! function_n {
+    ($name:ident, $ruby_name:ident, $block_name:ident, $ruby_block_name:ident, $n:literal) => {
+        seq!(N in 0..$n {
+            /// Helper trait for wrapping a function as a Ruby method ignoring
+            /// self and taking N arguments, with type conversions and error
+            /// handling.
+            ///
+            /// See the [`function`](crate::function!) macro.
+            #[doc(hidden)]
+            pub trait $name<#(T~N,)* Res>
+            where
+                Self: Sized + Fn(#(T~N,)*) -> Res,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {
+                #[inline]
+                fn call_convert_value(self, #(arg~N: Value,)*) -> Result<Value, Error> {
+                    (self)(
+                        #(TryConvert::try_convert(arg~N)?,)*
+                    ).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, #(arg~N: Value,)*) -> Value {
+                    match catch_panic(|| self.call_convert_value(#(arg~N,)*)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, #(T~N,)* Res> $name<#(T~N,)* Res> for Func
+            where
+                Func: Fn(#(T~N,)*) -> Res,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {}
+
+            /// Helper trait for wrapping a function as a Ruby method taking
+            /// [`&Ruby`](Ruby), ignoring self, and taking N arguments, with
+            /// type conversions and error handling.
+            ///
+            /// See the [`function`](crate::function!) macro.
+            #[doc(hidden)]
+            pub trait $ruby_name<#(T~N,)* Res>
+            where
+                Self: Sized + Fn(&Ruby, #(T~N,)*) -> Res,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {
+                #[inline]
+                unsafe fn call_convert_value(self, #(arg~N: Value,)*) -> Result<Value, Error> {
+                    (self)(
+                        &Ruby::get_unchecked(),
+                        #(TryConvert::try_convert(arg~N)?,)*
+                    ).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, #(arg~N: Value,)*) -> Value {
+                    match catch_panic(|| self.call_convert_value(#(arg~N,)*)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, #(T~N,)* Res> $ruby_name<#(T~N,)* Res> for Func
+            where
+                Func: Fn(&Ruby, #(T~N,)*) -> Res,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {}
+
+            impl<Func, #(T~N,)* Res> FunctionArity<$n, (#(T~N,)* Res,)> for Func
+            where
+                Func: Fn(#(T~N,)*) -> Res,
+            {}
+
+            impl<Func, #(T~N,)* Res> FunctionArity<$n, (WithRuby, #(T~N,)* Res)> for Func
+            where
+                Func: Fn(&Ruby, #(T~N,)*) -> Res,
+            {}
+
+            /// Helper trait for wrapping a function as a Ruby method ignoring
+            /// self and taking N arguments and an optional block, with type
+            /// conversions and error handling.
+            ///
+            /// See the [`function`](crate::function!) macro.
+            #[doc(hidden)]
+            pub trait $block_name<#(T~N,)* Res>
+            where
+                Self: Sized + Fn(#(T~N,)* Option<Proc>) -> Res,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {
+                #[inline]
+                unsafe fn call_convert_value(self, #(arg~N: Value,)*) -> Result<Value, Error> {
+                    (self)(
+                        #(TryConvert::try_convert(arg~N)?,)*
+                        block_arg(&Ruby::get_unchecked())?,
+                    ).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, #(arg~N: Value,)*) -> Value {
+                    match catch_panic(|| self.call_convert_value(#(arg~N,)*)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, #(T~N,)* Res> $block_name<#(T~N,)* Res> for Func
+            where
+                Func: Fn(#(T~N,)* Option<Proc>) -> Res,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {}
+
+            /// Helper trait for wrapping a function as a Ruby method taking
+            /// [`&Ruby`](Ruby), ignoring self, and taking N arguments and an
+            /// optional block, with type conversions and error handling.
+            ///
+            /// See the [`function`](crate::function!) macro.
+            #[doc(hidden)]
+            pub trait $ruby_block_name<#(T~N,)* Res>
+            where
+                Self: Sized + Fn(&Ruby, #(T~N,)* Option<Proc>) -> Res,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {
+                #[inline]
+                unsafe fn call_convert_value(self, #(arg~N: Value,)*) -> Result<Value, Error> {
+                    let ruby = Ruby::get_unchecked();
+                    (self)(
+                        &ruby,
+                        #(TryConvert::try_convert(arg~N)?,)*
+                        block_arg(&ruby)?,
+                    ).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, #(arg~N: Value,)*) -> Value {
+                    match catch_panic(|| self.call_convert_value(#(arg~N,)*)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, #(T~N,)* Res> $ruby_block_name<#(T~N,)* Res> for Func
+            where
+                Func: Fn(&Ruby, #(T~N,)* Option<Proc>) -> Res,
+                #(T~N: TryConvert,)*
+                Res: ReturnValue,
+            {}
+
+            impl<Func, #(T~N,)* Res> FunctionBlockArity<$n, (#(T~N,)* Res,)> for Func
+            where
+                Func: Fn(#(T~N,)* Option<Proc>) -> Res,
+            {}
+
+            impl<Func, #(T~N,)* Res> FunctionBlockArity<$n, (WithRuby, #(T~N,)* Res)> for Func
+            where
+                Func: Fn(&Ruby, #(T~N,)* Option<Proc>) -> Res,
+            {}
+        });
+    }
+}
+
+seq!(N in 0..=16 {
+    function_n!(Function~N, RubyFunction~N, FunctionBlock~N, RubyFunctionBlock~N, N);
+});
+
+macro_rules! function_kw_n {
+    ($name:ident, $ruby_name:ident, $n:literal) => {
+        seq!(N in 0..$n {
+            /// Helper trait for wrapping a function as a Ruby method ignoring
+            /// self and taking N arguments and keywords, with type
+            /// conversions and error handling.
+            ///
+            /// See the [`function`](crate::function!) macro.
+            #[doc(hidden)]
+            pub trait $name<#(T~N,)* Kw, Res>
+            where
+                Self: Sized + Fn(#(T~N,)* Kw) -> Res,
+                #(T~N: TryConvert,)*
+                Kw: ScanArgsKw,
+                Res: ReturnValue,
+            {
+                #[inline]
+                unsafe fn call_convert_value(self, argc: c_int, argv: *const Value) -> Result<Value, Error> {
+                    let args = scan_args::<(#(T~N,)*), (), (), (), Kw, ()>(
+                        slice::from_raw_parts(argv, argc as usize),
+                    )?;
+                    let (#(arg~N,)*) = args.required;
+                    (self)(#(arg~N,)* args.keywords).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, argc: c_int, argv: *const Value) -> Value {
+                    match catch_panic(|| self.call_convert_value(argc, argv)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, #(T~N,)* Kw, Res> $name<#(T~N,)* Kw, Res> for Func
+            where
+                Func: Fn(#(T~N,)* Kw) -> Res,
+                #(T~N: TryConvert,)*
+                Kw: ScanArgsKw,
+                Res: ReturnValue,
+            {}
+
+            /// Helper trait for wrapping a function as a Ruby method taking
+            /// [`&Ruby`](Ruby), ignoring self, and taking N arguments and
+            /// keywords, with type conversions and error handling.
+            ///
+            /// See the [`function`](crate::function!) macro.
+            #[doc(hidden)]
+            pub trait $ruby_name<#(T~N,)* Kw, Res>
+            where
+                Self: Sized + Fn(&Ruby, #(T~N,)* Kw) -> Res,
+                #(T~N: TryConvert,)*
+                Kw: ScanArgsKw,
+                Res: ReturnValue,
+            {
+                #[inline]
+                unsafe fn call_convert_value(self, argc: c_int, argv: *const Value) -> Result<Value, Error> {
+                    let args = scan_args::<(#(T~N,)*), (), (), (), Kw, ()>(
+                        slice::from_raw_parts(argv, argc as usize),
+                    )?;
+                    let (#(arg~N,)*) = args.required;
+                    (self)(&Ruby::get_unchecked(), #(arg~N,)* args.keywords).into_return_value()
+                }
+
+                #[inline]
+                unsafe fn call_handle_error(self, argc: c_int, argv: *const Value) -> Value {
+                    match catch_panic(|| self.call_convert_value(argc, argv)) {
+                        Ok(v) => v,
+                        Err(e) => raise(e),
+                    }
+                }
+            }
+
+            impl<Func, #(T~N,)* Kw, Res> $ruby_name<#(T~N,)* Kw, Res> for Func
+            where
+                Func: Fn(&Ruby, #(T~N,)* Kw) -> Res,
+                #(T~N: TryConvert,)*
+                Kw: ScanArgsKw,
+                Res: ReturnValue,
+            {}
+
+            impl<Func, #(T~N,)* Kw, Res> FunctionKwArity<$n, (#(T~N,)* Kw, Res)> for Func
+            where
+                Func: Fn(#(T~N,)* Kw) -> Res,
+            {}
+
+            impl<Func, #(T~N,)* Kw, Res> FunctionKwArity<$n, (WithRuby, #(T~N,)* Kw, Res)> for Func
+            where
+                Func: Fn(&Ruby, #(T~N,)* Kw) -> Res,
+            {}
+        });
     }
+}
 
-    #[inline]
-    unsafe fn call_handle_error(self, argc: c_int, argv: *const Value) -> Value {
-        let res = match std::panic::catch_unwind(AssertUnwindSafe(|| {
-            self.call_convert_value(argc, argv)
-        })) {
-            Ok(v) => v,
-            Err(e) => Err(Error::from_panic(e)),
-        };
-        match res {
-            Ok(v) => v,
-            Err(e) => raise(e),
+seq!(N in 0..=9 {
+    function_kw_n!(FunctionKw~N, RubyFunctionKw~N, N);
+});
+
+/// Wrap a Rust function item with Ruby type conversion and error handling,
+/// ignoring Ruby's `self` argument.
+///
+/// This macro wraps the given function and returns a function pointer
+/// implementing the [`Method`] trait, suitable for passing to functions that
+/// define Ruby methods such as
+/// [`define_method`](crate::module::Module::define_method).
+///
+/// Ruby code implicitly always has a `self` parameter available. In the
+/// extention API this is passed explicitly. The wrapper this macro generates
+/// ignores that argument, and does not pass it to the wrapped function.
+///
+/// The values `-2` and `-1` for `arity` have special meaning. Both indicate
+/// functions with any number of arguments, with `-2` the arguments are passed
+/// as a [`RArray`], with `-1` they are passed as a slice of [`Value`]s.
+/// Arity of `-1` can be used with [`scan_args`](crate::scan_args::scan_args)
+/// and [`get_kwargs`](crate::scan_args::get_kwargs) for more complex method
+/// signatures.
+///
+/// | Arity | Signature                                     |
+/// |-------|-----------------------------------------------|
+/// |    -2 | `fn(arguments: RArray) -> Result<R, Error>`   |
+/// |    -1 | `fn(arguments: &[Value]) -> Result<R, Error>` |
+/// |     0 | `fn()-> Result<R, Error>`                     |
+/// |     1 | `fn(arg1: T) -> Result<R, Error>`             |
+/// |     2 | `fn(arg1: T, arg2: U) -> Result<R, Error>`    |
+/// |   ... | ...                                           |
+/// |    16 | ...                                           |
+///
+/// Where `T`, `U`, and so on are any types that implement `TryConvert`,
+/// and `R` implements [`IntoValue`]. It is also possible to return just `R`
+/// rather than a `Result` for functions that will never error, and omit the
+/// return value (i.e. return `()`) for a function that returns `nil` to Ruby.
+/// See [`ReturnValue`] for more details on what can be returned.
+///
+/// See the [`method`](crate::method!) macro for cases where the `self`
+/// argument is required.
+///
+/// As with [`method`](crate::method!), passing `kwargs` after the arity, e.g.
+/// `function!(name, 1, kwargs)`, wraps a function taking an additional final
+/// argument for the keyword arguments passed to the method, as a
+/// [`RHash`](crate::RHash) or a type implementing
+/// [`Keywords`](crate::scan_args::Keywords).
+///
+/// As with [`method`](crate::method!), passing `block` after the arity, e.g.
+/// `function!(name, 1, block)`, wraps a function taking an additional final
+/// argument of `Option<Proc>`, the block passed to the method, if any. This
+/// includes an arity of `-1`, for a function taking a slice of any number of
+/// arguments followed by the block.
+///
+/// # Examples
+///
+/// ```
+/// fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+///     ((b.0 - a.0).powi(2) + (b.0 - a.0).powi(2)).sqrt()
+/// }
+///
+/// #[magnus::init]
+/// fn init(ruby: &magnus::Ruby) {
+///     ruby.define_global_function("distance", magnus::function!(distance, 2));
+/// }
+/// # let cleanup = unsafe { magnus::embed::init() };
+/// # init(&cleanup);
+/// ```
+#[macro_export]
+macro_rules! function {
+    ($name:expr, -2) => {{
+        unsafe extern "C" fn anon(rb_self: $crate::Value, args: $crate::RArray) -> $crate::Value {
+            use $crate::method::{FunctionRbAry, RubyFunctionRbAry};
+            $name.call_handle_error(args)
+        }
+        anon as unsafe extern "C" fn($crate::Value, $crate::RArray) -> $crate::Value
+    }};
+    ($name:expr, -1) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionCAry, RubyFunctionCAry};
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 0) => {{
+        unsafe extern "C" fn anon(rb_self: $crate::Value) -> $crate::Value {
+            use $crate::method::{Function0, RubyFunction0};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<0>);
+            $name.call_handle_error()
+        }
+        anon as unsafe extern "C" fn($crate::Value) -> $crate::Value
+    }};
+    ($name:expr, 1) => {{
+        unsafe extern "C" fn anon(rb_self: $crate::Value, a: $crate::Value) -> $crate::Value {
+            use $crate::method::{Function1, RubyFunction1};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<1>);
+            $name.call_handle_error(a)
+        }
+        anon as unsafe extern "C" fn($crate::Value, $crate::Value) -> $crate::Value
+    }};
+    ($name:expr, 2) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function2, RubyFunction2};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<2>);
+            $name.call_handle_error(a, b)
+        }
+        anon as unsafe extern "C" fn($crate::Value, $crate::Value, $crate::Value) -> $crate::Value
+    }};
+    ($name:expr, 3) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function3, RubyFunction3};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<3>);
+            $name.call_handle_error(a, b, c)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 4) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function4, RubyFunction4};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<4>);
+            $name.call_handle_error(a, b, c, d)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 5) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function5, RubyFunction5};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<5>);
+            $name.call_handle_error(a, b, c, d, e)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 6) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function6, RubyFunction6};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<6>);
+            $name.call_handle_error(a, b, c, d, e, f)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 7) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function7, RubyFunction7};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<7>);
+            $name.call_handle_error(a, b, c, d, e, f, g)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 8) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function8, RubyFunction8};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<8>);
+            $name.call_handle_error(a, b, c, d, e, f, g, h)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 9) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function9, RubyFunction9};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<9>);
+            $name.call_handle_error(a, b, c, d, e, f, g, h, i)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 10) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function10, RubyFunction10};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<10>);
+            $name.call_handle_error(a, b, c, d, e, f, g, h, i, j)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 11) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function11, RubyFunction11};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<11>);
+            $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 12) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function12, RubyFunction12};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<12>);
+            $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l)
         }
-    }
-}
-
-impl<Func, Res> RubyFunctionCAry<Res> for Func
-where
-    Func: Fn(&Ruby, &[Value]) -> Res,
-    Res: ReturnValue,
-{
-}
-
-macro_rules! function_n {
-    ($name:ident, $ruby_name:ident, $n:literal) => {
-        seq!(N in 0..$n {
-            /// Helper trait for wrapping a function as a Ruby method ignoring
-            /// self and taking N arguments, with type conversions and error
-            /// handling.
-            ///
-            /// See the [`function`](crate::function!) macro.
-            #[doc(hidden)]
-            pub trait $name<#(T~N,)* Res>
-            where
-                Self: Sized + Fn(#(T~N,)*) -> Res,
-                #(T~N: TryConvert,)*
-                Res: ReturnValue,
-            {
-                #[inline]
-                fn call_convert_value(self, #(arg~N: Value,)*) -> Result<Value, Error> {
-                    (self)(
-                        #(TryConvert::try_convert(arg~N)?,)*
-                    ).into_return_value()
-                }
-
-                #[inline]
-                unsafe fn call_handle_error(self, #(arg~N: Value,)*) -> Value {
-                    let res =
-                        match std::panic::catch_unwind(AssertUnwindSafe(|| {
-                            self.call_convert_value(#(arg~N,)*)
-                        })) {
-                            Ok(v) => v,
-                            Err(e) => Err(Error::from_panic(e)),
-                        };
-                    match res {
-                        Ok(v) => v,
-                        Err(e) => raise(e),
-                    }
-                }
-            }
-
-            impl<Func, #(T~N,)* Res> $name<#(T~N,)* Res> for Func
-            where
-                Func: Fn(#(T~N,)*) -> Res,
-                #(T~N: TryConvert,)*
-                Res: ReturnValue,
-            {}
-
-            /// Helper trait for wrapping a function as a Ruby method taking
-            /// [`&Ruby`](Ruby), ignoring self, and taking N arguments, with
-            /// type conversions and error handling.
-            ///
-            /// See the [`function`](crate::function!) macro.
-            #[doc(hidden)]
-            pub trait $ruby_name<#(T~N,)* Res>
-            where
-                Self: Sized + Fn(&Ruby, #(T~N,)*) -> Res,
-                #(T~N: TryConvert,)*
-                Res: ReturnValue,
-            {
-                #[inline]
-                unsafe fn call_convert_value(self, #(arg~N: Value,)*) -> Result<Value, Error> {
-                    (self)(
-                        &Ruby::get_unchecked(),
-                        #(TryConvert::try_convert(arg~N)?,)*
-                    ).into_return_value()
-                }
-
-                #[inline]
-                unsafe fn call_handle_error(self, #(arg~N: Value,)*) -> Value {
-                    let res =
-                        match std::panic::catch_unwind(AssertUnwindSafe(|| {
-                            self.call_convert_value(#(arg~N,)*)
-                        })) {
-                            Ok(v) => v,
-                            Err(e) => Err(Error::from_panic(e)),
-                        };
-                    match res {
-                        Ok(v) => v,
-                        Err(e) => raise(e),
-                    }
-                }
-            }
-
-            impl<Func, #(T~N,)* Res> $ruby_name<#(T~N,)* Res> for Func
-            where
-                Func: Fn(&Ruby, #(T~N,)*) -> Res,
-                #(T~N: TryConvert,)*
-                Res: ReturnValue,
-            {}
-        });
-    }
-}
-
-seq!(N in 0..=16 {
-    function_n!(Function~N, RubyFunction~N, N);
-});
-
-/// Wrap a Rust function item with Ruby type conversion and error handling,
-/// ignoring Ruby's `self` argument.
-///
-/// This macro wraps the given function and returns a function pointer
-/// implementing the [`Method`] trait, suitable for passing to functions that
-/// define Ruby methods such as
-/// [`define_method`](crate::module::Module::define_method).
-///
-/// Ruby code implicitly always has a `self` parameter available. In the
-/// extention API this is passed explicitly. The wrapper this macro generates
-/// ignores that argument, and does not pass it to the wrapped function.
-///
-/// The values `-2` and `-1` for `arity` have special meaning. Both indicate
-/// functions with any number of arguments, with `-2` the arguments are passed
-/// as a [`RArray`], with `-1` they are passed as a slice of [`Value`]s.
-/// Arity of `-1` can be used with [`scan_args`](crate::scan_args::scan_args)
-/// and [`get_kwargs`](crate::scan_args::get_kwargs) for more complex method
-/// signatures.
-///
-/// | Arity | Signature                                     |
-/// |-------|-----------------------------------------------|
-/// |    -2 | `fn(arguments: RArray) -> Result<R, Error>`   |
-/// |    -1 | `fn(arguments: &[Value]) -> Result<R, Error>` |
-/// |     0 | `fn()-> Result<R, Error>`                     |
-/// |     1 | `fn(arg1: T) -> Result<R, Error>`             |
-/// |     2 | `fn(arg1: T, arg2: U) -> Result<R, Error>`    |
-/// |   ... | ...                                           |
-/// |    16 | ...                                           |
-///
-/// Where `T`, `U`, and so on are any types that implement `TryConvert`,
-/// and `R` implements [`IntoValue`]. It is also possible to return just `R`
-/// rather than a `Result` for functions that will never error, and omit the
-/// return value (i.e. return `()`) for a function that returns `nil` to Ruby.
-/// See [`ReturnValue`] for more details on what can be returned.
-///
-/// See the [`method`](crate::method!) macro for cases where the `self`
-/// argument is required.
-///
-/// # Examples
-///
-/// ```
-/// fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
-///     ((b.0 - a.0).powi(2) + (b.0 - a.0).powi(2)).sqrt()
-/// }
-///
-/// #[magnus::init]
-/// fn init(ruby: &magnus::Ruby) {
-///     ruby.define_global_function("distance", magnus::function!(distance, 2));
-/// }
-/// # let cleanup = unsafe { magnus::embed::init() };
-/// # init(&cleanup);
-/// ```
-#[macro_export]
-macro_rules! function {
-    ($name:expr, -2) => {{
-        unsafe extern "C" fn anon(rb_self: $crate::Value, args: $crate::RArray) -> $crate::Value {
-            use $crate::method::{FunctionRbAry, RubyFunctionRbAry};
-            $name.call_handle_error(args)
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 13) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+            m: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function13, RubyFunction13};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<13>);
+            $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l, m)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 14) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+            m: $crate::Value,
+            n: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function14, RubyFunction14};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<14>);
+            $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l, m, n)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 15) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+            m: $crate::Value,
+            n: $crate::Value,
+            o: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function15, RubyFunction15};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<15>);
+            $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l, m, n, o)
         }
-        anon as unsafe extern "C" fn($crate::Value, $crate::RArray) -> $crate::Value
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
     }};
-    ($name:expr, -1) => {{
+    ($name:expr, 16) => {{
+        unsafe extern "C" fn anon(
+            rb_self: $crate::Value,
+            a: $crate::Value,
+            b: $crate::Value,
+            c: $crate::Value,
+            d: $crate::Value,
+            e: $crate::Value,
+            f: $crate::Value,
+            g: $crate::Value,
+            h: $crate::Value,
+            i: $crate::Value,
+            j: $crate::Value,
+            k: $crate::Value,
+            l: $crate::Value,
+            m: $crate::Value,
+            n: $crate::Value,
+            o: $crate::Value,
+            p: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{Function16, RubyFunction16};
+            $crate::method::check_function_arity(&$name, $crate::method::Arity::<16>);
+            $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p)
+        }
+        anon as unsafe extern "C" fn(
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, -1, block) => {{
         unsafe extern "C" fn anon(
             argc: std::os::raw::c_int,
             argv: *const $crate::Value,
             rb_self: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{FunctionCAry, RubyFunctionCAry};
+            use $crate::method::{FunctionCAryBlock, RubyFunctionCAryBlock};
             $name.call_handle_error(argc, argv)
         }
         anon as unsafe extern "C" fn(
@@ -1634,39 +3565,43 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 0) => {{
+    ($name:expr, 0, block) => {{
         unsafe extern "C" fn anon(rb_self: $crate::Value) -> $crate::Value {
-            use $crate::method::{Function0, RubyFunction0};
+            use $crate::method::{FunctionBlock0, RubyFunctionBlock0};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<0>);
             $name.call_handle_error()
         }
         anon as unsafe extern "C" fn($crate::Value) -> $crate::Value
     }};
-    ($name:expr, 1) => {{
+    ($name:expr, 1, block) => {{
         unsafe extern "C" fn anon(rb_self: $crate::Value, a: $crate::Value) -> $crate::Value {
-            use $crate::method::{Function1, RubyFunction1};
+            use $crate::method::{FunctionBlock1, RubyFunctionBlock1};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<1>);
             $name.call_handle_error(a)
         }
         anon as unsafe extern "C" fn($crate::Value, $crate::Value) -> $crate::Value
     }};
-    ($name:expr, 2) => {{
+    ($name:expr, 2, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
             b: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function2, RubyFunction2};
+            use $crate::method::{FunctionBlock2, RubyFunctionBlock2};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<2>);
             $name.call_handle_error(a, b)
         }
         anon as unsafe extern "C" fn($crate::Value, $crate::Value, $crate::Value) -> $crate::Value
     }};
-    ($name:expr, 3) => {{
+    ($name:expr, 3, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
             b: $crate::Value,
             c: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function3, RubyFunction3};
+            use $crate::method::{FunctionBlock3, RubyFunctionBlock3};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<3>);
             $name.call_handle_error(a, b, c)
         }
         anon as unsafe extern "C" fn(
@@ -1676,7 +3611,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 4) => {{
+    ($name:expr, 4, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1684,7 +3619,8 @@ macro_rules! function {
             c: $crate::Value,
             d: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function4, RubyFunction4};
+            use $crate::method::{FunctionBlock4, RubyFunctionBlock4};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<4>);
             $name.call_handle_error(a, b, c, d)
         }
         anon as unsafe extern "C" fn(
@@ -1695,7 +3631,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 5) => {{
+    ($name:expr, 5, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1704,7 +3640,8 @@ macro_rules! function {
             d: $crate::Value,
             e: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function5, RubyFunction5};
+            use $crate::method::{FunctionBlock5, RubyFunctionBlock5};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<5>);
             $name.call_handle_error(a, b, c, d, e)
         }
         anon as unsafe extern "C" fn(
@@ -1716,7 +3653,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 6) => {{
+    ($name:expr, 6, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1726,7 +3663,8 @@ macro_rules! function {
             e: $crate::Value,
             f: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function6, RubyFunction6};
+            use $crate::method::{FunctionBlock6, RubyFunctionBlock6};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<6>);
             $name.call_handle_error(a, b, c, d, e, f)
         }
         anon as unsafe extern "C" fn(
@@ -1739,7 +3677,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 7) => {{
+    ($name:expr, 7, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1750,7 +3688,8 @@ macro_rules! function {
             f: $crate::Value,
             g: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function7, RubyFunction7};
+            use $crate::method::{FunctionBlock7, RubyFunctionBlock7};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<7>);
             $name.call_handle_error(a, b, c, d, e, f, g)
         }
         anon as unsafe extern "C" fn(
@@ -1764,7 +3703,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 8) => {{
+    ($name:expr, 8, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1776,7 +3715,8 @@ macro_rules! function {
             g: $crate::Value,
             h: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function8, RubyFunction8};
+            use $crate::method::{FunctionBlock8, RubyFunctionBlock8};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<8>);
             $name.call_handle_error(a, b, c, d, e, f, g, h)
         }
         anon as unsafe extern "C" fn(
@@ -1791,7 +3731,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 9) => {{
+    ($name:expr, 9, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1804,7 +3744,8 @@ macro_rules! function {
             h: $crate::Value,
             i: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function9, RubyFunction9};
+            use $crate::method::{FunctionBlock9, RubyFunctionBlock9};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<9>);
             $name.call_handle_error(a, b, c, d, e, f, g, h, i)
         }
         anon as unsafe extern "C" fn(
@@ -1820,7 +3761,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 10) => {{
+    ($name:expr, 10, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1834,7 +3775,8 @@ macro_rules! function {
             i: $crate::Value,
             j: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function10, RubyFunction10};
+            use $crate::method::{FunctionBlock10, RubyFunctionBlock10};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<10>);
             $name.call_handle_error(a, b, c, d, e, f, g, h, i, j)
         }
         anon as unsafe extern "C" fn(
@@ -1851,7 +3793,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 11) => {{
+    ($name:expr, 11, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1866,7 +3808,8 @@ macro_rules! function {
             j: $crate::Value,
             k: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function11, RubyFunction11};
+            use $crate::method::{FunctionBlock11, RubyFunctionBlock11};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<11>);
             $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k)
         }
         anon as unsafe extern "C" fn(
@@ -1884,7 +3827,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 12) => {{
+    ($name:expr, 12, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1900,7 +3843,8 @@ macro_rules! function {
             k: $crate::Value,
             l: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function12, RubyFunction12};
+            use $crate::method::{FunctionBlock12, RubyFunctionBlock12};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<12>);
             $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l)
         }
         anon as unsafe extern "C" fn(
@@ -1919,7 +3863,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 13) => {{
+    ($name:expr, 13, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1936,7 +3880,8 @@ macro_rules! function {
             l: $crate::Value,
             m: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function13, RubyFunction13};
+            use $crate::method::{FunctionBlock13, RubyFunctionBlock13};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<13>);
             $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l, m)
         }
         anon as unsafe extern "C" fn(
@@ -1956,7 +3901,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 14) => {{
+    ($name:expr, 14, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -1974,7 +3919,8 @@ macro_rules! function {
             m: $crate::Value,
             n: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function14, RubyFunction14};
+            use $crate::method::{FunctionBlock14, RubyFunctionBlock14};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<14>);
             $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l, m, n)
         }
         anon as unsafe extern "C" fn(
@@ -1995,7 +3941,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 15) => {{
+    ($name:expr, 15, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -2014,7 +3960,8 @@ macro_rules! function {
             n: $crate::Value,
             o: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function15, RubyFunction15};
+            use $crate::method::{FunctionBlock15, RubyFunctionBlock15};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<15>);
             $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l, m, n, o)
         }
         anon as unsafe extern "C" fn(
@@ -2036,7 +3983,7 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
-    ($name:expr, 16) => {{
+    ($name:expr, 16, block) => {{
         unsafe extern "C" fn anon(
             rb_self: $crate::Value,
             a: $crate::Value,
@@ -2056,7 +4003,8 @@ macro_rules! function {
             o: $crate::Value,
             p: $crate::Value,
         ) -> $crate::Value {
-            use $crate::method::{Function16, RubyFunction16};
+            use $crate::method::{FunctionBlock16, RubyFunctionBlock16};
+            $crate::method::check_function_block_arity(&$name, $crate::method::Arity::<16>);
             $name.call_handle_error(a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p)
         }
         anon as unsafe extern "C" fn(
@@ -2079,7 +4027,173 @@ macro_rules! function {
             $crate::Value,
         ) -> $crate::Value
     }};
+    ($name:expr, 0, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw0, RubyFunctionKw0};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<0>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 1, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw1, RubyFunctionKw1};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<1>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 2, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw2, RubyFunctionKw2};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<2>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 3, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw3, RubyFunctionKw3};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<3>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 4, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw4, RubyFunctionKw4};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<4>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 5, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw5, RubyFunctionKw5};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<5>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 6, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw6, RubyFunctionKw6};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<6>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 7, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw7, RubyFunctionKw7};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<7>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 8, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw8, RubyFunctionKw8};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<8>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
+    ($name:expr, 9, kwargs) => {{
+        unsafe extern "C" fn anon(
+            argc: std::os::raw::c_int,
+            argv: *const $crate::Value,
+            rb_self: $crate::Value,
+        ) -> $crate::Value {
+            use $crate::method::{FunctionKw9, RubyFunctionKw9};
+            $crate::method::check_function_kw_arity(&$name, $crate::method::Arity::<9>);
+            $name.call_handle_error(argc, argv)
+        }
+        anon as unsafe extern "C" fn(
+            std::os::raw::c_int,
+            *const $crate::Value,
+            $crate::Value,
+        ) -> $crate::Value
+    }};
     ($name:expr, $arity:expr) => {
-        compile_error!("arity must be an integer literal between -2..=16")
+        compile_error!("arity must be an integer literal between -2..=16, use -1 to take a slice of any number of arguments")
+    };
+    ($name:expr, $arity:expr, block) => {
+        compile_error!("arity must be an integer literal between -1..=16 when taking a block")
+    };
+    ($name:expr, $arity:expr, kwargs) => {
+        compile_error!("arity must be an integer literal between 0..=9 when taking keywords")
     };
 }
@@ -7,24 +7,25 @@ use std::{ffi::CString, fmt, mem::transmute, os::raw::c_int};
 use rb_sys::{
     rb_alias, rb_attr, rb_class_inherited_p, rb_const_get, rb_const_set, rb_define_class_id_under,
     rb_define_method_id, rb_define_module_function, rb_define_module_id_under,
-    rb_define_private_method, rb_define_protected_method, rb_include_module, rb_mComparable,
-    rb_mEnumerable, rb_mErrno, rb_mFileTest, rb_mGC, rb_mKernel, rb_mMath, rb_mProcess,
-    rb_mWaitReadable, rb_mWaitWritable, rb_mod_ancestors, rb_module_new, rb_prepend_module,
-    ruby_value_type, VALUE,
+    rb_define_private_method, rb_define_protected_method, rb_funcall_with_block_kw, rb_funcallv_kw,
+    rb_include_module, rb_keyword_given_p, rb_mComparable, rb_mEnumerable, rb_mErrno, rb_mFileTest,
+    rb_mGC, rb_mKernel, rb_mMath, rb_mProcess, rb_mWaitReadable, rb_mWaitWritable,
+    rb_mod_ancestors, rb_module_new, rb_prepend_module, ruby_value_type, VALUE,
 };
 
 use crate::{
+    block::Proc,
     class::{Class, RClass},
     error::{protect, Error},
     exception::ExceptionClass,
     into_value::IntoValue,
-    method::Method,
+    method::{private::BlockReturn as _, BlockReturn, Method},
     object::Object,
     r_array::RArray,
     try_convert::TryConvert,
     value::{
         private::{self, ReprValue as _},
-        IntoId, NonZeroValue, ReprValue, Value,
+        IntoId, LazyId, NonZeroValue, Opaque, ReprValue, Value,
     },
     Ruby,
 };
@@ -53,6 +54,98 @@ impl Ruby {
     pub fn module_new(&self) -> RModule {
         unsafe { RModule::from_rb_value_unchecked(rb_module_new()) }
     }
+
+    /// Add methods to a core class, such as `String` or `Array`.
+    ///
+    /// `f` is called with a new module in which methods can be defined. When
+    /// `refine` is `false` the module is included in `class`, so the methods
+    /// are available to all instances of `class`. As the module is included
+    /// rather than methods being defined on `class` directly, any existing
+    /// methods of `class` take precedence over those from the module,
+    /// avoiding accidentally overriding core behaviour.
+    ///
+    /// When `refine` is `true` the methods are instead defined in a
+    /// refinement of `class`, and are only visible to Ruby code that
+    /// activates the refinement with `using`. This is the safer option when
+    /// extending core classes, as the methods can not conflict with other
+    /// libraries.
+    ///
+    /// Returns the module containing the refinement when `refine` is `true`,
+    /// otherwise the module included in `class`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{method, prelude::*, rb_assert, Error, RString, Ruby};
+    ///
+    /// fn shout(s: RString) -> Result<String, Error> {
+    ///     Ok(s.to_string()?.to_uppercase())
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let refinement = ruby.extend_core(ruby.class_string(), true, |m| {
+    ///         m.define_method("shout", method!(shout, 0))
+    ///     })?;
+    ///     ruby.define_global_const("Shout", refinement)?;
+    ///
+    ///     rb_assert!(ruby, r#"!"hello".respond_to?(:shout)"#);
+    ///     let res: String = ruby.eval(
+    ///         r#"
+    ///             class Greeter
+    ///               using Shout
+    ///
+    ///               def self.greet
+    ///                 "hello".shout
+    ///               end
+    ///             end
+    ///             Greeter.greet
+    ///         "#,
+    ///     )?;
+    ///     assert_eq!(res, "HELLO");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    ///
+    /// ```
+    /// use magnus::{method, prelude::*, rb_assert, Error, RString, Ruby};
+    ///
+    /// fn shout(s: RString) -> Result<String, Error> {
+    ///     Ok(s.to_string()?.to_uppercase())
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.extend_core(ruby.class_string(), false, |m| {
+    ///         m.define_method("shout", method!(shout, 0))
+    ///     })?;
+    ///
+    ///     rb_assert!(ruby, r#""hello".shout == "HELLO""#);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn extend_core<F>(&self, class: RClass, refine: bool, f: F) -> Result<RModule, Error>
+    where
+        F: FnOnce(RModule) -> Result<(), Error>,
+    {
+        static CALL: LazyId = LazyId::new("call");
+
+        let module = self.module_new();
+        if refine {
+            // Module#refine only accepts a literal block, a Proc created in
+            // Rust raises "can't pass a Proc as a block", so go via Ruby code
+            let refine_fn: Value =
+                self.eval("->(mod, klass) { mod.module_eval { refine(klass) { self } } }")?;
+            let refinement: RModule = refine_fn.funcall(*CALL, (module, class))?;
+            f(refinement)?;
+        } else {
+            f(module)?;
+            class.include_module(module)?;
+        }
+        Ok(module)
+    }
 }
 
 /// A Value pointer to a RModule struct, Ruby's internal representation of
@@ -449,6 +542,38 @@ pub trait Module: Object + ReprValue + Copy {
         res.and_then(TryConvert::try_convert)
     }
 
+    /// Define the constant `dst` within `self`'s scope with the same value as
+    /// the constant `src`.
+    ///
+    /// `src` is looked up as with [`const_get`](Module::const_get), so may be
+    /// inherited or autoloaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Module, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let module = ruby.define_module("Example")?;
+    ///     module.const_set("NEW_NAME", 42)?;
+    ///     module.const_alias("OLD_NAME", "NEW_NAME")?;
+    ///
+    ///     rb_assert!(ruby, "Example::OLD_NAME == 42");
+    ///     rb_assert!(ruby, "Example::OLD_NAME.equal?(Example::NEW_NAME)");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn const_alias<T, U>(self, dst: T, src: U) -> Result<(), Error>
+    where
+        T: IntoId,
+        U: IntoId,
+    {
+        let val: Value = self.const_get(src)?;
+        self.const_set(dst, val)
+    }
+
     /// Returns whether or not `self` inherits from `other`.
     ///
     /// Classes including a module are considered to inherit from that module.
@@ -675,6 +800,61 @@ pub trait Module: Object + ReprValue + Copy {
         Ok(())
     }
 
+    /// Define a method in `self`'s scope, implemented by a Rust closure.
+    ///
+    /// Unlike [`define_method`](Module::define_method), `func` may capture
+    /// state, allowing behaviour configured at runtime to be bound to a
+    /// method without the use of global statics. The closure is stored in a
+    /// hidden Ruby object kept alive by the method, and will be dropped when
+    /// the method is garbage collected.
+    ///
+    /// `func` is called with the receiver, the arguments, and the block (if
+    /// any) the method was called with. The method accepts any number of
+    /// arguments, checking the argument count is left to `func`.
+    ///
+    /// As the method may be called again before an earlier call returns (e.g.
+    /// if it recurses) `func` must be [`Fn`]. Use a [`Cell`](std::cell::Cell)
+    /// or [`Mutex`](std::sync::Mutex) for any state that needs to change.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, rb_assert, Error, RClass, Ruby, Value};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let class = RClass::new(ruby.class_object())?;
+    ///
+    ///     let prefix = String::from("Hello, ");
+    ///     class.define_method_closure("greet", move |_ruby, _rb_self, args, _block| {
+    ///         let name = String::try_convert(*args.first().unwrap())?;
+    ///         Ok::<_, Error>(format!("{}{}!", prefix, name))
+    ///     })?;
+    ///
+    ///     let obj: Value = class.new_instance(())?;
+    ///     rb_assert!(ruby, r#"obj.greet("world") == "Hello, world!""#, obj);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn define_method_closure<T, F, R>(self, name: T, func: F) -> Result<(), Error>
+    where
+        T: IntoId,
+        F: 'static + Send + Fn(&Ruby, Value, &[Value], Option<Proc>) -> R,
+        R: BlockReturn,
+    {
+        static DEFINE_METHOD: LazyId = LazyId::new("define_method");
+
+        let handle = Ruby::get_with(self);
+        let id = name.into_id_with(&handle);
+        let body = handle.proc_from_shared_fn(move |ruby, args, block| {
+            let recv: Value = ruby.current_receiver()?;
+            func(ruby, recv, args, block).into_block_return()
+        });
+        let _: Value = self.funcall(*DEFINE_METHOD, (id, body))?;
+        Ok(())
+    }
+
     /// Define public accessor methods for the attribute `name`.
     ///
     /// `name` should be **without** the preceding `@`.
@@ -754,6 +934,114 @@ pub trait Module: Object + ReprValue + Copy {
         })?;
         Ok(())
     }
+
+    /// Mark the method `name` of `self` as deprecated.
+    ///
+    /// The existing method is wrapped so that calling it outputs a warning,
+    /// naming `replacement` and `since` if given, before running the
+    /// original method. The warning is output once for each calling
+    /// location (tracking at most 1024 locations, after which locations may
+    /// warn again), and only when `Warning[:deprecated]` is enabled.
+    ///
+    /// Returns `Err` if `name` is not defined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{function, prelude::*, rb_assert, Error, Module, Ruby};
+    ///
+    /// fn add(a: i64, b: i64) -> i64 {
+    ///     a + b
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let class = ruby.define_class("Example", ruby.class_object())?;
+    ///     class.define_method("sum", function!(add, 2))?;
+    ///     class.define_method("plus", function!(add, 2))?;
+    ///     class.deprecate_method("plus", Some("sum"), Some("1.2.0"))?;
+    ///
+    ///     // still works, but outputs a warning if Warning[:deprecated] is set
+    ///     rb_assert!(ruby, "Example.new.plus(1, 2) == 3");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn deprecate_method(
+        self,
+        name: &str,
+        replacement: Option<&str>,
+        since: Option<&str>,
+    ) -> Result<(), Error> {
+        static BIND: LazyId = LazyId::new("bind");
+        static CALL: LazyId = LazyId::new("call");
+        static CALLER_LOCATIONS: LazyId = LazyId::new("caller_locations");
+        static DEFINE_METHOD: LazyId = LazyId::new("define_method");
+        static INSTANCE_METHOD: LazyId = LazyId::new("instance_method");
+        static WARN: LazyId = LazyId::new("warn");
+        const MAX_WARNED_LOCATIONS: usize = 1024;
+
+        let handle = Ruby::get_with(self);
+        let original_method: Value = self.funcall(*INSTANCE_METHOD, (handle.to_symbol(name),))?;
+        let original: Opaque<Value> = original_method.into();
+
+        let mut message = format!("{}#{} is deprecated", self.inspect(), name);
+        if let Some(since) = since {
+            message.push_str(&format!(" since {}", since));
+        }
+        if let Some(replacement) = replacement {
+            message.push_str(&format!(", use {} instead", replacement));
+        }
+        let mut warned = std::collections::HashSet::new();
+
+        let wrapper = handle.proc_from_fn(move |ruby, args, block| {
+            let warning: RModule = ruby.class_object().const_get("Warning")?;
+            let enabled: bool = warning.funcall("[]", (ruby.to_symbol("deprecated"),))?;
+            if enabled {
+                let locations: RArray = ruby.module_kernel().funcall(*CALLER_LOCATIONS, (1, 1))?;
+                let location = match locations.entry::<Option<Value>>(0)? {
+                    Some(l) => l.to_r_string()?.to_string()?,
+                    None => String::new(),
+                };
+                if warned.len() >= MAX_WARNED_LOCATIONS && !warned.contains(&location) {
+                    warned.clear();
+                }
+                if warned.insert(location.clone()) {
+                    let _: Value = warning
+                        .funcall(*WARN, (format!("{}: warning: {}\n", location, message),))?;
+                }
+            }
+            let recv: Value = ruby.current_receiver()?;
+            let bound: Value = ruby.get_inner(original).funcall(*BIND, (recv,))?;
+            // pass on keyword arguments as keywords, not a trailing Hash
+            let kw_splat = unsafe { rb_keyword_given_p() };
+            protect(|| unsafe {
+                Value::new(match block {
+                    Some(block) => rb_funcall_with_block_kw(
+                        bound.as_rb_value(),
+                        CALL.as_rb_id(),
+                        args.len() as c_int,
+                        args.as_ptr() as *const VALUE,
+                        block.as_rb_value(),
+                        kw_splat,
+                    ),
+                    None => rb_funcallv_kw(
+                        bound.as_rb_value(),
+                        CALL.as_rb_id(),
+                        args.len() as c_int,
+                        args.as_ptr() as *const VALUE,
+                        kw_splat,
+                    ),
+                })
+            })
+        });
+        // the closure's captures aren't marked, so keep the original method
+        // alive via the wrapper, which is kept alive by the new method.
+        // ivar without @ prefix is invisible from Ruby
+        wrapper.ivar_set("__original_method", original_method)?;
+        let _: Value = self.funcall(*DEFINE_METHOD, (handle.to_symbol(name), wrapper))?;
+        Ok(())
+    }
 }
 
 /// Argument for [`define_attr`](Module::define_attr).
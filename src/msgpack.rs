@@ -0,0 +1,386 @@
+//! Conversion between Ruby objects and [MessagePack](https://msgpack.org).
+//!
+//! Ruby objects are mapped to MessagePack types the same as the
+//! [msgpack](https://rubygems.org/gems/msgpack) gem, so data packed in Rust
+//! can be unpacked in Ruby with `MessagePack.unpack`, and vice versa.
+//!
+//! | Ruby                        | MessagePack                       |
+//! |-----------------------------|-----------------------------------|
+//! | `nil`                       | nil                               |
+//! | `true`/`false`              | boolean                           |
+//! | `Integer`                   | int/uint (up to 64 bits)          |
+//! | `Float`                     | float 64                          |
+//! | `String` (binary encoding)  | bin                               |
+//! | `String` (other encodings)  | str                               |
+//! | `Symbol`                    | str, or ext (see [`Options`])     |
+//! | `Array`                     | array                             |
+//! | `Hash`                      | map                               |
+//! | `Time`                      | timestamp (ext type -1)           |
+//! | `MessagePack::ExtensionValue` | ext                             |
+//!
+//! Strings are always unpacked as UTF-8 (str) or binary (bin) encoded.
+//! Unknown extension types are unpacked as `MessagePack::ExtensionValue`
+//! if the msgpack gem is loaded, otherwise they are an error.
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{rb_assert, Error, Ruby, Value};
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     let val: Value = ruby.eval(r#"{"a" => [1, 2.5, nil], "b" => :sym}"#)?;
+//!     let packed = ruby.msgpack_pack(val)?;
+//!
+//!     let unpacked = ruby.msgpack_unpack(unsafe { packed.as_slice() })?;
+//!     rb_assert!(ruby, r#"unpacked == {"a" => [1, 2.5, nil], "b" => "sym"}"#, unpacked);
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use rmpv::{decode::read_value, encode::write_value, Integer as MpInteger, Value as MpValue};
+
+use crate::{
+    class::{Class, RClass},
+    encoding::EncodingCapable,
+    error::Error,
+    float::Float,
+    integer::Integer,
+    module::{Module, RModule},
+    r_array::RArray,
+    r_hash::RHash,
+    r_string::RString,
+    recursion::Nesting,
+    symbol::Symbol,
+    time::Time,
+    value::{Qfalse, Qnil, Qtrue, ReprValue, Value},
+    Ruby,
+};
+
+const TIMESTAMP_EXT: i8 = -1;
+
+/// # MessagePack
+///
+/// Functions for converting Ruby objects to and from MessagePack.
+///
+/// See also the [`msgpack`](self) module.
+impl Ruby {
+    /// Pack `val` to MessagePack, returning a binary encoded `RString`.
+    ///
+    /// This is equivalent to `Options::new().pack(val)`, see [`Options`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let packed = ruby.msgpack_pack(ruby.ary_from_vec(vec![1, 2, 3]))?;
+    ///     assert_eq!(unsafe { packed.as_slice() }, &[0x93, 0x01, 0x02, 0x03]);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn msgpack_pack<T>(&self, val: T) -> Result<RString, Error>
+    where
+        T: ReprValue,
+    {
+        Options::new().pack(val)
+    }
+
+    /// Unpack a Ruby object from the MessagePack data in `bytes`.
+    ///
+    /// This is equivalent to `Options::new().unpack(ruby, bytes)`, see
+    /// [`Options`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let val = ruby.msgpack_unpack(&[0x93, 0x01, 0x02, 0x03])?;
+    ///     rb_assert!(ruby, "val == [1, 2, 3]", val);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn msgpack_unpack(&self, bytes: &[u8]) -> Result<Value, Error> {
+        Options::new().unpack(self, bytes)
+    }
+}
+
+/// Options for packing and unpacking MessagePack.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{msgpack::Options, rb_assert, Error, Ruby, Value};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let options = Options::new().symbolize_keys(true).symbol_ext_type(0);
+///     let val: Value = ruby.eval(r#"{"a" => :b}"#)?;
+///
+///     let packed = options.pack(val)?;
+///     let unpacked = options.unpack(ruby, unsafe { packed.as_slice() })?;
+///     rb_assert!(ruby, "unpacked == {a: :b}", unpacked);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Options {
+    symbolize_keys: bool,
+    symbol_ext_type: Option<i8>,
+    max_depth: Option<usize>,
+}
+
+impl Options {
+    /// Create a new `Options` with the default settings.
+    ///
+    /// By default Symbols are packed as strings, and map keys are unpacked
+    /// as Strings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Unpack map keys that are strings as Symbols, the same as the msgpack
+    /// gem's `symbolize_keys` option.
+    pub fn symbolize_keys(mut self, enable: bool) -> Self {
+        self.symbolize_keys = enable;
+        self
+    }
+
+    /// Pack Symbols as the extension type `ext_type`, with the Symbol's name
+    /// as the payload, and unpack that extension type as Symbols.
+    ///
+    /// This matches the msgpack gem's
+    /// `factory.register_type(ext_type, Symbol)`.
+    pub fn symbol_ext_type(mut self, ext_type: i8) -> Self {
+        self.symbol_ext_type = Some(ext_type);
+        self
+    }
+
+    /// Return an error when packing Arrays or Hashes nested more than
+    /// `max_depth` deep.
+    ///
+    /// Arrays and Hashes that contain themselves are always an error.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Pack `val` to MessagePack, returning a binary encoded `RString`.
+    ///
+    /// Returns `Err` if `val` is, or contains, an object that can not be
+    /// packed, an Integer that doesn't fit in 64 bits, or an Array or Hash
+    /// that contains itself.
+    pub fn pack<T>(&self, val: T) -> Result<RString, Error>
+    where
+        T: ReprValue,
+    {
+        let ruby = Ruby::get_with(val);
+        let mp = self.pack_value(val.as_value())?;
+        let mut buf = Vec::new();
+        write_value(&mut buf, &mp)
+            .map_err(|e| Error::new(ruby.exception_io_error(), e.to_string()))?;
+        Ok(ruby.enc_str_new(buf, ruby.ascii8bit_encoding()))
+    }
+
+    /// Unpack a Ruby object from the MessagePack data in `bytes`.
+    ///
+    /// Returns `Err` if `bytes` is not valid MessagePack, or contains
+    /// anything after the first object.
+    pub fn unpack(&self, ruby: &Ruby, bytes: &[u8]) -> Result<Value, Error> {
+        let mut rest = bytes;
+        let mp = read_value(&mut rest)
+            .map_err(|e| Error::new(ruby.exception_arg_error(), e.to_string()))?;
+        if !rest.is_empty() {
+            return Err(Error::new(
+                ruby.exception_arg_error(),
+                format!("{} extra bytes after the deserialized object", rest.len()),
+            ));
+        }
+        self.unpack_value(ruby, &mp)
+    }
+
+    /// Convert `val` to a [`rmpv::Value`], without encoding it.
+    pub fn pack_value(&self, val: Value) -> Result<MpValue, Error> {
+        self.pack_nested(val, &Nesting::new(self.max_depth))
+    }
+
+    fn pack_nested(&self, val: Value, nesting: &Nesting) -> Result<MpValue, Error> {
+        let ruby = Ruby::get_with(val);
+        if Qnil::from_value(val).is_some() {
+            Ok(MpValue::Nil)
+        } else if Qtrue::from_value(val).is_some() {
+            Ok(MpValue::Boolean(true))
+        } else if Qfalse::from_value(val).is_some() {
+            Ok(MpValue::Boolean(false))
+        } else if let Some(i) = Integer::from_value(val) {
+            if let Ok(i) = i.to_i64() {
+                Ok(MpValue::from(i))
+            } else {
+                i.to_u64().map(MpValue::from).map_err(|_| {
+                    Error::new(
+                        ruby.exception_range_error(),
+                        "Integer too big to pack as MessagePack",
+                    )
+                })
+            }
+        } else if let Some(f) = Float::from_value(val) {
+            Ok(MpValue::F64(f.to_f64()))
+        } else if let Some(s) = RString::from_value(val) {
+            let bytes = unsafe { s.as_slice() }.to_vec();
+            if s.enc_get() == ruby.ascii8bit_encindex() {
+                Ok(MpValue::Binary(bytes))
+            } else {
+                // strings that aren't valid UTF-8 can't be represented as
+                // a MessagePack str, so fall back to bin
+                Ok(String::from_utf8(bytes)
+                    .map(MpValue::from)
+                    .unwrap_or_else(|e| MpValue::Binary(e.into_bytes())))
+            }
+        } else if let Some(s) = Symbol::from_value(val) {
+            let name = s.name()?.into_owned();
+            Ok(match self.symbol_ext_type {
+                Some(ext_type) => MpValue::Ext(ext_type, name.into_bytes()),
+                None => MpValue::from(name),
+            })
+        } else if let Some(a) = RArray::from_value(val) {
+            let _guard = nesting.enter(val)?;
+            a.to_vec::<Value>()?
+                .into_iter()
+                .map(|v| self.pack_nested(v, nesting))
+                .collect::<Result<_, _>>()
+                .map(MpValue::Array)
+        } else if let Some(h) = RHash::from_value(val) {
+            let _guard = nesting.enter(val)?;
+            h.to_vec::<Value, Value>()?
+                .into_iter()
+                .map(|(k, v)| Ok((self.pack_nested(k, nesting)?, self.pack_nested(v, nesting)?)))
+                .collect::<Result<_, Error>>()
+                .map(MpValue::Map)
+        } else if let Some(t) = Time::from_value(val) {
+            let sec: i64 = t.funcall("tv_sec", ())?;
+            let nsec: u32 = t.funcall("tv_nsec", ())?;
+            Ok(MpValue::Ext(TIMESTAMP_EXT, timestamp_payload(sec, nsec)))
+        } else if extension_value_class(&ruby).map_or(false, |c| val.is_kind_of(c)) {
+            let ext_type: i8 = val.funcall("type", ())?;
+            let payload: RString = val.funcall("payload", ())?;
+            Ok(MpValue::Ext(
+                ext_type,
+                unsafe { payload.as_slice() }.to_vec(),
+            ))
+        } else {
+            Err(Error::new(
+                ruby.exception_type_error(),
+                format!("can't pack {} as MessagePack", unsafe { val.classname() }),
+            ))
+        }
+    }
+
+    /// Convert the already decoded `val` to a Ruby object.
+    pub fn unpack_value(&self, ruby: &Ruby, val: &MpValue) -> Result<Value, Error> {
+        Ok(match val {
+            MpValue::Nil => ruby.qnil().as_value(),
+            MpValue::Boolean(b) => ruby.into_value(*b),
+            MpValue::Integer(i) => integer_from_msgpack(ruby, i),
+            MpValue::F32(f) => ruby.float_from_f64(*f as f64).as_value(),
+            MpValue::F64(f) => ruby.float_from_f64(*f).as_value(),
+            MpValue::String(s) => ruby
+                .enc_str_new(s.as_bytes(), ruby.utf8_encoding())
+                .as_value(),
+            MpValue::Binary(b) => ruby.enc_str_new(b, ruby.ascii8bit_encoding()).as_value(),
+            MpValue::Array(a) => {
+                let ary = ruby.ary_new_capa(a.len());
+                for v in a {
+                    ary.push(self.unpack_value(ruby, v)?)?;
+                }
+                ary.as_value()
+            }
+            MpValue::Map(m) => {
+                let hash = ruby.hash_new_capa(m.len());
+                for (k, v) in m {
+                    let key = match k {
+                        MpValue::String(s) if self.symbolize_keys => ruby
+                            .to_symbol(String::from_utf8_lossy(s.as_bytes()))
+                            .as_value(),
+                        _ => self.unpack_value(ruby, k)?,
+                    };
+                    hash.aset(key, self.unpack_value(ruby, v)?)?;
+                }
+                hash.as_value()
+            }
+            MpValue::Ext(TIMESTAMP_EXT, payload) => {
+                let (sec, nsec) = parse_timestamp(payload).ok_or_else(|| {
+                    Error::new(ruby.exception_arg_error(), "invalid MessagePack timestamp")
+                })?;
+                ruby.class_time()
+                    .funcall("at", (sec, nsec, ruby.to_symbol("nsec")))?
+            }
+            MpValue::Ext(ext_type, payload) if Some(*ext_type) == self.symbol_ext_type => {
+                ruby.to_symbol(String::from_utf8_lossy(payload)).as_value()
+            }
+            MpValue::Ext(ext_type, payload) => match extension_value_class(ruby) {
+                Some(class) => class.new_instance((*ext_type, ruby.str_from_slice(payload)))?,
+                None => {
+                    return Err(Error::new(
+                        ruby.exception_arg_error(),
+                        format!("unknown MessagePack extension type {}", ext_type),
+                    ))
+                }
+            },
+        })
+    }
+}
+
+fn integer_from_msgpack(ruby: &Ruby, i: &MpInteger) -> Value {
+    match (i.as_i64(), i.as_u64()) {
+        (Some(i), _) => ruby.integer_from_i64(i).as_value(),
+        (None, Some(u)) => ruby.integer_from_u64(u).as_value(),
+        (None, None) => unreachable!("MessagePack integers are at most 64 bits"),
+    }
+}
+
+fn extension_value_class(ruby: &Ruby) -> Option<RClass> {
+    ruby.class_object()
+        .const_get::<_, RModule>("MessagePack")
+        .ok()
+        .and_then(|m| m.const_get::<_, RClass>("ExtensionValue").ok())
+}
+
+// See https://github.com/msgpack/msgpack/blob/master/spec.md#timestamp-extension-type
+fn timestamp_payload(sec: i64, nsec: u32) -> Vec<u8> {
+    if sec >> 34 == 0 {
+        let data = (u64::from(nsec) << 34) | sec as u64;
+        if data >> 32 == 0 {
+            (data as u32).to_be_bytes().to_vec()
+        } else {
+            data.to_be_bytes().to_vec()
+        }
+    } else {
+        let mut buf = nsec.to_be_bytes().to_vec();
+        buf.extend_from_slice(&sec.to_be_bytes());
+        buf
+    }
+}
+
+fn parse_timestamp(payload: &[u8]) -> Option<(i64, u32)> {
+    match payload.len() {
+        4 => Some((u32::from_be_bytes(payload.try_into().ok()?) as i64, 0)),
+        8 => {
+            let data = u64::from_be_bytes(payload.try_into().ok()?);
+            Some(((data & 0x3_ffff_ffff) as i64, (data >> 34) as u32))
+        }
+        12 => Some((
+            i64::from_be_bytes(payload[4..].try_into().ok()?),
+            u32::from_be_bytes(payload[..4].try_into().ok()?),
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,97 @@
+use std::cell::Cell;
+
+use crate::{
+    error::Error,
+    function,
+    module::Module,
+    object::Object,
+    value::{LazyId, ReprValue, Value},
+    Ruby,
+};
+
+thread_local! {
+    static ACTIVE: Cell<usize> = Cell::new(0);
+}
+
+/// # Native Frames
+///
+/// Functions for labeling sections of Rust code in Ruby profilers.
+impl Ruby {
+    /// Call `func` within a Ruby stack frame named `label`.
+    ///
+    /// Sampling profilers such as stackprof and vernier only see the Ruby
+    /// stack, so time spent in Rust shows up as the Ruby method that called
+    /// in to Rust, with no further detail. This function calls `func` from a
+    /// native method `label` defined on the module `Magnus::NativeFrame`, so
+    /// that time spent in `func` is attributed to `Magnus::NativeFrame.label`
+    /// in profiles and flamegraphs.
+    ///
+    /// Frames may be nested, and are also visible in backtraces and to
+    /// `TracePoint`'s `:c_call` and `:c_return` events.
+    ///
+    /// A method is defined for each distinct `label`, so labels should be a
+    /// small, fixed set of names, rather than generated from data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let sum = ruby.native_frame("sum_squares", || {
+    ///         Ok((1..=10_u64).map(|i| i * i).sum::<u64>())
+    ///     })?;
+    ///     assert_eq!(sum, 385);
+    ///
+    ///     let trace: Vec<String> = ruby.native_frame("get_trace", || ruby.eval("caller(0)"))?;
+    ///     assert!(trace.iter().any(|line| line.contains("get_trace")));
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn native_frame<F, T>(&self, label: &str, func: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> Result<T, Error>,
+    {
+        static METHOD_DEFINED: LazyId = LazyId::new("method_defined?");
+
+        let module = self.define_module("Magnus")?.define_module("NativeFrame")?;
+        let id = self.intern(label);
+        // only check the singleton's own methods, labels such as `name` or
+        // `exit` must not resolve to an inherited method
+        let defined: bool = module
+            .singleton_class()?
+            .funcall(*METHOD_DEFINED, (id, false))?;
+        if !defined {
+            module.define_singleton_method(label, function!(enter_native_frame, 1))?;
+        }
+
+        let mut func = Some(func);
+        let mut res = None;
+        let mut call = || res = Some(func.take().unwrap()());
+        let mut call: &mut dyn FnMut() = &mut call;
+        let ptr = &mut call as *mut &mut dyn FnMut() as usize;
+        let prev = ACTIVE.with(|active| active.replace(ptr));
+        let frame_res = module.funcall::<_, _, Value>(id, (ptr,));
+        ACTIVE.with(|active| active.set(prev));
+        frame_res?;
+        res.unwrap()
+    }
+}
+
+fn enter_native_frame(ruby: &Ruby, ptr: usize) -> Result<(), Error> {
+    // guard against the frame method being called directly from Ruby with
+    // an arbitrary pointer
+    if ptr == 0 || ACTIVE.with(|active| active.replace(0)) != ptr {
+        return Err(Error::new(
+            ruby.exception_runtime_error(),
+            "native frames can not be called directly",
+        ));
+    }
+    // checked above that ptr was set by Ruby::native_frame, where it points
+    // to a closure that outlives this call
+    let call = unsafe { &mut *(ptr as *mut &mut dyn FnMut()) };
+    call();
+    Ok(())
+}
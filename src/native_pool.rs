@@ -0,0 +1,293 @@
+//! A pool of native threads that can be used across `fork`.
+//!
+//! Threads do not survive `fork(2)`, in the child process only the thread
+//! that called `fork` exists. A thread pool created before a fork, such as
+//! one created while an extension is loaded in a Puma or Unicorn master
+//! process, will have no worker threads in the forked children, and jobs sent
+//! to it will never run.
+//!
+//! [`NativePool`] hooks in to Ruby's `Process._fork` to stop its worker
+//! threads just before Ruby forks, after they have finished any queued jobs.
+//! Jobs submitted while the fork is in progress are held until it completes.
+//! Workers are started again on demand the next time a job is submitted, in
+//! both the parent and child processes.
+//!
+//! `Process._fork` was added in Ruby 3.1, on earlier versions forks are not
+//! detected. Forks that don't go through Ruby's `fork`/`Process.fork`, such as
+//! calling `fork(2)` directly from C, are also not detected.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::mpsc;
+//!
+//! use magnus::{Error, Ruby};
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     let pool = ruby.native_pool_new(4)?;
+//!
+//!     let (tx, rx) = mpsc::channel();
+//!     for i in 0..8_u64 {
+//!         let tx = tx.clone();
+//!         pool.execute(move || tx.send(i * i).unwrap());
+//!     }
+//!     drop(tx);
+//!
+//!     // wait for the results without blocking other Ruby threads
+//!     let total: u64 = ruby.without_gvl(|| rx.iter().sum())?;
+//!     assert_eq!(total, 140);
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use std::{
+    fmt,
+    mem::take,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex, Weak},
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    error::Error, module::Module, object::Object, try_convert::TryConvert, value::Value, Ruby,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static POOLS: Mutex<Vec<Weak<Inner>>> = Mutex::new(Vec::new());
+static FORK_HOOK_INSTALLED: Mutex<bool> = Mutex::new(false);
+
+/// # Native thread pools
+///
+/// Functions for creating a [`NativePool`].
+///
+/// See also the [`native_pool`](crate::native_pool) module.
+impl Ruby {
+    /// Create a new [`NativePool`] with `size` worker threads.
+    ///
+    /// Worker threads are started when the first job is submitted.
+    ///
+    /// The first call to this function installs a hook on Ruby's
+    /// `Process._fork`, returning an error if that fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let pool = ruby.native_pool_new(2)?;
+    ///     assert_eq!(pool.size(), 2);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn native_pool_new(&self, size: usize) -> Result<NativePool, Error> {
+        assert!(size > 0, "NativePool size must be greater than 0");
+        install_fork_hook(self)?;
+        let inner = Arc::new(Inner {
+            size,
+            workers: Mutex::new(Workers::default()),
+        });
+        let mut pools = POOLS.lock().unwrap();
+        pools.retain(|pool| pool.strong_count() > 0);
+        pools.push(Arc::downgrade(&inner));
+        Ok(NativePool(inner))
+    }
+}
+
+/// A pool of native threads for running Rust code in the background.
+///
+/// Worker threads do not hold Ruby's GVL, and must not use Ruby's API, use
+/// [`nogvl::with_gvl`](crate::nogvl::with_gvl) to call back in to Ruby.
+///
+/// Dropping the `NativePool` waits for all submitted jobs to finish. If jobs
+/// call back in to Ruby the pool should be dropped with the GVL released.
+///
+/// See the [module level documentation](self) for how `NativePool` behaves
+/// across `fork`.
+pub struct NativePool(Arc<Inner>);
+
+impl NativePool {
+    /// Submit `func` to be run on one of the pool's worker threads.
+    ///
+    /// Jobs are run in the order they are submitted. A panic in `func` does
+    /// not affect the pool or other jobs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::mpsc;
+    ///
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let pool = ruby.native_pool_new(1)?;
+    ///
+    ///     let (tx, rx) = mpsc::channel();
+    ///     pool.execute(move || tx.send("hello").unwrap());
+    ///     assert_eq!(ruby.without_gvl(|| rx.recv())?.unwrap(), "hello");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn execute<F>(&self, func: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut workers = self.0.workers.lock().unwrap();
+        if workers.forking {
+            workers.pending.push(Box::new(func));
+            return;
+        }
+        let sender = match workers.sender {
+            Some(ref sender) => sender.clone(),
+            None => self.0.start(&mut workers),
+        };
+        // workers only exit once all senders are dropped, so can't fail
+        sender.send(Box::new(func)).unwrap();
+    }
+
+    /// Return the number of worker threads in the pool.
+    pub fn size(&self) -> usize {
+        self.0.size
+    }
+}
+
+impl fmt::Debug for NativePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NativePool")
+            .field("size", &self.0.size)
+            .finish()
+    }
+}
+
+struct Inner {
+    size: usize,
+    workers: Mutex<Workers>,
+}
+
+#[derive(Default)]
+struct Workers {
+    sender: Option<mpsc::Sender<Job>>,
+    handles: Vec<JoinHandle<()>>,
+    // set while Ruby forks, jobs submitted in the meantime wait in `pending`
+    // rather than restarting workers that would not survive the fork
+    forking: bool,
+    pending: Vec<Job>,
+}
+
+impl Inner {
+    fn start(&self, workers: &mut Workers) -> mpsc::Sender<Job> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        workers.handles = (0..self.size)
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || loop {
+                    let job = match receiver.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    let _ = catch_unwind(AssertUnwindSafe(job));
+                })
+            })
+            .collect();
+        workers.sender = Some(sender.clone());
+        sender
+    }
+
+    /// Stop the worker threads, after they finish any queued jobs.
+    fn stop(&self) {
+        self.stop_with(|_| ());
+    }
+
+    /// Stop the worker threads ahead of a fork, holding any jobs submitted
+    /// until [`resume`](Self::resume) is called.
+    fn pause(&self) {
+        self.stop_with(|workers| workers.forking = true);
+    }
+
+    /// Submit any jobs held since [`pause`](Self::pause) was called,
+    /// starting the workers if required.
+    ///
+    /// Held jobs were submitted by threads in the parent process, so in the
+    /// child they are discarded rather than run a second time.
+    fn resume(&self, in_child: bool) {
+        let mut workers = self.workers.lock().unwrap();
+        workers.forking = false;
+        let pending = take(&mut workers.pending);
+        if pending.is_empty() || in_child {
+            return;
+        }
+        let sender = match workers.sender {
+            Some(ref sender) => sender.clone(),
+            None => self.start(&mut workers),
+        };
+        for job in pending {
+            sender.send(job).unwrap();
+        }
+    }
+
+    fn stop_with<F>(&self, func: F)
+    where
+        F: FnOnce(&mut Workers),
+    {
+        let handles = {
+            let mut workers = self.workers.lock().unwrap();
+            func(&mut workers);
+            workers.sender = None;
+            take(&mut workers.handles)
+        };
+        for handle in handles {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn install_fork_hook(ruby: &Ruby) -> Result<(), Error> {
+    let mut installed = FORK_HOOK_INSTALLED.lock().unwrap();
+    if *installed {
+        return Ok(());
+    }
+    let hook = ruby.module_new();
+    hook.define_method("_fork", crate::method!(before_fork, 0))?;
+    ruby.module_process()
+        .singleton_class()?
+        .prepend_module(hook)?;
+    *installed = true;
+    Ok(())
+}
+
+fn before_fork(ruby: &Ruby, _rb_self: Value) -> Result<Value, Error> {
+    let pools = POOLS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect::<Vec<_>>();
+    // jobs may be waiting on the GVL, so release it while they finish
+    if let Err(e) = ruby.without_gvl(|| pools.iter().for_each(|pool| pool.pause())) {
+        pools.iter().for_each(|pool| pool.resume(false));
+        return Err(e);
+    }
+    // returns in both the parent and the child, with 0 in the child
+    let res = ruby.call_super::<_, Value>(());
+    let in_child = matches!(res, Ok(pid) if i64::try_convert(pid).ok() == Some(0));
+    pools.iter().for_each(|pool| pool.resume(in_child));
+    res
+}
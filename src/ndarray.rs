@@ -0,0 +1,217 @@
+//! Conversions between [`ndarray`](::ndarray) arrays and Ruby matrices.
+//!
+//! [`Array2<f64>`](Array2) converts to a Ruby Array of row Arrays, and from a
+//! nested Array, a `Matrix`, or a 2 dimensional `Numo::NArray`.
+//! [`Array1<f64>`](Array1) converts to and from a flat Array, or a 1
+//! dimensional `Numo::NArray`.
+//!
+//! [`Ruby::matrix_from_array2`] and [`Ruby::narray_from_array2`] create a
+//! `Matrix` or `Numo::DFloat` from an `Array2<f64>`, for numerical
+//! extensions that want to return data in those forms.
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{rb_assert, Error, Ruby, TryConvert, Value};
+//! use ndarray::{array, Array2};
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     let a = array![[1.0, 2.0], [3.0, 4.0]];
+//!     rb_assert!(ruby, "a == [[1.0, 2.0], [3.0, 4.0]]", a = a.clone());
+//!
+//!     let val: Value = ruby.eval("[[1, 2], [3, 4.0]]")?;
+//!     let b: Array2<f64> = TryConvert::try_convert(val)?;
+//!     assert_eq!(a, b);
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use ::ndarray::{Array1, Array2};
+
+use crate::{
+    class::RClass,
+    error::Error,
+    into_value::{IntoValue, IntoValueFromNative},
+    module::{Module, RModule},
+    r_array::RArray,
+    r_string::RString,
+    try_convert::TryConvert,
+    value::{LazyId, ReprValue, Value},
+    Ruby,
+};
+
+static CAST: LazyId = LazyId::new("cast");
+static FROM_BINARY: LazyId = LazyId::new("from_binary");
+static ROWS: LazyId = LazyId::new("rows");
+static SHAPE: LazyId = LazyId::new("shape");
+static TO_A: LazyId = LazyId::new("to_a");
+static TO_BINARY: LazyId = LazyId::new("to_binary");
+
+/// # `ndarray`
+///
+/// Functions for converting [`ndarray`](::ndarray) arrays to Ruby matrix
+/// types.
+///
+/// See also the [`ndarray`](self) module.
+impl Ruby {
+    /// Create a Ruby `Matrix` from `array`.
+    ///
+    /// This will `require "matrix"`, returning `Err` if it is not available.
+    /// From Ruby 3.1 `matrix` is a bundled gem, rather than part of the
+    /// standard library, so may need to be added to your Gemfile.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use magnus::{rb_assert, Error, Ruby};
+    /// use ndarray::array;
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let m = ruby.matrix_from_array2(&array![[1.0, 2.0], [3.0, 4.0]])?;
+    ///     rb_assert!(ruby, "m == Matrix[[1.0, 2.0], [3.0, 4.0]]", m);
+    ///     rb_assert!(ruby, "m.determinant == -2.0", m);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn matrix_from_array2(&self, array: &Array2<f64>) -> Result<Value, Error> {
+        self.require("matrix")?;
+        let class: RClass = self.class_object().const_get("Matrix")?;
+        class.funcall(*ROWS, (rows_to_ary(self, array), false))
+    }
+
+    /// Create a `Numo::DFloat` from `array`.
+    ///
+    /// Returns `Err` if `Numo::NArray` has not been loaded.
+    pub fn narray_from_array2(&self, array: &Array2<f64>) -> Result<Value, Error> {
+        let class = dfloat_class(self)?;
+        let (rows, cols) = array.dim();
+        let bytes = array
+            .iter()
+            .flat_map(|f| f.to_ne_bytes())
+            .collect::<Vec<u8>>();
+        class.funcall(
+            *FROM_BINARY,
+            (
+                self.str_from_slice(&bytes),
+                self.ary_from_vec(vec![rows, cols]),
+            ),
+        )
+    }
+}
+
+fn rows_to_ary(ruby: &Ruby, array: &Array2<f64>) -> RArray {
+    ruby.ary_from_iter(
+        array
+            .rows()
+            .into_iter()
+            .map(|row| ruby.ary_from_iter(row.iter().copied())),
+    )
+}
+
+fn dfloat_class(ruby: &Ruby) -> Result<RClass, Error> {
+    ruby.class_object()
+        .const_get::<_, RModule>("Numo")?
+        .const_get("DFloat")
+}
+
+fn narray_class(ruby: &Ruby) -> Option<RClass> {
+    ruby.class_object()
+        .const_get::<_, RModule>("Numo")
+        .and_then(|numo| numo.const_get("NArray"))
+        .ok()
+}
+
+fn matrix_class(ruby: &Ruby) -> Option<RClass> {
+    ruby.class_object().const_get("Matrix").ok()
+}
+
+// Returns the shape and elements, in row-major order, of a Numo::NArray.
+fn narray_data(ruby: &Ruby, val: Value) -> Result<(Vec<usize>, Vec<f64>), Error> {
+    let dfloat: Value = dfloat_class(ruby)?.funcall(*CAST, (val,))?;
+    let shape: Vec<usize> = dfloat.funcall(*SHAPE, ())?;
+    let bytes: RString = dfloat.funcall(*TO_BINARY, ())?;
+    let data = unsafe { bytes.as_slice() }
+        .chunks_exact(8)
+        .map(|chunk| f64::from_ne_bytes(chunk.try_into().unwrap()))
+        .collect();
+    Ok((shape, data))
+}
+
+fn dimension_error(ruby: &Ruby, expected: usize, found: usize) -> Error {
+    Error::new(
+        ruby.exception_arg_error(),
+        format!(
+            "expected {} dimensional array, got {} dimensions",
+            expected, found
+        ),
+    )
+}
+
+impl IntoValue for Array2<f64> {
+    #[inline]
+    fn into_value_with(self, ruby: &Ruby) -> Value {
+        rows_to_ary(ruby, &self).as_value()
+    }
+}
+
+unsafe impl IntoValueFromNative for Array2<f64> {}
+
+impl IntoValue for Array1<f64> {
+    #[inline]
+    fn into_value_with(self, ruby: &Ruby) -> Value {
+        ruby.ary_from_iter(self).as_value()
+    }
+}
+
+unsafe impl IntoValueFromNative for Array1<f64> {}
+
+impl TryConvert for Array2<f64> {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        let ruby = Ruby::get_with(val);
+        if narray_class(&ruby).map_or(false, |c| val.is_kind_of(c)) {
+            let (shape, data) = narray_data(&ruby, val)?;
+            if shape.len() != 2 {
+                return Err(dimension_error(&ruby, 2, shape.len()));
+            }
+            return Array2::from_shape_vec((shape[0], shape[1]), data)
+                .map_err(|e| Error::new(ruby.exception_arg_error(), e.to_string()));
+        }
+        let rows = if matrix_class(&ruby).map_or(false, |c| val.is_kind_of(c)) {
+            val.funcall(*TO_A, ())?
+        } else {
+            Vec::<Vec<f64>>::try_convert(val)?
+        };
+        let cols = rows.first().map_or(0, Vec::len);
+        if let Some(row) = rows.iter().find(|row| row.len() != cols) {
+            return Err(Error::new(
+                ruby.exception_arg_error(),
+                format!(
+                    "rows must all be the same length, expected {} got {}",
+                    cols,
+                    row.len()
+                ),
+            ));
+        }
+        let nrows = rows.len();
+        let data = rows.into_iter().flatten().collect();
+        Ok(Array2::from_shape_vec((nrows, cols), data).unwrap())
+    }
+}
+
+impl TryConvert for Array1<f64> {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        let ruby = Ruby::get_with(val);
+        if narray_class(&ruby).map_or(false, |c| val.is_kind_of(c)) {
+            let (shape, data) = narray_data(&ruby, val)?;
+            if shape.len() != 1 {
+                return Err(dimension_error(&ruby, 1, shape.len()));
+            }
+            return Ok(Array1::from_vec(data));
+        }
+        Vec::<f64>::try_convert(val).map(Array1::from_vec)
+    }
+}
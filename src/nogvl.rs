@@ -0,0 +1,268 @@
+//! Functions for running Rust code without holding Ruby's Global VM Lock.
+//!
+//! Only one thread can run Ruby code at a time, guarded by the Global VM Lock
+//! (GVL). Long running computations or blocking IO in Rust code called from
+//! Ruby will stall all other Ruby threads unless the GVL is released with
+//! [`Ruby::without_gvl`].
+//!
+//! While the GVL is released Ruby's API must not be used. [`with_gvl`] can be
+//! used to temporarily re-acquire the GVL to call back into Ruby.
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{nogvl, Error, Ruby};
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     let sum = ruby.without_gvl(|| {
+//!         let sum = (0..1_000_000u64).sum::<u64>();
+//!         // briefly re-acquire the GVL to call Ruby
+//!         nogvl::with_gvl(|ruby| ruby.thread_schedule()).unwrap();
+//!         sum
+//!     })?;
+//!     assert_eq!(sum, 499999500000);
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use std::{
+    ffi::c_void,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    ptr::null_mut,
+    thread,
+};
+
+use rb_sys::{rb_thread_call_with_gvl, rb_thread_call_without_gvl};
+
+use crate::{
+    api::set_gvl_locked,
+    error::{protect, Error, RubyUnavailableError},
+    Ruby,
+};
+
+struct CallData<F, R> {
+    func: Option<F>,
+    result: Option<thread::Result<R>>,
+}
+
+impl<F, R> CallData<F, R> {
+    fn new(func: F) -> Self {
+        Self {
+            func: Some(func),
+            result: None,
+        }
+    }
+
+    fn as_ptr(&mut self) -> *mut c_void {
+        self as *mut Self as *mut c_void
+    }
+
+    fn into_result(self) -> R {
+        match self.result {
+            Some(Ok(res)) => res,
+            Some(Err(e)) => resume_unwind(e),
+            None => unreachable!("GVL callback not run"),
+        }
+    }
+}
+
+/// # GVL
+///
+/// Functions for releasing the Global VM Lock.
+///
+/// See also the [`nogvl`](crate::nogvl) module.
+impl Ruby {
+    /// Run `func` with the GVL released, allowing other Ruby threads to run.
+    ///
+    /// Ruby's API must not be used from `func`, this is enforced by requiring
+    /// `func` to be [`Send`], so it can not capture Ruby values or a `Ruby`
+    /// handle. Use [`nogvl::with_gvl`](crate::nogvl::with_gvl) to call back
+    /// in to Ruby.
+    ///
+    /// If the Ruby thread is interrupted (e.g. killed, by `Thread#raise`, or
+    /// the process is sent a signal) `func` will continue to run. See
+    /// [`without_gvl_unblock`](Ruby::without_gvl_unblock) to handle
+    /// interrupts.
+    ///
+    /// Returns `Err` if an interrupt was pending before `func` could be run,
+    /// or was received while `func` ran, in which case the result of `func`
+    /// (if any) is discarded. The error should be returned to Ruby so the
+    /// interrupt can be handled.
+    ///
+    /// Panics in `func` are propagated to the caller once the GVL has been
+    /// re-acquired.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let data = vec![3, 1, 2];
+    ///     let sorted = ruby.without_gvl(|| {
+    ///         let mut data = data;
+    ///         data.sort();
+    ///         data
+    ///     })?;
+    ///     assert_eq!(sorted, [1, 2, 3]);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn without_gvl<F, R>(&self, func: F) -> Result<R, Error>
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        self.call_without_gvl::<F, R, fn()>(func, None)
+    }
+
+    /// Run `func` with the GVL released, calling `unblock` if the Ruby thread
+    /// is interrupted.
+    ///
+    /// `unblock` is called from another thread, and should cause `func` to
+    /// return early, for example by setting a flag that `func` checks, or
+    /// closing a file descriptor `func` is blocked on. The interrupt is then
+    /// returned as `Err`.
+    ///
+    /// See [`without_gvl`](Ruby::without_gvl) for more details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let cancelled = AtomicBool::new(false);
+    ///     let count = ruby.without_gvl_unblock(
+    ///         || {
+    ///             let mut count = 0;
+    ///             while !cancelled.load(Ordering::Relaxed) && count < 1000 {
+    ///                 count += 1;
+    ///             }
+    ///             count
+    ///         },
+    ///         || cancelled.store(true, Ordering::Relaxed),
+    ///     )?;
+    ///     assert!(count <= 1000);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn without_gvl_unblock<F, R, U>(&self, func: F, unblock: U) -> Result<R, Error>
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+        U: Fn() + Sync,
+    {
+        self.call_without_gvl(func, Some(&unblock))
+    }
+
+    fn call_without_gvl<F, R, U>(&self, func: F, unblock: Option<&U>) -> Result<R, Error>
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+        U: Fn() + Sync,
+    {
+        unsafe extern "C" fn call<F, R>(data: *mut c_void) -> *mut c_void
+        where
+            F: FnOnce() -> R,
+        {
+            let data = &mut *(data as *mut CallData<F, R>);
+            let func = data.func.take().unwrap();
+            set_gvl_locked(false);
+            data.result = Some(catch_unwind(AssertUnwindSafe(func)));
+            null_mut()
+        }
+
+        unsafe extern "C" fn call_unblock<U>(data: *mut c_void)
+        where
+            U: Fn(),
+        {
+            let unblock = &*(data as *const U);
+            // there's nowhere to propagate a panic to from Ruby's interrupt
+            // handling
+            let _ = catch_unwind(AssertUnwindSafe(unblock));
+        }
+
+        let mut data = CallData::new(func);
+        let (ubf, ubf_data) = match unblock {
+            Some(u) => (
+                Some(call_unblock::<U> as unsafe extern "C" fn(*mut c_void)),
+                u as *const U as *mut c_void,
+            ),
+            None => (None, null_mut()),
+        };
+        // Ruby checks for interrupts before releasing and after re-acquiring
+        // the GVL, which may raise, so this must be protected
+        let res = protect(|| unsafe {
+            rb_thread_call_without_gvl(Some(call::<F, R>), data.as_ptr(), ubf, ubf_data);
+            self.qnil()
+        });
+        set_gvl_locked(true);
+        match (res, data.result) {
+            (_, Some(Err(e))) => resume_unwind(e),
+            (Err(e), _) => Err(e),
+            (Ok(_), Some(Ok(res))) => Ok(res),
+            (Ok(_), None) => unreachable!("GVL callback not run"),
+        }
+    }
+}
+
+/// Run `func` with the GVL held, from within [`Ruby::without_gvl`].
+///
+/// If the current thread already holds the GVL `func` is called
+/// immediately.
+///
+/// Panics in `func` are propagated to the caller once the GVL has been
+/// released again.
+///
+/// Returns `Err` if called from a non-Ruby thread.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{nogvl, Error, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let len = ruby.without_gvl(|| {
+///         nogvl::with_gvl(|ruby| ruby.str_new("example").len()).unwrap()
+///     });
+///     assert_eq!(len, 7);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn with_gvl<F, R>(func: F) -> Result<R, RubyUnavailableError>
+where
+    F: FnOnce(&Ruby) -> R,
+{
+    unsafe extern "C" fn call<F, R>(data: *mut c_void) -> *mut c_void
+    where
+        F: FnOnce(&Ruby) -> R,
+    {
+        let data = &mut *(data as *mut CallData<F, R>);
+        let func = data.func.take().unwrap();
+        set_gvl_locked(true);
+        let ruby = Ruby::get_unchecked();
+        data.result = Some(catch_unwind(AssertUnwindSafe(|| func(&ruby))));
+        set_gvl_locked(false);
+        null_mut()
+    }
+
+    match Ruby::get() {
+        Ok(ruby) => return Ok(func(&ruby)),
+        Err(RubyUnavailableError::GvlUnlocked) => (),
+        Err(e) => return Err(e),
+    }
+    let mut data = CallData::new(func);
+    unsafe { rb_thread_call_with_gvl(Some(call::<F, R>), data.as_ptr()) };
+    Ok(data.into_result())
+}
@@ -6,6 +6,8 @@ use rb_sys::{rb_num_coerce_bin, rb_num_coerce_bit, rb_num_coerce_cmp, rb_num_coe
 
 use crate::{
     error::{protect, Error},
+    float::Float,
+    integer::Integer,
     into_value::IntoValue,
     try_convert::TryConvert,
     value::{
@@ -222,6 +224,131 @@ pub trait Numeric: ReprValue + Copy {
         })
         .and_then(TryConvert::try_convert)
     }
+
+    /// Convert `self` to an `i64`, discarding any fractional part.
+    ///
+    /// The value is rounded towards zero (as with Ruby's `truncate` method),
+    /// and values out of range for `i64` saturate at `i64::MIN`/`i64::MAX`.
+    ///
+    /// Returns `Err` if `self` can not be truncated, e.g. is NaN or infinite.
+    ///
+    /// See also [`Numeric::to_i64_checked`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::num::NonZeroI64;
+    ///
+    /// use magnus::{Error, Numeric, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert_eq!(ruby.float_from_f64(-2.7).to_i64_truncate()?, -2);
+    ///     assert_eq!(
+    ///         ruby.rational_new(7, NonZeroI64::new(2).unwrap())
+    ///             .to_i64_truncate()?,
+    ///         3
+    ///     );
+    ///     assert_eq!(ruby.float_from_f64(1e30).to_i64_truncate()?, i64::MAX);
+    ///     assert!(ruby.float_from_f64(f64::NAN).to_i64_truncate().is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn to_i64_truncate(self) -> Result<i64, Error> {
+        let int: Integer = self.funcall("truncate", ())?;
+        match int.to_i64() {
+            Ok(i) => Ok(i),
+            Err(_) if int < Ruby::get_with(self).integer_from_i64(0) => Ok(i64::MIN),
+            Err(_) => Ok(i64::MAX),
+        }
+    }
+
+    /// Convert `self` to an `i64`, erroring if this would lose any
+    /// information.
+    ///
+    /// Returns `Err` if `self` has a fractional part, or is out of range for
+    /// `i64`.
+    ///
+    /// See also [`Numeric::to_i64_truncate`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Integer, Numeric, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert_eq!(ruby.float_from_f64(2.0).to_i64_checked()?, 2);
+    ///     assert!(ruby.float_from_f64(2.5).to_i64_checked().is_err());
+    ///     let big: Integer = ruby.eval("2 ** 64")?;
+    ///     assert!(big.to_i64_checked().is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn to_i64_checked(self) -> Result<i64, Error> {
+        let int: Integer = self.funcall("truncate", ())?;
+        if !int.equal(self)? {
+            return Err(Error::new(
+                Ruby::get_with(self).exception_range_error(),
+                format!("{} is not an integer", self.inspect()),
+            ));
+        }
+        int.to_i64()
+    }
+
+    /// Convert `self` to an `f64`, erroring if this would lose any
+    /// information.
+    ///
+    /// Floats always convert successfully. Other values return `Err` if they
+    /// can not be exactly represented as an `f64`, for example integers
+    /// larger than 2<sup>53</sup> that are not a multiple of a power of 2, or
+    /// rationals such as 1/3.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::num::NonZeroI64;
+    ///
+    /// use magnus::{Error, Integer, Numeric, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert_eq!(ruby.integer_from_i64(3).to_f64_lossless_or_error()?, 3.0);
+    ///     assert_eq!(
+    ///         ruby.rational_new(1, NonZeroI64::new(4).unwrap())
+    ///             .to_f64_lossless_or_error()?,
+    ///         0.25
+    ///     );
+    ///     assert!(ruby
+    ///         .rational_new(1, NonZeroI64::new(3).unwrap())
+    ///         .to_f64_lossless_or_error()
+    ///         .is_err());
+    ///     let big: Integer = ruby.eval("2 ** 53 + 1")?;
+    ///     assert!(big.to_f64_lossless_or_error().is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn to_f64_lossless_or_error(self) -> Result<f64, Error> {
+        let handle = Ruby::get_with(self);
+        if let Some(f) = Float::from_value(self.as_value()) {
+            return Ok(f.to_f64());
+        }
+        let f = f64::try_convert(self.as_value())?;
+        let exact: Value = handle.float_from_f64(f).funcall("to_r", ())?;
+        if !exact.equal(self)? {
+            return Err(Error::new(
+                handle.exception_range_error(),
+                format!(
+                    "{} can not be exactly represented as a Float",
+                    self.inspect()
+                ),
+            ));
+        }
+        Ok(f)
+    }
 }
 
 /// Wrapper type for a Value known to be an instance of Ruby’s Numeric class.
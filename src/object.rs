@@ -9,7 +9,7 @@ use crate::{
     error::{protect, Error},
     into_value::IntoValue,
     method::Method,
-    module::RModule,
+    module::{Module, RModule},
     try_convert::TryConvert,
     value::{private::ReprValue as _, IntoId, ReprValue, Value},
     Ruby,
@@ -87,6 +87,38 @@ pub trait Object: ReprValue + Copy {
         Ok(())
     }
 
+    /// Alias the singleton method `src` of `self` as `dst`.
+    ///
+    /// This can be used to keep an old name for a 'class' method working
+    /// after it has been renamed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{function, prelude::*, rb_assert, Error, Ruby};
+    ///
+    /// fn test() -> i64 {
+    ///     42
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let module = ruby.define_module("Example")?;
+    ///     module.define_singleton_method("test", function!(test, 0))?;
+    ///     module.define_singleton_alias("old_test", "test")?;
+    ///     rb_assert!(ruby, "Example.old_test == 42");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn define_singleton_alias<T, U>(self, dst: T, src: U) -> Result<(), Error>
+    where
+        T: IntoId,
+        U: IntoId,
+    {
+        self.singleton_class()?.define_alias(dst, src)
+    }
+
     /// Get the value for the instance variable `name` within `self`'s scope.
     ///
     /// Note, the `@` is part of the name. An instance variable can be set and
@@ -182,6 +214,57 @@ pub trait Object: ReprValue + Copy {
         Ok(())
     }
 
+    /// Set the values for several instance variables within `self`'s scope.
+    ///
+    /// Instance variables are set strictly in the order given.
+    ///
+    /// From Ruby 3.2 objects track the set of instance variables they hold
+    /// with an 'object shape', with each new instance variable moving the
+    /// object to a new shape. Objects with instance variables set in the same
+    /// order share shapes, which keeps instance variable access fast. Setting
+    /// instance variables in differing orders (for example by iterating a
+    /// `HashMap`) creates many shapes, and can cause Ruby to fall back to a
+    /// slower representation. Using this method with a fixed list of names
+    /// for all objects of a class avoids this.
+    ///
+    /// Note, the `@` is part of the name. Setting an instance variable without
+    /// a preceding `@` will work, but the instance variable will be invisible
+    /// to Ruby code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, rb_assert, Error, RObject, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let obj: RObject = ruby.eval(
+    ///         r#"
+    ///             class Example
+    ///               attr_reader :a, :b
+    ///             end
+    ///             Example.new
+    ///         "#,
+    ///     )?;
+    ///
+    ///     obj.ivar_set_many(&[("@a", 1), ("@b", 2)])?;
+    ///     rb_assert!(ruby, "obj.a == 1 && obj.b == 2", obj);
+    ///     rb_assert!(ruby, "obj.instance_variables == [:@a, :@b]", obj);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn ivar_set_many<T, U>(self, ivars: &[(T, U)]) -> Result<(), Error>
+    where
+        T: IntoId + Clone,
+        U: IntoValue + Clone,
+    {
+        for (name, value) in ivars {
+            self.ivar_set(name.clone(), value.clone())?;
+        }
+        Ok(())
+    }
+
     /// Finds or creates the singleton class of `self`.
     ///
     /// Returns `Err` if `self` can not have a singleton class.
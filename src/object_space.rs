@@ -0,0 +1,281 @@
+//! Functions for working with Ruby's `ObjectSpace`.
+//!
+//! See also [`Ruby`](Ruby#objectspace) for more `ObjectSpace` related
+//! methods.
+
+use std::{mem::forget, path::Path};
+
+use crate::{
+    error::Error,
+    into_value::KwArgs,
+    module::{Module, RModule},
+    value::{LazyId, ReprValue, Value},
+    Ruby,
+};
+
+static TRACE_OBJECT_ALLOCATIONS_START: LazyId = LazyId::new("trace_object_allocations_start");
+static TRACE_OBJECT_ALLOCATIONS_STOP: LazyId = LazyId::new("trace_object_allocations_stop");
+static TRACE_OBJECT_ALLOCATIONS_CLEAR: LazyId = LazyId::new("trace_object_allocations_clear");
+static ALLOCATION_SOURCEFILE: LazyId = LazyId::new("allocation_sourcefile");
+static ALLOCATION_SOURCELINE: LazyId = LazyId::new("allocation_sourceline");
+static ALLOCATION_CLASS_PATH: LazyId = LazyId::new("allocation_class_path");
+static ALLOCATION_METHOD_ID: LazyId = LazyId::new("allocation_method_id");
+static ALLOCATION_GENERATION: LazyId = LazyId::new("allocation_generation");
+static CLOSE: LazyId = LazyId::new("close");
+static DUMP_ALL: LazyId = LazyId::new("dump_all");
+static OPEN: LazyId = LazyId::new("open");
+
+/// Where and when an object was allocated.
+///
+/// See [`Ruby::allocation_source`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AllocationSource {
+    /// The source file of the code that allocated the object.
+    pub file: String,
+    /// The line number of the code that allocated the object.
+    pub line: usize,
+    /// The name of the class of the method that allocated the object, if
+    /// any.
+    pub class_path: Option<String>,
+    /// The name of the method that allocated the object, if any.
+    pub method: Option<String>,
+    /// The GC generation (see [`Ruby::gc_count`]) the object was allocated
+    /// in.
+    pub generation: usize,
+}
+
+/// Where to write a heap dump.
+///
+/// See [`Ruby::heap_dump`].
+#[derive(Clone, Copy, Debug)]
+pub enum HeapDumpTarget<'a> {
+    /// Write to a new file at the given path, replacing any existing file.
+    Path(&'a Path),
+    /// Write to a Ruby IO object, or any object responding to `write`.
+    Io(Value),
+}
+
+impl<'a> From<&'a Path> for HeapDumpTarget<'a> {
+    fn from(val: &'a Path) -> Self {
+        Self::Path(val)
+    }
+}
+
+/// Options for [`Ruby::heap_dump`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeapDumpOptions {
+    full: bool,
+    since: Option<usize>,
+    shapes: Option<bool>,
+}
+
+impl HeapDumpOptions {
+    /// Create a new `HeapDumpOptions` with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include empty heap slots in the dump.
+    ///
+    /// This is equivalent to `dump_all`'s `full:` option.
+    pub fn full(mut self, full: bool) -> Self {
+        self.full = full;
+        self
+    }
+
+    /// Only dump objects allocated since the GC generation `generation` (see
+    /// [`Ruby::gc_count`]).
+    ///
+    /// This is equivalent to `dump_all`'s `since:` option, and is most
+    /// useful with allocation tracing enabled.
+    pub fn since(mut self, generation: usize) -> Self {
+        self.since = Some(generation);
+        self
+    }
+
+    /// Include object shapes in the dump (Ruby >= 3.3).
+    ///
+    /// This is equivalent to `dump_all`'s `shapes:` option. When not set
+    /// Ruby's default is used.
+    pub fn shapes(mut self, shapes: bool) -> Self {
+        self.shapes = Some(shapes);
+        self
+    }
+}
+
+// Stops allocation tracing if the function being traced panics.
+struct StopTracing<'a>(&'a Ruby);
+
+impl Drop for StopTracing<'_> {
+    fn drop(&mut self) {
+        let _ = self.0.trace_object_allocations_stop();
+    }
+}
+
+fn object_space(ruby: &Ruby) -> Result<RModule, Error> {
+    ruby.require("objspace")?;
+    ruby.class_object().const_get("ObjectSpace")
+}
+
+/// # `ObjectSpace`
+///
+/// Functions for tracing where objects are allocated and dumping the heap,
+/// useful for memory debugging tools.
+///
+/// These require Ruby's `objspace` library, which will be loaded on first
+/// use.
+///
+/// See also the [`object_space`](self) module.
+impl Ruby {
+    /// Start recording the source location of all object allocations.
+    ///
+    /// This is equivalent to Ruby's
+    /// `ObjectSpace.trace_object_allocations_start`. Tracing slows down
+    /// allocation, so should only be enabled while debugging.
+    ///
+    /// Calls to `trace_object_allocations_start` nest, tracing will only stop
+    /// once [`Ruby::trace_object_allocations_stop`] has been called the same
+    /// number of times.
+    pub fn trace_object_allocations_start(&self) -> Result<(), Error> {
+        let _: Value = object_space(self)?.funcall(*TRACE_OBJECT_ALLOCATIONS_START, ())?;
+        Ok(())
+    }
+
+    /// Stop recording the source location of object allocations.
+    ///
+    /// This is equivalent to Ruby's
+    /// `ObjectSpace.trace_object_allocations_stop`. Already recorded
+    /// locations are kept until [`Ruby::trace_object_allocations_clear`] is
+    /// called.
+    pub fn trace_object_allocations_stop(&self) -> Result<(), Error> {
+        let _: Value = object_space(self)?.funcall(*TRACE_OBJECT_ALLOCATIONS_STOP, ())?;
+        Ok(())
+    }
+
+    /// Discard all recorded allocation locations.
+    ///
+    /// This is equivalent to Ruby's
+    /// `ObjectSpace.trace_object_allocations_clear`.
+    pub fn trace_object_allocations_clear(&self) -> Result<(), Error> {
+        let _: Value = object_space(self)?.funcall(*TRACE_OBJECT_ALLOCATIONS_CLEAR, ())?;
+        Ok(())
+    }
+
+    /// Record the source location of all object allocations while `func`
+    /// runs.
+    ///
+    /// Tracing is stopped whether `func` returns an error or panics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby, Value};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let obj = ruby.trace_object_allocations(|| ruby.eval::<Value>("Object.new"))?;
+    ///
+    ///     let source = ruby.allocation_source(obj)?.unwrap();
+    ///     assert_eq!(source.method.as_deref(), Some("new"));
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn trace_object_allocations<F, T>(&self, func: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> Result<T, Error>,
+    {
+        self.trace_object_allocations_start()?;
+        let guard = StopTracing(self);
+        let res = func();
+        forget(guard);
+        self.trace_object_allocations_stop()?;
+        res
+    }
+
+    /// Returns where `obj` was allocated, or `None` if its allocation was not
+    /// recorded.
+    ///
+    /// Allocations are only recorded while tracing is enabled with
+    /// [`Ruby::trace_object_allocations_start`] or
+    /// [`Ruby::trace_object_allocations`].
+    pub fn allocation_source<T>(&self, obj: T) -> Result<Option<AllocationSource>, Error>
+    where
+        T: ReprValue,
+    {
+        let object_space = object_space(self)?;
+        let file: Option<String> =
+            object_space.funcall(*ALLOCATION_SOURCEFILE, (obj.as_value(),))?;
+        let file = match file {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+        Ok(Some(AllocationSource {
+            file,
+            line: object_space.funcall(*ALLOCATION_SOURCELINE, (obj.as_value(),))?,
+            class_path: object_space.funcall(*ALLOCATION_CLASS_PATH, (obj.as_value(),))?,
+            method: object_space
+                .funcall::<_, _, Option<Value>>(*ALLOCATION_METHOD_ID, (obj.as_value(),))?
+                .map(|m| m.to_r_string()?.to_string())
+                .transpose()?,
+            generation: object_space.funcall(*ALLOCATION_GENERATION, (obj.as_value(),))?,
+        }))
+    }
+
+    /// Write a dump of the Ruby heap, in the JSON lines format produced by
+    /// `ObjectSpace.dump_all`, to `target`.
+    ///
+    /// This can be used by embedders to capture diagnostics when memory
+    /// growth is detected. Dumping the heap can take some time and produce
+    /// a large file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{
+    ///     object_space::{HeapDumpOptions, HeapDumpTarget},
+    ///     prelude::*,
+    ///     Error, Ruby, Value,
+    /// };
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let io: Value = ruby.eval("require 'stringio'; StringIO.new")?;
+    ///     ruby.heap_dump(HeapDumpTarget::Io(io), HeapDumpOptions::new())?;
+    ///
+    ///     let dump: String = io.funcall("string", ())?;
+    ///     assert!(dump.lines().count() > 0);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn heap_dump<'a, T>(&self, target: T, options: HeapDumpOptions) -> Result<(), Error>
+    where
+        T: Into<HeapDumpTarget<'a>>,
+    {
+        let object_space = object_space(self)?;
+        let kwargs = self.hash_new();
+        kwargs.aset(self.to_symbol("full"), options.full)?;
+        if let Some(since) = options.since {
+            kwargs.aset(self.to_symbol("since"), since)?;
+        }
+        if let Some(shapes) = options.shapes {
+            kwargs.aset(self.to_symbol("shapes"), shapes)?;
+        }
+        match target.into() {
+            HeapDumpTarget::Io(io) => {
+                kwargs.aset(self.to_symbol("output"), io)?;
+                let _: Value = object_space.funcall(*DUMP_ALL, (KwArgs(kwargs),))?;
+                Ok(())
+            }
+            HeapDumpTarget::Path(path) => {
+                let file: Value = self.class_file().funcall(*OPEN, (path, "w"))?;
+                kwargs.aset(self.to_symbol("output"), file)?;
+                let res = object_space.funcall::<_, _, Value>(*DUMP_ALL, (KwArgs(kwargs),));
+                let closed = file.funcall::<_, _, Value>(*CLOSE, ());
+                // report the dump error in preference to a close error
+                res.and(closed).map(|_| ())
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Timing of methods defined with magnus.
+//!
+//! When the `profile` feature is enabled every call to a method or function
+//! wrapped with the [`method`](crate::method!) or
+//! [`function`](crate::function!) macros is timed, with the number of calls
+//! and time taken recorded per method name. This allows profiling an
+//! extension without reaching for external tools.
+//!
+//! Timings are keyed only by method name, so methods of the same name
+//! defined on different classes are counted together. The recorded time
+//! includes converting arguments and return values.
+//!
+//! The counts can be read from Rust with [`stats`], or from Ruby with
+//! `Magnus.stats` after calling [`Ruby::define_profile_stats`].
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{function, profile, rb_assert, Error, Ruby};
+//!
+//! fn double(i: i64) -> i64 {
+//!     i * 2
+//! }
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     ruby.define_global_function("double", function!(double, 1));
+//!     ruby.define_profile_stats()?;
+//!
+//!     let _: i64 = ruby.eval("double(1) + double(2)")?;
+//!
+//!     let stats = profile::stats_for("double").unwrap();
+//!     assert_eq!(stats.calls, 2);
+//!     rb_assert!(ruby, "Magnus.stats[:double][:calls] == 2");
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap();
+//! ```
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use rb_sys::{rb_frame_this_func, ID};
+
+use crate::{
+    error::Error, function, module::RModule, object::Object, r_hash::RHash, value::Id, Ruby,
+};
+
+// method names are looked up when a method is first recorded, while the
+// GVL is held, so reading the stats doesn't need to call Ruby
+static STATS: Mutex<Option<HashMap<ID, (&'static str, MethodStats)>>> = Mutex::new(None);
+
+/// Call counts and timings for a single method name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MethodStats {
+    /// The number of times the method has been called.
+    pub calls: u64,
+    /// The total time spent in the method.
+    pub total: Duration,
+    /// The longest time spent in a single call to the method.
+    pub max: Duration,
+}
+
+fn lock() -> MutexGuard<'static, Option<HashMap<ID, (&'static str, MethodStats)>>> {
+    // this is locked from every profiled method call, so must not panic.
+    // The only panic possible while holding the lock is a `Duration`
+    // overflow in `record`, which would leave one method's timings partly
+    // updated, and that's no reason to stop profiling
+    STATS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[inline]
+pub(crate) fn start() -> Instant {
+    Instant::now()
+}
+
+#[inline]
+pub(crate) fn record(start: Instant) {
+    let elapsed = start.elapsed();
+    let id = unsafe { rb_frame_this_func() };
+    if id == 0 {
+        return;
+    }
+    let mut guard = lock();
+    let map = guard.get_or_insert_with(HashMap::new);
+    let stats = match map.entry(id) {
+        Entry::Occupied(entry) => &mut entry.into_mut().1,
+        Entry::Vacant(entry) => match Id::from_rb_id(id).name() {
+            Ok(name) => &mut entry.insert((name, MethodStats::default())).1,
+            Err(_) => return,
+        },
+    };
+    stats.calls += 1;
+    stats.total += elapsed;
+    stats.max = stats.max.max(elapsed);
+}
+
+/// Clear all recorded timings.
+pub fn reset() {
+    *lock() = None;
+}
+
+/// Returns the recorded timings for all methods, sorted by total time spent,
+/// most first.
+pub fn stats() -> Vec<(&'static str, MethodStats)> {
+    let mut res = lock()
+        .as_ref()
+        .map(|map| {
+            map.values()
+                .map(|(name, stats)| (*name, *stats))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    res.sort_by(|a, b| b.1.total.cmp(&a.1.total).then(a.0.cmp(b.0)));
+    res
+}
+
+/// Returns the recorded timings for methods named `name`, or `None` if
+/// nothing has been recorded for `name`.
+pub fn stats_for(name: &str) -> Option<MethodStats> {
+    stats()
+        .into_iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, stats)| stats)
+}
+
+fn stats_hash(ruby: &Ruby) -> Result<RHash, Error> {
+    let hash = ruby.hash_new();
+    for (name, stats) in stats() {
+        let entry = ruby.hash_new();
+        entry.aset(ruby.to_symbol("calls"), stats.calls)?;
+        entry.aset(ruby.to_symbol("total"), stats.total.as_secs_f64())?;
+        entry.aset(ruby.to_symbol("max"), stats.max.as_secs_f64())?;
+        hash.aset(ruby.to_symbol(name), entry)?;
+    }
+    Ok(hash)
+}
+
+fn reset_stats() {
+    reset()
+}
+
+/// # Profiling
+///
+/// Functions for reading method timings from Ruby.
+///
+/// See also the [`profile`](self) module.
+impl Ruby {
+    /// Define the `Magnus` module, with the singleton methods `stats` and
+    /// `reset_stats`.
+    ///
+    /// `Magnus.stats` returns a Hash of method name Symbols to Hashes with
+    /// the keys `:calls`, `:total`, and `:max`. Times are Floats in seconds.
+    /// `Magnus.reset_stats` clears all recorded timings.
+    ///
+    /// See the [`profile`](self) module for more details.
+    pub fn define_profile_stats(&self) -> Result<RModule, Error> {
+        let module = self.define_module("Magnus")?;
+        module.define_singleton_method("stats", function!(stats_hash, 0))?;
+        module.define_singleton_method("reset_stats", function!(reset_stats, 0))?;
+        Ok(module)
+    }
+}
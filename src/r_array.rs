@@ -7,23 +7,25 @@ use rb_sys::rb_ary_hidden_new;
 #[cfg(ruby_lt_3_2)]
 use rb_sys::rb_ary_tmp_new as rb_ary_hidden_new;
 use rb_sys::{
-    self, rb_ary_assoc, rb_ary_cat, rb_ary_clear, rb_ary_cmp, rb_ary_concat, rb_ary_delete,
-    rb_ary_delete_at, rb_ary_entry, rb_ary_includes, rb_ary_join, rb_ary_new, rb_ary_new_capa,
-    rb_ary_new_from_values, rb_ary_plus, rb_ary_pop, rb_ary_push, rb_ary_rassoc, rb_ary_replace,
-    rb_ary_resize, rb_ary_reverse, rb_ary_rotate, rb_ary_shared_with_p, rb_ary_shift,
-    rb_ary_sort_bang, rb_ary_store, rb_ary_subseq, rb_ary_to_ary, rb_ary_unshift,
+    self, rb_ary_aref, rb_ary_assoc, rb_ary_cat, rb_ary_clear, rb_ary_cmp, rb_ary_concat,
+    rb_ary_delete, rb_ary_delete_at, rb_ary_entry, rb_ary_includes, rb_ary_join, rb_ary_new,
+    rb_ary_new_capa, rb_ary_new_from_values, rb_ary_plus, rb_ary_pop, rb_ary_push, rb_ary_rassoc,
+    rb_ary_replace, rb_ary_resize, rb_ary_reverse, rb_ary_rotate, rb_ary_shared_with_p,
+    rb_ary_shift, rb_ary_sort_bang, rb_ary_store, rb_ary_subseq, rb_ary_to_ary, rb_ary_unshift,
     rb_check_array_type, rb_obj_hide, rb_obj_reveal, ruby_value_type, RARRAY_CONST_PTR, RARRAY_LEN,
     VALUE,
 };
 use seq_macro::seq;
 
 use crate::{
+    class::{Class, RClass},
     enumerator::Enumerator,
     error::{protect, Error},
     gc,
     into_value::{IntoValue, IntoValueFromNative},
+    module::Module,
     object::Object,
-    r_string::{IntoRString, RString},
+    r_string::{self, IntoRString, RString},
     try_convert::{TryConvert, TryConvertOwned},
     value::{
         private::{self, ReprValue as _},
@@ -168,6 +170,40 @@ impl Ruby {
             .unwrap()
     }
 
+    /// Create a new frozen `RArray` from a Rust iterator.
+    ///
+    /// Any strings in the array are also frozen and deduplicated (interned
+    /// on Ruby 3.0 and later), making this suitable for returning constant
+    /// lookup data to Ruby.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary = ruby.ary_frozen_from_iter(["a", "b", "c"]);
+    ///     assert!(ary.is_frozen());
+    ///     rb_assert!(ruby, r#"ary == ["a", "b", "c"]"#, ary);
+    ///     rb_assert!(ruby, "ary.all?(&:frozen?)", ary);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn ary_frozen_from_iter<I, T>(&self, iter: I) -> RArray
+    where
+        I: IntoIterator<Item = T>,
+        T: IntoValue,
+    {
+        let ary = self.ary_from_iter(
+            iter.into_iter()
+                .map(|v| r_string::dedup_str(self.into_value(v))),
+        );
+        ary.freeze();
+        ary
+    }
+
     /// Create a new `RArray` from a fallible Rust iterator.
     ///
     /// Returns `Ok(RArray)` on sucess or `Err(E)` with the first error
@@ -255,6 +291,37 @@ impl Ruby {
             TypedArray(NonZeroValue::new_unchecked(Value::new(ary)), PhantomData)
         }
     }
+
+    /// Create a new Ruby `Set` from a Rust iterator, such as a
+    /// [`HashSet`](std::collections::HashSet) or
+    /// [`BTreeSet`](std::collections::BTreeSet).
+    ///
+    /// Loads the `set` library if required. Returns `Err` if `Set` can not be
+    /// loaded, or an element can not be hashed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let set = ruby.set_from_iter(BTreeSet::from([1, 2, 3]))?;
+    ///     rb_assert!(ruby, "set == Set[1, 2, 3]", set);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn set_from_iter<I, T>(&self, iter: I) -> Result<Value, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: IntoValue,
+    {
+        let ary = self.ary_from_iter(iter);
+        set_class(self)?.new_instance((ary,))
+    }
 }
 
 /// A Value pointer to a RArray struct, Ruby's internal representation of an
@@ -1145,7 +1212,69 @@ impl RArray {
     where
         T: TryConvertOwned,
     {
-        unsafe { self.as_slice().iter().map(|v| T::try_convert(*v)).collect() }
+        // converting an element may call back into Ruby and modify `self`, so
+        // don't hold a slice of the array's contents while converting
+        let mut vec = Vec::with_capacity(self.len());
+        let mut i = 0;
+        while i < self.len() {
+            vec.push(self.entry(i as isize)?);
+            i += 1;
+        }
+        Ok(vec)
+    }
+
+    /// Returns an iterator converting each element of `self` to a `T` as it
+    /// is reached.
+    ///
+    /// Unlike [`to_vec`](RArray::to_vec) this does not allocate a `Vec` for
+    /// the converted elements, so is suitable for processing very large
+    /// arrays. Iteration stops after the first element that fails to convert.
+    ///
+    /// As with [`RArray::into_iter`] the iterator is over a copy of `self`, so
+    /// modifications to `self` during iteration will not be seen. The copy
+    /// shares the backing store of `self`, so uses no extra memory unless
+    /// `self` is modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, RArray, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary: RArray = ruby.eval("(1..1_000_000).to_a")?;
+    ///     let mut sum = 0;
+    ///     for i in ary.try_convert_iter::<i64>() {
+    ///         sum += i?;
+    ///     }
+    ///     assert_eq!(sum, 500_000_500_000);
+    ///
+    ///     let ary: RArray = ruby.eval(r#"[1, 2, "three", 4]"#)?;
+    ///     let res = ary
+    ///         .try_convert_iter::<i64>()
+    ///         .collect::<Result<Vec<_>, _>>();
+    ///     assert!(res.is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn try_convert_iter<T>(self) -> TryConvertIter<T>
+    where
+        T: TryConvert,
+    {
+        let ary = if self.is_frozen() {
+            self
+        } else {
+            let tmp = self.dup();
+            unsafe { rb_obj_hide(tmp.as_rb_value()) };
+            tmp
+        };
+        TryConvertIter {
+            data: ary,
+            len: ary.len(),
+            idx: 0,
+            item_type: PhantomData,
+        }
     }
 
     /// Convert `self` to a Rust array of [`Value`]s, of length `N`.
@@ -1202,23 +1331,17 @@ impl RArray {
     where
         T: TryConvert,
     {
-        unsafe {
-            let slice = self.as_slice();
-            if slice.len() != N {
-                return Err(Error::new(
-                    Ruby::get_with(self).exception_type_error(),
-                    format!("expected Array of length {}", N),
-                ));
-            }
-            // one day might be able to collect direct into an array, but for
-            // now need to go via Vec
-            slice
-                .iter()
-                .copied()
-                .map(TryConvert::try_convert)
-                .collect::<Result<Vec<T>, Error>>()
-                .map(|v| v.try_into().ok().unwrap())
-        }
+        // copy the elements to the stack before converting, as converting an
+        // element may call back into Ruby and modify `self`
+        let values = self.to_value_array::<N>()?;
+        // one day might be able to collect direct into an array, but for
+        // now need to go via Vec
+        values
+            .iter()
+            .copied()
+            .map(TryConvert::try_convert)
+            .collect::<Result<Vec<T>, Error>>()
+            .map(|v| v.try_into().ok().unwrap())
     }
 
     /// Stringify the contents of `self` and join the sequence with `sep`.
@@ -1448,7 +1571,9 @@ impl RArray {
     /// }
     /// # Ruby::init(example).unwrap()
     /// ```
-    // TODO maybe take a range instead of offset and length
+    ///
+    /// See also [`RArray::aref`] and [`RArray::aref_len`] to use Ruby's
+    /// indexing semantics, with negative indexes and ranges.
     pub fn subseq(self, offset: usize, length: usize) -> Option<Self> {
         unsafe {
             let val = Value::new(rb_ary_subseq(
@@ -1460,6 +1585,206 @@ impl RArray {
         }
     }
 
+    /// Index `self` with Ruby's `Array#[]` semantics.
+    ///
+    /// `index` may be an integer, where negative values count back from the
+    /// end of the array, or a range (either a Rust range or a Ruby
+    /// [`Range`](crate::Range)), which will return a new array.
+    ///
+    /// An index out of range results in `nil`, so `U` should generally be an
+    /// `Option`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, RArray, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary = ruby.ary_from_vec(vec![1, 2, 3, 4, 5]);
+    ///     assert_eq!(ary.aref::<_, Option<i64>>(-1)?, Some(5));
+    ///     assert_eq!(ary.aref::<_, Option<i64>>(5)?, None);
+    ///
+    ///     let a: RArray = ary.aref(1..3)?;
+    ///     rb_assert!(ruby, "a == [2, 3]", a);
+    ///     let b: RArray = ary.aref(ruby.range_new(-2, -1, false)?)?;
+    ///     rb_assert!(ruby, "b == [4, 5]", b);
+    ///     assert!(ary.aref::<_, Option<RArray>>(6..)?.is_none());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn aref<T, U>(self, index: T) -> Result<U, Error>
+    where
+        T: IntoValue,
+        U: TryConvert,
+    {
+        let handle = Ruby::get_with(self);
+        let index = handle.into_value(index);
+        protect(|| unsafe {
+            Value::new(rb_ary_aref(
+                1,
+                &index.as_rb_value() as *const VALUE,
+                self.as_rb_value(),
+            ))
+        })
+        .and_then(TryConvert::try_convert)
+    }
+
+    /// Create a new array from a subsequence of `self`, with Ruby's
+    /// `Array#[start, length]` semantics.
+    ///
+    /// A negative `start` counts back from the end of the array. Returns
+    /// `None` if `start` is out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary = ruby.ary_from_vec(vec![1, 2, 3, 4, 5]);
+    ///     let a = ary.aref_len(-3, 2).unwrap();
+    ///     rb_assert!(ruby, "a == [3, 4]", a);
+    ///     let b = ary.aref_len(3, 10).unwrap();
+    ///     rb_assert!(ruby, "b == [4, 5]", b);
+    ///     assert!(ary.aref_len(-6, 1).is_none());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn aref_len(self, start: isize, length: usize) -> Option<Self> {
+        let len = self.len() as isize;
+        let start = if start < 0 { start + len } else { start };
+        if start < 0 || start > len {
+            return None;
+        }
+        self.subseq(start as usize, length)
+    }
+
+    /// Create a new array with the contents of `self` recursively flattened
+    /// into it.
+    ///
+    /// Flattens to at most `depth` levels of nesting, or completely if
+    /// `depth` is `None`.
+    ///
+    /// Returns `Err` if `depth` is `None` and `self` contains a reference to
+    /// itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary: magnus::RArray = ruby.eval("[1, [2, [3, [4]]]]")?;
+    ///     let a = ary.flatten(None)?;
+    ///     rb_assert!(ruby, "a == [1, 2, 3, 4]", a);
+    ///     let b = ary.flatten(Some(1))?;
+    ///     rb_assert!(ruby, "b == [1, 2, [3, [4]]]", b);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn flatten(self, depth: Option<usize>) -> Result<Self, Error> {
+        let ruby = Ruby::get_with(self);
+        let res = ruby.ary_new_capa(self.len());
+        self.flatten_into(&ruby, res, depth)?;
+        Ok(res)
+    }
+
+    fn flatten_into(self, ruby: &Ruby, res: Self, depth: Option<usize>) -> Result<(), Error> {
+        ruby.stack_check()?;
+        let _: Value = ruby.with_recursion_guard(self, |recursive| -> Result<(), Error> {
+            // with a depth limit a recursive array is only flattened so far
+            if recursive && depth.is_none() {
+                return Err(Error::new(
+                    ruby.exception_arg_error(),
+                    "tried to flatten recursive array",
+                ));
+            }
+            for i in 0..self.len() {
+                let val: Value = self.entry(i as isize)?;
+                let inner = if depth == Some(0) {
+                    None
+                } else {
+                    protect(|| unsafe { Value::new(rb_check_array_type(val.as_rb_value())) })
+                        .map(Self::from_value)?
+                };
+                match inner {
+                    Some(ary) => ary.flatten_into(ruby, res, depth.map(|d| d - 1))?,
+                    None => res.push(val)?,
+                }
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Create a new array with the contents of `self` with any `nil` elements
+    /// removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary = ruby.ary_from_vec(vec![Some(1), None, Some(2), None]);
+    ///     let res = ary.compact()?;
+    ///     rb_assert!(ruby, "res == [1, 2]", res);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn compact(self) -> Result<Self, Error> {
+        let res = Ruby::get_with(self).ary_new_capa(self.len());
+        for i in 0..self.len() {
+            let val: Value = self.entry(i as isize)?;
+            if !val.is_nil() {
+                res.push(val)?;
+            }
+        }
+        Ok(res)
+    }
+
+    /// Create a new array with the contents of `self` with duplicate elements
+    /// removed.
+    ///
+    /// Elements are compared with `#hash` and `#eql?`, the first occurrence
+    /// of each element is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary = ruby.ary_from_vec(vec![1, 2, 1, 3, 2]);
+    ///     let res = ary.uniq()?;
+    ///     rb_assert!(ruby, "res == [1, 2, 3]", res);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn uniq(self) -> Result<Self, Error> {
+        let ruby = Ruby::get_with(self);
+        let res = ruby.ary_new_capa(self.len());
+        let seen = ruby.hash_new();
+        for i in 0..self.len() {
+            let val: Value = self.entry(i as isize)?;
+            if !seen.lookup2::<_, _, bool>(val, false)? {
+                seen.aset(val, true)?;
+                res.push(val)?;
+            }
+        }
+        Ok(res)
+    }
+
     /// Search `self` as an 'associative array' for `key`.
     ///
     /// Assumes `self` is an array of arrays, searching from the start of the
@@ -1677,6 +2002,20 @@ where
 
 unsafe impl<T> IntoValueFromNative for Vec<T> where T: IntoValueFromNative {}
 
+/// Returns Ruby's `Set` class, loading the `set` library if required.
+///
+/// `Set` is autoloaded from Ruby 3.2, and part of core from Ruby 3.5, but
+/// must be explicitly required with earlier versions.
+pub(crate) fn set_class(handle: &Ruby) -> Result<RClass, Error> {
+    match handle.class_object().const_get("Set") {
+        Ok(class) => Ok(class),
+        Err(_) => {
+            handle.require("set")?;
+            handle.class_object().const_get("Set")
+        }
+    }
+}
+
 #[cfg(feature = "old-api")]
 impl<T> FromIterator<T> for RArray
 where
@@ -2004,7 +2343,7 @@ where
 }
 
 impl<T> Iter<T> {
-    fn new(data: RArray) -> Self {
+    pub(crate) fn new(data: RArray) -> Self {
         Self {
             data,
             len: data.len(),
@@ -2013,3 +2352,40 @@ impl<T> Iter<T> {
         }
     }
 }
+
+/// An iterator converting the elements of an array, returning an error if
+/// the conversion fails.
+///
+/// See [`RArray::try_convert_iter`].
+pub struct TryConvertIter<T> {
+    data: RArray,
+    len: usize,
+    idx: usize,
+    item_type: PhantomData<T>,
+}
+
+impl<T> Iterator for TryConvertIter<T>
+where
+    T: TryConvert,
+{
+    type Item = Result<T, Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx >= self.len {
+            return None;
+        }
+        let value = self.data.entry(self.idx as isize);
+        // stop after the first error
+        self.idx = if value.is_ok() {
+            self.idx + 1
+        } else {
+            self.len
+        };
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.len - self.idx))
+    }
+}
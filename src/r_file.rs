@@ -1,25 +1,44 @@
-use std::fmt;
 #[cfg(ruby_lt_3_3)]
 use std::ptr::NonNull;
+use std::{ffi::c_void, fmt, io, time::Duration};
 
 #[cfg(ruby_gte_3_3)]
 use rb_sys::rb_io_descriptor;
-use rb_sys::ruby_value_type;
+use rb_sys::{
+    rb_io_bufwrite, rb_io_flush, rb_io_get_write_io, rb_str_resize, rb_sys_fail, ruby_value_type,
+};
 
 use crate::{
-    error::Error,
+    error::{protect, Error},
     into_value::IntoValue,
     object::Object,
+    r_array::RArray,
+    r_string::RString,
+    symbol::Symbol,
     try_convert::TryConvert,
     value::{
         private::{self, ReprValue as _},
-        NonZeroValue, ReprValue, Value,
+        LazyId, NonZeroValue, ReprValue, Value,
     },
     Ruby,
 };
 
+// Ruby defines these with the same values on all platforms
+const LOCK_SH: i32 = 1;
+const LOCK_EX: i32 = 2;
+const LOCK_NB: i32 = 4;
+const LOCK_UN: i32 = 8;
+
 /// A Value pointer to a RFile struct, Ruby's internal representation of IO.
 ///
+/// The [`io::Read`] and [`io::Write`] implementations must return an
+/// [`io::Error`], which can not hold a Ruby exception. `SystemCallError`s keep
+/// their OS error code, but any other error is reduced to its message, and the
+/// original exception is lost. This includes exceptions not usually rescued,
+/// such as `Interrupt` and `SystemExit`, and `throw`. Use
+/// [`read_into`](Self::read_into), [`write_from`](Self::write_from), and
+/// [`flush`](Self::flush) to keep the original [`Error`].
+///
 /// See the [`ReprValue`] and [`Object`] traits for additional methods
 /// available on this type.
 #[derive(Clone, Copy)]
@@ -52,6 +71,456 @@ impl RFile {
         }
     }
 
+    /// Read up to `buf.len()` bytes from `self` into `buf`, returning the
+    /// number of bytes read.
+    ///
+    /// This reads from Ruby's internal buffer if it holds any data, otherwise
+    /// blocks until at least one byte is available, as with
+    /// `IO#readpartial`. Returns `Ok(0)` at end of file, or if `buf` is
+    /// empty.
+    ///
+    /// Ruby reads directly in to a temporary buffer string that is released
+    /// as soon as the data has been copied to `buf`, rather than leaving a
+    /// string of up to `buf.len()` bytes for the garbage collector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, RFile, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let io: RFile = ruby.eval(
+    ///         r#"IO.pipe.then { |r, w| w.write("hello world"); w.close; r }"#,
+    ///     )?;
+    ///
+    ///     let mut buf = [0; 5];
+    ///     assert_eq!(io.read_into(&mut buf)?, 5);
+    ///     assert_eq!(&buf, b"hello");
+    ///
+    ///     let mut rest = Vec::new();
+    ///     let mut buf = [0; 4];
+    ///     loop {
+    ///         let n = io.read_into(&mut buf)?;
+    ///         if n == 0 {
+    ///             break;
+    ///         }
+    ///         rest.extend_from_slice(&buf[..n]);
+    ///     }
+    ///     assert_eq!(rest, b" world");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn read_into(self, buf: &mut [u8]) -> Result<usize, Error> {
+        static READPARTIAL: LazyId = LazyId::new("readpartial");
+
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let handle = Ruby::get_with(self);
+        let outbuf = handle.str_buf_new(buf.len());
+        let res = self.funcall::<_, _, Value>(*READPARTIAL, (buf.len(), outbuf));
+        let len = match res {
+            Ok(_) => {
+                let bytes = unsafe { outbuf.as_slice() };
+                buf[..bytes.len()].copy_from_slice(bytes);
+                Ok(bytes.len())
+            }
+            Err(e) if e.is_kind_of(handle.exception_eof_error()) => Ok(0),
+            Err(e) => Err(e),
+        };
+        unsafe { rb_str_resize(outbuf.as_rb_value(), 0) };
+        len
+    }
+
+    /// Write all of `buf` to `self`, returning the number of bytes written.
+    ///
+    /// The bytes are copied directly into Ruby's internal write buffer (or
+    /// written to the underlying file descriptor if `self` is unbuffered),
+    /// without creating an intermediate Ruby string. As with `IO#write` data
+    /// may remain buffered until `self` is flushed or closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, rb_assert, Error, RFile, Ruby, Value};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let (r, w): (RFile, RFile) = ruby.eval("IO.pipe")?;
+    ///
+    ///     assert_eq!(w.write_from(b"hello world")?, 11);
+    ///     let _: Value = w.funcall("close", ())?;
+    ///     rb_assert!(ruby, r#"r.read == "hello world""#, r);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn write_from(self, buf: &[u8]) -> Result<usize, Error> {
+        let mut written = 0;
+        protect(|| unsafe {
+            // duplex IO (e.g. from IO.popen) has a separate IO for writing
+            let io = rb_io_get_write_io(self.as_rb_value());
+            let n = rb_io_bufwrite(io, buf.as_ptr() as *const c_void, buf.len() as _);
+            if n < 0 {
+                rb_sys_fail(std::ptr::null());
+            }
+            written = n as usize;
+            Ruby::get_with(self).qnil()
+        })?;
+        Ok(written)
+    }
+
+    /// Read up to `buf.len()` bytes from `self` into `buf` without blocking.
+    ///
+    /// Returns `Ok(None)` if no data is currently available, otherwise the
+    /// number of bytes read, with `Ok(Some(0))` at end of file.
+    ///
+    /// Equivalent to `IO#read_nonblock` with `exception: false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, RFile, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let (r, w): (RFile, RFile) = ruby.eval("IO.pipe")?;
+    ///
+    ///     let mut buf = [0; 16];
+    ///     assert_eq!(r.read_nonblock_into(&mut buf)?, None);
+    ///
+    ///     w.write_nonblock_from(b"hello")?;
+    ///     assert_eq!(r.read_nonblock_into(&mut buf)?, Some(5));
+    ///     assert_eq!(&buf[..5], b"hello");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn read_nonblock_into(self, buf: &mut [u8]) -> Result<Option<usize>, Error> {
+        static READ_NONBLOCK: LazyId = LazyId::new("read_nonblock");
+
+        if buf.is_empty() {
+            return Ok(Some(0));
+        }
+        let handle = Ruby::get_with(self);
+        let outbuf = handle.str_buf_new(buf.len());
+        let res = self.funcall::<_, _, Value>(
+            *READ_NONBLOCK,
+            (
+                buf.len(),
+                outbuf,
+                crate::kwargs!(&handle, "exception" => false),
+            ),
+        );
+        let len = match res {
+            // end of file
+            Ok(v) if v.is_nil() => Ok(Some(0)),
+            // :wait_readable
+            Ok(v) if Symbol::from_value(v).is_some() => Ok(None),
+            Ok(_) => {
+                let bytes = unsafe { outbuf.as_slice() };
+                buf[..bytes.len()].copy_from_slice(bytes);
+                Ok(Some(bytes.len()))
+            }
+            Err(e) => Err(e),
+        };
+        unsafe { rb_str_resize(outbuf.as_rb_value(), 0) };
+        len
+    }
+
+    /// Write as much of `buf` as possible to `self` without blocking.
+    ///
+    /// Returns `Ok(None)` if no data could be written without blocking,
+    /// otherwise the number of bytes written, which may be less than
+    /// `buf.len()`.
+    ///
+    /// Equivalent to `IO#write_nonblock` with `exception: false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, RFile, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let (_r, w): (RFile, RFile) = ruby.eval("IO.pipe")?;
+    ///
+    ///     let data = vec![0; 1024 * 1024];
+    ///     let mut total = 0;
+    ///     // write until the pipe's buffer is full
+    ///     while let Some(n) = w.write_nonblock_from(&data)? {
+    ///         total += n;
+    ///     }
+    ///     assert!(total > 0);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn write_nonblock_from(self, buf: &[u8]) -> Result<Option<usize>, Error> {
+        static WRITE_NONBLOCK: LazyId = LazyId::new("write_nonblock");
+
+        let handle = Ruby::get_with(self);
+        let res: Value = self.funcall(
+            *WRITE_NONBLOCK,
+            (
+                handle.str_from_slice(buf),
+                crate::kwargs!(&handle, "exception" => false),
+            ),
+        )?;
+        // :wait_writable
+        if Symbol::from_value(res).is_some() {
+            return Ok(None);
+        }
+        TryConvert::try_convert(res).map(Some)
+    }
+
+    /// Wait until `self` is readable, or `timeout` has elapsed.
+    ///
+    /// Returns `true` if `self` is readable, `false` if the timeout elapsed.
+    /// With a `timeout` of `None` waits indefinitely.
+    ///
+    /// Data already held in Ruby's internal buffer counts as readable. Other
+    /// Ruby threads, or fibers when a fiber scheduler is in use, can run
+    /// while waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use magnus::{Error, RFile, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let (r, w): (RFile, RFile) = ruby.eval("IO.pipe")?;
+    ///
+    ///     assert!(!r.wait_readable(Some(Duration::from_millis(10)))?);
+    ///     w.write_nonblock_from(b"hello")?;
+    ///     assert!(r.wait_readable(Some(Duration::from_millis(10)))?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn wait_readable(self, timeout: Option<Duration>) -> Result<bool, Error> {
+        let handle = Ruby::get_with(self);
+        self.select(Some(handle.ary_from_values(&[self])), None, timeout)
+    }
+
+    /// Wait until `self` is writable, or `timeout` has elapsed.
+    ///
+    /// Returns `true` if `self` is writable, `false` if the timeout elapsed.
+    /// With a `timeout` of `None` waits indefinitely.
+    ///
+    /// Other Ruby threads, or fibers when a fiber scheduler is in use, can run
+    /// while waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use magnus::{Error, RFile, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let (_r, w): (RFile, RFile) = ruby.eval("IO.pipe")?;
+    ///
+    ///     assert!(w.wait_writable(Some(Duration::from_millis(10)))?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn wait_writable(self, timeout: Option<Duration>) -> Result<bool, Error> {
+        let handle = Ruby::get_with(self);
+        self.select(None, Some(handle.ary_from_values(&[self])), timeout)
+    }
+
+    /// Acquire a shared lock on the file, waiting until it is available.
+    ///
+    /// Equivalent to `File#flock(File::LOCK_SH)`. Other Ruby threads can run
+    /// while waiting. The lock is released by [`RFile::unlock`], or when the
+    /// file is closed.
+    ///
+    /// Returns an error if `self` is not an instance of `File`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.tempfile_create("example", |_ruby, file| {
+    ///         file.lock_shared()?;
+    ///         file.unlock()
+    ///     })?;
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn lock_shared(self) -> Result<(), Error> {
+        self.flock(LOCK_SH).map(|_| ())
+    }
+
+    /// Acquire an exclusive lock on the file, waiting until it is available.
+    ///
+    /// Equivalent to `File#flock(File::LOCK_EX)`. Other Ruby threads can run
+    /// while waiting. The lock is released by [`RFile::unlock`], or when the
+    /// file is closed.
+    ///
+    /// Returns an error if `self` is not an instance of `File`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.tempfile_create("example", |_ruby, file| {
+    ///         file.lock_exclusive()?;
+    ///         file.write_from(b"locked")?;
+    ///         file.unlock()
+    ///     })?;
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn lock_exclusive(self) -> Result<(), Error> {
+        self.flock(LOCK_EX).map(|_| ())
+    }
+
+    /// Attempt to acquire a shared lock on the file without waiting.
+    ///
+    /// Returns `true` if the lock was acquired, `false` if another process
+    /// or file holds an exclusive lock.
+    ///
+    /// Equivalent to `File#flock(File::LOCK_SH | File::LOCK_NB)`.
+    ///
+    /// Returns an error if `self` is not an instance of `File`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, Error, RFile, Ruby, Value};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.tempfile_create("example", |ruby, file| {
+    ///         let path: String = file.funcall("path", ())?;
+    ///         let other: RFile = ruby.class_file().funcall("open", (path,))?;
+    ///
+    ///         assert!(file.try_lock_shared()?);
+    ///         assert!(other.try_lock_shared()?);
+    ///
+    ///         let _: Value = other.funcall("close", ())?;
+    ///         Ok(())
+    ///     })?;
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn try_lock_shared(self) -> Result<bool, Error> {
+        self.flock(LOCK_SH | LOCK_NB)
+    }
+
+    /// Attempt to acquire an exclusive lock on the file without waiting.
+    ///
+    /// Returns `true` if the lock was acquired, `false` if another process
+    /// or file holds a lock.
+    ///
+    /// Equivalent to `File#flock(File::LOCK_EX | File::LOCK_NB)`.
+    ///
+    /// Returns an error if `self` is not an instance of `File`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, Error, RFile, Ruby, Value};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.tempfile_create("example", |ruby, file| {
+    ///         let path: String = file.funcall("path", ())?;
+    ///         let other: RFile = ruby.class_file().funcall("open", (path,))?;
+    ///
+    ///         assert!(file.try_lock_exclusive()?);
+    ///         assert!(!other.try_lock_exclusive()?);
+    ///         file.unlock()?;
+    ///         assert!(other.try_lock_exclusive()?);
+    ///
+    ///         let _: Value = other.funcall("close", ())?;
+    ///         Ok(())
+    ///     })?;
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn try_lock_exclusive(self) -> Result<bool, Error> {
+        self.flock(LOCK_EX | LOCK_NB)
+    }
+
+    /// Release a lock held on the file.
+    ///
+    /// Equivalent to `File#flock(File::LOCK_UN)`.
+    ///
+    /// Returns an error if `self` is not an instance of `File`.
+    ///
+    /// See [`RFile::lock_exclusive`] for an example.
+    pub fn unlock(self) -> Result<(), Error> {
+        self.flock(LOCK_UN).map(|_| ())
+    }
+
+    fn flock(self, operation: i32) -> Result<bool, Error> {
+        static FLOCK: LazyId = LazyId::new("flock");
+
+        // returns 0 on success, false if LOCK_NB was given and would block
+        let res: Value = self.funcall(*FLOCK, (operation,))?;
+        Ok(res.to_bool())
+    }
+
+    fn select(
+        self,
+        read: Option<RArray>,
+        write: Option<RArray>,
+        timeout: Option<Duration>,
+    ) -> Result<bool, Error> {
+        static SELECT: LazyId = LazyId::new("select");
+
+        let handle = Ruby::get_with(self);
+        let ready: Option<Value> = handle.class_io().funcall(
+            *SELECT,
+            (read, write, handle.qnil(), timeout.map(|d| d.as_secs_f64())),
+        )?;
+        Ok(ready.is_some())
+    }
+
+    /// Write any data held in Ruby's internal write buffer to the underlying
+    /// file descriptor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, RFile, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let (r, w): (RFile, RFile) = ruby.eval("IO.pipe")?;
+    ///
+    ///     w.write_from(b"hello")?;
+    ///     w.flush()?;
+    ///     rb_assert!(ruby, r#"r.readpartial(5) == "hello""#, r);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn flush(self) -> Result<(), Error> {
+        protect(|| unsafe { Value::new(rb_io_flush(self.as_rb_value())) })?;
+        Ok(())
+    }
+
     #[cfg(ruby_lt_3_3)]
     fn as_internal(self) -> NonNull<rb_sys::RFile> {
         // safe as inner value is NonZero
@@ -97,6 +566,275 @@ impl TryConvert for RFile {
     }
 }
 
+impl io::Read for RFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_into(buf).map_err(into_io_error)
+    }
+}
+
+impl io::Write for RFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_from(buf).map_err(into_io_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        RFile::flush(*self).map_err(into_io_error)
+    }
+}
+
+/// Convert a Ruby exception to an [`io::Error`], keeping the OS error code
+/// for `SystemCallError`s.
+///
+/// The original exception is not kept, as [`Error`] is not `Send`.
+fn into_io_error(e: Error) -> io::Error {
+    static ERRNO: LazyId = LazyId::new("errno");
+
+    let ruby = unsafe { Ruby::get_unchecked() };
+    if e.is_kind_of(ruby.exception_system_call_error()) {
+        if let Some(errno) = e.value().and_then(|v| v.funcall(*ERRNO, ()).ok()) {
+            return io::Error::from_raw_os_error(errno);
+        }
+    }
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+/// A Ruby `IO`, or any object that can be read from or written to like an
+/// `IO`, such as a `StringIO`.
+///
+/// Instances of `IO` (including `File` and sockets) are read and written
+/// directly, as with [`RFile`], other objects are called with `read`, `write`,
+/// and `flush` methods.
+///
+/// The [`io::Read`] and [`io::Write`] implementations must return an
+/// [`io::Error`], which can not hold a Ruby exception. `SystemCallError`s keep
+/// their OS error code, but any other error is reduced to its message, and the
+/// original exception is lost. This includes exceptions not usually rescued,
+/// such as `Interrupt` and `SystemExit`, and `throw`. Use
+/// [`read_into`](Self::read_into), [`write_from`](Self::write_from), and
+/// [`flush`](Self::flush) to keep the original [`Error`].
+///
+/// See the [`ReprValue`] and [`Object`] traits for additional methods
+/// available on this type.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Read, Write};
+///
+/// use magnus::{rb_assert, Error, RIo, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.require("stringio")?;
+///
+///     let mut io: RIo = ruby.eval("StringIO.new")?;
+///     write!(io, "hello {}", "world").unwrap();
+///     rb_assert!(ruby, r#"io.string == "hello world""#, io);
+///
+///     let mut io: RIo = ruby.eval(r#"StringIO.new("from ruby")"#)?;
+///     let mut s = String::new();
+///     io.read_to_string(&mut s).unwrap();
+///     assert_eq!(s, "from ruby");
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct RIo(NonZeroValue);
+
+impl RIo {
+    /// Return `Some(RIo)` if `val` is an `IO`, or responds to `read` or
+    /// `write`, `None` otherwise.
+    ///
+    /// Returns `None` if `val`'s `respond_to?` raises an exception, use
+    /// [`TryConvert`] to get that exception.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, RIo, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.require("stringio")?;
+    ///
+    ///     assert!(RIo::from_value(ruby.eval("STDOUT")?).is_some());
+    ///     assert!(RIo::from_value(ruby.eval("StringIO.new")?).is_some());
+    ///     assert!(RIo::from_value(ruby.eval("Object.new")?).is_none());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn from_value(val: Value) -> Option<Self> {
+        Self::check(val).ok().flatten()
+    }
+
+    fn check(val: Value) -> Result<Option<Self>, Error> {
+        static READ: LazyId = LazyId::new("read");
+        static WRITE: LazyId = LazyId::new("write");
+
+        let is_io = RFile::from_value(val).is_some()
+            || val.respond_to(*READ, false)?
+            || val.respond_to(*WRITE, false)?;
+        Ok(is_io.then(|| unsafe { Self(NonZeroValue::new_unchecked(val)) }))
+    }
+
+    /// Return `Some(RFile)` if `self` is an instance of `IO`, `None` if it
+    /// is some other object that acts like an `IO`.
+    pub fn as_r_file(self) -> Option<RFile> {
+        RFile::from_value(self.as_value())
+    }
+
+    /// Read up to `buf.len()` bytes from `self` into `buf`, returning the
+    /// number of bytes read.
+    ///
+    /// Returns `Ok(0)` at end of file, or if `buf` is empty.
+    ///
+    /// See [`RFile::read_into`] for how `IO`s are read, other objects are
+    /// called with `read(buf.len())`.
+    pub fn read_into(self, buf: &mut [u8]) -> Result<usize, Error> {
+        static READ: LazyId = LazyId::new("read");
+
+        if let Some(file) = self.as_r_file() {
+            return file.read_into(buf);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let res: Option<RString> = self.funcall(*READ, (buf.len(),))?;
+        let bytes = match res {
+            Some(ref s) => unsafe { s.as_slice() },
+            None => return Ok(0),
+        };
+        if bytes.len() > buf.len() {
+            return Err(Error::new(
+                Ruby::get_with(self).exception_io_error(),
+                "read returned more bytes than requested",
+            ));
+        }
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    /// Write all of `buf` to `self`, returning the number of bytes written.
+    ///
+    /// See [`RFile::write_from`] for how `IO`s are written, other objects are
+    /// called with `write(string)`.
+    pub fn write_from(self, buf: &[u8]) -> Result<usize, Error> {
+        static WRITE: LazyId = LazyId::new("write");
+
+        if let Some(file) = self.as_r_file() {
+            return file.write_from(buf);
+        }
+        let s = Ruby::get_with(self).str_from_slice(buf);
+        let _: Value = self.funcall(*WRITE, (s,))?;
+        Ok(buf.len())
+    }
+
+    /// Flush any data buffered by `self`.
+    ///
+    /// Objects that aren't an `IO` and don't respond to `flush` are assumed
+    /// not to buffer data, and this does nothing.
+    pub fn flush(self) -> Result<(), Error> {
+        static FLUSH: LazyId = LazyId::new("flush");
+
+        if let Some(file) = self.as_r_file() {
+            return file.flush();
+        }
+        if self.respond_to(*FLUSH, false)? {
+            let _: Value = self.funcall(*FLUSH, ())?;
+        }
+        Ok(())
+    }
+
+    /// Wait for `self` to be readable.
+    ///
+    /// Returns `true` if `self` is readable, `false` if the timeout elapsed.
+    /// Objects that aren't an `IO` are always considered readable.
+    ///
+    /// See [`RFile::wait_readable`].
+    pub fn wait_readable(self, timeout: Option<Duration>) -> Result<bool, Error> {
+        match self.as_r_file() {
+            Some(file) => file.wait_readable(timeout),
+            None => Ok(true),
+        }
+    }
+
+    /// Wait for `self` to be writable.
+    ///
+    /// Returns `true` if `self` is writable, `false` if the timeout elapsed.
+    /// Objects that aren't an `IO` are always considered writable.
+    ///
+    /// See [`RFile::wait_writable`].
+    pub fn wait_writable(self, timeout: Option<Duration>) -> Result<bool, Error> {
+        match self.as_r_file() {
+            Some(file) => file.wait_writable(timeout),
+            None => Ok(true),
+        }
+    }
+
+    /// Return the file descriptor of `self`, or `None` if `self` isn't an
+    /// `IO`.
+    pub fn fileno(self) -> Option<fd::RawFd> {
+        self.as_r_file().map(|file| fd::AsRawFd::as_raw_fd(&file))
+    }
+}
+
+impl fmt::Display for RIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", unsafe { self.to_s_infallible() })
+    }
+}
+
+impl fmt::Debug for RIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.inspect())
+    }
+}
+
+impl IntoValue for RIo {
+    #[inline]
+    fn into_value_with(self, _: &Ruby) -> Value {
+        self.0.get()
+    }
+}
+
+impl Object for RIo {}
+
+unsafe impl private::ReprValue for RIo {}
+
+impl ReprValue for RIo {}
+
+impl TryConvert for RIo {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        Self::check(val)?.ok_or_else(|| {
+            Error::new(
+                Ruby::get_with(val).exception_type_error(),
+                format!("no implicit conversion of {} into IO", unsafe {
+                    val.classname()
+                },),
+            )
+        })
+    }
+}
+
+impl io::Read for RIo {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_into(buf).map_err(into_io_error)
+    }
+}
+
+impl io::Write for RIo {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_from(buf).map_err(into_io_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        RIo::flush(*self).map_err(into_io_error)
+    }
+}
+
 #[cfg(not(unix))]
 pub mod fd {
     use std::os::raw::c_int;
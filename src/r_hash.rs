@@ -21,6 +21,7 @@ use crate::{
     error::{protect, raise, Error},
     into_value::{IntoValue, IntoValueFromNative},
     object::Object,
+    r_string::dedup_str,
     try_convert::{TryConvert, TryConvertOwned},
     value::{
         private::{self, ReprValue as _},
@@ -150,6 +151,41 @@ impl Ruby {
             .unwrap()
     }
 
+    /// Create a new frozen `RHash` from a Rust iterator of key-value pairs.
+    ///
+    /// Any string keys or values are also frozen and deduplicated (interned
+    /// on Ruby 3.0 and later), making this suitable for returning constant
+    /// lookup data to Ruby.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let hash = ruby.hash_frozen_from_iter([("a", "x"), ("b", "y")]);
+    ///     assert!(hash.is_frozen());
+    ///     rb_assert!(ruby, r#"hash == {"a" => "x", "b" => "y"}"#, hash);
+    ///     rb_assert!(ruby, "hash.values.all?(&:frozen?)", hash);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn hash_frozen_from_iter<I, K, V>(&self, iter: I) -> RHash
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: IntoValue,
+        V: IntoValue,
+    {
+        let hash = self.hash_from_iter(
+            iter.into_iter()
+                .map(|(k, v)| (dedup_str(self.into_value(k)), dedup_str(self.into_value(v)))),
+        );
+        hash.freeze();
+        hash
+    }
+
     /// Create a new `RHash` from a fallible Rust iterator.
     ///
     /// Returns `Ok(RHash)` on sucess or `Err(E)` with the first error
@@ -840,6 +876,70 @@ impl RHash {
         Ok(vec)
     }
 
+    /// Convert the keys of `self` to a Rust vector, in insertion order.
+    ///
+    /// This will only convert to a [`Vec`] of 'owned' Rust native types, see
+    /// [`to_vec`](RHash::to_vec) for details.
+    ///
+    /// Errors if the conversion of any key fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, RHash, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let r_hash: RHash = ruby.eval(r#"{"a" => 1, "b" => 2}"#)?;
+    ///     assert_eq!(r_hash.keys::<String>()?, vec!["a", "b"]);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn keys<K>(self) -> Result<Vec<K>, Error>
+    where
+        K: TryConvertOwned,
+    {
+        let mut vec = Vec::with_capacity(self.len());
+        self.foreach(|key, _: Value| {
+            vec.push(key);
+            Ok(ForEach::Continue)
+        })?;
+        Ok(vec)
+    }
+
+    /// Convert the values of `self` to a Rust vector, in insertion order.
+    ///
+    /// This will only convert to a [`Vec`] of 'owned' Rust native types, see
+    /// [`to_vec`](RHash::to_vec) for details.
+    ///
+    /// Errors if the conversion of any value fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, RHash, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let r_hash: RHash = ruby.eval(r#"{"a" => 1, "b" => 2}"#)?;
+    ///     assert_eq!(r_hash.values::<i64>()?, vec![1, 2]);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn values<V>(self) -> Result<Vec<V>, Error>
+    where
+        V: TryConvertOwned,
+    {
+        let mut vec = Vec::with_capacity(self.len());
+        self.foreach(|_: Value, value| {
+            vec.push(value);
+            Ok(ForEach::Continue)
+        })?;
+        Ok(vec)
+    }
+
     /// Return the number of entries in `self` as a Ruby [`Fixnum`].
     ///
     /// # Examples
@@ -7,7 +7,8 @@ use std::{
     fmt, io,
     iter::Iterator,
     mem::transmute,
-    os::raw::{c_char, c_long},
+    ops::{Deref, DerefMut},
+    os::raw::{c_char, c_int, c_long},
     path::{Path, PathBuf},
     ptr, slice, str,
 };
@@ -15,28 +16,46 @@ use std::{
 #[cfg(ruby_gte_3_0)]
 use rb_sys::rb_str_to_interned_str;
 use rb_sys::{
-    self, rb_enc_str_coderange, rb_enc_str_new, rb_str_buf_append, rb_str_buf_new, rb_str_capacity,
-    rb_str_cat, rb_str_cmp, rb_str_comparable, rb_str_conv_enc, rb_str_drop_bytes, rb_str_dump,
-    rb_str_ellipsize, rb_str_new, rb_str_new_frozen, rb_str_new_shared, rb_str_offset, rb_str_plus,
-    rb_str_replace, rb_str_scrub, rb_str_shared_replace, rb_str_split, rb_str_strlen, rb_str_times,
-    rb_str_to_str, rb_str_update, rb_utf8_str_new, rb_utf8_str_new_static, ruby_coderange_type,
-    ruby_rstring_flags, ruby_value_type, RSTRING_LEN, RSTRING_PTR, VALUE,
+    self, rb_block_call, rb_enc_str_coderange, rb_enc_str_new, rb_str_buf_append, rb_str_buf_new,
+    rb_str_capacity, rb_str_cat, rb_str_cmp, rb_str_comparable, rb_str_conv_enc, rb_str_drop_bytes,
+    rb_str_dump, rb_str_ellipsize, rb_str_locktmp, rb_str_modify, rb_str_modify_expand, rb_str_new,
+    rb_str_new_frozen, rb_str_new_shared, rb_str_offset, rb_str_plus, rb_str_replace,
+    rb_str_resize, rb_str_scrub, rb_str_shared_replace, rb_str_split, rb_str_strlen, rb_str_times,
+    rb_str_to_str, rb_str_unlocktmp, rb_str_update, rb_utf8_str_new, rb_utf8_str_new_static,
+    ruby_coderange_type, ruby_fl_type, ruby_rstring_flags, ruby_value_type, RSTRING_LEN,
+    RSTRING_PTR, VALUE,
 };
 
 use crate::{
+    block::Proc,
     encoding::{Coderange, EncodingCapable, RbEncoding},
     error::{protect, Error},
     into_value::{IntoValue, IntoValueFromNative},
+    method::Block,
     object::Object,
-    r_array::RArray,
+    r_array::{Iter, RArray},
+    r_match::RMatch,
     try_convert::TryConvert,
     value::{
         private::{self, ReprValue as _},
-        NonZeroValue, ReprValue, Value,
+        LazyId, NonZeroValue, ReprValue, Value,
     },
     Ruby,
 };
 
+/// How [`RString::append_encoded`] handles characters that can't be
+/// transcoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Replacement<'a> {
+    /// Return an error.
+    Error,
+    /// Replace them with the target encoding's default replacement
+    /// character, `U+FFFD` for Unicode encodings or `?` otherwise.
+    Default,
+    /// Replace them with the given string.
+    With(&'a str),
+}
+
 /// # `RString`
 ///
 /// Functions that can be used to create Ruby `String`s.
@@ -1398,6 +1417,69 @@ impl RString {
         Ok(())
     }
 
+    /// Mutate `self`, adding `other` to the end, transcoding `other` to
+    /// `self`'s encoding if required.
+    ///
+    /// Characters in `other` that are invalid, or can't be represented in
+    /// `self`'s encoding, are handled according to `replacement`.
+    ///
+    /// If `self`'s encoding is ASCII-8BIT (aka BINARY) the bytes of `other`
+    /// are appended unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{eval, r_string::Replacement, Error, RString, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s: RString = eval!(ruby, r#""caf".encode("ISO-8859-1")"#)?;
+    ///
+    ///     s.append_encoded(ruby.str_new("é"), Replacement::Error)?;
+    ///     unsafe { assert_eq!(s.as_slice(), b"caf\xe9") };
+    ///
+    ///     assert!(s
+    ///         .append_encoded(ruby.str_new(" ☕"), Replacement::Error)
+    ///         .is_err());
+    ///     s.append_encoded(ruby.str_new(" ☕"), Replacement::With("?"))?;
+    ///     unsafe { assert_eq!(s.as_slice(), b"caf\xe9 ?") };
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn append_encoded(self, other: Self, replacement: Replacement) -> Result<(), Error> {
+        static ENCODE: LazyId = LazyId::new("encode");
+
+        let handle = Ruby::get_with(self);
+        let enc = self.enc_get();
+        if enc == handle.ascii8bit_encindex() {
+            unsafe { self.cat(other.as_slice()) };
+            return Ok(());
+        }
+        if enc == other.enc_get() {
+            return self.buf_append(other);
+        }
+        let name = RbEncoding::from(enc).name().to_owned();
+        let encoded: RString = match replacement {
+            Replacement::Error => other.funcall(*ENCODE, (name,))?,
+            Replacement::Default => other.funcall(
+                *ENCODE,
+                (
+                    name,
+                    crate::kwargs!(&handle, "invalid" => handle.to_symbol("replace"), "undef" => handle.to_symbol("replace")),
+                ),
+            )?,
+            Replacement::With(with) => other.funcall(
+                *ENCODE,
+                (
+                    name,
+                    crate::kwargs!(&handle, "invalid" => handle.to_symbol("replace"), "undef" => handle.to_symbol("replace"), "replace" => with),
+                ),
+            )?,
+        };
+        self.buf_append(encoded)
+    }
+
     /// Mutate `self`, adding `buf` to the end.
     ///
     /// Note: This ignore's `self`'s encoding, and may result in `self`
@@ -1429,6 +1511,172 @@ impl RString {
         }
     }
 
+    /// Ensure `self` has capacity for at least `additional` more bytes
+    /// without reallocating.
+    ///
+    /// This also ensures `self` does not share its buffer with any other
+    /// string.
+    ///
+    /// Returns `Err` if `self` is frozen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_new("foo");
+    ///     s.reserve(1024)?;
+    ///     assert!(s.capacity() >= 1027);
+    ///     assert_eq!(s.to_string()?, "foo");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn reserve(self, additional: usize) -> Result<(), Error> {
+        protect(|| {
+            unsafe { rb_str_modify_expand(self.as_rb_value(), additional as c_long) };
+            Ruby::get_with(self).qnil()
+        })?;
+        Ok(())
+    }
+
+    /// Mutate `self`, adding each of `bufs` to the end.
+    ///
+    /// Space for all of `bufs` is reserved up front, so `self` is reallocated
+    /// at most once.
+    ///
+    /// As with [`cat`](RString::cat) this ignore's `self`'s encoding.
+    ///
+    /// Returns `Err` if `self` is frozen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let buf = ruby.str_new("HTTP/1.1 200 OK\r\n");
+    ///     buf.extend_from_slices(&[b"Content-Length: 2\r\n", b"\r\n", b"ok"])?;
+    ///     assert_eq!(
+    ///         buf.to_string()?,
+    ///         "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok"
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn extend_from_slices(self, bufs: &[&[u8]]) -> Result<(), Error> {
+        self.reserve(bufs.iter().map(|b| b.len()).sum())?;
+        for buf in bufs {
+            self.cat(buf);
+        }
+        Ok(())
+    }
+
+    /// Returns whether `self` shares its backing data with another string.
+    ///
+    /// Ruby strings are copy-on-write, a shared string's data will be copied
+    /// the first time it is modified through the Ruby API. Modifying the bytes
+    /// of a shared string directly through a raw pointer would also modify
+    /// the other strings sharing that data. See
+    /// [`make_independent`](RString::make_independent).
+    ///
+    /// Short strings are stored inline within the object, and are never
+    /// shared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, RString, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_new(&"x".repeat(100));
+    ///     assert!(!s.is_shared());
+    ///     let dup = RString::new_shared(s);
+    ///     assert!(dup.is_shared());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn is_shared(self) -> bool {
+        // STR_SHARED is only meaningful for non-embedded strings
+        let mask =
+            ruby_rstring_flags::RSTRING_NOEMBED as VALUE | ruby_fl_type::RUBY_FL_USER2 as VALUE;
+        unsafe { self.r_basic_unchecked().as_ref().flags & mask == mask }
+    }
+
+    /// Ensure `self` does not share its backing data with any other string,
+    /// copying it if required, so that it can be safely modified in place.
+    ///
+    /// Returns `Err` if `self` is frozen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, RString, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_new(&"x".repeat(100));
+    ///     let dup = RString::new_shared(s);
+    ///     assert!(dup.is_shared());
+    ///     dup.make_independent()?;
+    ///     assert!(!dup.is_shared());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn make_independent(self) -> Result<(), Error> {
+        protect(|| {
+            unsafe { rb_str_modify(self.as_rb_value()) };
+            Ruby::get_with(self).qnil()
+        })?;
+        Ok(())
+    }
+
+    /// Lock `self`, returning a guard that allows mutable access to its
+    /// bytes.
+    ///
+    /// While the guard is held `self` can not be modified by Ruby code, any
+    /// attempt to do so will raise a `RuntimeError`. This allows a string's
+    /// buffer to be filled in-place, e.g. by reading from IO, without copying.
+    ///
+    /// The length of `self` can't be changed while locked, so should be set
+    /// before locking, e.g. by creating the string with
+    /// [`Ruby::str_from_slice`] and a zeroed buffer. The string's coderange is
+    /// cleared when the guard is dropped.
+    ///
+    /// Returns `Err` if `self` is frozen or already locked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_from_slice(&[0; 5]);
+    ///
+    ///     let mut guard = s.lock()?;
+    ///     guard.copy_from_slice(b"hello");
+    ///     assert!(s.lock().is_err());
+    ///     drop(guard);
+    ///
+    ///     rb_assert!(ruby, r#"s == "hello""#, s);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn lock(self) -> Result<RStringLock, Error> {
+        self.make_independent()?;
+        protect(|| unsafe { Value::new(rb_str_locktmp(self.as_rb_value())) })?;
+        Ok(RStringLock(self))
+    }
+
     /// Replace the contents and encoding of `self` with those of `other`.
     ///
     /// # Examples
@@ -1488,6 +1736,71 @@ impl RString {
         Ok(())
     }
 
+    /// Shorten `self` to `len` bytes.
+    ///
+    /// If `len` is greater than or equal to the string's current length this
+    /// has no effect.
+    ///
+    /// `len` is in bytes, not characters, and truncating a string with a
+    /// multi-byte encoding part way through a character will leave the string
+    /// with an invalid byte sequence.
+    ///
+    /// Returns an error if `self` is frozen, even if `len` would leave it
+    /// unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_new("hello world");
+    ///     s.truncate(5)?;
+    ///     assert_eq!(s.to_string()?, "hello");
+    ///     s.truncate(10)?;
+    ///     assert_eq!(s.to_string()?, "hello");
+    ///
+    ///     s.freeze();
+    ///     assert!(s.truncate(10).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn truncate(self, len: usize) -> Result<(), Error> {
+        self.check_frozen()?;
+        if len >= self.len() {
+            return Ok(());
+        }
+        protect(|| unsafe { Value::new(rb_str_resize(self.as_rb_value(), len as c_long)) })?;
+        Ok(())
+    }
+
+    /// Remove the contents of `self`, leaving it empty.
+    ///
+    /// The string's encoding is unchanged.
+    ///
+    /// Returns an error if `self` is frozen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_new("hello world");
+    ///     s.clear()?;
+    ///     assert!(s.is_empty());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn clear(self) -> Result<(), Error> {
+        protect(|| unsafe { Value::new(rb_str_resize(self.as_rb_value(), 0)) })?;
+        Ok(())
+    }
+
     /// Replace a portion of `self` with `other`.
     ///
     /// `beg` is the offset of the portion of `self` to replace. Negative
@@ -1728,6 +2041,90 @@ impl RString {
         unsafe { rb_str_comparable(self.as_rb_value(), other.as_rb_value()) != 0 }
     }
 
+    /// Returns whether the bytes of `self` are equal to `bytes`.
+    ///
+    /// This compares the raw bytes, ignoring `self`'s encoding, without
+    /// allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_from_slice(&[0, 159, 146, 150]);
+    ///     assert!(s.eq_bytes(&[0, 159, 146, 150]));
+    ///     assert!(!s.eq_bytes(&[0, 159, 146]));
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn eq_bytes(self, bytes: &[u8]) -> bool {
+        unsafe { self.as_slice() == bytes }
+    }
+
+    /// Compares the bytes of `self` with `bytes` to establish an ordering.
+    ///
+    /// This compares the raw bytes lexicographically, ignoring `self`'s
+    /// encoding, without allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_new("b");
+    ///     assert_eq!(s.cmp_bytes(b"a"), Ordering::Greater);
+    ///     assert_eq!(s.cmp_bytes(b"b"), Ordering::Equal);
+    ///     assert_eq!(s.cmp_bytes(b"ba"), Ordering::Less);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn cmp_bytes(self, bytes: &[u8]) -> Ordering {
+        unsafe { self.as_slice() }.cmp(bytes)
+    }
+
+    /// Returns whether the bytes of `self` are equal to `bytes`, taking the
+    /// same time regardless of where the first differing byte is.
+    ///
+    /// This should be used when comparing secrets, such as tokens or
+    /// signatures, to avoid leaking information about the secret through
+    /// timing. The time taken does depend on the length of `bytes`, and
+    /// whether the lengths are equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let token = ruby.str_new("s3cr3t");
+    ///     assert!(token.eq_bytes_constant_time(b"s3cr3t"));
+    ///     assert!(!token.eq_bytes_constant_time(b"s3cr3x"));
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn eq_bytes_constant_time(self, bytes: &[u8]) -> bool {
+        let slice = unsafe { self.as_slice() };
+        if slice.len() != bytes.len() {
+            return false;
+        }
+        let diff = slice
+            .iter()
+            .zip(bytes)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        // stop the compiler short-circuiting the fold above
+        unsafe { std::ptr::read_volatile(&diff) == 0 }
+    }
+
     /// Shorten `self` to `len`, adding "...".
     ///
     /// If `self` is shorter than `len` the returned value will be `self`.
@@ -1786,6 +2183,195 @@ impl RString {
         let delim = CString::new(delim).unwrap();
         unsafe { RArray::from_rb_value_unchecked(rb_str_split(self.as_rb_value(), delim.as_ptr())) }
     }
+
+    /// Split `self` around each match of `pattern`, returning an iterator of
+    /// the fields.
+    ///
+    /// This is equivalent to Ruby's `String#split(pattern, limit)`. `pattern`
+    /// may be a `String`, a [`RRegexp`](crate::RRegexp), or `nil` to split
+    /// around whitespace. If `pattern` is a regexp with capture groups, the
+    /// captures are included in the result.
+    ///
+    /// If `limit` is positive at most `limit` fields are returned, with the
+    /// last field containing the unsplit remainder of the string. If `limit`
+    /// is zero trailing empty fields are removed. If `limit` is negative
+    /// there is no limit and trailing empty fields are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{r_regexp::Opts, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_new("a1b22c,,");
+    ///     let re = ruby.reg_new(r"\d+", Opts::new())?;
+    ///
+    ///     let fields = s
+    ///         .split_pattern(re, 0)?
+    ///         .map(|f| f.to_string())
+    ///         .collect::<Result<Vec<_>, _>>()?;
+    ///     assert_eq!(fields, ["a", "b", "c,,"]);
+    ///
+    ///     let fields = s
+    ///         .split_pattern(",", 0)?
+    ///         .map(|f| f.to_string())
+    ///         .collect::<Result<Vec<_>, _>>()?;
+    ///     assert_eq!(fields, ["a1b22c"]);
+    ///
+    ///     let fields = s
+    ///         .split_pattern(",", -1)?
+    ///         .map(|f| f.to_string())
+    ///         .collect::<Result<Vec<_>, _>>()?;
+    ///     assert_eq!(fields, ["a1b22c", "", ""]);
+    ///
+    ///     let fields = s
+    ///         .split_pattern(re, 2)?
+    ///         .map(|f| f.to_string())
+    ///         .collect::<Result<Vec<_>, _>>()?;
+    ///     assert_eq!(fields, ["a", "b22c,,"]);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn split_pattern<P>(self, pattern: P, limit: isize) -> Result<Iter<RString>, Error>
+    where
+        P: IntoValue,
+    {
+        static SPLIT: LazyId = LazyId::new("split");
+
+        let fields: RArray = self.funcall(*SPLIT, (pattern, limit))?;
+        Ok(Iter::new(fields))
+    }
+
+    /// Find each match of `pattern` in `self`, returning an iterator of the
+    /// matched strings.
+    ///
+    /// This is similar to Ruby's `String#scan(pattern)`. `pattern` may be a
+    /// `String` or a [`RRegexp`](crate::RRegexp). Unlike `String#scan` the
+    /// whole match is always returned, even when `pattern` has capture
+    /// groups.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{r_regexp::Opts, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let s = ruby.str_new("a1b22c333");
+    ///     let re = ruby.reg_new(r"(\d)\d*", Opts::new())?;
+    ///
+    ///     let matches = s
+    ///         .scan(re)?
+    ///         .map(|m| m.to_string())
+    ///         .collect::<Result<Vec<_>, _>>()?;
+    ///     assert_eq!(matches, ["1", "22", "333"]);
+    ///
+    ///     assert_eq!(s.scan("2")?.count(), 2);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn scan<P>(self, pattern: P) -> Result<Iter<RString>, Error>
+    where
+        P: IntoValue,
+    {
+        static GSUB: LazyId = LazyId::new("gsub");
+        static TO_A: LazyId = LazyId::new("to_a");
+
+        // gsub's enumerator yields the whole match even when the pattern has
+        // captures, where scan would yield an array of the captures
+        let matches: Value = self.funcall(*GSUB, (pattern,))?;
+        let matches: RArray = matches.funcall(*TO_A, ())?;
+        Ok(Iter::new(matches))
+    }
+
+    /// Return a copy of `self` with each match of `pattern` replaced by the
+    /// result of calling `func` with the match.
+    ///
+    /// This is equivalent to Ruby's `String#gsub(pattern) { |m| ... }`, with
+    /// `func` receiving the match's [`RMatch`] (Ruby's `$~`) rather than the
+    /// matched string. `pattern` may be a `String` or a
+    /// [`RRegexp`](crate::RRegexp). The value returned by `func` is converted
+    /// to a string with `to_s`.
+    ///
+    /// If `func` returns an error it is raised from `gsub`, and returned from
+    /// this function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use magnus::{r_regexp::Opts, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let vars = HashMap::from([("name", "world"), ("greeting", "hello")]);
+    ///     let re = ruby.reg_new(r"\{\{(\w+)\}\}", Opts::new())?;
+    ///
+    ///     let s = ruby.str_new("{{greeting}}, {{name}}!");
+    ///     let res = s.gsub_with(re, |m| {
+    ///         let name = m.nth_match(1).unwrap().to_string()?;
+    ///         Ok(vars.get(name.as_str()).copied().unwrap_or(""))
+    ///     })?;
+    ///     assert_eq!(res.to_string()?, "hello, world!");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn gsub_with<P, F, R>(self, pattern: P, mut func: F) -> Result<RString, Error>
+    where
+        P: IntoValue,
+        F: FnMut(RMatch) -> Result<R, Error>,
+        R: IntoValue,
+    {
+        static GSUB: LazyId = LazyId::new("gsub");
+
+        unsafe extern "C" fn call<F, R>(
+            _yielded_arg: VALUE,
+            callback_arg: VALUE,
+            argc: c_int,
+            argv: *const VALUE,
+            blockarg: VALUE,
+        ) -> VALUE
+        where
+            F: FnMut(RMatch) -> Result<R, Error>,
+            R: IntoValue,
+        {
+            let func = &mut *(callback_arg as *mut F);
+            let block = |ruby: &Ruby, _: &[Value], _: Option<Proc>| -> Result<Value, Error> {
+                // gsub sets $~ before yielding
+                let m = ruby.backref_get().ok_or_else(|| {
+                    Error::new(
+                        ruby.exception_runtime_error(),
+                        "no match data in gsub block",
+                    )
+                })?;
+                func(m).map(|v| v.into_value_with(ruby))
+            };
+            block
+                .call_handle_error(argc, argv as *const Value, Value::new(blockarg))
+                .as_rb_value()
+        }
+
+        let handle = Ruby::get_with(self);
+        let pattern = handle.into_value(pattern);
+        let call_func =
+            call::<F, R> as unsafe extern "C" fn(VALUE, VALUE, c_int, *const VALUE, VALUE) -> VALUE;
+
+        protect(|| unsafe {
+            RString::from_rb_value_unchecked(rb_block_call(
+                self.as_rb_value(),
+                GSUB.as_rb_id(),
+                1,
+                &pattern.as_rb_value() as *const VALUE,
+                Some(call_func),
+                &mut func as *mut F as VALUE,
+            ))
+        })
+    }
 }
 
 impl fmt::Display for RString {
@@ -1963,6 +2549,20 @@ impl IntoValue for PathBuf {
 
 unsafe impl IntoValueFromNative for PathBuf {}
 
+#[cfg(feature = "uuid")]
+impl IntoValue for uuid::Uuid {
+    #[inline]
+    fn into_value_with(self, handle: &Ruby) -> Value {
+        let mut buf = uuid::Uuid::encode_buffer();
+        handle
+            .str_new(self.hyphenated().encode_lower(&mut buf))
+            .into_value_with(handle)
+    }
+}
+
+#[cfg(feature = "uuid")]
+unsafe impl IntoValueFromNative for uuid::Uuid {}
+
 impl Object for RString {}
 
 unsafe impl private::ReprValue for RString {}
@@ -2133,6 +2733,167 @@ unsafe impl private::ReprValue for FString {}
 
 impl ReprValue for FString {}
 
+/// Returns a frozen and, where supported, deduplicated (interned) copy of
+/// `val` if it is a String, otherwise returns `val` unchanged.
+pub(crate) fn dedup_str(val: Value) -> Value {
+    match RString::from_value(val) {
+        #[cfg(ruby_gte_3_0)]
+        Some(s) => s.to_interned_str().as_value(),
+        #[cfg(not(ruby_gte_3_0))]
+        Some(s) => unsafe { Value::new(rb_str_new_frozen(s.as_rb_value())) },
+        None => val,
+    }
+}
+
+/// A guard holding a Ruby string locked, with mutable access to its bytes.
+///
+/// Created by [`RString::lock`]. The string is unlocked when the guard is
+/// dropped.
+pub struct RStringLock(RString);
+
+impl RStringLock {
+    /// Return the locked string.
+    pub fn as_r_string(&self) -> RString {
+        self.0
+    }
+}
+
+impl Deref for RStringLock {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        // the string can't be modified by Ruby while locked
+        unsafe { self.0.as_slice_unconstrained() }
+    }
+}
+
+impl DerefMut for RStringLock {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // the string is independent, and can't be modified by Ruby while
+        // locked
+        unsafe {
+            slice::from_raw_parts_mut(
+                RSTRING_PTR(self.0.as_rb_value()) as *mut u8,
+                RSTRING_LEN(self.0.as_rb_value()) as _,
+            )
+        }
+    }
+}
+
+impl Drop for RStringLock {
+    fn drop(&mut self) {
+        unsafe { rb_str_unlocktmp(self.0.as_rb_value()) };
+        self.0.enc_coderange_clear();
+    }
+}
+
+impl fmt::Debug for RStringLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RStringLock").field(&self.0).finish()
+    }
+}
+
+/// A cursor reading the bytes of a Ruby string, implementing [`io::Read`] and
+/// [`io::Seek`].
+///
+/// Bytes are read from the string's current contents, if the string is
+/// modified between reads the next read continues from the same byte offset
+/// in the modified string.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Read, Seek, SeekFrom};
+///
+/// use magnus::{r_string::RStringReader, Error, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let mut reader = RStringReader::new(ruby.str_new("hello world"));
+///
+///     let mut buf = [0; 5];
+///     reader.read_exact(&mut buf).unwrap();
+///     assert_eq!(&buf, b"hello");
+///
+///     reader.seek(SeekFrom::End(-5)).unwrap();
+///     let mut rest = String::new();
+///     reader.read_to_string(&mut rest).unwrap();
+///     assert_eq!(rest, "world");
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[derive(Clone, Debug)]
+pub struct RStringReader {
+    string: RString,
+    pos: u64,
+}
+
+impl RStringReader {
+    /// Create a new reader, starting at the beginning of `string`.
+    pub fn new(string: RString) -> Self {
+        Self { string, pos: 0 }
+    }
+
+    /// Return the string being read.
+    pub fn get_ref(&self) -> RString {
+        self.string
+    }
+
+    /// Return the current position of the reader, in bytes.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Set the current position of the reader, in bytes.
+    ///
+    /// The position may be set past the end of the string, in which case
+    /// reads will return no bytes.
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+impl io::Read for RStringReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // no Ruby code can run while the slice is borrowed
+        let slice = unsafe { self.string.as_slice() };
+        let start = usize::try_from(self.pos).map_or(slice.len(), |pos| pos.min(slice.len()));
+        let len = buf.len().min(slice.len() - start);
+        buf[..len].copy_from_slice(&slice[start..start + len]);
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl io::Seek for RStringReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            io::SeekFrom::Start(n) => {
+                self.pos = n;
+                return Ok(n);
+            }
+            io::SeekFrom::End(n) => (self.string.len() as u64, n),
+            io::SeekFrom::Current(n) => (self.pos, n),
+        };
+        let new_pos = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        };
+        match new_pos {
+            Some(n) => {
+                self.pos = n;
+                Ok(n)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
 /// An iterator over a Ruby string's codepoints.
 pub struct Codepoints<'a> {
     slice: &'a [u8],
@@ -121,7 +121,7 @@ impl RStruct {
     ///
     /// Ruby may modify or free the memory backing the returned slice, the
     /// caller must ensure this does not happen.
-    unsafe fn as_slice(&self) -> &[Value] {
+    pub(crate) unsafe fn as_slice(&self) -> &[Value] {
         self.as_slice_unconstrained()
     }
 
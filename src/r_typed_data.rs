@@ -143,6 +143,8 @@ impl Ruby {
             class,
             T::class(self)
         );
+        #[cfg(feature = "alloc-trace")]
+        crate::alloc_trace::record_wrap::<T>(class);
         let boxed = Box::new(data);
         unsafe {
             let value_ptr = rb_data_typed_object_wrap(
@@ -397,6 +399,10 @@ impl RTypedData {
                 .as_ref();
             handle.qnil()
         });
+        #[cfg(feature = "alloc-trace")]
+        if res.is_some() {
+            crate::alloc_trace::record_unwrap::<T>();
+        }
         res.ok_or_else(|| {
             Error::new(
                 handle.exception_type_error(),
@@ -0,0 +1,541 @@
+//! Types for working with Ruby's Ractors.
+//!
+//! Ractors are Ruby's mechanism for parallel execution, available from Ruby
+//! 3.0. Each Ractor has its own GVL, so Rust code called from different
+//! Ractors may run at the same time.
+
+use std::{cell::UnsafeCell, fmt, sync::Once};
+#[cfg(ruby_gte_3_0)]
+use std::{ffi::c_void, ptr::null_mut};
+
+#[cfg(ruby_gte_3_0)]
+use rb_sys::{
+    rb_ext_ractor_safe, rb_ractor_local_key_t, rb_ractor_local_storage_ptr,
+    rb_ractor_local_storage_ptr_newkey, rb_ractor_local_storage_ptr_set,
+    rb_ractor_local_storage_type, rb_ractor_make_shareable, rb_ractor_shareable_p_continue,
+};
+
+use crate::Ruby;
+#[cfg(any(ruby_gte_3_0, docsrs))]
+use crate::{
+    class::RClass,
+    error::{protect, Error},
+    into_value::IntoValue,
+    module::Module,
+    object::Object,
+    try_convert::TryConvert,
+    value::{private, private::ReprValue as _, Lazy, LazyId, NonZeroValue, ReprValue, Value},
+};
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+static RACTOR: Lazy<RClass> = Lazy::new(|ruby| ruby.class_object().const_get("Ractor").unwrap());
+#[cfg(any(ruby_gte_3_0, docsrs))]
+static CURRENT: LazyId = LazyId::new("current");
+#[cfg(any(ruby_gte_3_0, docsrs))]
+static NAME: LazyId = LazyId::new("name");
+#[cfg(any(ruby_gte_3_0, docsrs))]
+static RECEIVE: LazyId = LazyId::new("receive");
+#[cfg(any(ruby_gte_3_0, docsrs))]
+static SEND: LazyId = LazyId::new("send");
+#[cfg(any(all(ruby_gte_3_0, ruby_lt_3_5), docsrs))]
+static TAKE: LazyId = LazyId::new("take");
+#[cfg(any(all(ruby_gte_3_0, ruby_lt_3_5), docsrs))]
+static YIELD: LazyId = LazyId::new("yield");
+
+/// # Ractor
+///
+/// Functions for working with Ractors.
+///
+/// See also the [`ractor`](crate::ractor) module.
+impl Ruby {
+    /// Declare whether the extension being loaded is safe to use from
+    /// Ractors other than the main Ractor.
+    ///
+    /// This should be called from your [`init`](macro@crate::init) function
+    /// before defining any methods. Methods defined by an extension that has
+    /// not been declared Ractor safe can only be called from the main Ractor.
+    ///
+    /// Does nothing on Ruby versions before 3.0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{function, Error, Ruby};
+    ///
+    /// fn double(i: i64) -> i64 {
+    ///     i * 2
+    /// }
+    ///
+    /// #[magnus::init]
+    /// fn init(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.ext_ractor_safe(true);
+    ///     ruby.define_global_function("double", function!(double, 1));
+    ///     Ok(())
+    /// }
+    /// # let ruby = unsafe { magnus::embed::init() };
+    /// # init(&ruby).unwrap();
+    /// ```
+    #[allow(unused_variables)]
+    pub fn ext_ractor_safe(&self, flag: bool) {
+        #[cfg(ruby_gte_3_0)]
+        unsafe {
+            rb_ext_ractor_safe(flag)
+        };
+    }
+
+    /// Return Ruby's `Ractor` class.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     rb_assert!(ruby, "klass == Ractor", klass = ruby.class_ractor());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg(any(ruby_gte_3_0, docsrs))]
+    #[cfg_attr(docsrs, doc(cfg(ruby_gte_3_0)))]
+    pub fn class_ractor(&self) -> RClass {
+        self.get_inner(&RACTOR)
+    }
+
+    /// Return the current Ractor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     rb_assert!(ruby, "ractor == Ractor.main", ractor = ruby.ractor_current()?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg(any(ruby_gte_3_0, docsrs))]
+    #[cfg_attr(docsrs, doc(cfg(ruby_gte_3_0)))]
+    pub fn ractor_current(&self) -> Result<Ractor, Error> {
+        self.class_ractor().funcall(*CURRENT, ())
+    }
+
+    /// Make `val` shareable between Ractors, by deeply freezing it.
+    ///
+    /// Returns `val`. Returns an error if `val`, or an object it references,
+    /// can not be made shareable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, Error, RArray, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary: RArray = ruby.eval(r#"["a", ["b"]]"#)?;
+    ///     assert!(!ruby.ractor_is_shareable(ary));
+    ///
+    ///     let ary = ruby.ractor_make_shareable(ary)?;
+    ///     assert!(ruby.ractor_is_shareable(ary));
+    ///     assert!(ary.is_frozen());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg(any(ruby_gte_3_0, docsrs))]
+    #[cfg_attr(docsrs, doc(cfg(ruby_gte_3_0)))]
+    pub fn ractor_make_shareable<T>(&self, val: T) -> Result<T, Error>
+    where
+        T: ReprValue,
+    {
+        protect(|| unsafe { Value::new(rb_ractor_make_shareable(val.as_rb_value())) })?;
+        Ok(val)
+    }
+
+    /// Check if `val` is shareable between Ractors.
+    ///
+    /// Equivalent to `Ractor.shareable?(val)`.
+    ///
+    /// See [`Ruby::ractor_make_shareable`] for an example.
+    #[cfg(any(ruby_gte_3_0, docsrs))]
+    #[cfg_attr(docsrs, doc(cfg(ruby_gte_3_0)))]
+    pub fn ractor_is_shareable<T>(&self, val: T) -> bool
+    where
+        T: ReprValue,
+    {
+        // special constants are always shareable, and aren't handled by
+        // rb_ractor_shareable_p_continue
+        val.as_value().is_immediate()
+            || unsafe { rb_ractor_shareable_p_continue(val.as_rb_value()) }
+    }
+
+    /// Yield `msg` from the current Ractor, to be taken by another Ractor
+    /// with [`Ractor::take`].
+    ///
+    /// Blocks until the message is taken. See [`Transfer`] for how `msg` is
+    /// passed between Ractors.
+    ///
+    /// Equivalent to `Ractor.yield(msg)`. Not available with Ruby 3.5 and
+    /// later, where `Ractor.yield` has been removed.
+    #[cfg(any(all(ruby_gte_3_0, ruby_lt_3_5), docsrs))]
+    #[cfg_attr(docsrs, doc(cfg(all(ruby_gte_3_0, ruby_lt_3_5))))]
+    pub fn ractor_yield<T>(&self, msg: T, transfer: Transfer) -> Result<(), Error>
+    where
+        T: IntoValue,
+    {
+        let msg = transfer.check(self, msg.into_value_with(self))?;
+        let _: Value = self.class_ractor().funcall(
+            *YIELD,
+            (msg, crate::kwargs!(self, "move" => transfer.is_move())),
+        )?;
+        Ok(())
+    }
+
+    /// Receive a message sent to the current Ractor with [`Ractor::send`].
+    ///
+    /// Blocks until a message is available, other Ruby threads can run while
+    /// waiting.
+    ///
+    /// Equivalent to `Ractor.receive`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{ractor::Transfer, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ractor = ruby.ractor_current()?;
+    ///     ractor.send(ruby.str_new("hello"), Transfer::Copy)?;
+    ///     let msg: String = ruby.ractor_receive()?;
+    ///     assert_eq!(msg, "hello");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg(any(ruby_gte_3_0, docsrs))]
+    #[cfg_attr(docsrs, doc(cfg(ruby_gte_3_0)))]
+    pub fn ractor_receive<T>(&self) -> Result<T, Error>
+    where
+        T: TryConvert,
+    {
+        self.class_ractor().funcall(*RECEIVE, ())
+    }
+}
+
+/// A Value known to be an instance of Ruby's `Ractor` class.
+///
+/// See the [`ReprValue`] and [`Object`] traits for additional methods
+/// available on this type. See [`Ruby`](Ruby#ractor) for related functions.
+#[cfg(any(ruby_gte_3_0, docsrs))]
+#[cfg_attr(docsrs, doc(cfg(ruby_gte_3_0)))]
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct Ractor(NonZeroValue);
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+impl Ractor {
+    /// Return `Some(Ractor)` if `val` is a `Ractor`, `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{ractor::Ractor, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert!(Ractor::from_value(ruby.eval("Ractor.current")?).is_some());
+    ///     assert!(Ractor::from_value(ruby.eval("Thread.current")?).is_none());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[inline]
+    pub fn from_value(val: Value) -> Option<Self> {
+        unsafe {
+            val.is_kind_of(Ruby::get_with(val).class_ractor())
+                .then(|| Self(NonZeroValue::new_unchecked(val)))
+        }
+    }
+
+    /// Return the name of the Ractor, if it has one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert_eq!(ruby.ractor_current()?.name()?, None);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn name(self) -> Result<Option<String>, Error> {
+        self.funcall(*NAME, ())
+    }
+
+    /// Send `msg` to `self`, to be received with [`Ruby::ractor_receive`].
+    ///
+    /// Does not block, messages are queued by the receiving Ractor. See
+    /// [`Transfer`] for how `msg` is passed between Ractors.
+    ///
+    /// Equivalent to `Ractor#send(msg)`.
+    ///
+    /// See [`Ruby::ractor_receive`] for an example.
+    pub fn send<T>(self, msg: T, transfer: Transfer) -> Result<(), Error>
+    where
+        T: IntoValue,
+    {
+        let handle = Ruby::get_with(self);
+        let msg = transfer.check(&handle, msg.into_value_with(&handle))?;
+        let _: Value = self.funcall(
+            *SEND,
+            (msg, crate::kwargs!(&handle, "move" => transfer.is_move())),
+        )?;
+        Ok(())
+    }
+
+    /// Take a message yielded by `self`, or `self`'s final value once it has
+    /// finished.
+    ///
+    /// Blocks until a message is available, other Ruby threads can run while
+    /// waiting.
+    ///
+    /// Equivalent to `Ractor#take`. Not available with Ruby 3.5 and later,
+    /// where `Ractor#take` has been removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{
+    ///     ractor::{Ractor, Transfer},
+    ///     Error, Ruby,
+    /// };
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let doubler: Ractor = ruby.eval("Ractor.new { Ractor.receive * 2 }")?;
+    ///     doubler.send(21, Transfer::Share)?;
+    ///     assert_eq!(doubler.take::<i64>()?, 42);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg(any(ruby_lt_3_5, docsrs))]
+    #[cfg_attr(docsrs, doc(cfg(ruby_lt_3_5)))]
+    pub fn take<T>(self) -> Result<T, Error>
+    where
+        T: TryConvert,
+    {
+        self.funcall(*TAKE, ())
+    }
+}
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+impl fmt::Display for Ractor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", unsafe { self.to_s_infallible() })
+    }
+}
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+impl fmt::Debug for Ractor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.inspect())
+    }
+}
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+impl IntoValue for Ractor {
+    #[inline]
+    fn into_value_with(self, _: &Ruby) -> Value {
+        self.0.get()
+    }
+}
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+impl Object for Ractor {}
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+unsafe impl private::ReprValue for Ractor {}
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+impl ReprValue for Ractor {}
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+impl TryConvert for Ractor {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        Self::from_value(val).ok_or_else(|| {
+            Error::new(
+                Ruby::get_with(val).exception_type_error(),
+                format!("no implicit conversion of {} into Ractor", unsafe {
+                    val.classname()
+                },),
+            )
+        })
+    }
+}
+
+/// Storage for a value that is separate for each Ractor.
+///
+/// Extensions that keep global state, such as caches, can use a
+/// `RactorLocal` to give each Ractor its own copy of that state. The value
+/// for each Ractor is created on first access from that Ractor by calling the
+/// function passed to [`RactorLocal::new`], and is dropped when the Ractor
+/// terminates.
+///
+/// With Ruby versions before 3.0, which don't have Ractors, a single value is
+/// shared by the whole process.
+///
+/// Ruby objects held in the value are not marked by Ruby's garbage collector.
+/// Use [`BoxValue`](crate::value::BoxValue) to keep them alive.
+///
+/// # Examples
+///
+/// ```
+/// use std::{collections::HashMap, sync::Mutex};
+///
+/// use magnus::{ractor::RactorLocal, Error, Ruby};
+///
+/// static CACHE: RactorLocal<Mutex<HashMap<String, usize>>> =
+///     RactorLocal::new(|_ruby| Mutex::new(HashMap::new()));
+///
+/// fn cached_len(ruby: &Ruby, s: String) -> usize {
+///     let mut cache = CACHE.get(ruby).lock().unwrap();
+///     let len = s.len();
+///     *cache.entry(s).or_insert(len)
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     assert_eq!(cached_len(ruby, String::from("hello")), 5);
+///     assert_eq!(CACHE.get(ruby).lock().unwrap().len(), 1);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub struct RactorLocal<T> {
+    init: Once,
+    func: fn(&Ruby) -> T,
+    #[cfg(ruby_gte_3_0)]
+    storage_type: rb_ractor_local_storage_type,
+    #[cfg(ruby_gte_3_0)]
+    key: UnsafeCell<rb_ractor_local_key_t>,
+    #[cfg(ruby_lt_3_0)]
+    value: UnsafeCell<Option<T>>,
+}
+
+impl<T> RactorLocal<T> {
+    /// Create a new `RactorLocal<T>`.
+    ///
+    /// This function can be called in a `const` context, and assumes the
+    /// `RactorLocal<T>` will be assigned to a `static`. `func` is called to
+    /// create the value for a Ractor the first time that Ractor accesses the
+    /// `RactorLocal<T>`.
+    pub const fn new(func: fn(&Ruby) -> T) -> Self {
+        Self {
+            init: Once::new(),
+            func,
+            #[cfg(ruby_gte_3_0)]
+            storage_type: rb_ractor_local_storage_type {
+                mark: None,
+                free: Some(free::<T>),
+            },
+            #[cfg(ruby_gte_3_0)]
+            key: UnsafeCell::new(null_mut()),
+            #[cfg(ruby_lt_3_0)]
+            value: UnsafeCell::new(None),
+        }
+    }
+
+    /// Get the value for the current Ractor, creating it if this is the
+    /// first access from the current Ractor.
+    ///
+    /// See [`RactorLocal`] for an example.
+    pub fn get<'a>(&'a self, ruby: &'a Ruby) -> &'a T {
+        #[cfg(ruby_gte_3_0)]
+        unsafe {
+            self.init.call_once(|| {
+                *self.key.get() = rb_ractor_local_storage_ptr_newkey(&self.storage_type);
+            });
+            let key = *self.key.get();
+            let ptr = rb_ractor_local_storage_ptr(key) as *const T;
+            if !ptr.is_null() {
+                return &*ptr;
+            }
+            let ptr = Box::into_raw(Box::new((self.func)(ruby)));
+            rb_ractor_local_storage_ptr_set(key, ptr as *mut c_void);
+            &*ptr
+        }
+        #[cfg(ruby_lt_3_0)]
+        unsafe {
+            self.init.call_once(|| {
+                *self.value.get() = Some((self.func)(ruby));
+            });
+            (*self.value.get()).as_ref().unwrap()
+        }
+    }
+}
+
+#[cfg(ruby_gte_3_0)]
+unsafe extern "C" fn free<T>(ptr: *mut c_void) {
+    if !ptr.is_null() {
+        drop(Box::from_raw(ptr as *mut T));
+    }
+}
+
+unsafe impl<T> Send for RactorLocal<T> where T: Send {}
+unsafe impl<T> Sync for RactorLocal<T> where T: Send + Sync {}
+
+impl<T> fmt::Debug for RactorLocal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RactorLocal").finish_non_exhaustive()
+    }
+}
+
+/// How a message is passed between Ractors.
+///
+/// Shareable objects, such as frozen Strings and Integers, are always passed
+/// by reference.
+#[cfg(any(ruby_gte_3_0, docsrs))]
+#[cfg_attr(docsrs, doc(cfg(ruby_gte_3_0)))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Transfer {
+    /// Unshareable objects are deep copied.
+    Copy,
+    /// Unshareable objects are moved, and can no longer be used by the
+    /// sending Ractor.
+    Move,
+    /// Only shareable objects are allowed, returning an `ArgumentError` for
+    /// unshareable objects rather than copying or moving them.
+    Share,
+}
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+impl Transfer {
+    fn check(self, handle: &Ruby, msg: Value) -> Result<Value, Error> {
+        if self == Self::Share && !handle.ractor_is_shareable(msg) {
+            return Err(Error::new(
+                handle.exception_arg_error(),
+                format!("can not share unshareable {}", unsafe { msg.classname() }),
+            ));
+        }
+        Ok(msg)
+    }
+
+    fn is_move(self) -> bool {
+        self == Self::Move
+    }
+}
+
+#[cfg(any(ruby_gte_3_0, docsrs))]
+impl Default for Transfer {
+    fn default() -> Self {
+        Self::Copy
+    }
+}
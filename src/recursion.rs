@@ -0,0 +1,178 @@
+use std::{cell::RefCell, os::raw::c_int};
+
+use rb_sys::{rb_exec_recursive, ruby_stack_check, VALUE};
+
+use crate::{
+    error::{protect, Error},
+    method::{BlockReturn, Synchronize},
+    try_convert::TryConvert,
+    value::{ReprValue, Value},
+    Ruby,
+};
+
+/// # Recursion
+///
+/// Functions for guarding against deep or infinite recursion.
+impl Ruby {
+    /// Check if the machine stack is close to overflowing.
+    ///
+    /// Returns a `SystemStackError` if there is not enough stack left to
+    /// safely continue. This can be called at the start of a recursive
+    /// function to report runaway recursion the same way Ruby does, rather
+    /// than crashing the process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn depth(ruby: &Ruby, n: usize) -> Result<usize, Error> {
+    ///     ruby.stack_check()?;
+    ///     if n == 0 {
+    ///         Ok(0)
+    ///     } else {
+    ///         Ok(depth(ruby, n - 1)? + 1)
+    ///     }
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert_eq!(depth(ruby, 100)?, 100);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn stack_check(&self) -> Result<(), Error> {
+        if unsafe { ruby_stack_check() } != 0 {
+            return Err(Error::new(
+                self.exception_sys_stack_error(),
+                "stack level too deep",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Call `func`, passing `true` if `obj` is already being processed by an
+    /// enclosing call to `with_recursion_guard` from the same Ruby method on
+    /// the current thread, `false` otherwise.
+    ///
+    /// This can be used to handle cyclic data structures, in the way Ruby's
+    /// `Array#inspect` returns `[...]` for an array that contains itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, Error, RArray, Ruby, Value};
+    ///
+    /// fn describe(ruby: &Ruby, val: Value) -> Result<String, Error> {
+    ///     let ary = match RArray::from_value(val) {
+    ///         Some(ary) => ary,
+    ///         None => return Ok(val.inspect()),
+    ///     };
+    ///     ruby.with_recursion_guard(ary, |recursive| -> Result<String, Error> {
+    ///         if recursive {
+    ///             return Ok(String::from("[...]"));
+    ///         }
+    ///         let parts = ary
+    ///             .into_iter()
+    ///             .map(|v| describe(ruby, v))
+    ///             .collect::<Result<Vec<_>, _>>()?;
+    ///         Ok(format!("<{}>", parts.join(" ")))
+    ///     })
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary: RArray = ruby.eval("a = [1, 2]; a << a; a")?;
+    ///     assert_eq!(describe(ruby, ary.as_value())?, "<1 2 [...]>");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn with_recursion_guard<O, F, R, T>(&self, obj: O, func: F) -> Result<T, Error>
+    where
+        O: ReprValue,
+        F: FnOnce(bool) -> R,
+        R: BlockReturn,
+        T: TryConvert,
+    {
+        unsafe extern "C" fn call<F, R>(_obj: VALUE, arg: VALUE, recursive: c_int) -> VALUE
+        where
+            F: FnOnce(bool) -> R,
+            R: BlockReturn,
+        {
+            let func = (*(arg as *mut Option<F>)).take().unwrap();
+            (move || func(recursive != 0))
+                .call_handle_error()
+                .as_rb_value()
+        }
+
+        protect(|| unsafe {
+            let mut some_func = Some(func);
+            let closure = &mut some_func as *mut Option<F> as VALUE;
+            Value::new(rb_exec_recursive(
+                Some(call::<F, R>),
+                obj.as_rb_value(),
+                closure,
+            ))
+        })
+        .and_then(TryConvert::try_convert)
+    }
+}
+
+/// Tracks the Arrays and Hashes currently being converted by a recursive
+/// conversion, to detect cycles and limit how deeply nested data can be.
+pub(crate) struct Nesting {
+    max_depth: Option<usize>,
+    ancestors: RefCell<Vec<VALUE>>,
+}
+
+impl Nesting {
+    pub(crate) fn new(max_depth: Option<usize>) -> Self {
+        Self {
+            max_depth,
+            ancestors: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Check if `val` is currently being converted by an enclosing call.
+    pub(crate) fn is_entered(&self, val: Value) -> bool {
+        self.ancestors.borrow().contains(&val.as_rb_value())
+    }
+
+    /// Record that `val` is being converted, until the returned guard is
+    /// dropped.
+    ///
+    /// Returns an `ArgumentError` if `val` is already being converted, or if
+    /// this would exceed the maximum depth, and a `SystemStackError` if the
+    /// machine stack is close to overflowing.
+    pub(crate) fn enter(&self, val: Value) -> Result<NestingGuard<'_>, Error> {
+        let ruby = Ruby::get_with(val);
+        ruby.stack_check()?;
+        let mut ancestors = self.ancestors.borrow_mut();
+        if ancestors.contains(&val.as_rb_value()) {
+            return Err(Error::new(
+                ruby.exception_arg_error(),
+                format!("can't convert recursive {}", unsafe { val.classname() }),
+            ));
+        }
+        if let Some(max_depth) = self.max_depth {
+            if ancestors.len() >= max_depth {
+                return Err(Error::new(
+                    ruby.exception_arg_error(),
+                    format!("nesting of {} is too deep", ancestors.len() + 1),
+                ));
+            }
+        }
+        ancestors.push(val.as_rb_value());
+        Ok(NestingGuard(self))
+    }
+}
+
+pub(crate) struct NestingGuard<'a>(&'a Nesting);
+
+impl Drop for NestingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.ancestors.borrow_mut().pop();
+    }
+}
@@ -44,9 +44,10 @@ use crate::{
     block::Proc,
     error::{protect, Error},
     r_array::RArray,
-    r_hash::RHash,
+    r_hash::{ForEach, RHash},
+    symbol::Symbol,
     try_convert::{TryConvert, TryConvertOwned},
-    value::{private::ReprValue as _, Id, IntoId, ReprValue, Value},
+    value::{private::ReprValue as _, Id, IntoId, ReprValue, Value, QUNDEF},
     Ruby,
 };
 
@@ -337,6 +338,17 @@ mod private {
         }
     }
 
+    impl<T> ScanArgsKw for T
+    where
+        T: Keywords,
+    {
+        const REQ: bool = true;
+
+        fn from_opt(val: Option<Value>) -> Result<Self, Error> {
+            T::from_kwargs(<RHash as ScanArgsKw>::from_opt(val)?)
+        }
+    }
+
     pub trait ScanArgsBlock: Sized {
         const REQ: bool;
 
@@ -416,7 +428,8 @@ impl<T> ScanArgsSplat for T where T: private::ScanArgsSplat {}
 /// Trait implemented for types that can be retrieved as keyword arguments by
 /// [`scan_args`].
 ///
-/// This trait is implemented for [`RHash`].
+/// This trait is implemented for [`RHash`], and types implementing
+/// [`Keywords`].
 ///
 /// `()` also implements this trait as a placeholder indicating no keyword
 /// arguments are required.
@@ -774,6 +787,12 @@ where
         .map(|id| id.into_id_with(&handle))
         .chain(optional.iter().copied().map(|id| id.into_id_with(&handle)))
         .collect::<Vec<Id>>();
+
+    if let Some(map) = IdMap::from_hash(kw)? {
+        return get_kwargs_from_map(&handle, kw, map, &ids);
+    }
+
+    // fall back to Ruby's implementation for hashes with non-Symbol keys
     let optional_len = if Splat::REQ {
         -(optional.len() as i8 + 1)
     } else {
@@ -804,6 +823,175 @@ where
     })
 }
 
+fn get_kwargs_from_map<Req, Opt, Splat>(
+    handle: &Ruby,
+    kw: RHash,
+    mut map: IdMap,
+    ids: &[Id],
+) -> Result<KwArgs<Req, Opt, Splat>, Error>
+where
+    Req: ScanArgsRequired,
+    Opt: ScanArgsOpt,
+    Splat: ScanArgsKw,
+{
+    let mut out = [unsafe { QUNDEF.as_value() }; 18];
+
+    let mut missing = Vec::new();
+    for (i, id) in ids[..Req::LEN].iter().enumerate() {
+        match map.remove(*id) {
+            Some(val) => out[i] = val,
+            None => missing.push(*id),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(keyword_error(handle, "missing", &missing));
+    }
+    for (i, id) in ids[Req::LEN..].iter().enumerate() {
+        if let Some(val) = map.remove(*id) {
+            out[Req::LEN + i] = val;
+        }
+    }
+
+    if Splat::REQ {
+        // match rb_get_kwargs, which removes the extracted keywords from the
+        // hash passed as the splat
+        if map.len() != kw.len() {
+            for id in ids {
+                let _: Value = kw.delete(*id)?;
+            }
+        }
+    } else if !map.is_empty() {
+        let unknown = map.iter().map(|(id, _)| id).collect::<Vec<_>>();
+        return Err(keyword_error(handle, "unknown", &unknown));
+    }
+
+    let opt_end = Req::LEN + Opt::LEN;
+    Ok(KwArgs {
+        required: Req::from_slice(&out[..Req::LEN])?,
+        optional: Opt::from_slice(&out[Req::LEN..opt_end])?,
+        splat: Splat::from_opt(Splat::REQ.then(|| handle.into_value(kw)))?,
+    })
+}
+
+fn keyword_error(handle: &Ruby, kind: &str, ids: &[Id]) -> Error {
+    let names = ids
+        .iter()
+        .map(|id| handle.into_value(*id).inspect())
+        .collect::<Vec<_>>();
+    Error::new(
+        handle.exception_arg_error(),
+        format!(
+            "{} keyword{}: {}",
+            kind,
+            if names.len() == 1 { "" } else { "s" },
+            names.join(", ")
+        ),
+    )
+}
+
+/// A small map of [`Id`]s to [`Value`]s, used to parse keyword arguments.
+///
+/// Entries are stored in a `Vec` and found by a linear scan comparing
+/// interned [`Id`]s, which for the handful of entries typical of keyword
+/// arguments avoids the overhead of hashing and calling into Ruby.
+///
+/// The entries are on the heap, hidden from Ruby's garbage collector, so an
+/// `IdMap` must not outlive the hash it was created from, which keeps the
+/// values alive.
+#[derive(Debug)]
+pub(crate) struct IdMap {
+    entries: Vec<(Id, Value)>,
+}
+
+impl IdMap {
+    /// Create an `IdMap` from `hash`, returning `None` if any of `hash`'s
+    /// keys are not Symbols.
+    fn from_hash(hash: RHash) -> Result<Option<Self>, Error> {
+        let mut map = Self {
+            entries: Vec::with_capacity(hash.len()),
+        };
+        let mut all_symbols = true;
+        hash.foreach(|key: Value, val: Value| match Symbol::from_value(key) {
+            Some(sym) => {
+                map.entries.push((sym.into(), val));
+                Ok(ForEach::Continue)
+            }
+            None => {
+                all_symbols = false;
+                Ok(ForEach::Stop)
+            }
+        })?;
+        Ok(all_symbols.then(|| map))
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn remove(&mut self, id: Id) -> Option<Value> {
+        let i = self.entries.iter().position(|(k, _)| *k == id)?;
+        Some(self.entries.remove(i).1)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (Id, Value)> + '_ {
+        self.entries.iter().copied()
+    }
+}
+
+/// Trait for types that can be extracted from a method's keyword arguments.
+///
+/// Types implementing this trait can be used as the keywords argument for
+/// [`scan_args`], with the [`method`](crate::method!) and
+/// [`function`](crate::function!) macros as `method!(name, arity, kwargs)`,
+/// or with the `#[magnus(kwargs)]` argument attribute of
+/// [`wrap_method`](crate::wrap_method) and
+/// [`wrap_function`](crate::wrap_function).
+///
+/// This trait can be derived for structs with named fields, see
+/// [`Keywords`](macro@crate::Keywords).
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{function, rb_assert, Error, Ruby};
+///
+/// #[derive(magnus::Keywords)]
+/// struct Opts {
+///     width: usize,
+///     fill: Option<char>,
+/// }
+///
+/// fn pad(s: String, opts: Opts) -> String {
+///     let fill = opts.fill.unwrap_or(' ');
+///     let padding = opts.width.saturating_sub(s.chars().count());
+///     std::iter::repeat(fill).take(padding).chain(s.chars()).collect()
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.define_global_function("pad", function!(pad, 1, kwargs));
+///
+///     rb_assert!(ruby, r#"pad("a", width: 3) == "  a""#);
+///     rb_assert!(ruby, r#"pad("a", width: 3, fill: "0") == "00a""#);
+///     rb_assert!(
+///         ruby,
+///         r#"(pad("a") rescue $!.message) == "missing keyword: :width""#
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub trait Keywords: Sized {
+    /// Extract `Self` from the hash of keyword arguments `kw`.
+    ///
+    /// `kw` will be empty if no keywords were passed.
+    fn from_kwargs(kw: RHash) -> Result<Self, Error>;
+}
+
 /// # Argument Parsing
 ///
 /// Functions for handling argument parsing.
@@ -846,27 +1034,66 @@ impl Ruby {
         T: RangeBounds<usize>,
     {
         if !bounds.contains(&len) {
-            let min = match bounds.start_bound() {
-                Bound::Included(v) => *v as c_int,
-                Bound::Excluded(_) => unreachable!(),
-                Bound::Unbounded => 0,
-            };
-            let max = match bounds.end_bound() {
-                Bound::Included(v) => *v as c_int,
-                Bound::Excluded(v) if *v == 0 => 0,
-                Bound::Excluded(v) => (v - 1) as c_int,
-                Bound::Unbounded => -1,
-            };
-            protect(|| {
-                unsafe { rb_error_arity(len as c_int, min, max) };
-                // we never get here, but this is needed to satisfy the type
-                // system
-                #[allow(unreachable_code)]
-                self.qnil()
-            })?;
+            return Err(self.arity_error(len, bounds));
         }
         Ok(())
     }
+
+    /// Returns a Ruby `ArgumentError` reporting that `given` arguments were
+    /// passed where `bounds` were expected.
+    ///
+    /// The message matches the one Ruby uses for its own methods, e.g.
+    /// `"wrong number of arguments (given 3, expected 1..2)"`. This is useful
+    /// when the argument count has already been checked by other means, see
+    /// [`Ruby::check_arity`] to check and raise in one step.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert_eq!(
+    ///         ruby.arity_error(3, 1..=2).to_string(),
+    ///         "wrong number of arguments (given 3, expected 1..2)"
+    ///     );
+    ///     assert_eq!(
+    ///         ruby.arity_error(0, 1..).to_string(),
+    ///         "wrong number of arguments (given 0, expected 1+)"
+    ///     );
+    ///     assert_eq!(
+    ///         ruby.arity_error(2, 1..2).to_string(),
+    ///         "wrong number of arguments (given 2, expected 1)"
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn arity_error<T>(&self, given: usize, bounds: T) -> Error
+    where
+        T: RangeBounds<usize>,
+    {
+        let min = match bounds.start_bound() {
+            Bound::Included(v) => *v as c_int,
+            Bound::Excluded(v) => (v + 1) as c_int,
+            Bound::Unbounded => 0,
+        };
+        let max = match bounds.end_bound() {
+            Bound::Included(v) => *v as c_int,
+            Bound::Excluded(v) if *v == 0 => 0,
+            Bound::Excluded(v) => (v - 1) as c_int,
+            Bound::Unbounded => -1,
+        };
+        protect(|| {
+            unsafe { rb_error_arity(given as c_int, min, max) };
+            // we never get here, but this is needed to satisfy the type
+            // system
+            #[allow(unreachable_code)]
+            self.qnil()
+        })
+        .unwrap_err()
+    }
 }
 
 /// Returns `Err` containing a Ruby `ArgumentError` if `len` is not within
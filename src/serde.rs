@@ -0,0 +1,925 @@
+//! Conversions between Ruby objects and Rust types implementing
+//! [`serde`](::serde)'s `Serialize` and `Deserialize` traits.
+//!
+//! Rust values are serialized to Ruby as follows:
+//!
+//! | Rust                                 | Ruby                                |
+//! |--------------------------------------|-------------------------------------|
+//! | `bool`                               | `true`/`false`                      |
+//! | integers                             | `Integer`                           |
+//! | `f32`/`f64`                          | `Float`                             |
+//! | `char`, `String`, `&str`             | `String`                            |
+//! | bytes                                | `String` (with binary encoding)     |
+//! | `None`, `()`, unit structs           | `nil`                               |
+//! | sequences, tuples, tuple structs     | `Array`                             |
+//! | maps                                 | `Hash`                              |
+//! | structs                              | `Hash` with `Symbol` keys           |
+//! | unit enum variants                   | `Symbol`                            |
+//! | other enum variants                  | `Hash` of `{variant: value}`        |
+//!
+//! Deserializing accepts the same Ruby types, with Symbols and Strings
+//! interchangeable.
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{rb_assert, Error, Ruby, Value};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! enum Shape {
+//!     Circle { radius: f64 },
+//!     Point,
+//! }
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Drawing {
+//!     name: String,
+//!     shapes: Vec<Shape>,
+//!     tags: Option<Vec<String>>,
+//! }
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     let drawing = Drawing {
+//!         name: String::from("example"),
+//!         shapes: vec![Shape::Circle { radius: 1.5 }, Shape::Point],
+//!         tags: None,
+//!     };
+//!
+//!     let val: Value = magnus::serde::serialize(ruby, &drawing)?;
+//!     rb_assert!(
+//!         ruby,
+//!         r#"val == {name: "example", shapes: [{Circle: {radius: 1.5}}, :Point], tags: nil}"#,
+//!         val
+//!     );
+//!
+//!     let round_trip: Drawing = magnus::serde::deserialize(ruby, val)?;
+//!     assert_eq!(round_trip, drawing);
+//!
+//!     let val: Value = ruby.eval(r#"{"name" => "x", "shapes" => ["Point"]}"#)?;
+//!     let from_ruby: Drawing = magnus::serde::deserialize(ruby, val)?;
+//!     assert_eq!(from_ruby.shapes, [Shape::Point]);
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use std::fmt;
+
+use ::serde::{
+    de::{self, DeserializeOwned, IntoDeserializer, Visitor},
+    forward_to_deserialize_any,
+    ser::{self, Serialize},
+};
+use rb_sys::ruby_value_type;
+
+use crate::{
+    error::Error,
+    integer::Integer,
+    into_value::IntoValue,
+    r_array::RArray,
+    r_hash::RHash,
+    r_string::RString,
+    recursion::Nesting,
+    symbol::Symbol,
+    try_convert::TryConvert,
+    value::{private::ReprValue as _, ReprValue, Value},
+    Ruby,
+};
+
+/// Serialize `value` to a Ruby object.
+///
+/// See the [module level documentation](self) for details of how Rust types
+/// map to Ruby.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use magnus::{rb_assert, Error, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let mut map = HashMap::new();
+///     map.insert("a", vec![1, 2]);
+///     let val = magnus::serde::serialize(ruby, &map)?;
+///     rb_assert!(ruby, r#"val == {"a" => [1, 2]}"#, val);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn serialize<T>(ruby: &Ruby, value: &T) -> Result<Value, Error>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(Serializer { ruby }).map_err(|e| e.0)
+}
+
+/// Deserialize a Rust value of type `T` from the Ruby object `val`.
+///
+/// See the [module level documentation](self) for details of how Ruby types
+/// map to Rust.
+///
+/// Returns an error if `val` is, or contains, an Array or Hash that contains
+/// itself. See also [`deserialize_with_max_depth`].
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{Error, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let val = ruby.eval("[[1, 2.5], [3, 4.0]]")?;
+///     let pairs: Vec<(u8, f64)> = magnus::serde::deserialize(ruby, val)?;
+///     assert_eq!(pairs, [(1, 2.5), (3, 4.0)]);
+///
+///     let res = magnus::serde::deserialize::<Vec<u8>>(ruby, ruby.eval("[1, :a]")?);
+///     assert!(res.is_err());
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn deserialize<T>(ruby: &Ruby, val: Value) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    deserialize_nested(ruby, val, None)
+}
+
+/// Deserialize a Rust value of type `T` from the Ruby object `val`, returning
+/// an error if `val` has Arrays or Hashes nested more than `max_depth` deep.
+///
+/// This can be used to limit the resources used deserializing untrusted data.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{Error, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let val = ruby.eval("[[[1]]]")?;
+///     let res: Vec<Vec<Vec<u8>>> = magnus::serde::deserialize_with_max_depth(ruby, val, 3)?;
+///     assert_eq!(res, [[[1]]]);
+///
+///     let res = magnus::serde::deserialize_with_max_depth::<Vec<Vec<Vec<u8>>>>(ruby, val, 2);
+///     assert!(res.is_err());
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn deserialize_with_max_depth<T>(ruby: &Ruby, val: Value, max_depth: usize) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    deserialize_nested(ruby, val, Some(max_depth))
+}
+
+fn deserialize_nested<T>(ruby: &Ruby, val: Value, max_depth: Option<usize>) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    // the handle isn't otherwise needed, but guarantees we're on a Ruby thread
+    let _ = ruby;
+    let nesting = Nesting::new(max_depth);
+    T::deserialize(Deserializer {
+        val,
+        nesting: &nesting,
+    })
+    .map_err(|e| e.0)
+}
+
+/// Wrapper for [`Error`] implementing serde's error traits.
+#[derive(Debug)]
+struct SerdeError(Error);
+
+impl SerdeError {
+    fn new<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        // serialization only happens from `serialize`/`deserialize`, which
+        // require a Ruby handle, so we must be on a Ruby thread
+        let ruby = unsafe { Ruby::get_unchecked() };
+        Self(Error::new(ruby.exception_type_error(), msg.to_string()))
+    }
+}
+
+impl From<Error> for SerdeError {
+    fn from(val: Error) -> Self {
+        Self(val)
+    }
+}
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+impl ser::Error for SerdeError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Self::new(msg)
+    }
+}
+
+impl de::Error for SerdeError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Self::new(msg)
+    }
+}
+
+struct Serializer<'a> {
+    ruby: &'a Ruby,
+}
+
+impl<'a> Serializer<'a> {
+    fn variant_hash(&self, variant: &'static str, val: Value) -> Result<Value, SerdeError> {
+        let hash = self.ruby.hash_new();
+        hash.aset(self.ruby.to_symbol(variant), val)?;
+        Ok(hash.as_value())
+    }
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = SeqSerializer<'a>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = MapSerializer<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, SerdeError> {
+        Ok(v.into_value_with(self.ruby))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, SerdeError> {
+        Ok(self.ruby.str_new(v.encode_utf8(&mut [0; 4])).as_value())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, SerdeError> {
+        Ok(self.ruby.str_new(v).as_value())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, SerdeError> {
+        Ok(self.ruby.str_from_slice(v).as_value())
+    }
+
+    fn serialize_none(self) -> Result<Value, SerdeError> {
+        Ok(self.ruby.qnil().as_value())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Value, SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, SerdeError> {
+        Ok(self.ruby.qnil().as_value())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, SerdeError> {
+        Ok(self.ruby.qnil().as_value())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, SerdeError> {
+        Ok(self.ruby.to_symbol(variant).as_value())
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let val = value.serialize(Serializer { ruby: self.ruby })?;
+        self.variant_hash(variant, val)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer<'a>, SerdeError> {
+        Ok(SeqSerializer {
+            ruby: self.ruby,
+            ary: self.ruby.ary_new_capa(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'a>, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'a>, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'a>, SerdeError> {
+        Ok(SeqSerializer {
+            ruby: self.ruby,
+            ary: self.ruby.ary_new_capa(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>, SerdeError> {
+        Ok(MapSerializer {
+            ruby: self.ruby,
+            hash: self.ruby.hash_new(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer<'a>, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer<'a>, SerdeError> {
+        Ok(MapSerializer {
+            ruby: self.ruby,
+            hash: self.ruby.hash_new(),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+struct SeqSerializer<'a> {
+    ruby: &'a Ruby,
+    ary: RArray,
+    variant: Option<&'static str>,
+}
+
+impl<'a> SeqSerializer<'a> {
+    fn push<T>(&mut self, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let val = value.serialize(Serializer { ruby: self.ruby })?;
+        self.ary.push(val)?;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, SerdeError> {
+        match self.variant {
+            Some(variant) => {
+                Serializer { ruby: self.ruby }.variant_hash(variant, self.ary.as_value())
+            }
+            None => Ok(self.ary.as_value()),
+        }
+    }
+}
+
+impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for SeqSerializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for SeqSerializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+struct MapSerializer<'a> {
+    ruby: &'a Ruby,
+    hash: RHash,
+    key: Option<Value>,
+    variant: Option<&'static str>,
+}
+
+impl<'a> MapSerializer<'a> {
+    fn field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let val = value.serialize(Serializer { ruby: self.ruby })?;
+        self.hash.aset(self.ruby.to_symbol(key), val)?;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, SerdeError> {
+        match self.variant {
+            Some(variant) => {
+                Serializer { ruby: self.ruby }.variant_hash(variant, self.hash.as_value())
+            }
+            None => Ok(self.hash.as_value()),
+        }
+    }
+}
+
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.key = Some(key.serialize(Serializer { ruby: self.ruby })?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| SerdeError::new("serialize_value called before serialize_key"))?;
+        let val = value.serialize(Serializer { ruby: self.ruby })?;
+        self.hash.aset(key, val)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for MapSerializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for MapSerializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Deserializer<'a> {
+    val: Value,
+    nesting: &'a Nesting,
+}
+
+impl<'a> Deserializer<'a> {
+    fn child(self, val: Value) -> Self {
+        Self {
+            val,
+            nesting: self.nesting,
+        }
+    }
+
+    fn unsupported(self) -> SerdeError {
+        SerdeError::new(format!("can't deserialize {}", unsafe {
+            self.val.classname()
+        }))
+    }
+
+    /// The name of a Symbol or String, for identifiers and enum variants.
+    fn name(self) -> Result<Option<String>, SerdeError> {
+        if let Some(sym) = Symbol::from_value(self.val) {
+            return Ok(Some(sym.name()?.into_owned()));
+        }
+        if let Some(s) = RString::from_value(self.val) {
+            return Ok(Some(s.to_string()?));
+        }
+        Ok(None)
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, SerdeError>
+    where
+        V: Visitor<'de>,
+    {
+        let val = self.val;
+        match val.rb_type() {
+            ruby_value_type::RUBY_T_NIL => visitor.visit_unit(),
+            ruby_value_type::RUBY_T_TRUE => visitor.visit_bool(true),
+            ruby_value_type::RUBY_T_FALSE => visitor.visit_bool(false),
+            ruby_value_type::RUBY_T_FIXNUM | ruby_value_type::RUBY_T_BIGNUM => {
+                let i = Integer::from_value(val).unwrap();
+                match i.to_i64() {
+                    Ok(v) => visitor.visit_i64(v),
+                    Err(_) => visitor.visit_u64(i.to_u64()?),
+                }
+            }
+            ruby_value_type::RUBY_T_FLOAT => visitor.visit_f64(f64::try_convert(val)?),
+            ruby_value_type::RUBY_T_STRING => {
+                let s = RString::from_value(val).unwrap();
+                match s.to_string() {
+                    Ok(v) => visitor.visit_string(v),
+                    Err(_) => visitor.visit_byte_buf(unsafe { s.as_slice() }.to_vec()),
+                }
+            }
+            ruby_value_type::RUBY_T_SYMBOL => {
+                visitor.visit_string(Symbol::from_value(val).unwrap().name()?.into_owned())
+            }
+            ruby_value_type::RUBY_T_ARRAY => {
+                let _guard = self.nesting.enter(val)?;
+                visitor.visit_seq(SeqDeserializer {
+                    ary: RArray::from_value(val).unwrap(),
+                    idx: 0,
+                    nesting: self.nesting,
+                })
+            }
+            ruby_value_type::RUBY_T_HASH => {
+                let _guard = self.nesting.enter(val)?;
+                let pairs = RHash::from_value(val).unwrap().to_vec::<Value, Value>()?;
+                visitor.visit_map(MapDeserializer {
+                    iter: pairs.into_iter(),
+                    value: None,
+                    nesting: self.nesting,
+                })
+            }
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, SerdeError>
+    where
+        V: Visitor<'de>,
+    {
+        if self.val.is_nil() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, SerdeError>
+    where
+        V: Visitor<'de>,
+    {
+        match RString::from_value(self.val) {
+            Some(s) => visitor.visit_byte_buf(unsafe { s.as_slice() }.to_vec()),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, SerdeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError>
+    where
+        V: Visitor<'de>,
+    {
+        if let Some(variant) = self.name()? {
+            return visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            });
+        }
+        if let Some(hash) = RHash::from_value(self.val) {
+            let _guard = self.nesting.enter(self.val)?;
+            let mut pairs = hash.to_vec::<Value, Value>()?;
+            if pairs.len() == 1 {
+                let (key, value) = pairs.pop().unwrap();
+                if let Some(variant) = self.child(key).name()? {
+                    return visitor.visit_enum(EnumDeserializer {
+                        variant,
+                        value: Some(self.child(value)),
+                    });
+                }
+            }
+        }
+        Err(SerdeError::new(format!(
+            "expected a Symbol, String, or single entry Hash for enum, got {}",
+            unsafe { self.val.classname() }
+        )))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+struct SeqDeserializer<'a> {
+    ary: RArray,
+    idx: usize,
+    nesting: &'a Nesting,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for SeqDeserializer<'a> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, SerdeError>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.idx >= self.ary.len() {
+            return Ok(None);
+        }
+        let val = self.ary.entry::<Value>(self.idx as isize)?;
+        self.idx += 1;
+        seed.deserialize(Deserializer {
+            val,
+            nesting: self.nesting,
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.ary.len().saturating_sub(self.idx))
+    }
+}
+
+struct MapDeserializer<'a> {
+    iter: std::vec::IntoIter<(Value, Value)>,
+    value: Option<Value>,
+    nesting: &'a Nesting,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapDeserializer<'a> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, SerdeError>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Deserializer {
+                    val: key,
+                    nesting: self.nesting,
+                })
+                .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, SerdeError>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let val = self
+            .value
+            .take()
+            .ok_or_else(|| SerdeError::new("next_value_seed called before next_key_seed"))?;
+        seed.deserialize(Deserializer {
+            val,
+            nesting: self.nesting,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EnumDeserializer<'a> {
+    variant: String,
+    value: Option<Deserializer<'a>>,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumDeserializer<'a> {
+    type Error = SerdeError;
+    type Variant = VariantDeserializer<'a>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantDeserializer<'a>), SerdeError>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, VariantDeserializer { value: self.value }))
+    }
+}
+
+struct VariantDeserializer<'a> {
+    value: Option<Deserializer<'a>>,
+}
+
+impl<'a> VariantDeserializer<'a> {
+    fn value(self) -> Result<Deserializer<'a>, SerdeError> {
+        self.value
+            .ok_or_else(|| SerdeError::new("expected a Hash for non-unit enum variant"))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for VariantDeserializer<'a> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        match self.value {
+            Some(de) if !de.val.is_nil() => {
+                Err(SerdeError::new("unexpected value for unit enum variant"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, SerdeError>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.value()?)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, SerdeError>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self.value()?, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self.value()?, visitor)
+    }
+}
@@ -0,0 +1,282 @@
+#[cfg(unix)]
+use std::{
+    io,
+    mem::ManuallyDrop,
+    net::{TcpStream, UdpSocket},
+    os::unix::{
+        io::{AsRawFd, FromRawFd, IntoRawFd, RawFd},
+        net::UnixStream,
+    },
+};
+
+use crate::{class::RClass, error::Error, module::Module, Ruby};
+#[cfg(unix)]
+use crate::{
+    into_value::{IntoValue, IntoValueFromNative},
+    r_file::RFile,
+    try_convert::{TryConvert, TryConvertOwned},
+    value::{LazyId, ReprValue, Value},
+};
+
+/// # Sockets
+///
+/// Functions for working with Ruby's socket classes.
+///
+/// On Unix platforms Rust's [`TcpStream`](std::net::TcpStream),
+/// [`UdpSocket`](std::net::UdpSocket), and
+/// [`UnixStream`](std::os::unix::net::UnixStream) convert to and from
+/// `TCPSocket`, `UDPSocket`, and `UNIXSocket` objects.
+///
+/// Converting a Rust socket to Ruby transfers ownership of the socket's file
+/// descriptor to the Ruby object, which will close it when the Ruby object is
+/// closed or garbage collected.
+///
+/// Converting a Ruby socket to Rust duplicates the file descriptor (as with
+/// `dup(2)`). Both the Ruby and Rust sockets refer to the same underlying
+/// socket, and the socket is only closed once both have been closed. As the
+/// underlying socket is shared:
+///
+/// * any data already read in to the Ruby socket's internal buffer will not be
+///   seen by Rust.
+/// * the socket will be in nonblocking mode if Ruby has set it so (the default
+///   from Ruby 3.0). Calling `set_nonblocking(false)` on the Rust socket will
+///   also affect the Ruby socket.
+impl Ruby {
+    /// Return Ruby's `TCPSocket` class, requiring `"socket"` if it hasn't
+    /// already been loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::net::{TcpListener, TcpStream};
+    ///
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    ///     let port = listener.local_addr().unwrap().port();
+    ///
+    ///     // Rust socket to Ruby
+    ///     let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    ///     rb_assert!(ruby, "stream.is_a?(TCPSocket)", stream);
+    ///
+    ///     // Ruby socket to Rust
+    ///     let stream: TcpStream =
+    ///         ruby.eval(&format!(r#"TCPSocket.new("127.0.0.1", {})"#, port))?;
+    ///     assert_eq!(stream.peer_addr().unwrap().port(), port);
+    ///
+    ///     rb_assert!(ruby, "klass == TCPSocket", klass = ruby.class_tcp_socket()?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// # }
+    /// ```
+    pub fn class_tcp_socket(&self) -> Result<RClass, Error> {
+        self.socket_class("TCPSocket")
+    }
+
+    /// Return Ruby's `UDPSocket` class, requiring `"socket"` if it hasn't
+    /// already been loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::net::UdpSocket;
+    ///
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    ///     rb_assert!(ruby, "socket.is_a?(UDPSocket)", socket);
+    ///
+    ///     let socket: UdpSocket =
+    ///         ruby.eval(r#"UDPSocket.new.tap { |s| s.bind("127.0.0.1", 0) }"#)?;
+    ///     assert!(socket.local_addr().unwrap().ip().is_loopback());
+    ///
+    ///     rb_assert!(ruby, "klass == UDPSocket", klass = ruby.class_udp_socket()?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// # }
+    /// ```
+    pub fn class_udp_socket(&self) -> Result<RClass, Error> {
+        self.socket_class("UDPSocket")
+    }
+
+    /// Return Ruby's `UNIXSocket` class, requiring `"socket"` if it hasn't
+    /// already been loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::{io::Write, os::unix::net::UnixStream};
+    ///
+    /// use magnus::{rb_assert, Error, RArray, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let (a, b) = UnixStream::pair().unwrap();
+    ///     rb_assert!(ruby, "a.is_a?(UNIXSocket)", a);
+    ///     drop(b);
+    ///
+    ///     let pair: RArray = ruby.eval("UNIXSocket.pair")?;
+    ///     let mut b: UnixStream = pair.entry(1)?;
+    ///     b.set_nonblocking(false).unwrap();
+    ///     b.write_all(b"hello").unwrap();
+    ///     rb_assert!(ruby, r#"pair[0].readpartial(5) == "hello""#, pair);
+    ///
+    ///     rb_assert!(ruby, "klass == UNIXSocket", klass = ruby.class_unix_socket()?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// # }
+    /// ```
+    pub fn class_unix_socket(&self) -> Result<RClass, Error> {
+        self.socket_class("UNIXSocket")
+    }
+
+    fn socket_class(&self, name: &str) -> Result<RClass, Error> {
+        if let Ok(class) = self.class_object().const_get(name) {
+            return Ok(class);
+        }
+        self.require("socket")?;
+        self.class_object().const_get(name)
+    }
+}
+
+/// Create a new Ruby socket of `class` taking ownership of `sock`'s file
+/// descriptor.
+#[cfg(unix)]
+fn socket_into_value<T>(class: Result<RClass, Error>, sock: T) -> Value
+where
+    T: AsRawFd + IntoRawFd,
+{
+    static FOR_FD: LazyId = LazyId::new("for_fd");
+
+    let val = class
+        .and_then(|class| class.funcall(*FOR_FD, (sock.as_raw_fd(),)))
+        .unwrap();
+    // the fd is now owned by the Ruby object
+    let _ = sock.into_raw_fd();
+    val
+}
+
+/// Duplicate the file descriptor of `val`, checking it is an instance of the
+/// Ruby socket class `name`.
+///
+/// `dup` should be a function like `TcpStream::try_clone`.
+#[cfg(unix)]
+fn socket_try_convert<T>(val: Value, name: &str, dup: fn(&T) -> io::Result<T>) -> Result<T, Error>
+where
+    T: FromRawFd,
+{
+    static CLOSED_P: LazyId = LazyId::new("closed?");
+
+    let ruby = Ruby::get_with(val);
+    let file = RFile::from_value(val).filter(|_| {
+        ruby.class_object()
+            .const_get::<_, RClass>(name)
+            .map_or(false, |class| val.is_kind_of(class))
+    });
+    let file = match file {
+        Some(file) => file,
+        None => {
+            return Err(Error::new(
+                ruby.exception_type_error(),
+                format!(
+                    "no implicit conversion of {} into {}",
+                    unsafe { val.classname() },
+                    name
+                ),
+            ))
+        }
+    };
+    // on Ruby 3.3+ getting the fd of a closed stream raises, so check first
+    if file.funcall::<_, _, bool>(*CLOSED_P, ())? {
+        return Err(Error::new(ruby.exception_io_error(), "closed stream"));
+    }
+    let fd: RawFd = file.as_raw_fd();
+    // borrow the fd without taking ownership, then duplicate it
+    let borrowed = ManuallyDrop::new(unsafe { T::from_raw_fd(fd) });
+    dup(&borrowed).map_err(|e| Error::new(ruby.exception_io_error(), e.to_string()))
+}
+
+#[cfg(unix)]
+impl IntoValue for TcpStream {
+    /// # Panics
+    ///
+    /// Panics if the `socket` library can't be loaded.
+    #[inline]
+    fn into_value_with(self, handle: &Ruby) -> Value {
+        socket_into_value(handle.class_tcp_socket(), self)
+    }
+}
+
+#[cfg(unix)]
+unsafe impl IntoValueFromNative for TcpStream {}
+
+#[cfg(unix)]
+impl TryConvert for TcpStream {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        socket_try_convert(val, "TCPSocket", TcpStream::try_clone)
+    }
+}
+
+#[cfg(unix)]
+unsafe impl TryConvertOwned for TcpStream {}
+
+#[cfg(unix)]
+impl IntoValue for UdpSocket {
+    /// # Panics
+    ///
+    /// Panics if the `socket` library can't be loaded.
+    #[inline]
+    fn into_value_with(self, handle: &Ruby) -> Value {
+        socket_into_value(handle.class_udp_socket(), self)
+    }
+}
+
+#[cfg(unix)]
+unsafe impl IntoValueFromNative for UdpSocket {}
+
+#[cfg(unix)]
+impl TryConvert for UdpSocket {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        socket_try_convert(val, "UDPSocket", UdpSocket::try_clone)
+    }
+}
+
+#[cfg(unix)]
+unsafe impl TryConvertOwned for UdpSocket {}
+
+#[cfg(unix)]
+impl IntoValue for UnixStream {
+    /// # Panics
+    ///
+    /// Panics if the `socket` library can't be loaded.
+    #[inline]
+    fn into_value_with(self, handle: &Ruby) -> Value {
+        socket_into_value(handle.class_unix_socket(), self)
+    }
+}
+
+#[cfg(unix)]
+unsafe impl IntoValueFromNative for UnixStream {}
+
+#[cfg(unix)]
+impl TryConvert for UnixStream {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        socket_try_convert(val, "UNIXSocket", UnixStream::try_clone)
+    }
+}
+
+#[cfg(unix)]
+unsafe impl TryConvertOwned for UnixStream {}
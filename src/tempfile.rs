@@ -0,0 +1,120 @@
+use std::os::raw::c_int;
+
+use rb_sys::{rb_block_call, VALUE};
+
+use crate::{
+    block::Proc,
+    class::RClass,
+    error::{protect, Error},
+    method::Block,
+    module::Module,
+    r_file::RFile,
+    try_convert::TryConvert,
+    value::{LazyId, ReprValue, Value},
+    Ruby,
+};
+
+/// # `Tempfile`
+///
+/// Functions for working with Ruby's `Tempfile` class.
+impl Ruby {
+    /// Return Ruby's `Tempfile` class, requiring `"tempfile"` if it hasn't
+    /// already been loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     rb_assert!(ruby, "klass == Tempfile", klass = ruby.class_tempfile()?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn class_tempfile(&self) -> Result<RClass, Error> {
+        if let Ok(class) = self.class_object().const_get("Tempfile") {
+            return Ok(class);
+        }
+        self.require("tempfile")?;
+        self.class_object().const_get("Tempfile")
+    }
+
+    /// Create a temporary file and call `func` with it, returning the result
+    /// of `func`.
+    ///
+    /// The file's name starts with `basename`, and it is created in the
+    /// system's temporary directory.
+    ///
+    /// Equivalent to Ruby's `Tempfile.create(basename) { |file| ... }`. The
+    /// file is closed and deleted by Ruby when `func` returns, including when
+    /// it returns an error or panics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, Error, Ruby, Value};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let (path, contents) = ruby.tempfile_create("example", |ruby, file| {
+    ///         file.write_from(b"hello world")?;
+    ///         let _: Value = file.funcall("rewind", ())?;
+    ///         let contents: String = file.funcall("read", ())?;
+    ///         let path: String = file.funcall("path", ())?;
+    ///         assert!(ruby.class_file().funcall::<_, _, bool>("exist?", (&*path,))?);
+    ///         Ok((path, contents))
+    ///     })?;
+    ///     assert_eq!(contents, "hello world");
+    ///     assert!(!std::path::Path::new(&path).exists());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn tempfile_create<F, T>(&self, basename: &str, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&Ruby, RFile) -> Result<T, Error>,
+    {
+        static CREATE: LazyId = LazyId::new("create");
+
+        unsafe extern "C" fn call<F, T>(
+            yielded_arg: VALUE,
+            callback_arg: VALUE,
+            argc: c_int,
+            argv: *const VALUE,
+            blockarg: VALUE,
+        ) -> VALUE
+        where
+            F: FnOnce(&Ruby, RFile) -> Result<T, Error>,
+        {
+            let (func, result) = &mut *(callback_arg as *mut (Option<F>, Option<T>));
+            let func = func.take().unwrap();
+            let block = |ruby: &Ruby, _: &[Value], _: Option<Proc>| -> Result<Value, Error> {
+                *result = Some(func(ruby, RFile::try_convert(Value::new(yielded_arg))?)?);
+                Ok(ruby.qnil().as_value())
+            };
+            block
+                .call_handle_error(argc, argv as *const Value, Value::new(blockarg))
+                .as_rb_value()
+        }
+
+        let class = self.class_tempfile()?;
+        let basename = self.str_new(basename);
+        let mut data: (Option<F>, Option<T>) = (Some(func), None);
+        let call_func =
+            call::<F, T> as unsafe extern "C" fn(VALUE, VALUE, c_int, *const VALUE, VALUE) -> VALUE;
+
+        protect(|| unsafe {
+            Value::new(rb_block_call(
+                class.as_rb_value(),
+                CREATE.as_rb_id(),
+                1,
+                &basename.as_rb_value() as *const VALUE,
+                Some(call_func),
+                &mut data as *mut _ as VALUE,
+            ))
+        })?;
+        Ok(data.1.unwrap())
+    }
+}
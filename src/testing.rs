@@ -0,0 +1,312 @@
+//! Utilities for testing code that works with Ruby objects.
+//!
+//! [`snapshot`] converts a Ruby object to a deterministic, multi-line text
+//! representation, suitable for comparing against an expected value in a
+//! test. Hash keys and instance variables are sorted, and the addresses in
+//! the names of anonymous classes are removed, so the output is stable
+//! between runs. [`diff`] compares two snapshots line by line.
+//!
+//! See also the [`rb_assert`](crate::rb_assert!),
+//! [`assert_ruby_eq`](crate::assert_ruby_eq!), and
+//! [`assert_raises`](crate::assert_raises!) macros.
+//!
+//! # Examples
+//!
+//! ```
+//! use magnus::{testing, Error, Ruby, Value};
+//!
+//! fn example(ruby: &Ruby) -> Result<(), Error> {
+//!     let val: Value = ruby.eval(r#"{b: [1, 2], a: "x"}"#)?;
+//!
+//!     assert_eq!(
+//!         testing::snapshot(val)?,
+//!         r#"{
+//!   :a => "x",
+//!   :b => [
+//!     1,
+//!     2,
+//!   ],
+//! }"#
+//!     );
+//!
+//!     Ok(())
+//! }
+//! # Ruby::init(example).unwrap()
+//! ```
+
+use std::fmt::{Debug, Write};
+
+use crate::{
+    error::Error,
+    module::Module,
+    object::Object,
+    r_array::RArray,
+    r_hash::{ForEach, RHash},
+    r_object::RObject,
+    r_struct::RStruct,
+    recursion::Nesting,
+    symbol::Symbol,
+    try_convert::TryConvert,
+    value::{private::ReprValue as _, LazyId, ReprValue, Value},
+};
+
+static INSTANCE_VARIABLES: LazyId = LazyId::new("instance_variables");
+
+/// Convert `val` to a stable, deeply-ordered text representation.
+///
+/// * Arrays and Hashes are written one element per line, with Hash entries
+///   sorted by the snapshot of their key.
+/// * Structs are written with their class name and members.
+/// * Plain objects are written with their class name and instance
+///   variables, sorted by name.
+/// * All other values are written with `#inspect`. This is used as is, so
+///   will include an address if the value's `#inspect` does (e.g. `Proc`).
+///
+/// Anonymous classes are named `#<Class:anonymous>` rather than with their
+/// address.
+///
+/// Recursive references are written as `[...]`, `{...}`, or `#<Class ...>`.
+///
+/// Returns `Err` if any method called while building the snapshot raises,
+/// or a `SystemStackError` if `val` is nested too deeply.
+pub fn snapshot<T>(val: T) -> Result<String, Error>
+where
+    T: ReprValue,
+{
+    let nesting = Nesting::new(None);
+    let mut snapshot = Snapshot::new(&nesting);
+    snapshot.write(val.as_value(), 0)?;
+    Ok(snapshot.out)
+}
+
+/// Compare two snapshots, returning `None` if they are equal, or a line
+/// based diff if they differ.
+///
+/// Lines only in `expected` are prefixed with `-`, lines only in `actual`
+/// with `+`.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{testing, Error, Ruby, Value};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let a = testing::snapshot(ruby.eval::<Value>("[1, 2, 3]")?)?;
+///     let b = testing::snapshot(ruby.eval::<Value>("[1, 4, 3]")?)?;
+///
+///     assert_eq!(testing::diff(&a, &a), None);
+///     assert_eq!(
+///         testing::diff(&a, &b).unwrap(),
+///         "  [\n    1,\n-   2,\n+   4,\n    3,\n  ]\n"
+///     );
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub fn diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let a = expected.lines().collect::<Vec<_>>();
+    let b = actual.lines().collect::<Vec<_>>();
+
+    // lengths of the longest common subsequences of the suffixes of a and b
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            let _ = writeln!(out, "  {}", a[i]);
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            let _ = writeln!(out, "+ {}", b[j]);
+            j += 1;
+        } else {
+            let _ = writeln!(out, "- {}", a[i]);
+            i += 1;
+        }
+    }
+    Some(out)
+}
+
+#[doc(hidden)]
+pub fn convert_like<T>(_: &T, val: Value) -> Result<T, Error>
+where
+    T: TryConvert,
+{
+    T::try_convert(val)
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_raises<C, F, T>(class: C, message: Option<&str>, func: F)
+where
+    C: ReprValue + Module,
+    F: FnOnce() -> Result<T, Error>,
+    T: Debug,
+{
+    let err = match func() {
+        Ok(v) => panic!("expected {} to be raised, got Ok({:?})", class.inspect(), v),
+        Err(e) => e,
+    };
+    if !err.is_kind_of(class) {
+        panic!("expected {} to be raised, got {}", class.inspect(), err);
+    }
+    if let Some(message) = message {
+        let actual = err.to_string();
+        if !actual.contains(message) {
+            panic!(
+                "expected {} message to contain {:?}, got {:?}",
+                class.inspect(),
+                message,
+                actual
+            );
+        }
+    }
+}
+
+struct Snapshot<'a> {
+    out: String,
+    nesting: &'a Nesting,
+}
+
+impl<'a> Snapshot<'a> {
+    fn new(nesting: &'a Nesting) -> Self {
+        Self {
+            out: String::new(),
+            nesting,
+        }
+    }
+
+    fn write(&mut self, val: Value, indent: usize) -> Result<(), Error> {
+        let nesting = self.nesting;
+        if let Some(ary) = RArray::from_value(val) {
+            if nesting.is_entered(val) {
+                self.out.push_str("[...]");
+                return Ok(());
+            }
+            if ary.is_empty() {
+                self.out.push_str("[]");
+                return Ok(());
+            }
+            let _guard = nesting.enter(val)?;
+            self.out.push_str("[\n");
+            for i in 0..ary.len() {
+                self.indent(indent + 1);
+                self.write(ary.entry(i as isize)?, indent + 1)?;
+                self.out.push_str(",\n");
+            }
+            self.indent(indent);
+            self.out.push(']');
+        } else if let Some(hash) = RHash::from_value(val) {
+            if nesting.is_entered(val) {
+                self.out.push_str("{...}");
+                return Ok(());
+            }
+            if hash.is_empty() {
+                self.out.push_str("{}");
+                return Ok(());
+            }
+            let _guard = nesting.enter(val)?;
+            let mut entries = Vec::with_capacity(hash.len());
+            hash.foreach(|key: Value, _: Value| {
+                let mut key_snapshot = Snapshot::new(nesting);
+                key_snapshot.write(key, indent + 1)?;
+                entries.push((key_snapshot.out, key));
+                Ok(ForEach::Continue)
+            })?;
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            self.out.push_str("{\n");
+            for (key, raw_key) in entries {
+                self.indent(indent + 1);
+                self.out.push_str(&key);
+                self.out.push_str(" => ");
+                self.write(hash.aref(raw_key)?, indent + 1)?;
+                self.out.push_str(",\n");
+            }
+            self.indent(indent);
+            self.out.push('}');
+        } else if let Some(st) = RStruct::from_value(val) {
+            let class = class_name(val);
+            if nesting.is_entered(val) {
+                let _ = write!(self.out, "#<struct {} ...>", class);
+                return Ok(());
+            }
+            let _guard = nesting.enter(val)?;
+            let _ = write!(self.out, "#<struct {}", class);
+            let members = st.members()?;
+            if !members.is_empty() {
+                self.out.push('\n');
+                for member in members.iter() {
+                    self.indent(indent + 1);
+                    let _ = write!(self.out, "{} = ", member);
+                    self.write(st.getmember(&**member)?, indent + 1)?;
+                    self.out.push_str(",\n");
+                }
+                self.indent(indent);
+            }
+            self.out.push('>');
+        } else if let Some(obj) = RObject::from_value(val) {
+            let class = class_name(val);
+            if nesting.is_entered(val) {
+                let _ = write!(self.out, "#<{} ...>", class);
+                return Ok(());
+            }
+            let ivars: RArray = obj.funcall(*INSTANCE_VARIABLES, ())?;
+            let mut names = ivars
+                .into_iter()
+                .map(|name| Symbol::try_convert(name)?.name().map(|n| n.into_owned()))
+                .collect::<Result<Vec<_>, Error>>()?;
+            names.sort();
+            let _guard = nesting.enter(val)?;
+            let _ = write!(self.out, "#<{}", class);
+            if !names.is_empty() {
+                self.out.push('\n');
+                for name in names {
+                    self.indent(indent + 1);
+                    let _ = write!(self.out, "{} = ", name);
+                    self.write(obj.ivar_get(name.as_str())?, indent + 1)?;
+                    self.out.push_str(",\n");
+                }
+                self.indent(indent);
+            }
+            self.out.push('>');
+        } else {
+            self.out.push_str(&val.inspect());
+        }
+        Ok(())
+    }
+
+    fn indent(&mut self, level: usize) {
+        for _ in 0..level {
+            self.out.push_str("  ");
+        }
+    }
+}
+
+// anonymous classes and modules are named like `#<Class:0x000...>`, replace
+// the address so the name is the same between runs
+fn class_name(val: Value) -> String {
+    let name = unsafe { val.classname() };
+    let mut out = String::with_capacity(name.len());
+    let mut rest = &*name;
+    while let Some(i) = rest.find(":0x") {
+        out.push_str(&rest[..i]);
+        out.push_str(":anonymous");
+        rest = rest[i + 3..].trim_start_matches(|c: char| c.is_ascii_hexdigit());
+    }
+    out.push_str(rest);
+    out
+}
@@ -1,17 +1,21 @@
 use std::{
     fmt,
+    os::raw::{c_int, c_long},
     time::{Duration, SystemTime},
 };
 
-use rb_sys::{rb_time_new, rb_time_timeval, rb_time_utc_offset, timeval, VALUE};
+use rb_sys::{
+    rb_time_new, rb_time_timespec, rb_time_timespec_interval, rb_time_timespec_new,
+    rb_time_utc_offset, timespec, VALUE,
+};
 
 use crate::{
     api::Ruby,
     error::{protect, Error},
-    into_value::IntoValue,
+    into_value::{IntoValue, IntoValueFromNative},
     object::Object,
     r_typed_data::RTypedData,
-    try_convert::TryConvert,
+    try_convert::{TryConvert, TryConvertOwned},
     value::{
         private::{self, ReprValue as _},
         Fixnum, ReprValue, Value,
@@ -48,6 +52,108 @@ impl Ruby {
             ))
         })
     }
+
+    /// Create a new `Time` in the local timezone, with nanosecond precision.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let t = ruby.time_nano_new(1654013280, 123456789)?;
+    ///
+    ///     rb_assert!(ruby, "t.to_i == 1654013280", t);
+    ///     rb_assert!(ruby, "t.nsec == 123456789", t);
+    ///     rb_assert!(ruby, "t.utc_offset == Time.now.utc_offset", t);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn time_nano_new(&self, seconds: i64, nanoseconds: i64) -> Result<Time, Error> {
+        // Ruby's magic value for the local timezone
+        self.time_timespec_new(seconds, nanoseconds, c_int::MAX)
+    }
+
+    /// Create a new `Time` in UTC, with nanosecond precision.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let t = ruby.time_utc_new(1654013280, 500)?;
+    ///
+    ///     rb_assert!(ruby, r#"t == Time.utc(2022, 5, 31, 16, 8, 0, 0.5)"#, t);
+    ///     rb_assert!(ruby, "t.utc?", t);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn time_utc_new(&self, seconds: i64, nanoseconds: i64) -> Result<Time, Error> {
+        // Ruby's magic value for UTC
+        self.time_timespec_new(seconds, nanoseconds, c_int::MAX - 1)
+    }
+
+    /// Create a new `Time` with a fixed offset from UTC of `utc_offset`
+    /// seconds, with nanosecond precision.
+    ///
+    /// Returns an error if `utc_offset` is not within one day either side of
+    /// UTC.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let t = ruby.time_new_with_offset(1654013280, 0, -25_200)?;
+    ///
+    ///     rb_assert!(ruby, r#"t == Time.new(2022, 5, 31, 9, 8, 0, "-07:00")"#, t);
+    ///     rb_assert!(ruby, "t.utc_offset == -25_200", t);
+    ///
+    ///     assert!(ruby.time_new_with_offset(0, 0, 86_400).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn time_new_with_offset(
+        &self,
+        seconds: i64,
+        nanoseconds: i64,
+        utc_offset: i32,
+    ) -> Result<Time, Error> {
+        if utc_offset <= -86_400 || utc_offset >= 86_400 {
+            return Err(Error::new(
+                self.exception_arg_error(),
+                "utc_offset out of range",
+            ));
+        }
+        self.time_timespec_new(seconds, nanoseconds, utc_offset)
+    }
+
+    fn time_timespec_new(
+        &self,
+        seconds: i64,
+        nanoseconds: i64,
+        offset: c_int,
+    ) -> Result<Time, Error> {
+        // normalise so that tv_nsec is always in 0..1_000_000_000
+        let seconds = seconds
+            .checked_add(nanoseconds.div_euclid(1_000_000_000))
+            .ok_or_else(|| Error::new(self.exception_range_error(), "time out of range"))?;
+        let ts = timespec {
+            tv_sec: seconds
+                .try_into()
+                .map_err(|_| Error::new(self.exception_range_error(), "time out of range"))?,
+            tv_nsec: nanoseconds.rem_euclid(1_000_000_000) as c_long,
+        };
+        protect(|| unsafe { Time::from_rb_value_unchecked(rb_time_timespec_new(&ts, offset)) })
+    }
 }
 
 /// Wrapper type for a Value known to be an instance of Ruby's Time class.
@@ -102,6 +208,46 @@ impl Time {
     pub fn utc_offset(self) -> i64 {
         unsafe { Fixnum::from_rb_value_unchecked(rb_time_utc_offset(self.as_rb_value())).to_i64() }
     }
+
+    /// Returns the number of whole seconds since the Unix epoch, and the
+    /// additional nanoseconds, for `self`.
+    ///
+    /// For times before the epoch the seconds are negative, nanoseconds are
+    /// always positive.
+    ///
+    /// Returns an error if `self` can't be represented with 64 bit seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby, Time};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let t: Time = ruby.eval("Time.at(1654013280, 123456789, :nsec)")?;
+    ///     assert_eq!(t.timespec()?, (1654013280, 123456789));
+    ///
+    ///     let t: Time = ruby.eval("Time.at(-1.5)")?;
+    ///     assert_eq!(t.timespec()?, (-2, 500_000_000));
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn timespec(self) -> Result<(i64, u32), Error> {
+        timespec(self.as_value())
+    }
+}
+
+fn timespec(val: Value) -> Result<(i64, u32), Error> {
+    let mut ts = timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    protect(|| unsafe {
+        ts = rb_time_timespec(val.as_rb_value());
+        Ruby::get_unchecked().qnil()
+    })?;
+    Ok((ts.tv_sec as i64, ts.tv_nsec as u32))
 }
 
 impl fmt::Display for Time {
@@ -126,27 +272,35 @@ impl IntoValue for Time {
 impl IntoValue for SystemTime {
     #[inline]
     fn into_value_with(self, ruby: &Ruby) -> Value {
-        match self.duration_since(Self::UNIX_EPOCH) {
-            Ok(duration) => ruby
-                .time_new(
-                    duration.as_secs().try_into().unwrap(),
-                    duration.subsec_micros().try_into().unwrap(),
-                )
-                .unwrap()
-                .as_value(),
+        let time = match self.duration_since(Self::UNIX_EPOCH) {
+            Ok(duration) => ruby.time_nano_new(
+                duration.as_secs().try_into().unwrap(),
+                duration.subsec_nanos().into(),
+            ),
             Err(_) => {
                 let duration = Self::UNIX_EPOCH.duration_since(self).unwrap();
-                ruby.time_new(
+                ruby.time_nano_new(
                     -i64::try_from(duration.as_secs()).unwrap(),
-                    -i64::try_from(duration.subsec_micros()).unwrap(),
+                    -i64::from(duration.subsec_nanos()),
                 )
-                .unwrap()
-                .as_value()
             }
-        }
+        };
+        time.unwrap().as_value()
+    }
+}
+
+unsafe impl IntoValueFromNative for SystemTime {}
+
+impl IntoValue for Duration {
+    /// Converts to a Ruby `Float` of the number of seconds.
+    #[inline]
+    fn into_value_with(self, ruby: &Ruby) -> Value {
+        ruby.float_from_f64(self.as_secs_f64()).as_value()
     }
 }
 
+unsafe impl IntoValueFromNative for Duration {}
+
 impl Object for Time {}
 
 unsafe impl private::ReprValue for Time {}
@@ -168,23 +322,130 @@ impl TryConvert for Time {
 
 impl TryConvert for SystemTime {
     fn try_convert(val: Value) -> Result<Self, Error> {
-        let mut timeval = timeval {
+        let (secs, nsecs) = timespec(val)?;
+        let time = if secs >= 0 {
+            Self::UNIX_EPOCH.checked_add(Duration::new(secs as u64, nsecs))
+        } else {
+            Self::UNIX_EPOCH
+                .checked_sub(Duration::from_secs(secs.unsigned_abs()))
+                .and_then(|t| t.checked_add(Duration::from_nanos(nsecs.into())))
+        };
+        time.ok_or_else(|| {
+            Error::new(
+                Ruby::get_with(val).exception_range_error(),
+                "time out of range",
+            )
+        })
+    }
+}
+
+unsafe impl TryConvertOwned for SystemTime {}
+
+impl TryConvert for Duration {
+    /// Converts from a non-negative Ruby `Numeric` number of seconds.
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        let mut ts = timespec {
             tv_sec: 0,
-            tv_usec: 0,
+            tv_nsec: 0,
         };
         protect(|| unsafe {
-            timeval = rb_time_timeval(val.as_rb_value());
+            ts = rb_time_timespec_interval(val.as_rb_value());
             Ruby::get_unchecked().qnil()
         })?;
-        if timeval.tv_sec >= 0 && timeval.tv_usec >= 0 {
-            let mut duration = Duration::from_secs(timeval.tv_sec as _);
-            duration += Duration::from_micros(timeval.tv_usec as _);
-            Ok(Self::UNIX_EPOCH + duration)
-        } else {
-            Err(Error::new(
-                Ruby::get_with(val).exception_arg_error(),
-                "time must not be negative",
-            ))
+        Ok(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+    }
+}
+
+unsafe impl TryConvertOwned for Duration {}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+
+    use super::Time;
+    use crate::{
+        error::Error,
+        into_value::{IntoValue, IntoValueFromNative},
+        try_convert::{TryConvert, TryConvertOwned},
+        value::{ReprValue, Value},
+        Ruby,
+    };
+
+    fn out_of_range(val: Value) -> Error {
+        Error::new(
+            Ruby::get_with(val).exception_range_error(),
+            "time out of range",
+        )
+    }
+
+    impl IntoValue for DateTime<Utc> {
+        #[inline]
+        fn into_value_with(self, ruby: &Ruby) -> Value {
+            ruby.time_utc_new(self.timestamp(), self.timestamp_subsec_nanos().into())
+                .unwrap()
+                .as_value()
+        }
+    }
+
+    unsafe impl IntoValueFromNative for DateTime<Utc> {}
+
+    impl TryConvert for DateTime<Utc> {
+        fn try_convert(val: Value) -> Result<Self, Error> {
+            let (secs, nsecs) = Time::try_convert(val)?.timespec()?;
+            Utc.timestamp_opt(secs, nsecs)
+                .single()
+                .ok_or_else(|| out_of_range(val))
+        }
+    }
+
+    unsafe impl TryConvertOwned for DateTime<Utc> {}
+
+    impl IntoValue for DateTime<FixedOffset> {
+        #[inline]
+        fn into_value_with(self, ruby: &Ruby) -> Value {
+            ruby.time_new_with_offset(
+                self.timestamp(),
+                self.timestamp_subsec_nanos().into(),
+                self.offset().local_minus_utc(),
+            )
+            .unwrap()
+            .as_value()
+        }
+    }
+
+    unsafe impl IntoValueFromNative for DateTime<FixedOffset> {}
+
+    impl TryConvert for DateTime<FixedOffset> {
+        fn try_convert(val: Value) -> Result<Self, Error> {
+            let time = Time::try_convert(val)?;
+            let (secs, nsecs) = time.timespec()?;
+            i32::try_from(time.utc_offset())
+                .ok()
+                .and_then(FixedOffset::east_opt)
+                .and_then(|offset| offset.timestamp_opt(secs, nsecs).single())
+                .ok_or_else(|| out_of_range(val))
+        }
+    }
+
+    unsafe impl TryConvertOwned for DateTime<FixedOffset> {}
+
+    impl IntoValue for NaiveDateTime {
+        /// Converts to a Ruby `Time` in UTC.
+        #[inline]
+        fn into_value_with(self, ruby: &Ruby) -> Value {
+            Utc.from_utc_datetime(&self).into_value_with(ruby)
+        }
+    }
+
+    unsafe impl IntoValueFromNative for NaiveDateTime {}
+
+    impl TryConvert for NaiveDateTime {
+        /// Converts from a Ruby `Time`, using the date and time in UTC,
+        /// matching the conversion to Ruby.
+        fn try_convert(val: Value) -> Result<Self, Error> {
+            DateTime::<Utc>::try_convert(val).map(|dt| dt.naive_utc())
         }
     }
+
+    unsafe impl TryConvertOwned for NaiveDateTime {}
 }
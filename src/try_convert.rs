@@ -9,11 +9,13 @@ use seq_macro::seq;
 use crate::value::Flonum;
 use crate::{
     error::{protect, Error},
+    float::Float,
     integer::Integer,
     r_array::RArray,
     r_hash::RHash,
     r_string::RString,
-    value::{Fixnum, ReprValue, Value},
+    r_struct::RStruct,
+    value::{Fixnum, LazyId, ReprValue, Value},
     Ruby,
 };
 
@@ -46,6 +48,240 @@ where
 
 unsafe impl<T> TryConvertOwned for Option<T> where T: TryConvertOwned {}
 
+/// Wrapper converting Ruby `nil` to `T`'s default value.
+///
+/// Converting a [`Value`] to `NilDefault<T>` will result in
+/// `NilDefault(T::default())` when the value is `nil`, otherwise the value is
+/// converted to `T` as normal. This is useful for optional arguments where
+/// `nil` should mean "use the default", such as an empty string or collection,
+/// or zero.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{function, rb_assert, try_convert::NilDefault, Error, Ruby};
+///
+/// fn join(parts: NilDefault<Vec<String>>, sep: NilDefault<String>) -> String {
+///     parts.into_inner().join(&sep.into_inner())
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.define_global_function("join", function!(join, 2));
+///
+///     rb_assert!(ruby, r#"join(["a", "b"], "-") == "a-b""#);
+///     rb_assert!(ruby, r#"join(["a", "b"], nil) == "ab""#);
+///     rb_assert!(ruby, r#"join(nil, "-") == """#);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NilDefault<T>(pub T);
+
+impl<T> NilDefault<T> {
+    /// Consume `self`, returning the wrapped value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> TryConvert for NilDefault<T>
+where
+    T: TryConvert + Default,
+{
+    #[inline]
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        Option::<T>::try_convert(val).map(|v| Self(v.unwrap_or_default()))
+    }
+}
+
+unsafe impl<T> TryConvertOwned for NilDefault<T> where T: TryConvertOwned + Default {}
+
+/// Wrapper converting any Ruby value to a `bool` by Ruby's rules of
+/// truthiness.
+///
+/// Only `nil` and `false` convert to `Truthy(false)`, all other values,
+/// including `0` and the empty string, convert to `Truthy(true)`.
+///
+/// This matches the conversion for [`bool`], but states the intent in the
+/// signature of predicate arguments and is unaffected should a stricter
+/// conversion be wanted for `bool` elsewhere.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{function, rb_assert, try_convert::Truthy, Error, Ruby};
+///
+/// fn check(flag: Truthy) -> &'static str {
+///     if flag.into() {
+///         "yes"
+///     } else {
+///         "no"
+///     }
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.define_global_function("check", function!(check, 1));
+///
+///     rb_assert!(ruby, r#"check(0) == "yes""#);
+///     rb_assert!(ruby, r#"check("") == "yes""#);
+///     rb_assert!(ruby, r#"check(nil) == "no""#);
+///     rb_assert!(ruby, r#"check(false) == "no""#);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Truthy(pub bool);
+
+impl From<Truthy> for bool {
+    #[inline]
+    fn from(val: Truthy) -> Self {
+        val.0
+    }
+}
+
+impl TryConvert for Truthy {
+    #[inline]
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        Ok(Self(val.to_bool()))
+    }
+}
+unsafe impl TryConvertOwned for Truthy {}
+
+/// Wrapper applying Ruby's numeric coercion protocol when converting to the
+/// numeric type `T`.
+///
+/// When the value being converted is an instance of `Numeric`, but not
+/// already an `Integer` or `Float`, it is asked to coerce itself with
+/// `coerce`, as Ruby's arithmetic operators do, and the result converted to
+/// `T`. This allows custom numeric classes that only implement `coerce` to be
+/// accepted where a number is expected, which the plain conversions to Rust
+/// numeric types would reject.
+///
+/// Values that are not `Numeric`, such as `Time`, Strings, and `nil`, are
+/// always rejected, even if they implement `to_int` or `to_f`.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{function, rb_assert, try_convert::NumCoerce, Error, Ruby, Value};
+///
+/// fn half(n: NumCoerce<f64>) -> f64 {
+///     n.into_inner() / 2.0
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.define_global_function("half", function!(half, 1));
+///
+///     let _: Value = ruby.eval(
+///         r#"
+///         class Meters < Numeric
+///           def initialize(n)
+///             @n = n
+///           end
+///
+///           def coerce(other)
+///             [other, @n.to_f]
+///           end
+///         end
+///         "#,
+///     )?;
+///
+///     rb_assert!(ruby, "half(3) == 1.5");
+///     rb_assert!(ruby, "half(Meters.new(5)) == 2.5");
+///     rb_assert!(ruby, "(half(Time.now) rescue $!).is_a?(TypeError)");
+///     rb_assert!(ruby, r#"(half("5") rescue $!).is_a?(TypeError)"#);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct NumCoerce<T>(pub T);
+
+impl<T> NumCoerce<T> {
+    /// Consume `self`, returning the wrapped value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> TryConvert for NumCoerce<T>
+where
+    T: Coercible,
+{
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        static COERCE: LazyId = LazyId::new("coerce");
+
+        if Integer::from_value(val).is_some() || Float::from_value(val).is_some() {
+            return T::try_convert(val).map(Self);
+        }
+        let handle = Ruby::get_with(val);
+        if !val.is_kind_of(handle.class_numeric()) {
+            return Err(Error::new(
+                handle.exception_type_error(),
+                format!("no implicit conversion of {} into Numeric", unsafe {
+                    val.classname()
+                },),
+            ));
+        }
+        let (_, coerced): (Value, Value) = val.funcall(*COERCE, (T::coerce_zero(&handle),))?;
+        T::try_convert(coerced).map(Self)
+    }
+}
+
+unsafe impl<T> TryConvertOwned for NumCoerce<T> where T: Coercible + TryConvertOwned {}
+
+mod private {
+    use crate::{value::Value, Ruby};
+
+    pub trait Coercible: super::TryConvert {
+        fn coerce_zero(ruby: &Ruby) -> Value;
+    }
+
+    macro_rules! impl_coercible {
+        ($zero:expr, $($t:ty),*) => {
+            $(
+                impl Coercible for $t {
+                    #[inline]
+                    fn coerce_zero(ruby: &Ruby) -> Value {
+                        ruby.into_value($zero)
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_coercible!(
+        0,
+        i8,
+        i16,
+        i32,
+        i64,
+        isize,
+        u8,
+        u16,
+        u32,
+        u64,
+        usize,
+        crate::integer::Integer
+    );
+    impl_coercible!(0.0, f32, f64, crate::float::Float);
+}
+
+/// Trait implemented for numeric types that can be used with [`NumCoerce`].
+///
+/// This trait is implemented for Rust's primitive integer types and
+/// [`Integer`], which coerce against `0`, and `f32`, `f64`, and
+/// [`Float`](crate::Float), which coerce against `0.0`.
+pub trait Coercible: private::Coercible {}
+impl<T> Coercible for T where T: private::Coercible {}
+
 impl TryConvert for bool {
     #[inline]
     fn try_convert(val: Value) -> Result<Self, Error> {
@@ -183,6 +419,22 @@ impl TryConvert for bytes::Bytes {
 #[cfg(feature = "bytes")]
 unsafe impl TryConvertOwned for bytes::Bytes {}
 
+#[cfg(feature = "uuid")]
+impl TryConvert for uuid::Uuid {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        let s = RString::try_convert(val)?;
+        uuid::Uuid::try_parse_ascii(unsafe { s.as_slice() }).map_err(|e| {
+            Error::new(
+                Ruby::get_with(val).exception_arg_error(),
+                format!("invalid UUID: {}", e),
+            )
+        })
+    }
+}
+
+#[cfg(feature = "uuid")]
+unsafe impl TryConvertOwned for uuid::Uuid {}
+
 impl TryConvert for char {
     #[inline]
     fn try_convert(val: Value) -> Result<Self, Error> {
@@ -225,16 +477,24 @@ macro_rules! impl_try_convert {
             {
                 fn try_convert(val: Value) -> Result<Self, Error> {
                     debug_assert_value!(val);
-                    let array = RArray::try_convert(val)?;
-                    let slice = unsafe { array.as_slice() };
-                    if slice.len() != $n {
-                        return Err(Error::new(
-                            Ruby::get_with(val).exception_type_error(),
-                            concat!("expected Array of length ", $n),
+                    if let Some(r_struct) = RStruct::from_value(val) {
+                        // copy the members out, as converting may run Ruby
+                        // code that modifies the struct
+                        let members: [Value; $n] = unsafe { r_struct.as_slice() }
+                            .try_into()
+                            .map_err(|_| {
+                                Error::new(
+                                    Ruby::get_with(val).exception_type_error(),
+                                    concat!("expected Struct with ", $n, " members"),
+                                )
+                            })?;
+                        return Ok((
+                            #(TryConvert::try_convert(members[N])?,)*
                         ));
                     }
+                    let elements = RArray::try_convert(val)?.to_value_array::<$n>()?;
                     Ok((
-                        #(TryConvert::try_convert(slice[N])?,)*
+                        #(TryConvert::try_convert(elements[N])?,)*
                     ))
                 }
             }
@@ -269,6 +529,50 @@ where
 {
 }
 
+fn set_to_vec<T>(val: Value) -> Result<Vec<T>, Error>
+where
+    T: TryConvertOwned,
+{
+    let handle = Ruby::get_with(val);
+    let class = crate::r_array::set_class(&handle)?;
+    if !val.is_kind_of(class) {
+        return Err(Error::new(
+            handle.exception_type_error(),
+            format!("no implicit conversion of {} into Set", unsafe {
+                val.classname()
+            }),
+        ));
+    }
+    val.funcall::<_, _, RArray>("to_a", ())?.to_vec()
+}
+
+impl<T> TryConvert for std::collections::HashSet<T>
+where
+    T: TryConvertOwned + Eq + std::hash::Hash,
+{
+    #[inline]
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        debug_assert_value!(val);
+        set_to_vec(val).map(|v| v.into_iter().collect())
+    }
+}
+unsafe impl<T> TryConvertOwned for std::collections::HashSet<T> where
+    T: TryConvertOwned + Eq + std::hash::Hash
+{
+}
+
+impl<T> TryConvert for std::collections::BTreeSet<T>
+where
+    T: TryConvertOwned + Ord,
+{
+    #[inline]
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        debug_assert_value!(val);
+        set_to_vec(val).map(|v| v.into_iter().collect())
+    }
+}
+unsafe impl<T> TryConvertOwned for std::collections::BTreeSet<T> where T: TryConvertOwned + Ord {}
+
 #[cfg(unix)]
 impl TryConvert for PathBuf {
     fn try_convert(val: Value) -> Result<Self, Error> {
@@ -4,6 +4,7 @@
 //! `rb_data_typed_object_wrap` function from Ruby's C API.
 
 use std::{
+    any::type_name,
     collections::hash_map::DefaultHasher,
     ffi::{c_void, CStr},
     fmt,
@@ -11,15 +12,18 @@ use std::{
     marker::PhantomData,
     mem::size_of_val,
     ops::Deref,
+    os::raw::c_long,
     panic::catch_unwind,
     ptr,
+    sync::Mutex,
 };
 
 #[cfg(ruby_gte_3_0)]
 use rb_sys::rbimpl_typeddata_flags::{self, RUBY_TYPED_FREE_IMMEDIATELY, RUBY_TYPED_WB_PROTECTED};
 use rb_sys::{
-    self, rb_data_type_struct__bindgen_ty_1, rb_data_type_t, rb_obj_reveal,
-    rb_singleton_class_attached, rb_singleton_class_clone, size_t, VALUE,
+    self, rb_data_type_struct__bindgen_ty_1, rb_data_type_t, rb_hash_end, rb_hash_start,
+    rb_hash_uint, rb_memhash, rb_obj_reveal, rb_singleton_class_attached, rb_singleton_class_clone,
+    size_t, st_index_t, VALUE,
 };
 
 #[cfg(ruby_lt_3_0)]
@@ -29,17 +33,19 @@ const RUBY_TYPED_FREE_IMMEDIATELY: u32 = 1;
 const RUBY_TYPED_WB_PROTECTED: u32 = rb_sys::ruby_fl_type::RUBY_FL_WB_PROTECTED as u32;
 
 use crate::{
-    class::RClass,
+    class::{Class, RClass},
     error::{bug_from_panic, Error},
     gc,
-    into_value::IntoValue,
+    into_value::{IntoValue, IntoValueFromNative},
+    method::Method,
+    module::Module,
     object::Object,
     r_typed_data::RTypedData,
     scan_args::{get_kwargs, scan_args},
     try_convert::TryConvert,
     value::{
         private::{self, ReprValue as _},
-        ReprValue, Value,
+        LazyId, Opaque, ReprValue, Value,
     },
     Ruby,
 };
@@ -163,6 +169,8 @@ where
     /// This function must not panic.
     #[doc(hidden)]
     unsafe extern "C" fn extern_free(ptr: *mut c_void) {
+        #[cfg(feature = "alloc-trace")]
+        crate::alloc_trace::record_free::<Self>();
         if let Err(e) = catch_unwind(|| Self::free(Box::from_raw(ptr as *mut _))) {
             bug_from_panic(e, "panic in DataTypeFunctions::free")
         }
@@ -364,6 +372,146 @@ where
     }
 }
 
+static REGISTRY: Mutex<Vec<RegisteredType>> = Mutex::new(Vec::new());
+
+/// Details of a Rust type registered as wrapped by a Ruby class.
+///
+/// See [`registered_types`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegisteredType {
+    /// The name of the Rust type.
+    pub type_name: &'static str,
+    /// The name of the Ruby class wrapping the type.
+    pub class: String,
+    /// The name from the type's [`DataType`].
+    pub data_type_name: String,
+    /// Whether Ruby calls the `DataTypeFunctions::mark` function.
+    pub mark: bool,
+    /// Whether Ruby calls the `DataTypeFunctions::size` function.
+    pub size: bool,
+    /// Whether Ruby calls the `DataTypeFunctions::compact` function.
+    pub compact: bool,
+    /// Whether the `free_immediately` flag is set.
+    pub free_immediately: bool,
+    /// Whether the `wb_protected` flag is set.
+    pub wb_protected: bool,
+    /// Whether the `frozen_shareable` flag is set.
+    pub frozen_shareable: bool,
+}
+
+impl IntoValue for RegisteredType {
+    fn into_value_with(self, handle: &Ruby) -> Value {
+        let hash = handle.hash_new();
+        // only fails if the hash is frozen, which it isn't
+        hash.aset(handle.to_symbol("type_name"), self.type_name)
+            .unwrap();
+        hash.aset(handle.to_symbol("class"), self.class).unwrap();
+        hash.aset(handle.to_symbol("data_type_name"), self.data_type_name)
+            .unwrap();
+        hash.aset(handle.to_symbol("mark"), self.mark).unwrap();
+        hash.aset(handle.to_symbol("size"), self.size).unwrap();
+        hash.aset(handle.to_symbol("compact"), self.compact)
+            .unwrap();
+        hash.aset(handle.to_symbol("free_immediately"), self.free_immediately)
+            .unwrap();
+        hash.aset(handle.to_symbol("wb_protected"), self.wb_protected)
+            .unwrap();
+        hash.aset(handle.to_symbol("frozen_shareable"), self.frozen_shareable)
+            .unwrap();
+        hash.as_value()
+    }
+}
+
+unsafe impl IntoValueFromNative for RegisteredType {}
+
+/// Record that the Rust type `T` is wrapped by `class`, for introspection
+/// with [`registered_types`].
+///
+/// This is called by the provided implementation of [`TypedData::setup`],
+/// and the implementations generated by the derive macro and
+/// [`wrap`](macro@crate::wrap). Manual implementations overriding `setup`
+/// should call this.
+pub fn register<T>(class: RClass)
+where
+    T: TypedData,
+{
+    let rb_data_type = T::data_type().as_rb_data_type();
+    let flags = rb_data_type.flags as usize;
+    #[cfg(ruby_gte_3_0)]
+    let frozen_shareable =
+        flags & rbimpl_typeddata_flags::RUBY_TYPED_FROZEN_SHAREABLE as usize != 0;
+    #[cfg(ruby_lt_3_0)]
+    let frozen_shareable = false;
+    let registered = RegisteredType {
+        type_name: type_name::<T>(),
+        class: unsafe { class.name() }.into_owned(),
+        data_type_name: unsafe { CStr::from_ptr(rb_data_type.wrap_struct_name) }
+            .to_string_lossy()
+            .into_owned(),
+        mark: rb_data_type.function.dmark.is_some(),
+        size: rb_data_type.function.dsize.is_some(),
+        compact: rb_data_type.function.dcompact.is_some(),
+        free_immediately: flags & RUBY_TYPED_FREE_IMMEDIATELY as usize != 0,
+        wb_protected: flags & RUBY_TYPED_WB_PROTECTED as usize != 0,
+        frozen_shareable,
+    };
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    match registry
+        .iter_mut()
+        .find(|r| r.type_name == registered.type_name)
+    {
+        Some(existing) => *existing = registered,
+        None => registry.push(registered),
+    }
+}
+
+/// Returns all Rust types registered as wrapped by a Ruby class, sorted by
+/// class name.
+///
+/// This can be exposed to Ruby to help debug mismatches between Ruby classes
+/// and wrapped Rust types.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{function, prelude::*, typed_data, Error, Ruby, TypedData};
+///
+/// #[magnus::wrap(class = "Point", free_immediately)]
+/// #[allow(dead_code)]
+/// struct Point {
+///     x: isize,
+///     y: isize,
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.define_class("Point", ruby.class_object())?;
+///     Point::setup(ruby)?;
+///
+///     let types = typed_data::registered_types();
+///     let point = types.iter().find(|t| t.class == "Point").unwrap();
+///     assert!(point.type_name.ends_with("Point"));
+///     assert!(point.free_immediately);
+///
+///     let module = ruby.define_module("Magnus")?;
+///     module.define_module_function(
+///         "registered_classes",
+///         function!(typed_data::registered_types, 0),
+///     )?;
+///     let res: bool = ruby.eval(
+///         "Magnus.registered_classes.any? { |t| t[:class] == 'Point' && t[:free_immediately] }",
+///     )?;
+///     assert!(res);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap();
+/// ```
+pub fn registered_types() -> Vec<RegisteredType> {
+    let mut res = REGISTRY.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    res.sort_by(|a, b| a.class.cmp(&b.class).then(a.type_name.cmp(b.type_name)));
+    res
+}
+
 /// A trait for Rust types that can be used with the
 /// `rb_data_typed_object_wrap` API.
 ///
@@ -488,6 +636,94 @@ where
     fn class_for(ruby: &Ruby, value: &Self) -> RClass {
         Self::class(ruby)
     }
+
+    /// Set up the Ruby class wrapping `Self`.
+    ///
+    /// The provided implementation registers `Self` for
+    /// [`registered_types`]. Implementations generated by the
+    /// [`TypedData`](derive@crate::TypedData) and [`wrap`](crate::wrap)
+    /// macros also define the methods requested with attributes such as
+    /// `hash_eql`, `inspect`, and `marshal`, skipping any method already
+    /// defined directly on the class.
+    ///
+    /// This should be called once, after the class has been defined and
+    /// before any instances are created or loaded, usually from the
+    /// extension's init function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{function, prelude::*, rb_assert, Error, Ruby, TypedData};
+    ///
+    /// #[magnus::wrap(class = "Point", free_immediately, inspect)]
+    /// #[derive(Debug)]
+    /// struct Point {
+    ///     x: isize,
+    ///     y: isize,
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let class = ruby.define_class("Point", ruby.class_object())?;
+    ///     class.define_singleton_method("new", function!(|x, y| Point { x, y }, 2))?;
+    ///     Point::setup(ruby)?;
+    ///
+    ///     rb_assert!(
+    ///         ruby,
+    ///         r##"Point.new(1, 2).inspect == "#<Point Point { x: 1, y: 2 }>""##
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn setup(ruby: &Ruby) -> Result<(), Error> {
+        register::<Self>(Self::class(ruby));
+        Ok(())
+    }
+}
+
+/// Define `func` as the method `name` of `class`, unless `class` itself
+/// already defines `name`.
+///
+/// Used by the code generated for [`TypedData::setup`].
+#[doc(hidden)]
+pub fn define_method_unless_defined<M>(class: RClass, name: &str, func: M) -> Result<(), Error>
+where
+    M: Method,
+{
+    if !is_own_method(class, name)? {
+        class.define_method(name, func)?;
+    }
+    Ok(())
+}
+
+/// Define `func` as the singleton method `name` of `class`, unless `class`
+/// already has the singleton method `name`.
+///
+/// Used by the code generated for [`TypedData::setup`].
+#[doc(hidden)]
+pub fn define_singleton_method_unless_defined<M>(
+    class: RClass,
+    name: &str,
+    func: M,
+) -> Result<(), Error>
+where
+    M: Method,
+{
+    if !is_own_method(class.singleton_class()?, name)? {
+        class.define_singleton_method(name, func)?;
+    }
+    Ok(())
+}
+
+fn is_own_method(class: RClass, name: &str) -> Result<bool, Error> {
+    static METHOD_DEFINED: LazyId = LazyId::new("method_defined?");
+    static PRIVATE_METHOD_DEFINED: LazyId = LazyId::new("private_method_defined?");
+
+    let handle = Ruby::get_with(class);
+    let name = handle.to_symbol(name);
+    Ok(class.funcall(*METHOD_DEFINED, (name, false))?
+        || class.funcall(*PRIVATE_METHOD_DEFINED, (name, false))?)
 }
 
 impl<T> TryConvert for &T
@@ -1030,6 +1266,149 @@ where
     }
 }
 
+/// # Hashing
+///
+/// Functions for building hash values compatible with Ruby's own.
+///
+/// See also [`RubyHasher`].
+impl Ruby {
+    /// Create a new [`RubyHasher`], seeded with Ruby's per-process hash
+    /// seed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::hash::{Hash, Hasher};
+    ///
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let mut a = ruby.hasher();
+    ///     ("foo", 1).hash(&mut a);
+    ///     let mut b = ruby.hasher();
+    ///     ("foo", 1).hash(&mut b);
+    ///     assert_eq!(a.finish(), b.finish());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn hasher(&self) -> RubyHasher {
+        RubyHasher::new(self)
+    }
+
+    /// Hash `bytes` with Ruby's internal hash function.
+    ///
+    /// This is the same function Ruby uses to hash the contents of Strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert_eq!(ruby.memhash(b"foo"), ruby.memhash(b"foo"));
+    ///     assert_ne!(ruby.memhash(b"foo"), ruby.memhash(b"bar"));
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn memhash(&self, bytes: &[u8]) -> u64 {
+        unsafe { rb_memhash(bytes.as_ptr() as *const c_void, bytes.len() as c_long) as u64 }
+    }
+}
+
+/// A [`Hasher`] using Ruby's internal hash functions.
+///
+/// This can be used to implement [`typed_data::Hash`](Hash) (or a `#hash`
+/// method directly) without calling back into Ruby, producing values in the
+/// same range and with the same seeding as Ruby's own `#hash` methods.
+///
+/// Create with [`Ruby::hasher`].
+///
+/// # Examples
+///
+/// ```
+/// use std::hash::{Hash, Hasher};
+///
+/// use magnus::{function, method, prelude::*, typed_data, Error, Ruby};
+///
+/// #[magnus::wrap(class = "Point", free_immediately)]
+/// #[derive(PartialEq, Eq)]
+/// struct Point {
+///     x: isize,
+///     y: isize,
+/// }
+///
+/// impl Point {
+///     fn new(x: isize, y: isize) -> Self {
+///         Self { x, y }
+///     }
+///
+///     fn hash(ruby: &Ruby, rb_self: &Self) -> i64 {
+///         let mut hasher = ruby.hasher();
+///         rb_self.x.hash(&mut hasher);
+///         rb_self.y.hash(&mut hasher);
+///         hasher.finish_i64()
+///     }
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let class = ruby.define_class("Point", ruby.class_object())?;
+///     class.define_singleton_method("new", function!(Point::new, 2))?;
+///     class.define_method("hash", method!(Point::hash, 0))?;
+///     class.define_method("eql?", method!(<Point as typed_data::IsEql>::is_eql, 1))?;
+///
+///     let hash = ruby.hash_new();
+///     hash.aset(Point::new(1, 2), "test value")?;
+///     assert_eq!("test value", hash.fetch::<_, String>(Point::new(1, 2))?);
+///     assert!(hash.get(Point::new(2, 1)).is_none());
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[derive(Clone, Debug)]
+pub struct RubyHasher {
+    state: st_index_t,
+}
+
+impl RubyHasher {
+    fn new(_ruby: &Ruby) -> Self {
+        Self {
+            state: unsafe { rb_hash_start(0) },
+        }
+    }
+
+    /// Returns the hash value for the values written so far, as an `i64`
+    /// suitable for returning from a Ruby `#hash` method.
+    pub fn finish_i64(&self) -> i64 {
+        self.finish() as i64
+    }
+}
+
+impl Hasher for RubyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        unsafe {
+            let hash = rb_memhash(bytes.as_ptr() as *const c_void, bytes.len() as c_long);
+            self.state = rb_hash_uint(self.state, hash);
+        }
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.state = unsafe { rb_hash_uint(self.state, i as st_index_t) };
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn finish(&self) -> u64 {
+        unsafe { rb_hash_end(self.state) as u64 }
+    }
+}
+
 /// Trait for a Ruby-compatible `#eql?` method.
 ///
 /// Automatically implemented for any type implementing [`Eq`] and
@@ -1356,10 +1735,168 @@ where
     }
 }
 
+#[doc(hidden)]
+pub fn inspect_with_class<T>(rb_self: Obj<T>) -> String
+where
+    T: TypedData + fmt::Debug,
+{
+    format!("#<{} {:?}>", unsafe { rb_self.classname() }, &*rb_self)
+}
+
+#[doc(hidden)]
+pub fn to_s_display<T>(rb_self: &T) -> String
+where
+    T: fmt::Display,
+{
+    rb_self.to_string()
+}
+
+/// Trait for a Ruby-compatible `#pretty_print` method, used by `pp`.
+///
+/// Wrapped objects are printed like Ruby Structs, with the class name
+/// followed by each field returned from `pretty_print_fields`.
+///
+/// The `#pretty_print` method can be defined automatically with the
+/// `pretty_print` attribute of [`wrap`](macro@crate::wrap) or
+/// [`TypedData`](derive@crate::TypedData).
+///
+/// See also [`Inspect`].
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{function, method, prelude::*, typed_data, Error, IntoValue, Ruby, Value};
+///
+/// #[magnus::wrap(class = "Point", free_immediately)]
+/// struct Point {
+///     x: isize,
+///     y: isize,
+/// }
+///
+/// impl Point {
+///     fn new(x: isize, y: isize) -> Self {
+///         Self { x, y }
+///     }
+/// }
+///
+/// impl typed_data::PrettyPrint for Point {
+///     fn pretty_print_fields(&self, ruby: &Ruby) -> Vec<(&'static str, Value)> {
+///         vec![
+///             ("x", self.x.into_value_with(ruby)),
+///             ("y", self.y.into_value_with(ruby)),
+///         ]
+///     }
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     ruby.require("pp")?;
+///     let class = ruby.define_class("Point", ruby.class_object())?;
+///     class.define_singleton_method("new", function!(Point::new, 2))?;
+///     class.define_method(
+///         "pretty_print",
+///         method!(typed_data::pretty_print::<Point>, 1),
+///     )?;
+///
+///     let s: String = ruby.eval("Point.new(1, 2).pretty_inspect")?;
+///     assert_eq!(s, "#<Point x=1, y=2>\n");
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub trait PrettyPrint {
+    /// Returns the field names and values to print for `self`.
+    fn pretty_print_fields(&self, ruby: &Ruby) -> Vec<(&'static str, Value)>;
+}
+
+/// Implementation of Ruby's `#pretty_print` for a type implementing
+/// [`PrettyPrint`].
+///
+/// `pp` is the `PP` pretty printer passed to the method.
+///
+/// See [`PrettyPrint`] for an example.
+pub fn pretty_print<T>(ruby: &Ruby, rb_self: Obj<T>, pp: Value) -> Result<(), Error>
+where
+    T: TypedData + PrettyPrint,
+{
+    static TEXT: LazyId = LazyId::new("text");
+    static SEPLIST: LazyId = LazyId::new("seplist");
+    static BREAKABLE: LazyId = LazyId::new("breakable");
+    static GROUP: LazyId = LazyId::new("group");
+    static PP: LazyId = LazyId::new("pp");
+
+    let fields = ruby.ary_new();
+    for (name, val) in rb_self.pretty_print_fields(ruby) {
+        fields.push((name, val))?;
+    }
+    let open = format!("#<{}", unsafe { rb_self.classname() });
+    let pp = Opaque::from(pp);
+    let fields_opaque = Opaque::from(fields);
+
+    let body = ruby.proc_from_fn(move |ruby, _args, _block| {
+        let pp = ruby.get_inner(pp);
+        let sep = ruby.proc_from_fn(move |ruby, _args, _block| {
+            ruby.get_inner(pp).funcall::<_, _, Value>(*TEXT, (",",))
+        });
+        pp.funcall_with_block::<_, _, Value>(
+            *SEPLIST,
+            (ruby.get_inner(fields_opaque), sep),
+            ruby.proc_from_fn(move |ruby, args, _block| {
+                let pp = ruby.get_inner(pp);
+                let (name, val) = <(String, Value)>::try_convert(args[0])?;
+                let val = Opaque::from(val);
+                pp.funcall::<_, _, Value>(*BREAKABLE, ())?;
+                pp.funcall::<_, _, Value>(*TEXT, (name,))?;
+                pp.funcall::<_, _, Value>(*TEXT, ("=",))?;
+                pp.funcall_with_block::<_, _, Value>(
+                    *GROUP,
+                    (1,),
+                    ruby.proc_from_fn(move |ruby, _args, _block| {
+                        let pp = ruby.get_inner(pp);
+                        pp.funcall::<_, _, Value>(*BREAKABLE, ("",))?;
+                        pp.funcall::<_, _, Value>(*PP, (ruby.get_inner(val),))
+                    }),
+                )
+            }),
+        )
+    });
+    ruby.get_inner(pp)
+        .funcall_with_block::<_, _, Value>(*GROUP, (1, open, ">"), body)?;
+    // keep `fields` on the stack, and so visible to the GC, until done
+    let _ = fields.len();
+    Ok(())
+}
+
+#[cfg(feature = "marshal")]
+#[doc(hidden)]
+pub fn marshal_dump<T>(ruby: &Ruby, rb_self: &T, _level: i64) -> Result<crate::RString, Error>
+where
+    T: serde::Serialize,
+{
+    rmp_serde::to_vec(rb_self)
+        .map(|bytes| ruby.str_from_slice(&bytes))
+        .map_err(|e| Error::new(ruby.exception_type_error(), e.to_string()))
+}
+
+#[cfg(feature = "marshal")]
+#[doc(hidden)]
+pub fn marshal_load<T>(ruby: &Ruby, data: crate::RString) -> Result<T, Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    rmp_serde::from_slice(unsafe { data.as_slice() })
+        .map_err(|e| Error::new(ruby.exception_arg_error(), e.to_string()))
+}
+
 /// Trait for a Ruby-compatible `#dup` and `#clone` methods.
 ///
 /// Automatically implemented for any type implementing [`Clone`].
 ///
+/// As the allocator is undefined for wrapped types Ruby's default `#dup` and
+/// `#clone` can not copy them. These methods can be defined automatically
+/// with the `dup` attribute of [`wrap`](macro@crate::wrap) or
+/// [`TypedData`](derive@crate::TypedData).
+///
 /// See also [`Inspect`], [`IsEql`], [`typed_data::Cmp`](Cmp), and
 /// [`typed_data::Hash`](Hash).
 ///
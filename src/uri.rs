@@ -0,0 +1,104 @@
+use url::Url;
+
+use crate::{
+    class::RClass,
+    error::Error,
+    module::{Module, RModule},
+    r_string::RString,
+    try_convert::{TryConvert, TryConvertOwned},
+    value::{LazyId, ReprValue, Value},
+    Ruby,
+};
+
+static PARSE: LazyId = LazyId::new("parse");
+
+/// # `URI`
+///
+/// Functions for working with Ruby's `URI` module.
+///
+/// Rust's [`Url`] can be converted from `URI` objects or Strings, and
+/// [`Ruby::uri_new`] creates a `URI` from a [`Url`].
+impl Ruby {
+    /// Return Ruby's `URI` module, requiring `"uri"` if it hasn't already
+    /// been loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    /// use url::Url;
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     rb_assert!(ruby, "uri == URI", uri = ruby.module_uri()?);
+    ///
+    ///     let url: Url = ruby.eval(r#"URI("http://example.com/a")"#)?;
+    ///     assert_eq!(url.path(), "/a");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "url")))]
+    pub fn module_uri(&self) -> Result<RModule, Error> {
+        if let Ok(module) = self.class_object().const_get("URI") {
+            return Ok(module);
+        }
+        self.require("uri")?;
+        self.class_object().const_get("URI")
+    }
+
+    /// Create a new Ruby `URI` from `url`, requiring `"uri"` if it hasn't
+    /// already been loaded.
+    ///
+    /// Returns `Err` if the `uri` library can't be loaded, or it fails to
+    /// parse `url`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{rb_assert, Error, Ruby};
+    /// use url::Url;
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let url = Url::parse("https://example.com/path?q=1").unwrap();
+    ///     let uri = ruby.uri_new(&url)?;
+    ///     rb_assert!(ruby, r#"uri.is_a?(URI::HTTPS) && uri.host == "example.com""#, uri);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "url")))]
+    pub fn uri_new(&self, url: &Url) -> Result<Value, Error> {
+        self.module_uri()?.funcall(*PARSE, (url.as_str(),))
+    }
+}
+
+impl TryConvert for Url {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        let ruby = Ruby::get_with(val);
+        let s = match RString::from_value(val) {
+            Some(s) => s,
+            None => {
+                let is_uri = ruby
+                    .class_object()
+                    .const_get::<_, RModule>("URI")
+                    .and_then(|uri| uri.const_get::<_, RClass>("Generic"))
+                    .map_or(false, |class| val.is_kind_of(class));
+                if !is_uri {
+                    return Err(Error::new(
+                        ruby.exception_type_error(),
+                        format!("no implicit conversion of {} into URI", unsafe {
+                            val.classname()
+                        }),
+                    ));
+                }
+                val.to_r_string()?
+            }
+        };
+        Url::parse(&s.to_string()?)
+            .map_err(|e| Error::new(ruby.exception_arg_error(), format!("invalid URL: {}", e)))
+    }
+}
+
+unsafe impl TryConvertOwned for Url {}
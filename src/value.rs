@@ -1,12 +1,18 @@
 //! Types for working with Ruby's VALUE type, representing all objects, and
 //! 'immediate' values such as Fixnum.
 
+mod classify;
+mod defer_drop;
 #[cfg(ruby_use_flonum)]
 mod flonum;
+mod method_cache;
+mod owned;
+mod tracked;
 
 use std::{
     borrow::{Borrow, Cow},
     cell::UnsafeCell,
+    cmp::Ordering,
     ffi::CStr,
     fmt,
     hash::{Hash, Hasher},
@@ -19,16 +25,22 @@ use std::{
     sync::Once,
 };
 
+pub use classify::ValueKind;
+pub use defer_drop::DeferDrop;
 #[cfg(ruby_use_flonum)]
 pub use flonum::Flonum;
+pub use method_cache::{MethodCache, MethodIds};
+pub use owned::{BigInteger, OwnedValue, ParseBigIntegerError};
 use rb_sys::{
-    rb_any_to_s, rb_block_call_kw, rb_check_funcall_kw, rb_check_id, rb_check_id_cstr,
-    rb_check_symbol_cstr, rb_enumeratorize_with_size_kw, rb_eql, rb_equal,
-    rb_funcall_with_block_kw, rb_funcallv_kw, rb_funcallv_public_kw, rb_gc_register_address,
-    rb_gc_unregister_address, rb_hash, rb_id2name, rb_id2sym, rb_inspect, rb_intern3, rb_ll2inum,
-    rb_obj_as_string, rb_obj_classname, rb_obj_freeze, rb_obj_is_kind_of, rb_obj_respond_to,
-    rb_sym2id, rb_ull2inum, ruby_fl_type, ruby_special_consts, ruby_value_type, RBasic, ID, VALUE,
+    rb_any_to_s, rb_ary_dup, rb_block_call_kw, rb_check_funcall_kw, rb_check_id, rb_check_id_cstr,
+    rb_check_symbol_cstr, rb_cmpint, rb_enumeratorize_with_size_kw, rb_eql, rb_equal,
+    rb_funcall_with_block_kw, rb_funcallv, rb_funcallv_kw, rb_funcallv_public_kw,
+    rb_gc_register_address, rb_gc_unregister_address, rb_hash, rb_hash_dup, rb_id2name, rb_id2sym,
+    rb_inspect, rb_intern3, rb_ll2inum, rb_obj_as_string, rb_obj_classname, rb_obj_freeze,
+    rb_obj_is_kind_of, rb_obj_respond_to, rb_str_new_frozen, rb_sym2id, rb_ull2inum, ruby_fl_type,
+    ruby_special_consts, ruby_value_type, RBasic, ID, VALUE,
 };
+pub use tracked::Tracked;
 
 // These don't seem to appear consistently in bindgen output, not sure if they
 // aren't consistently defined in the headers or what. Lets just do it
@@ -49,12 +61,33 @@ use crate::{
     module::Module,
     numeric::Numeric,
     r_bignum::RBignum,
+    r_hash::RHash,
     r_string::RString,
     symbol::{IntoSymbol, Symbol},
     try_convert::{TryConvert, TryConvertOwned},
     Ruby,
 };
 
+/// Compare `a` and `b` with `<=>`, erroring if they are not comparable.
+fn cmp_values(handle: &Ruby, a: Value, b: Value) -> Result<Ordering, Error> {
+    static SPACESHIP: LazyId = LazyId::new("<=>");
+    let id = (*SPACESHIP).into_id_with(handle);
+    let mut res = 0;
+    unsafe {
+        protect(|| {
+            let cmp = rb_funcallv(
+                a.as_rb_value(),
+                id.as_rb_id(),
+                1,
+                &b.as_rb_value() as *const VALUE,
+            );
+            res = rb_cmpint(cmp, a.as_rb_value(), b.as_rb_value());
+            handle.qnil()
+        })?;
+    }
+    Ok(res.cmp(&0))
+}
+
 /// Ruby's `VALUE` type, which can represent any Ruby object.
 ///
 /// Methods for `Value` are implemented on the [`ReprValue`] trait, which is
@@ -653,7 +686,7 @@ pub(crate) mod private {
 
         #[inline]
         unsafe fn r_basic_unchecked(self) -> ptr::NonNull<RBasic> {
-            #[cfg(debug_assertions)]
+            #[cfg(any(debug_assertions, feature = "sanitize"))]
             if self.is_immediate() {
                 panic!("attempting to access immediate value as pointer");
             }
@@ -957,6 +990,89 @@ pub trait ReprValue: private::ReprValue {
         unsafe { protect(|| Integer::from_rb_value_unchecked(rb_hash(self.as_rb_value()))) }
     }
 
+    /// Returns whether `self` is between `min` and `max` (inclusive), as
+    /// compared by `<=>`.
+    ///
+    /// This matches the behaviour of Ruby's `Comparable#between?`, but does
+    /// not require `self` to include `Comparable`.
+    ///
+    /// Returns `Err` if the comparison fails, or `<=>` raises an exception.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let val = ruby.integer_from_i64(5);
+    ///     assert!(val.between(1, 10)?);
+    ///     assert!(val.between(5, 5)?);
+    ///     assert!(!val.between(6, 10)?);
+    ///     assert!(val.between("a", "b").is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn between<T, U>(self, min: T, max: U) -> Result<bool, Error>
+    where
+        T: IntoValue,
+        U: IntoValue,
+    {
+        let handle = Ruby::get_with(self);
+        let val = self.as_value();
+        Ok(cmp_values(&handle, val, handle.into_value(min))?.is_ge()
+            && cmp_values(&handle, val, handle.into_value(max))?.is_le())
+    }
+
+    /// Returns `min` if `self` is less than `min`, `max` if `self` is greater
+    /// than `max`, or `self` otherwise, as compared by `<=>`.
+    ///
+    /// This matches the behaviour of Ruby's `Comparable#clamp`, but does not
+    /// require `self` to include `Comparable`.
+    ///
+    /// Returns `Err` if `min` is greater than `max`, the comparison fails, or
+    /// `<=>` raises an exception.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, Error, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let val = ruby.integer_from_i64(15);
+    ///     assert_eq!(i64::try_convert(val.clamp(1, 10)?)?, 10);
+    ///     assert_eq!(i64::try_convert(val.clamp(1, 20)?)?, 15);
+    ///     assert!(val.clamp(10, 1).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn clamp<T, U>(self, min: T, max: U) -> Result<Value, Error>
+    where
+        T: IntoValue,
+        U: IntoValue,
+    {
+        let handle = Ruby::get_with(self);
+        let val = self.as_value();
+        let min = handle.into_value(min);
+        let max = handle.into_value(max);
+        if cmp_values(&handle, min, max)?.is_gt() {
+            return Err(Error::new(
+                handle.exception_arg_error(),
+                "min argument must be less than or equal to max argument",
+            ));
+        }
+        if cmp_values(&handle, val, min)?.is_lt() {
+            return Ok(min);
+        }
+        if cmp_values(&handle, val, max)?.is_gt() {
+            return Ok(max);
+        }
+        Ok(val)
+    }
+
     /// Returns the class that `self` is an instance of.
     ///
     /// # Panics
@@ -1184,6 +1300,76 @@ pub trait ReprValue: private::ReprValue {
         }
     }
 
+    /// Call the method named `method` on `self` with `args`, passing `kwargs`
+    /// as keyword arguments.
+    ///
+    /// This is equivalent to calling [`funcall`](Value::funcall) with a
+    /// [`KwArgs`](crate::KwArgs) as the last element of the arguments tuple,
+    /// but allows the keyword arguments to be passed as an existing
+    /// [`RHash`] alongside any [`ArgList`], such as a slice. `args` should
+    /// only contain positional arguments.
+    ///
+    /// Returns `Ok(T)` if the method returns without error and the return
+    /// value converts to a `T`, or returns `Err` if the method raises or the
+    /// conversion fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{eval, prelude::*, Error, RObject, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let object: RObject = eval!(
+    ///         ruby,
+    ///         r#"
+    ///           class Adder
+    ///             def add(a, b, c:, d: 0)
+    ///               a + b + c + d
+    ///             end
+    ///           end
+    ///
+    ///           Adder.new
+    ///         "#
+    ///     )?;
+    ///
+    ///     let kwargs = ruby.hash_new();
+    ///     kwargs.aset(ruby.to_symbol("c"), 3)?;
+    ///     kwargs.aset(ruby.to_symbol("d"), 4)?;
+    ///     let result: i32 = object.funcall_with_kwargs("add", (1, 2), kwargs)?;
+    ///     assert_eq!(result, 10);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn funcall_with_kwargs<M, A, T>(self, method: M, args: A, kwargs: RHash) -> Result<T, Error>
+    where
+        M: IntoId,
+        A: ArgList,
+        T: TryConvert,
+    {
+        let handle = Ruby::get_with(self);
+        let id = method.into_id_with(&handle);
+        let args = args.into_arg_list_with(&handle);
+        let args = args.as_ref();
+        let ary = handle.ary_new_capa(args.len() + 1);
+        ary.cat(args)?;
+        ary.push(kwargs)?;
+        unsafe {
+            let slice = ary.as_slice();
+            protect(|| {
+                Value::new(rb_funcallv_kw(
+                    self.as_rb_value(),
+                    id.as_rb_id(),
+                    slice.len() as c_int,
+                    slice.as_ptr() as *const VALUE,
+                    rb_sys::RB_PASS_KEYWORDS as c_int,
+                ))
+            })
+            .and_then(TryConvert::try_convert)
+        }
+    }
+
     /// Call the public method named `method` on `self` with `args`.
     ///
     /// Returns `Ok(T)` if the method returns without error and the return
@@ -1665,6 +1851,168 @@ pub trait ReprValue: private::ReprValue {
             ))
         }
     }
+
+    /// Generate an [`Enumerator`] from `method` on `self`, passing `args` to
+    /// `method`, with `size` used to lazily calculate the size of the
+    /// enumerator.
+    ///
+    /// `size` is called with `args` when `Enumerator#size` is called, and
+    /// should return the number of values the enumerator will yield, or `nil`
+    /// if that can't be calculated without iterating.
+    ///
+    /// The enumerator is created by calling `enum_for` on `self`, so this will
+    /// return `Err` if `self` doesn't respond to `enum_for` (e.g. a
+    /// `BasicObject`) or if `enum_for` has been overridden and raises.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{block::Yield, method, prelude::*, rb_assert, Error, Ruby, Value};
+    ///
+    /// fn count_to(
+    ///     ruby: &Ruby,
+    ///     rb_self: Value,
+    ///     n: u64,
+    /// ) -> Result<Yield<impl Iterator<Item = u64>>, Error> {
+    ///     if ruby.block_given() {
+    ///         Ok(Yield::Iter(1..=n))
+    ///     } else {
+    ///         let enumerator = rb_self.enumeratorize_with_size("count_to", (n,), |_ruby, args| {
+    ///             u64::try_convert(args[0])
+    ///         })?;
+    ///         Ok(Yield::Enumerator(enumerator))
+    ///     }
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     ruby.define_global_function("count_to", method!(count_to, 1));
+    ///
+    ///     rb_assert!(ruby, "count_to(1_000_000_000).size == 1_000_000_000");
+    ///     rb_assert!(ruby, "count_to(3).to_a == [1, 2, 3]");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn enumeratorize_with_size<M, A, F, R>(
+        self,
+        method: M,
+        args: A,
+        size: F,
+    ) -> Result<Enumerator, Error>
+    where
+        M: IntoSymbol,
+        A: ArgList,
+        F: 'static + Send + FnMut(&Ruby, &[Value]) -> R,
+        R: BlockReturn,
+    {
+        static ENUM_FOR: LazyId = LazyId::new("enum_for");
+
+        let handle = Ruby::get_with(self);
+        let kw_splat = kw_splat(&args);
+        let args = args.into_arg_list_with(&handle);
+        let mut argv = Vec::with_capacity(args.as_ref().len() + 1);
+        argv.push(method.into_symbol_with(&handle).as_value());
+        argv.extend_from_slice(args.as_ref());
+        let mut size = size;
+        let block = handle.proc_from_fn(move |ruby, args, _block| size(ruby, args));
+        unsafe {
+            protect(|| {
+                Value::new(rb_funcall_with_block_kw(
+                    self.as_rb_value(),
+                    ENUM_FOR.as_rb_id(),
+                    argv.len() as c_int,
+                    argv.as_ptr() as *const VALUE,
+                    block.as_rb_value(),
+                    kw_splat as c_int,
+                ))
+            })
+            .map(|val| Enumerator::from_rb_value_unchecked(val.as_rb_value()))
+        }
+    }
+
+    /// Protect `self` from garbage collection and compaction for as long as
+    /// the returned [`Pinned`] guard is alive.
+    ///
+    /// While pinned the object will not be freed, and will not be moved by
+    /// `GC.compact`, so its raw `VALUE` can be stored in Rust data structures
+    /// that the garbage collector can not see. It is unregistered when the
+    /// guard is dropped.
+    ///
+    /// Unlike [`gc::register_mark_object`] this does not leak the object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use magnus::{prelude::*, value::Pinned, Error, RString, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let mut cache: HashMap<&str, Pinned<RString>> = HashMap::new();
+    ///     cache.insert("greeting", ruby.str_new("hello").pin());
+    ///
+    ///     ruby.gc_start();
+    ///
+    ///     assert_eq!(cache["greeting"].to_string()?, "hello");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn pin(self) -> Pinned<Self> {
+        Pinned::new(self)
+    }
+
+    /// Returns a frozen copy of `self` if it is a mutable String, Array, or
+    /// Hash, otherwise returns `self`.
+    ///
+    /// This is useful to take a snapshot of an argument that will be retained,
+    /// so that later mutation by the caller does not affect it. Already frozen
+    /// values are returned as-is, without copying.
+    ///
+    /// The copy is shallow, the elements of an Array or Hash are not copied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{prelude::*, rb_assert, Error, RArray, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let ary: RArray = ruby.eval("[1, 2]")?;
+    ///     let copy = ary.defensive_copy();
+    ///     ary.push(3)?;
+    ///
+    ///     assert!(copy.is_frozen());
+    ///     rb_assert!(ruby, "copy == [1, 2]", copy);
+    ///
+    ///     let frozen: RArray = ruby.eval("[1, 2].freeze")?;
+    ///     assert!(frozen.defensive_copy().equal(frozen)?);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    fn defensive_copy(self) -> Self {
+        if self.is_frozen() {
+            return self;
+        }
+        let val = self.as_rb_value();
+        let copy = unsafe {
+            match self.rb_type() {
+                ruby_value_type::RUBY_T_STRING => {
+                    return Self::from_value_unchecked(Value::new(rb_str_new_frozen(val)))
+                }
+                ruby_value_type::RUBY_T_ARRAY => rb_ary_dup(val),
+                ruby_value_type::RUBY_T_HASH => rb_hash_dup(val),
+                _ => return self,
+            }
+        };
+        unsafe {
+            rb_obj_freeze(copy);
+            Self::from_value_unchecked(Value::new(copy))
+        }
+    }
 }
 
 unsafe impl private::ReprValue for Value {}
@@ -1739,6 +2087,44 @@ where
         unsafe { rb_gc_register_address(boxed.as_mut() as *mut _ as *mut VALUE) };
         Self(boxed)
     }
+
+    /// Return a copy of the boxed value.
+    ///
+    /// The returned value is only guaranteed to remain valid while `self` is
+    /// alive, or while it is otherwise reachable by Ruby.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{value::BoxValue, Error, RString, Ruby};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let mut boxed = BoxValue::new(ruby.str_new("foo"));
+    ///     assert_eq!(boxed.get().to_string()?, "foo");
+    ///
+    ///     // the box can be updated in place, the new value is protected
+    ///     *boxed = ruby.str_new("bar");
+    ///     let s: RString = boxed.get();
+    ///     assert_eq!(s.to_string()?, "bar");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    #[inline]
+    pub fn get(&self) -> T {
+        *self.0
+    }
+}
+
+impl<T> Clone for BoxValue<T>
+where
+    T: ReprValue,
+{
+    /// Returns a new `BoxValue` protecting the same Ruby object.
+    fn clone(&self) -> Self {
+        Self::new(self.get())
+    }
 }
 
 impl<T> Drop for BoxValue<T> {
@@ -1809,6 +2195,74 @@ where
 
 unsafe impl<T> IntoValueFromNative for BoxValue<T> where T: ReprValue {}
 
+/// A guard keeping a Ruby object alive and in place.
+///
+/// Created with [`ReprValue::pin`]. The object is registered with the garbage
+/// collector as a root, which marks it without allowing it to be moved by
+/// compaction, and unregistered when the `Pinned` is dropped.
+///
+/// `Pinned<T>` dereferences to `T`, and [`Pinned::get`] returns a copy of the
+/// pinned value.
+pub struct Pinned<T>(BoxValue<T>);
+
+impl<T> Pinned<T>
+where
+    T: ReprValue,
+{
+    #[inline]
+    fn new(val: T) -> Self {
+        Self(BoxValue::new(val))
+    }
+
+    /// Return the pinned value.
+    ///
+    /// The returned value is only guaranteed to remain valid while `self` is
+    /// alive, or while it is otherwise reachable by Ruby.
+    #[inline]
+    pub fn get(&self) -> T {
+        self.0.get()
+    }
+}
+
+impl<T> Deref for Pinned<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> fmt::Display for Pinned<T>
+where
+    T: ReprValue,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<T> fmt::Debug for Pinned<T>
+where
+    T: ReprValue,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl<T> IntoValue for Pinned<T>
+where
+    T: ReprValue,
+{
+    #[inline]
+    fn into_value_with(self, _: &Ruby) -> Value {
+        self.get().as_value()
+    }
+}
+
+unsafe impl<T> IntoValueFromNative for Pinned<T> where T: ReprValue {}
+
 /// # `false`
 ///
 /// Get Ruby's `false` value.
@@ -0,0 +1,117 @@
+use crate::{
+    block::Proc,
+    class::RClass,
+    float::Float,
+    integer::Integer,
+    module::RModule,
+    r_array::RArray,
+    r_hash::RHash,
+    r_string::RString,
+    range::Range,
+    symbol::Symbol,
+    value::{Qfalse, Qnil, Qtrue, Value},
+};
+
+/// The type of a Ruby value, with the value converted to the matching Rust
+/// type.
+///
+/// See [`Value::classify`].
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum ValueKind {
+    /// `nil`.
+    Nil,
+    /// `true` or `false`.
+    Bool(bool),
+    /// An `Integer`, either a Fixnum or Bignum.
+    Integer(Integer),
+    /// A `Float`.
+    Float(f64),
+    /// A `String`.
+    String(RString),
+    /// A `Symbol`, either static or dynamic.
+    Symbol(Symbol),
+    /// An `Array`.
+    Array(RArray),
+    /// A `Hash`.
+    Hash(RHash),
+    /// A `Range`.
+    Range(Range),
+    /// A `Proc`, including lambdas.
+    Proc(Proc),
+    /// A `Class`.
+    Class(RClass),
+    /// A `Module` that is not a class.
+    Module(RModule),
+    /// Any other object.
+    Object(Value),
+}
+
+impl Value {
+    /// Classify `self` by its Ruby type, for use with `match`.
+    ///
+    /// Subclasses of core types, such as a subclass of `String`, are
+    /// classified as their core type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{value::ValueKind, Error, Ruby, Value};
+    ///
+    /// fn describe(val: Value) -> Result<String, Error> {
+    ///     Ok(match val.classify() {
+    ///         ValueKind::Nil => String::from("nothing"),
+    ///         ValueKind::Bool(b) => format!("the boolean {}", b),
+    ///         ValueKind::Integer(i) => format!("the integer {}", i),
+    ///         ValueKind::Float(f) => format!("the float {}", f),
+    ///         ValueKind::String(s) => format!("the string {:?}", s.to_string()?),
+    ///         ValueKind::Array(a) => format!("an array of {} elements", a.len()),
+    ///         _ => String::from("something else"),
+    ///     })
+    /// }
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     assert_eq!(describe(ruby.eval("nil")?)?, "nothing");
+    ///     assert_eq!(describe(ruby.eval("false")?)?, "the boolean false");
+    ///     assert_eq!(describe(ruby.eval("2 ** 70")?)?, "the integer 1180591620717411303424");
+    ///     assert_eq!(describe(ruby.eval("1.5")?)?, "the float 1.5");
+    ///     assert_eq!(describe(ruby.eval("'hi'")?)?, r#"the string "hi""#);
+    ///     assert_eq!(describe(ruby.eval("[1, 2]")?)?, "an array of 2 elements");
+    ///     assert_eq!(describe(ruby.eval("Object.new")?)?, "something else");
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn classify(self) -> ValueKind {
+        if Qnil::from_value(self).is_some() {
+            ValueKind::Nil
+        } else if Qtrue::from_value(self).is_some() {
+            ValueKind::Bool(true)
+        } else if Qfalse::from_value(self).is_some() {
+            ValueKind::Bool(false)
+        } else if let Some(i) = Integer::from_value(self) {
+            ValueKind::Integer(i)
+        } else if let Some(f) = Float::from_value(self) {
+            ValueKind::Float(f.to_f64())
+        } else if let Some(s) = RString::from_value(self) {
+            ValueKind::String(s)
+        } else if let Some(s) = Symbol::from_value(self) {
+            ValueKind::Symbol(s)
+        } else if let Some(a) = RArray::from_value(self) {
+            ValueKind::Array(a)
+        } else if let Some(h) = RHash::from_value(self) {
+            ValueKind::Hash(h)
+        } else if let Some(r) = Range::from_value(self) {
+            ValueKind::Range(r)
+        } else if let Some(p) = Proc::from_value(self) {
+            ValueKind::Proc(p)
+        } else if let Some(c) = RClass::from_value(self) {
+            ValueKind::Class(c)
+        } else if let Some(m) = RModule::from_value(self) {
+            ValueKind::Module(m)
+        } else {
+            ValueKind::Object(self)
+        }
+    }
+}
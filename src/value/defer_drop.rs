@@ -0,0 +1,117 @@
+use std::{fmt, mem::forget};
+
+use crate::{
+    block::callback::{start_executor, submit, Job},
+    Ruby,
+};
+
+/// A wrapper that ensures `T` is dropped on a Ruby thread.
+///
+/// Some types must only be dropped while holding the GVL, such as a struct
+/// that registers an [`Opaque`](crate::value::Opaque) value with the garbage
+/// collector and unregisters it on drop. Wrapping them in a `DeferDrop` makes
+/// them safe to drop on threads not managed by Ruby. If dropped on a non-Ruby
+/// thread the inner value is sent to a Ruby thread to be dropped.
+///
+/// The inner value can only be accessed on a Ruby thread. `DeferDrop<T>` is
+/// only [`Send`]/[`Sync`] when `T` is.
+///
+/// If Ruby is shutting down and the inner value can not be sent to a Ruby
+/// thread it will be leaked.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{
+///     gc,
+///     value::{DeferDrop, Opaque},
+///     Error, RString, Ruby,
+/// };
+///
+/// struct Registered(Box<Opaque<RString>>);
+///
+/// impl Registered {
+///     fn new(s: RString) -> Self {
+///         let s = Box::new(Opaque::from(s));
+///         gc::register_address(&*s);
+///         Self(s)
+///     }
+/// }
+///
+/// impl Drop for Registered {
+///     fn drop(&mut self) {
+///         gc::unregister_address(&*self.0);
+///     }
+/// }
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let value = DeferDrop::new(ruby, Registered::new(ruby.str_new("example")));
+///
+///     // the value is safely dropped on the Ruby thread, rather than
+///     // unregistering it without the GVL
+///     std::thread::spawn(move || drop(value)).join().unwrap();
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub struct DeferDrop<T: 'static>(Option<T>);
+
+// The inner value is only accessed, and dropped, on a Ruby thread.
+unsafe impl<T> Send for DeferDrop<T> where T: Send + 'static {}
+unsafe impl<T> Sync for DeferDrop<T> where T: Send + Sync + 'static {}
+
+struct AssertSend<T>(T);
+
+unsafe impl<T> Send for AssertSend<T> {}
+
+impl<T: 'static> DeferDrop<T> {
+    /// Wrap `val` so it will always be dropped on a Ruby thread.
+    ///
+    /// This starts a Ruby thread to drop values dropped on non-Ruby threads,
+    /// if it is not already running.
+    pub fn new(ruby: &Ruby, val: T) -> Self {
+        start_executor(ruby);
+        Self(Some(val))
+    }
+
+    /// Returns a reference to the inner value.
+    #[inline]
+    pub fn get(&self, _ruby: &Ruby) -> &T {
+        self.0.as_ref().unwrap()
+    }
+
+    /// Returns a mutable reference to the inner value.
+    #[inline]
+    pub fn get_mut(&mut self, _ruby: &Ruby) -> &mut T {
+        self.0.as_mut().unwrap()
+    }
+
+    /// Unwrap the inner value.
+    pub fn into_inner(mut self, _ruby: &Ruby) -> T {
+        self.0.take().unwrap()
+    }
+}
+
+impl<T: 'static> Drop for DeferDrop<T> {
+    fn drop(&mut self) {
+        let value = match self.0.take() {
+            Some(v) => v,
+            None => return,
+        };
+        if Ruby::get().is_ok() {
+            return drop(value);
+        }
+        let value = AssertSend(value);
+        let job: Job = Box::new(move |_| drop(value));
+        if let Err(job) = submit(job) {
+            forget(job);
+        }
+    }
+}
+
+impl<T: 'static> fmt::Debug for DeferDrop<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeferDrop").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,218 @@
+use std::{cell::UnsafeCell, fmt, mem::MaybeUninit, ops::Deref, sync::Once};
+
+use crate::{
+    error::Error,
+    into_value::ArgList,
+    try_convert::TryConvert,
+    value::{OpaqueId, ReprValue},
+    Ruby,
+};
+
+/// A set of method names interned as a group, for use with [`MethodCache`].
+///
+/// This trait should not be implemented directly, instead use the
+/// [`method_ids`](crate::method_ids!) macro.
+pub trait MethodIds: Sized {
+    #[doc(hidden)]
+    fn intern(ruby: &Ruby) -> Self;
+}
+
+/// Declare a struct of [`OpaqueId`]s, for use with [`MethodCache`].
+///
+/// Each field is named after the method it identifies. Method names that
+/// aren't valid Rust identifiers can be given with `field = "name"`.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{method_ids, value::MethodCache, Error, Ruby};
+///
+/// method_ids! {
+///     struct StringMethods {
+///         upcase,
+///         is_empty = "empty?",
+///     }
+/// }
+///
+/// static STRING: MethodCache<StringMethods> = MethodCache::new();
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let s = ruby.str_new("example");
+///     let upper: String = STRING.call(s, STRING.upcase, ())?;
+///     assert_eq!(upper, "EXAMPLE");
+///     let empty: bool = STRING.call(s, STRING.is_empty, ())?;
+///     assert!(!empty);
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+#[macro_export]
+macro_rules! method_ids {
+    (@name $field:ident $method:literal) => {
+        $method
+    };
+    (@name $field:ident) => {
+        stringify!($field)
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($field:ident $(= $method:literal)?),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug)]
+        $vis struct $name {
+            $(
+                #[allow(missing_docs)]
+                pub $field: $crate::value::OpaqueId,
+            )*
+        }
+
+        impl $crate::value::MethodIds for $name {
+            fn intern(ruby: &$crate::Ruby) -> Self {
+                Self {
+                    $(
+                        $field: ruby
+                            .intern($crate::method_ids!(@name $field $($method)?))
+                            .into(),
+                    )*
+                }
+            }
+        }
+    };
+}
+
+/// A group of method [`Id`](crate::value::Id)s that can be assigned to a
+/// `static` and [`Deref`]s to the struct of ids.
+///
+/// This is for wrappers that repeatedly call the same few Ruby methods. All
+/// the method names are interned together on first use, so each call avoids
+/// looking up the method name.
+///
+/// The ids will be lazily initialised when the `MethodCache` is first used.
+/// This initialisation must happen on a Ruby thread. If the first use is from
+/// a non-Ruby thread the `MethodCache` will panic and then become *poisoned*
+/// and all future use of it will panic.
+///
+/// See the [`method_ids`](crate::method_ids!) macro for an example.
+pub struct MethodCache<T> {
+    init: Once,
+    ids: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> MethodCache<T> {
+    /// Create a new `MethodCache`.
+    ///
+    /// This function can be called in a `const` context.
+    pub const fn new() -> Self {
+        Self {
+            init: Once::new(),
+            ids: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Get the struct of ids from a `MethodCache`, if it has already been
+    /// evaluated.
+    ///
+    /// This function will not call Ruby and will not initialise the ids. If
+    /// the `MethodCache` has not yet been initialised, returns `None`.
+    pub fn try_get_inner(this: &Self) -> Option<&T> {
+        this.init
+            .is_completed()
+            .then(|| unsafe { (*this.ids.get()).assume_init_ref() })
+    }
+}
+
+impl<T> MethodCache<T>
+where
+    T: MethodIds,
+{
+    /// Force evaluation of a `MethodCache`.
+    ///
+    /// This can be used in, for example, your [`init`](macro@crate::init)
+    /// function to force initialisation of the `MethodCache`, to ensure that
+    /// use of the `MethodCache` can't possibly panic.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `MethodCache` is *poisoned*. See [`MethodCache`].
+    #[inline]
+    pub fn force(this: &Self, handle: &Ruby) {
+        Self::get_inner_with(this, handle);
+    }
+
+    /// Get the struct of ids from a `MethodCache`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `MethodCache` is *poisoned*. See [`MethodCache`].
+    #[inline]
+    pub fn get_inner_with<'a>(this: &'a Self, handle: &Ruby) -> &'a T {
+        unsafe {
+            this.init.call_once(|| {
+                (*this.ids.get()).write(T::intern(handle));
+            });
+            (*this.ids.get()).assume_init_ref()
+        }
+    }
+
+    /// Call the method identified by `method` on `recv`, with the arguments
+    /// `args`.
+    ///
+    /// This is equivalent to [`ReprValue::funcall`], `method` is expected to
+    /// be one of the ids from this cache.
+    pub fn call<S, A, R>(&self, recv: S, method: OpaqueId, args: A) -> Result<R, Error>
+    where
+        S: ReprValue,
+        A: ArgList,
+        R: TryConvert,
+    {
+        recv.funcall(method, args)
+    }
+}
+
+impl<T> Default for MethodCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<T> Send for MethodCache<T> where T: Send {}
+unsafe impl<T> Sync for MethodCache<T> where T: Send + Sync {}
+
+impl<T> fmt::Debug for MethodCache<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[allow(non_camel_case_types)]
+        #[derive(Debug)]
+        struct uninit();
+
+        f.debug_tuple("MethodCache")
+            .field(
+                Self::try_get_inner(self)
+                    .map(|v| v as &dyn fmt::Debug)
+                    .unwrap_or(&uninit()),
+            )
+            .finish()
+    }
+}
+
+impl<T> Deref for MethodCache<T>
+where
+    T: MethodIds,
+{
+    type Target = T;
+
+    /// # Panics
+    ///
+    /// Panics if the first call is from a non-Ruby thread. This
+    /// `MethodCache` will then be *poisoned* and all future use of it will
+    /// panic.
+    fn deref(&self) -> &Self::Target {
+        Self::get_inner_with(self, &Ruby::get().unwrap())
+    }
+}
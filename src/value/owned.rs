@@ -0,0 +1,235 @@
+use std::{error, fmt, str::FromStr};
+
+use rb_sys::rb_str_to_inum;
+
+use crate::{
+    encoding::EncodingCapable,
+    error::Error,
+    into_value::{IntoValue, IntoValueFromNative},
+    r_hash::ForEach,
+    recursion::Nesting,
+    try_convert::{TryConvert, TryConvertOwned},
+    value::{private::ReprValue as _, ReprValue, Value, ValueKind},
+    Ruby,
+};
+
+/// A Rust-owned copy of a Ruby value.
+///
+/// Unlike Ruby objects an `OwnedValue` does not need to be protected from
+/// the garbage collector, and can be sent between threads and used without
+/// holding the GVL. It can be converted back to a Ruby object with
+/// [`IntoValue`].
+///
+/// See [`Value::to_owned_tree`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum OwnedValue {
+    /// `nil`.
+    Nil,
+    /// `true` or `false`.
+    Bool(bool),
+    /// An `Integer` that fits in an `i64`.
+    Integer(i64),
+    /// An `Integer` too large for an `i64`.
+    BigInteger(BigInteger),
+    /// A `Float`.
+    Float(f64),
+    /// A `String` with a text encoding, converted to UTF-8.
+    String(String),
+    /// A `String` with the ASCII-8BIT (aka binary) encoding.
+    Bytes(Vec<u8>),
+    /// A `Symbol`.
+    Symbol(String),
+    /// An `Array`.
+    Array(Vec<OwnedValue>),
+    /// A `Hash`, as a list of key/value pairs in the Hash's order.
+    Hash(Vec<(OwnedValue, OwnedValue)>),
+}
+
+impl Value {
+    /// Deeply copy `self` into an [`OwnedValue`].
+    ///
+    /// Only `nil`, booleans, Integers, Floats, Strings, Symbols, and Arrays
+    /// and Hashes of these types can be copied. Subclasses of these types are
+    /// copied as the core type.
+    ///
+    /// Returns `Err` if `self` is, or contains, any other type, a String that
+    /// can't be converted to UTF-8, or if it contains itself. Returns a
+    /// `SystemStackError` if it is nested too deeply to copy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use magnus::{value::OwnedValue, Error, Ruby, Value};
+    ///
+    /// fn example(ruby: &Ruby) -> Result<(), Error> {
+    ///     let val: Value = ruby.eval(r#"{name: "example", sizes: [1, 2.5]}"#)?;
+    ///     let owned = val.to_owned_tree()?;
+    ///
+    ///     // process on another thread, without the GVL
+    ///     let owned = std::thread::spawn(move || match owned {
+    ///         OwnedValue::Hash(mut pairs) => {
+    ///             pairs.retain(|(k, _)| *k != OwnedValue::Symbol(String::from("sizes")));
+    ///             OwnedValue::Hash(pairs)
+    ///         }
+    ///         other => other,
+    ///     })
+    ///     .join()
+    ///     .unwrap();
+    ///
+    ///     let res: bool = magnus::eval!(ruby, r#"val == {name: "example"}"#, val = owned)?;
+    ///     assert!(res);
+    ///
+    ///     Ok(())
+    /// }
+    /// # Ruby::init(example).unwrap()
+    /// ```
+    pub fn to_owned_tree(self) -> Result<OwnedValue, Error> {
+        to_owned_tree(self, &Nesting::new(None))
+    }
+}
+
+fn to_owned_tree(val: Value, nesting: &Nesting) -> Result<OwnedValue, Error> {
+    let ruby = Ruby::get_with(val);
+    Ok(match val.classify() {
+        ValueKind::Nil => OwnedValue::Nil,
+        ValueKind::Bool(b) => OwnedValue::Bool(b),
+        ValueKind::Integer(i) => match i.to_i64() {
+            Ok(i) => OwnedValue::Integer(i),
+            Err(_) => OwnedValue::BigInteger(BigInteger(i.to_string())),
+        },
+        ValueKind::Float(f) => OwnedValue::Float(f),
+        ValueKind::String(s) => {
+            if s.enc_get() == ruby.ascii8bit_encindex() {
+                OwnedValue::Bytes(unsafe { s.as_slice() }.to_vec())
+            } else {
+                OwnedValue::String(s.to_string()?)
+            }
+        }
+        ValueKind::Symbol(s) => OwnedValue::Symbol(s.name()?.into_owned()),
+        ValueKind::Array(a) => {
+            let _guard = nesting.enter(val)?;
+            let mut items = Vec::with_capacity(a.len());
+            for i in 0..a.len() {
+                items.push(to_owned_tree(a.entry(i as isize)?, nesting)?);
+            }
+            OwnedValue::Array(items)
+        }
+        ValueKind::Hash(h) => {
+            let _guard = nesting.enter(val)?;
+            let mut pairs = Vec::with_capacity(h.len());
+            h.foreach(|k: Value, v: Value| {
+                pairs.push((to_owned_tree(k, nesting)?, to_owned_tree(v, nesting)?));
+                Ok(ForEach::Continue)
+            })?;
+            OwnedValue::Hash(pairs)
+        }
+        _ => {
+            return Err(Error::new(
+                ruby.exception_type_error(),
+                format!("can't copy {} to an owned value", unsafe {
+                    val.classname()
+                }),
+            ))
+        }
+    })
+}
+
+/// An arbitrarily large integer, as a string of decimal digits.
+///
+/// Create with [`str::parse`], which checks the string is an optional `-`
+/// followed by one or more ASCII digits.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::value::BigInteger;
+///
+/// let i: BigInteger = "-1208925819614629174706176".parse().unwrap();
+/// assert_eq!(i.as_str(), "-1208925819614629174706176");
+///
+/// assert!("12e3".parse::<BigInteger>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BigInteger(String);
+
+impl BigInteger {
+    /// Return the decimal digits of `self`, with a leading `-` if negative.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for BigInteger {
+    type Err = ParseBigIntegerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigIntegerError(()));
+        }
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl fmt::Display for BigInteger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl IntoValue for BigInteger {
+    fn into_value_with(self, handle: &Ruby) -> Value {
+        let s = handle.str_new(&self.0);
+        // digits were checked on construction, and with badcheck off this
+        // doesn't raise
+        unsafe { Value::new(rb_str_to_inum(s.as_rb_value(), 10, 0)) }
+    }
+}
+
+unsafe impl IntoValueFromNative for BigInteger {}
+
+/// The error returned when parsing a [`BigInteger`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseBigIntegerError(());
+
+impl fmt::Display for ParseBigIntegerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid digit found in string")
+    }
+}
+
+impl error::Error for ParseBigIntegerError {}
+
+impl IntoValue for OwnedValue {
+    fn into_value_with(self, handle: &Ruby) -> Value {
+        match self {
+            Self::Nil => handle.qnil().as_value(),
+            Self::Bool(b) => b.into_value_with(handle),
+            Self::Integer(i) => i.into_value_with(handle),
+            Self::BigInteger(i) => i.into_value_with(handle),
+            Self::Float(f) => f.into_value_with(handle),
+            Self::String(s) => handle.str_new(&s).as_value(),
+            Self::Bytes(b) => handle.str_from_slice(&b).as_value(),
+            Self::Symbol(s) => handle.to_symbol(s).as_value(),
+            Self::Array(items) => handle.ary_from_iter(items).as_value(),
+            Self::Hash(pairs) => {
+                let hash = handle.hash_new_capa(pairs.len());
+                for (k, v) in pairs {
+                    // only fails if the hash is frozen, which it isn't
+                    hash.aset(k, v).unwrap();
+                }
+                hash.as_value()
+            }
+        }
+    }
+}
+
+unsafe impl IntoValueFromNative for OwnedValue {}
+
+impl TryConvert for OwnedValue {
+    fn try_convert(val: Value) -> Result<Self, Error> {
+        val.to_owned_tree()
+    }
+}
+
+unsafe impl TryConvertOwned for OwnedValue {}
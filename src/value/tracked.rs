@@ -0,0 +1,164 @@
+use std::{cell::Cell, collections::HashSet, fmt, marker::PhantomData, sync::Mutex, sync::Once};
+
+use rb_sys::rb_data_typed_object_wrap;
+
+use crate::{
+    data_type_builder, gc,
+    into_value::{IntoValue, IntoValueFromNative},
+    typed_data::{DataType, DataTypeFunctions},
+    value::{private::ReprValue as _, ReprValue, Value},
+    Ruby,
+};
+
+// Addresses of the slots of all live `Tracked` values.
+//
+// This is only accessed while holding the GVL, either from `Tracked` (which
+// is not `Send`) or from the GC. The lock is never held while calling Ruby,
+// so GC can't run, and try to take the lock, while it is held.
+static SLOTS: Mutex<Option<HashSet<usize>>> = Mutex::new(None);
+static ROOT: Once = Once::new();
+
+fn with_slots<F, R>(func: F) -> R
+where
+    F: FnOnce(&mut HashSet<usize>) -> R,
+{
+    let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+    func(slots.get_or_insert_with(HashSet::new))
+}
+
+// An object registered as a GC root that marks and updates all the slots.
+struct TrackedRoot;
+
+impl DataTypeFunctions for TrackedRoot {
+    fn mark(&self, marker: &gc::Marker) {
+        with_slots(|slots| {
+            for slot in slots.iter() {
+                marker.mark_movable(unsafe { &*(*slot as *const Cell<Value>) }.get());
+            }
+        });
+    }
+
+    fn compact(&self, compactor: &gc::Compactor) {
+        with_slots(|slots| {
+            for slot in slots.iter() {
+                let slot = unsafe { &*(*slot as *const Cell<Value>) };
+                slot.set(compactor.location(slot.get()));
+            }
+        });
+    }
+}
+
+static DATA_TYPE: DataType = data_type_builder!(TrackedRoot, "magnus tracked values")
+    .mark()
+    .compact()
+    .free_immediately()
+    .build();
+
+fn init_root() {
+    ROOT.call_once(|| unsafe {
+        let root = Value::new(rb_data_typed_object_wrap(
+            0, // using 0 for the class will hide the object from ObjectSpace
+            Box::into_raw(Box::new(TrackedRoot)) as *mut _,
+            DATA_TYPE.as_rb_data_type() as *const _,
+        ));
+        gc::register_mark_object(root);
+    });
+}
+
+/// A handle to a Ruby object that is kept alive and updated if the object is
+/// moved by GC compaction.
+///
+/// Unlike [`Pinned`](crate::value::Pinned), a `Tracked` object can still be
+/// moved by `GC.compact`, and the handle will be updated to point to the new
+/// location. This allows long-lived references to be held from Rust without
+/// fragmenting the heap.
+///
+/// As the object may move whenever GC runs, the value returned by
+/// [`Tracked::get`] should not be stored. Keep the `Tracked` and call `get`
+/// each time the object is needed.
+///
+/// # Examples
+///
+/// ```
+/// use magnus::{value::Tracked, Error, RString, Ruby};
+///
+/// fn example(ruby: &Ruby) -> Result<(), Error> {
+///     let tracked = Tracked::new(ruby.str_new("example"));
+///
+///     ruby.gc_start();
+///
+///     let s: RString = tracked.get();
+///     assert_eq!(s.to_string()?, "example");
+///
+///     Ok(())
+/// }
+/// # Ruby::init(example).unwrap()
+/// ```
+pub struct Tracked<T> {
+    slot: Box<Cell<Value>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Tracked<T>
+where
+    T: ReprValue,
+{
+    /// Create a new `Tracked` handle to `val`.
+    pub fn new(val: T) -> Self {
+        init_root();
+        let slot = Box::new(Cell::new(val.as_value()));
+        with_slots(|slots| slots.insert(&*slot as *const Cell<Value> as usize));
+        Self {
+            slot,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Return the tracked object at its current location.
+    #[inline]
+    pub fn get(&self) -> T {
+        unsafe { T::from_value_unchecked(self.slot.get()) }
+    }
+
+    /// Replace the tracked object with `val`.
+    #[inline]
+    pub fn set(&self, val: T) {
+        self.slot.set(val.as_value());
+    }
+}
+
+impl<T> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        with_slots(|slots| slots.remove(&(&*self.slot as *const Cell<Value> as usize)));
+    }
+}
+
+impl<T> fmt::Display for Tracked<T>
+where
+    T: ReprValue,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", unsafe { self.get().as_value().to_s_infallible() })
+    }
+}
+
+impl<T> fmt::Debug for Tracked<T>
+where
+    T: ReprValue,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get().as_value().inspect())
+    }
+}
+
+impl<T> IntoValue for Tracked<T>
+where
+    T: ReprValue,
+{
+    #[inline]
+    fn into_value_with(self, _: &Ruby) -> Value {
+        self.get().as_value()
+    }
+}
+
+unsafe impl<T> IntoValueFromNative for Tracked<T> where T: ReprValue {}
@@ -0,0 +1,45 @@
+use magnus::{alloc_trace, prelude::*, TryConvert, Value};
+
+#[magnus::wrap(class = "Point", free_immediately)]
+#[allow(dead_code)]
+struct Point {
+    x: isize,
+    y: isize,
+}
+
+#[test]
+fn it_traces_allocations() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.define_class("Point", ruby.class_object()).unwrap();
+    let _: Value = ruby.eval("class Thing; end").unwrap();
+
+    ruby.alloc_trace_enable();
+    for i in 0..10 {
+        let obj = ruby.wrap(Point { x: i, y: i });
+        let _ = <&Point>::try_convert(obj.as_value()).unwrap();
+    }
+    let _: Value = ruby.eval("5.times { Thing.new }").unwrap();
+    ruby.gc_start();
+    ruby.alloc_trace_disable();
+
+    let stats = alloc_trace::stats_for::<Point>().unwrap();
+    assert_eq!(stats.class, "Point");
+    assert_eq!(stats.wrapped, 10);
+    assert_eq!(stats.unwrapped, 10);
+    assert!(stats.freed <= stats.wrapped);
+
+    assert_eq!(alloc_trace::object_stats_for("Thing").unwrap().allocated, 5);
+    assert_eq!(
+        alloc_trace::object_stats_for("Point").unwrap().allocated,
+        10
+    );
+
+    // nothing is recorded once disabled
+    let _: Value = ruby.eval("Thing.new").unwrap();
+    assert_eq!(alloc_trace::object_stats_for("Thing").unwrap().allocated, 5);
+
+    alloc_trace::reset();
+    assert!(alloc_trace::stats_for::<Point>().is_none());
+    assert!(alloc_trace::object_stats().is_empty());
+}
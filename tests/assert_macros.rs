@@ -0,0 +1,19 @@
+use magnus::{assert_raises, assert_ruby_eq, Value};
+
+#[test]
+fn it_asserts_ruby_results() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    assert_ruby_eq!(ruby, "[1, 2, 3].sum", 6_i64);
+    assert_ruby_eq!(ruby, "a.upcase", String::from("FOO"), a = "foo");
+    assert_ruby_eq!(ruby, "[1, nil]", vec![Some(1_u8), None]);
+
+    assert_raises!(ruby.exception_name_error(), "undefined", || {
+        ruby.eval::<Value>("undefined_local_or_method")
+    });
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        assert_raises!(ruby.exception_type_error(), || ruby.eval::<Value>("1 / 0"));
+    }));
+    assert!(res.is_err());
+}
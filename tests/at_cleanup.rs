@@ -0,0 +1,26 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+#[test]
+fn it_runs_cleanup_hooks() {
+    let ran = Arc::new(AtomicUsize::new(0));
+
+    {
+        let ruby = unsafe { magnus::embed::init() };
+
+        let counter = ran.clone();
+        ruby.at_cleanup(move |ruby| {
+            // Ruby is still usable
+            let res: i64 = ruby.eval("1 + 2").unwrap();
+            counter.fetch_add(res as usize, Ordering::SeqCst);
+        });
+        // hooks run in reverse order, so this panics before the one above
+        ruby.at_cleanup(|_| panic!("cleanup hook panicked"));
+
+        assert_eq!(ran.load(Ordering::SeqCst), 0);
+    }
+
+    assert_eq!(ran.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,39 @@
+use magnus::{block::Proc, rb_assert};
+
+#[test]
+fn it_calls_callbacks_from_other_threads() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let proc: Proc = ruby
+        .eval("proc { |a| ($calls ||= []) << a; a * 2 }")
+        .unwrap();
+    let callback = proc.into_callback::<(i64,), i64>();
+
+    assert_eq!(callback.call_with(&ruby, (1,)).unwrap(), 2);
+
+    let handles = (2..5)
+        .map(|i| {
+            let callback = callback.clone();
+            std::thread::spawn(move || callback.call((i,)))
+        })
+        .collect::<Vec<_>>();
+    while !handles.iter().all(|h| h.is_finished()) {
+        ruby.thread_schedule();
+    }
+    let mut results = handles
+        .into_iter()
+        .map(|h| h.join().unwrap().unwrap())
+        .collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![4, 6, 8]);
+
+    let handle = std::thread::spawn(move || callback.call_async((5,)));
+    while !handle.is_finished() {
+        ruby.thread_schedule();
+    }
+    handle.join().unwrap().unwrap();
+    let _: bool = ruby
+        .eval("sleep 0.01 until $calls.length == 5; true")
+        .unwrap();
+    rb_assert!(ruby, "$calls.sort == [1, 2, 3, 4, 5]");
+}
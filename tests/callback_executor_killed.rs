@@ -0,0 +1,33 @@
+use magnus::{
+    block::{CallbackError, Proc},
+    Value,
+};
+
+#[test]
+fn it_stops_callbacks_when_the_executor_is_killed() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let proc: Proc = ruby.eval("proc { |a| a * 2 }").unwrap();
+    let callback = proc.into_callback::<(i64,), i64>();
+
+    let c = callback.clone();
+    let handle = std::thread::spawn(move || c.call((1,)));
+    while !handle.is_finished() {
+        ruby.thread_schedule();
+    }
+    assert_eq!(handle.join().unwrap().unwrap(), 2);
+
+    // the only thread other than main is the callback executor
+    let _: Value = ruby
+        .eval("Thread.list.reject { |t| t == Thread.main }.each(&:kill).each(&:join)")
+        .unwrap();
+
+    let handle = std::thread::spawn(move || callback.call((2,)));
+    while !handle.is_finished() {
+        ruby.thread_schedule();
+    }
+    assert_eq!(
+        handle.join().unwrap().unwrap_err(),
+        CallbackError::ExecutorStopped
+    );
+}
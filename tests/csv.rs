@@ -0,0 +1,33 @@
+use csv::{ByteRecord, StringRecord};
+use magnus::{csv::record_from_hash, rb_assert, RHash, TryConvert, Value};
+
+#[test]
+fn it_converts_csv_records() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let headers = StringRecord::from(vec!["id", "name", "id"]);
+    let record = StringRecord::from(vec!["1", "Alice", "2"]);
+
+    let row = ruby.csv_row_hash(&headers, &record);
+    rb_assert!(ruby, r#"row == {"id" => "1", "name" => "Alice"}"#, row);
+
+    let row = ruby.csv_row_new(&headers, &record).unwrap();
+    rb_assert!(
+        ruby,
+        r#"row.is_a?(CSV::Row) && row.fields == ["1", "Alice", "2"]"#,
+        row
+    );
+    assert_eq!(
+        row.funcall::<_, _, StringRecord>("itself", ()).unwrap(),
+        record
+    );
+
+    let val: Value = ruby.eval(r#"["a", nil, 3, "\xFF".b]"#).unwrap();
+    let bytes: ByteRecord = TryConvert::try_convert(val).unwrap();
+    assert_eq!(bytes, ByteRecord::from(vec![&b"a"[..], b"", b"3", b"\xFF"]));
+    rb_assert!(ruby, r#"ary == ["a", "", "3", "\xFF".b]"#, ary = bytes);
+
+    let hash: RHash = ruby.eval(r#"{name: "Bob", "id" => 7}"#).unwrap();
+    let record = record_from_hash(hash, &StringRecord::from(vec!["id", "name", "x"])).unwrap();
+    assert_eq!(record, StringRecord::from(vec!["7", "Bob", ""]));
+}
@@ -0,0 +1,50 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use magnus::{
+    gc,
+    value::{DeferDrop, Opaque},
+    RString,
+};
+
+struct Holder {
+    value: Box<Opaque<RString>>,
+    dropped_on_ruby_thread: Arc<AtomicBool>,
+}
+
+impl Drop for Holder {
+    fn drop(&mut self) {
+        gc::unregister_address(&*self.value);
+        self.dropped_on_ruby_thread
+            .store(magnus::Ruby::get().is_ok(), Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn it_drops_on_a_ruby_thread() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let flag = Arc::new(AtomicBool::new(false));
+    let value = Box::new(Opaque::from(ruby.str_new("example")));
+    gc::register_address(&*value);
+    let holder = DeferDrop::new(
+        &ruby,
+        Holder {
+            value,
+            dropped_on_ruby_thread: flag.clone(),
+        },
+    );
+
+    let handle = std::thread::spawn(move || drop(holder));
+    while !handle.is_finished() {
+        ruby.thread_schedule();
+    }
+    handle.join().unwrap();
+    while Arc::strong_count(&flag) > 1 {
+        ruby.thread_schedule();
+    }
+
+    assert!(flag.load(Ordering::SeqCst));
+}
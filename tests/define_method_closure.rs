@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use magnus::{prelude::*, rb_assert, Error, RClass, Value};
+
+#[test]
+fn it_defines_methods_from_closures() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = RClass::new(ruby.class_object()).unwrap();
+
+    let count = AtomicI64::new(0);
+    class
+        .define_method_closure("incr", move |_ruby, _rb_self, args, _block| {
+            let step = match args.first() {
+                Some(v) => i64::try_convert(*v)?,
+                None => 1,
+            };
+            Ok::<_, Error>(count.fetch_add(step, Ordering::SeqCst) + step)
+        })
+        .unwrap();
+
+    // re-entering the method from within itself
+    class
+        .define_method_closure("countdown", |_ruby, rb_self, args, _block| {
+            let n = i64::try_convert(args[0])?;
+            if n == 0 {
+                return Ok(Vec::new());
+            }
+            let mut rest: Vec<i64> = rb_self.funcall("countdown", (n - 1,))?;
+            rest.insert(0, n);
+            Ok::<_, Error>(rest)
+        })
+        .unwrap();
+
+    class
+        .define_method_closure("me", |_ruby, rb_self, _args, block| match block {
+            Some(block) => block.call::<_, Value>((rb_self,)),
+            None => Ok(rb_self),
+        })
+        .unwrap();
+
+    let obj: Value = class.new_instance(()).unwrap();
+    rb_assert!(ruby, "obj.incr == 1", obj);
+    rb_assert!(ruby, "obj.incr(2) == 3", obj);
+    rb_assert!(ruby, "obj.countdown(3) == [3, 2, 1]", obj);
+    rb_assert!(ruby, "obj.me.equal?(obj)", obj);
+    rb_assert!(ruby, "obj.me { |o| [o] } == [obj]", obj);
+}
@@ -0,0 +1,45 @@
+use magnus::{function, prelude::*, rb_assert};
+
+fn add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+#[test]
+fn it_warns_once_per_callsite() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = ruby.define_class("Example", ruby.class_object()).unwrap();
+    class.define_method("plus", function!(add, 2)).unwrap();
+    class
+        .deprecate_method("plus", Some("sum"), Some("1.2.0"))
+        .unwrap();
+
+    rb_assert!(
+        ruby,
+        r#"
+        $warnings = []
+        module Warning
+          def self.warn(msg, *)
+            $warnings << msg
+          end
+        end
+        Warning[:deprecated] = true
+
+        obj = Example.new
+        3.times { obj.plus(1, 2) }
+        obj.plus(3, 4) == 7 &&
+          $warnings.length == 2 &&
+          $warnings.all? { |w| w.include?("Example#plus is deprecated since 1.2.0, use sum instead") }
+        "#
+    );
+
+    rb_assert!(
+        ruby,
+        r#"
+        Warning[:deprecated] = false
+        # the original method is only referenced by the wrapper
+        GC.start(full_mark: true, immediate_sweep: true)
+        Example.new.plus(1, 1) == 2 && $warnings.length == 2
+        "#
+    );
+}
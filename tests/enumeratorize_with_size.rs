@@ -0,0 +1,34 @@
+use magnus::{prelude::*, rb_assert, Value};
+
+#[test]
+fn it_makes_a_sized_enumerator() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let val: Value = magnus::eval!(
+        ruby,
+        "
+    class Test
+      def each_upto(n)
+        (1..n).each { |i| yield i }
+      end
+    end
+    Test.new
+    "
+    )
+    .unwrap();
+
+    let enumerator = val
+        .enumeratorize_with_size("each_upto", (3,), |_ruby, args| i64::try_convert(args[0]))
+        .unwrap();
+
+    rb_assert!(ruby, "enumerator.size == 3", enumerator);
+    rb_assert!(ruby, "enumerator.to_a == [1, 2, 3]", enumerator);
+
+    let basic: Value = magnus::eval!(ruby, "BasicObject.new").unwrap();
+
+    let err = basic
+        .enumeratorize_with_size("each", (), |ruby, _args| ruby.qnil())
+        .unwrap_err();
+
+    assert!(err.is_kind_of(ruby.exception_no_method_error()));
+}
@@ -0,0 +1,38 @@
+use magnus::{prelude::*, rb_assert};
+
+#[test]
+fn it_shares_env_with_ruby() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let env = ruby.env();
+    env.set("MAGNUS_TEST_ENV", Some("42")).unwrap();
+    rb_assert!(ruby, r#"ENV["MAGNUS_TEST_ENV"] == "42""#);
+    assert_eq!(env.get_as::<i64>("MAGNUS_TEST_ENV").unwrap(), Some(42));
+    assert!(env.get_flag("MAGNUS_TEST_ENV").is_err());
+    assert!(env
+        .get_r_string("MAGNUS_TEST_ENV")
+        .unwrap()
+        .unwrap()
+        .is_frozen());
+
+    let _: bool = ruby
+        .eval(r#"ENV["MAGNUS_TEST_ENV"] = "yes"; true"#)
+        .unwrap();
+    assert_eq!(env.get_flag("MAGNUS_TEST_ENV").unwrap(), Some(true));
+    assert!(env.contains("MAGNUS_TEST_ENV").unwrap());
+    assert_eq!(
+        env.to_hash_map()
+            .unwrap()
+            .get("MAGNUS_TEST_ENV")
+            .map(String::as_str),
+        Some("yes")
+    );
+
+    assert_eq!(
+        env.remove("MAGNUS_TEST_ENV").unwrap(),
+        Some(String::from("yes"))
+    );
+    assert!(!env.contains("MAGNUS_TEST_ENV").unwrap());
+    assert_eq!(env.get("MAGNUS_TEST_ENV").unwrap(), None);
+    assert_eq!(env.get_as::<i64>("MAGNUS_TEST_ENV").unwrap(), None);
+}
@@ -0,0 +1,11 @@
+use magnus::{eval, RString};
+
+#[test]
+fn it_passes_values_as_locals_not_source() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let input = r#""; raise "injected"; ""#;
+    let res: RString = eval!(ruby, "input + suffix", input, suffix = "!").unwrap();
+
+    assert_eq!(format!("{}!", input), res.to_string().unwrap());
+}
@@ -0,0 +1,42 @@
+use magnus::{method, prelude::*, rb_assert, Error, RString};
+
+fn shout(s: RString) -> Result<String, Error> {
+    Ok(s.to_string()?.to_uppercase())
+}
+
+fn whisper(s: RString) -> Result<String, Error> {
+    Ok(s.to_string()?.to_lowercase())
+}
+
+#[test]
+fn it_extends_core_classes() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let refinement = ruby
+        .extend_core(ruby.class_string(), true, |m| {
+            m.define_method("shout", method!(shout, 0))
+        })
+        .unwrap();
+    ruby.define_global_const("Shout", refinement).unwrap();
+
+    rb_assert!(ruby, r#"!"hello".respond_to?(:shout)"#);
+    rb_assert!(
+        ruby,
+        r#"
+        class Greeter
+          using Shout
+
+          def self.greet
+            "hello".shout
+          end
+        end
+        Greeter.greet == "HELLO"
+        "#
+    );
+
+    ruby.extend_core(ruby.class_string(), false, |m| {
+        m.define_method("whisper", method!(whisper, 0))
+    })
+    .unwrap();
+    rb_assert!(ruby, r#""HELLO".whisper == "hello""#);
+}
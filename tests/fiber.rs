@@ -0,0 +1,31 @@
+use magnus::{prelude::*, rb_assert, Error, Value};
+
+#[test]
+fn it_resumes_fibers() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let fiber = ruby
+        .fiber_new(Default::default(), |ruby, args, _block| {
+            let n = i64::try_convert(args[0])?;
+            let m: i64 = ruby.fiber_yield((n + 1,))?;
+            Ok::<_, Error>(m * 2)
+        })
+        .unwrap();
+    assert_eq!(fiber.resume::<_, i64>((1,)).unwrap(), 2);
+    assert!(fiber.is_alive());
+    assert_eq!(fiber.resume::<_, i64>((5,)).unwrap(), 10);
+    assert!(!fiber.is_alive());
+
+    let err = fiber.resume::<_, Value>(()).unwrap_err();
+    let e = err.value().unwrap();
+    rb_assert!(ruby, "e.is_a?(FiberError)", e);
+
+    let fiber = ruby
+        .fiber_new(Default::default(), |_ruby, _args, _block| -> Value {
+            panic!("oh no")
+        })
+        .unwrap();
+    let err = fiber.resume::<_, Value>(()).unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_fatal()));
+    assert!(!fiber.is_alive());
+}
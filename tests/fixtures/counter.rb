@@ -0,0 +1,7 @@
+$fixture_loads = ($fixture_loads || 0) + 1
+
+class FixtureCounter
+  def self.loads
+    $fixture_loads
+  end
+end
@@ -0,0 +1,8 @@
+class FixturePoint
+  attr_reader :x, :y
+
+  def initialize(x, y)
+    @x = x
+    @y = y
+  end
+end
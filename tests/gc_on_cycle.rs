@@ -0,0 +1,50 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
+struct SetOnDrop(Arc<AtomicBool>);
+
+impl Drop for SetOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn it_calls_and_frees_gc_hooks() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let cycles = Arc::new(AtomicUsize::new(0));
+    let live_slots = Arc::new(AtomicUsize::new(0));
+    let freed = Arc::new(AtomicBool::new(false));
+
+    let recorded_cycles = cycles.clone();
+    let recorded_live_slots = live_slots.clone();
+    let guard = SetOnDrop(freed.clone());
+    let hook = ruby.gc_on_cycle(move |stats| {
+        let _ = &guard;
+        recorded_cycles.fetch_add(1, Ordering::SeqCst);
+        recorded_live_slots.store(stats.heap_live_slots, Ordering::SeqCst);
+    });
+
+    ruby.gc_start();
+    ruby.gc_start();
+    assert!(cycles.load(Ordering::SeqCst) >= 2);
+    assert!(live_slots.load(Ordering::SeqCst) > 0);
+
+    hook.disable();
+    let count = cycles.load(Ordering::SeqCst);
+    ruby.gc_start();
+    assert_eq!(cycles.load(Ordering::SeqCst), count);
+    hook.enable();
+    ruby.gc_start();
+    assert!(cycles.load(Ordering::SeqCst) > count);
+
+    assert!(!freed.load(Ordering::SeqCst));
+    drop(hook);
+    assert!(freed.load(Ordering::SeqCst));
+    let count = cycles.load(Ordering::SeqCst);
+    ruby.gc_start();
+    assert_eq!(cycles.load(Ordering::SeqCst), count);
+}
@@ -0,0 +1,35 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use magnus::gc::GcPause;
+
+#[test]
+fn it_reports_gc_pauses() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let pauses = Arc::new(Mutex::new(Vec::new()));
+    let recorded = pauses.clone();
+    let hook = ruby.gc_on_pause(move |pause| recorded.lock().unwrap().push(pause));
+
+    ruby.gc_start();
+    hook.disable();
+    ruby.gc_start();
+
+    let pauses = pauses.lock().unwrap();
+    assert!(!pauses.is_empty());
+    for pair in pauses.chunks(2) {
+        assert_eq!(pair[0], GcPause::Start);
+        assert!(matches!(pair[1], GcPause::End { .. }));
+    }
+
+    ruby.gc_profiler_enable().unwrap();
+    assert!(ruby.gc_profiler_is_enabled().unwrap());
+    ruby.gc_start();
+    assert!(ruby.gc_profiler_total_time().unwrap() > Duration::ZERO);
+    assert!(!ruby.gc_profiler_report().unwrap().is_empty());
+    ruby.gc_profiler_disable().unwrap();
+    ruby.gc_profiler_clear().unwrap();
+    assert_eq!(ruby.gc_profiler_total_time().unwrap(), Duration::ZERO);
+}
@@ -0,0 +1,34 @@
+use magnus::{function, rb_assert, scan_args::get_kwargs, Error, RHash};
+
+fn kw(kw: RHash) -> Result<(i64, Option<i64>), Error> {
+    let args = get_kwargs::<_, (i64,), (Option<i64>,), ()>(kw, &["a"], &["b"])?;
+    let (a,) = args.required;
+    let (b,) = args.optional;
+    Ok((a, b))
+}
+
+fn rest(kw: RHash) -> Result<RHash, Error> {
+    let args = get_kwargs::<_, (), (Option<i64>,), RHash>(kw, &[], &["a"])?;
+    Ok(args.splat)
+}
+
+#[test]
+fn it_parses_keywords() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.define_global_function("kw", function!(kw, 1));
+    ruby.define_global_function("rest", function!(rest, 1));
+
+    rb_assert!(ruby, "kw(a: 1) == [1, nil]");
+    rb_assert!(ruby, "kw(a: 1, b: 2) == [1, 2]");
+    rb_assert!(
+        ruby,
+        r#"(kw(b: 2) rescue $!.message) == "missing keyword: :a""#
+    );
+    rb_assert!(
+        ruby,
+        r#"(kw(a: 1, c: 3, d: 4) rescue $!.message) == "unknown keywords: :c, :d""#
+    );
+    rb_assert!(ruby, "rest(a: 1, b: 2) == {b: 2}");
+    rb_assert!(ruby, r#"rest(a: 1, "b" => 2) == {"b" => 2}"#);
+}
@@ -0,0 +1,30 @@
+use magnus::{r_regexp::Opts, Error};
+
+#[test]
+fn it_replaces_matches_with_closure_result() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let re = ruby.reg_new(r"(\w)(\d)", Opts::new()).unwrap();
+    let s = ruby.str_new("a1 b2 c3");
+    let res = s
+        .gsub_with(re, |m| {
+            let letter = m.nth_match(1).unwrap().to_string()?;
+            let digit: usize = m.nth_match(2).unwrap().to_string()?.parse().unwrap();
+            Ok(letter.repeat(digit))
+        })
+        .unwrap();
+    assert_eq!(res.to_string().unwrap(), "a bb ccc");
+
+    let s = ruby.str_new("a.b.c");
+    let res = s.gsub_with(".", |_| Ok("-")).unwrap();
+    assert_eq!(res.to_string().unwrap(), "a-b-c");
+
+    let s = ruby.str_new("abc");
+    let err = s
+        .gsub_with("b", |_| -> Result<&str, Error> {
+            Err(Error::new(ruby.exception_arg_error(), "bad match"))
+        })
+        .unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_arg_error()));
+    assert_eq!(s.to_string().unwrap(), "abc");
+}
@@ -0,0 +1,22 @@
+use magnus::{
+    object_space::{HeapDumpOptions, HeapDumpTarget},
+    Value,
+};
+
+#[test]
+fn it_dumps_the_heap() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let io: Value = ruby.eval("require 'stringio'; StringIO.new").unwrap();
+    ruby.heap_dump(HeapDumpTarget::Io(io), HeapDumpOptions::new())
+        .unwrap();
+    let dump: String = io.funcall("string", ()).unwrap();
+    assert!(dump.lines().any(|line| line.contains(r#""type":"STRING""#)));
+
+    let path = std::env::temp_dir().join(format!("magnus_heap_dump_{}.json", std::process::id()));
+    ruby.heap_dump(path.as_path(), HeapDumpOptions::new().full(true))
+        .unwrap();
+    let dump = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(dump.lines().any(|line| line.contains(r#""type":"NONE""#)));
+}
@@ -0,0 +1,15 @@
+use magnus::rb_assert;
+
+#[test]
+fn it_loads_fixtures_once() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    assert!(ruby.load_fixture("tests/fixtures/counter.rb").unwrap());
+    assert!(!ruby.load_fixture("tests/fixtures/counter.rb").unwrap());
+    let absolute =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/counter.rb");
+    assert!(!ruby.load_fixture(absolute).unwrap());
+    rb_assert!(ruby, "FixtureCounter.loads == 1");
+
+    assert!(ruby.load_fixture("tests/fixtures/missing.rb").is_err());
+}
@@ -0,0 +1,35 @@
+use magnus::{block::Proc, function, method, prelude::*, rb_assert, Error, Ruby, Value};
+
+fn map_pair(
+    ruby: &Ruby,
+    _rb_self: Value,
+    a: i64,
+    b: i64,
+    block: Option<Proc>,
+) -> Result<Value, Error> {
+    match block {
+        Some(block) => block.call((a, b)),
+        None => Ok(ruby.ary_from_vec(vec![a, b]).as_value()),
+    }
+}
+
+fn given(block: Option<Proc>) -> bool {
+    block.is_some()
+}
+
+#[test]
+fn it_passes_block_arg() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.define_global_function("map_pair", method!(map_pair, 2, block));
+    ruby.define_global_function("given?", function!(given, 0, block));
+
+    rb_assert!(ruby, "map_pair(1, 2) { |a, b| a + b } == 3");
+    rb_assert!(ruby, "map_pair(1, 2) == [1, 2]");
+    rb_assert!(ruby, "given? {}");
+    rb_assert!(ruby, "!given?");
+    rb_assert!(
+        ruby,
+        "(map_pair(1) {} rescue $!.message) == 'wrong number of arguments (given 1, expected 2)'"
+    );
+}
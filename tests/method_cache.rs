@@ -0,0 +1,36 @@
+use magnus::{method_ids, rb_assert, value::MethodCache, Error, RArray};
+
+method_ids! {
+    struct ArrayMethods {
+        push,
+        sum,
+        is_empty = "empty?",
+    }
+}
+
+static ARRAY: MethodCache<ArrayMethods> = MethodCache::new();
+
+#[test]
+fn it_calls_cached_methods() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    assert!(MethodCache::try_get_inner(&ARRAY).is_none());
+    MethodCache::force(&ARRAY, &ruby);
+    assert!(MethodCache::try_get_inner(&ARRAY).is_some());
+
+    let ary = ruby.ary_new();
+    let empty: bool = ARRAY.call(ary, ARRAY.is_empty, ()).unwrap();
+    assert!(empty);
+    for i in 1..=3 {
+        let _: RArray = ARRAY.call(ary, ARRAY.push, (i,)).unwrap();
+    }
+    let sum: i64 = ARRAY.call(ary, ARRAY.sum, ()).unwrap();
+    assert_eq!(sum, 6);
+    rb_assert!(ruby, "ary == [1, 2, 3]", ary);
+
+    let ids = MethodCache::get_inner_with(&ARRAY, &ruby);
+    let res: Result<i64, Error> = ARRAY.call(ruby.qnil(), ids.sum, ());
+    assert!(res
+        .unwrap_err()
+        .is_kind_of(ruby.exception_no_method_error()));
+}
@@ -0,0 +1,49 @@
+use magnus::{function, method, rb_assert, RHash, Value};
+
+#[derive(magnus::Keywords)]
+struct Opts {
+    scale: i64,
+    offset: Option<i64>,
+    #[magnus(name = "neg")]
+    negate: Option<bool>,
+}
+
+fn transform(_rb_self: Value, val: i64, opts: Opts) -> i64 {
+    let res = val * opts.scale + opts.offset.unwrap_or(0);
+    if opts.negate.unwrap_or(false) {
+        -res
+    } else {
+        res
+    }
+}
+
+fn kw_count(kw: RHash) -> usize {
+    kw.len()
+}
+
+#[test]
+fn it_extracts_keywords() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.define_global_function("transform", method!(transform, 1, kwargs));
+    ruby.define_global_function("kw_count", function!(kw_count, 0, kwargs));
+
+    rb_assert!(ruby, "transform(2, scale: 3) == 6");
+    rb_assert!(ruby, "transform(2, scale: 3, offset: 1) == 7");
+    rb_assert!(ruby, "transform(2, scale: 3, neg: true) == -6");
+    rb_assert!(
+        ruby,
+        "(transform(2) rescue $!.message) == 'missing keyword: :scale'"
+    );
+    rb_assert!(
+        ruby,
+        "(transform(2, scale: 1, foo: 1) rescue $!.message) == 'unknown keyword: :foo'"
+    );
+    rb_assert!(
+        ruby,
+        "(transform(scale: 1) rescue $!.message) == 'wrong number of arguments (given 0, expected 1)'"
+    );
+
+    rb_assert!(ruby, "kw_count == 0");
+    rb_assert!(ruby, "kw_count(a: 1, b: 2) == 2");
+}
@@ -0,0 +1,39 @@
+use magnus::{block::Proc, function, method, prelude::*, rb_assert, Error, Ruby, Value};
+
+fn each_arg(_rb_self: Value, args: &[Value], block: Option<Proc>) -> Result<usize, Error> {
+    if let Some(block) = block {
+        for arg in args {
+            let _: Value = block.call((*arg,))?;
+        }
+    }
+    Ok(args.len())
+}
+
+fn sum(_ruby: &Ruby, args: &[Value], block: Option<Proc>) -> Result<i64, Error> {
+    let mut total = 0;
+    for arg in args {
+        total += match block {
+            Some(block) => block.call::<_, i64>((*arg,))?,
+            None => i64::try_convert(*arg)?,
+        };
+    }
+    Ok(total)
+}
+
+#[test]
+fn it_passes_args_slice_and_block() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.define_global_function("each_arg", method!(each_arg, -1, block));
+    ruby.define_global_function("sum", function!(sum, -1, block));
+
+    rb_assert!(ruby, "each_arg == 0");
+    rb_assert!(ruby, "each_arg(1, 2, 3) == 3");
+    rb_assert!(
+        ruby,
+        "seen = []; each_arg(1, 2) { |a| seen << a }; seen == [1, 2]"
+    );
+    rb_assert!(ruby, "sum(1, 2, 3) == 6");
+    rb_assert!(ruby, "sum(1, 2, 3) { |a| a * 2 } == 12");
+    rb_assert!(ruby, "sum(*(1..20)) == 210");
+}
@@ -0,0 +1,29 @@
+use magnus::{function, rb_assert};
+
+fn double(i: i64) -> i64 {
+    i * 2
+}
+
+#[test]
+fn it_defines_module_functions() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let module = ruby.define_module("Doubler").unwrap();
+    module
+        .define_module_function("double", function!(double, 1))
+        .unwrap();
+
+    rb_assert!(ruby, "Doubler.double(2) == 4");
+    rb_assert!(
+        ruby,
+        "Doubler.singleton_class.public_method_defined?(:double)"
+    );
+    rb_assert!(ruby, "Doubler.private_method_defined?(:double)");
+    rb_assert!(
+        ruby,
+        r#"
+            obj = Object.new.extend(Doubler)
+            obj.send(:double, 3) == 6 && !obj.respond_to?(:double)
+        "#
+    );
+}
@@ -0,0 +1,45 @@
+use magnus::{msgpack::Options, rb_assert, Value};
+
+#[test]
+fn it_round_trips_msgpack() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let val: Value = ruby
+        .eval(
+            r#"{
+                "nil" => nil,
+                "bools" => [true, false],
+                "ints" => [0, -1, 2**63 - 1, 2**64 - 1, -2**63],
+                "float" => 1.5,
+                "str" => "hello",
+                "bin" => "\xFF\x00".b,
+                "time" => Time.at(1700000000, 123456789, :nsec),
+                "old" => Time.at(-1, 5, :nsec),
+            }"#,
+        )
+        .unwrap();
+    let packed = ruby.msgpack_pack(val).unwrap();
+    let unpacked = ruby.msgpack_unpack(unsafe { packed.as_slice() }).unwrap();
+    rb_assert!(ruby, "unpacked == val", unpacked, val);
+    rb_assert!(
+        ruby,
+        "unpacked['bin'].encoding == Encoding::BINARY",
+        unpacked
+    );
+    rb_assert!(
+        ruby,
+        "unpacked['str'].encoding == Encoding::UTF_8",
+        unpacked
+    );
+
+    let big: Value = ruby.eval("2**64").unwrap();
+    assert!(ruby.msgpack_pack(big).is_err());
+    assert!(ruby.msgpack_unpack(&[0x93, 0x01]).is_err());
+    assert!(ruby.msgpack_unpack(&[0x01, 0x02]).is_err());
+
+    let options = Options::new().symbolize_keys(true).symbol_ext_type(0);
+    let val: Value = ruby.eval(r#"{"a" => :b, 1 => "c"}"#).unwrap();
+    let packed = options.pack(val).unwrap();
+    let unpacked = options.unpack(&ruby, unsafe { packed.as_slice() }).unwrap();
+    rb_assert!(ruby, r#"unpacked == {a: :b, 1 => "c"}"#, unpacked);
+}
@@ -0,0 +1,33 @@
+use magnus::{rb_assert, Error};
+
+#[test]
+fn it_labels_native_frames() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let res: Result<i64, Error> = ruby.native_frame("outer", || {
+        ruby.native_frame("inner", || {
+            rb_assert!(ruby, "caller(0).any? { |l| l.include?('outer') }");
+            rb_assert!(ruby, "caller(0).any? { |l| l.include?('inner') }");
+            Ok(1)
+        })
+    });
+    assert_eq!(res.unwrap(), 1);
+
+    // labels matching inherited methods still get their own frame
+    for label in ["name", "exit", "send", "freeze"] {
+        let trace: Vec<String> = ruby.native_frame(label, || ruby.eval("caller(0)")).unwrap();
+        assert!(trace.iter().any(|l| l.contains(&format!("{}'", label))));
+    }
+
+    let err = ruby
+        .native_frame::<_, ()>("fails", || {
+            Err(Error::new(ruby.exception_arg_error(), "bad"))
+        })
+        .unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_arg_error()));
+
+    rb_assert!(
+        ruby,
+        "begin; Magnus::NativeFrame.outer(1234); false; rescue RuntimeError; true; end"
+    );
+}
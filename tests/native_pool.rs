@@ -0,0 +1,33 @@
+use std::sync::{mpsc, Arc};
+
+use magnus::{prelude::*, rb_assert, Error};
+
+#[test]
+fn it_restarts_workers_after_fork() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let pool = Arc::new(ruby.native_pool_new(2).unwrap());
+
+    let (tx, rx) = mpsc::channel();
+    let sender = tx.clone();
+    pool.execute(move || sender.send(1).unwrap());
+    assert_eq!(ruby.without_gvl(|| rx.recv()).unwrap().unwrap(), 1);
+
+    let job_pool = pool.clone();
+    ruby.class_object()
+        .define_method_closure("run_pool_job", move |ruby, _rb_self, _args, _block| {
+            let (tx, rx) = mpsc::channel();
+            job_pool.execute(move || tx.send(42).unwrap());
+            Ok::<_, Error>(ruby.without_gvl(|| rx.recv())?.ok())
+        })
+        .unwrap();
+
+    #[cfg(unix)]
+    rb_assert!(
+        ruby,
+        "Process.wait(fork { exit!(run_pool_job == 42 ? 0 : 1) }); $?.exitstatus == 0"
+    );
+
+    pool.execute(move || tx.send(2).unwrap());
+    assert_eq!(ruby.without_gvl(|| rx.recv()).unwrap().unwrap(), 2);
+}
@@ -0,0 +1,29 @@
+use magnus::{rb_assert, TryConvert, Value};
+use ndarray::{array, Array1, Array2};
+
+#[test]
+fn it_converts_ndarrays() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let a = array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+    rb_assert!(
+        ruby,
+        "val == [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]",
+        val = a.clone()
+    );
+
+    let val: Value = ruby.eval("[[1, 2, 3], [4, 5, 6]]").unwrap();
+    assert_eq!(Array2::<f64>::try_convert(val).unwrap(), a);
+
+    let val: Value = ruby.eval("[[1, 2], [3]]").unwrap();
+    assert!(Array2::<f64>::try_convert(val).is_err());
+
+    let val: Value = ruby.eval("[1, 2.5]").unwrap();
+    assert_eq!(Array1::<f64>::try_convert(val).unwrap(), array![1.0, 2.5]);
+
+    if ruby.require("matrix").is_ok() {
+        let m = ruby.matrix_from_array2(&a).unwrap();
+        rb_assert!(ruby, "m == Matrix[[1, 2, 3], [4, 5, 6]]", m);
+        assert_eq!(Array2::<f64>::try_convert(m).unwrap(), a);
+    }
+}
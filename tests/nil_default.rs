@@ -0,0 +1,28 @@
+use magnus::{function, rb_assert, try_convert::NilDefault, Error};
+
+fn describe(
+    name: NilDefault<String>,
+    count: NilDefault<i64>,
+    tags: NilDefault<Vec<String>>,
+) -> String {
+    format!("{}:{}:{}", name.0, count.0, tags.0.join(","))
+}
+
+#[test]
+fn it_converts_nil_to_default() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.define_global_function("describe", function!(describe, 3));
+
+    rb_assert!(ruby, r#"describe("a", 2, ["x", "y"]) == "a:2:x,y""#);
+    rb_assert!(ruby, r#"describe(nil, nil, nil) == ":0:""#);
+    rb_assert!(
+        ruby,
+        r#"(describe(1, nil, nil) rescue $!).is_a?(TypeError)"#
+    );
+
+    let val: NilDefault<u8> = ruby.eval("nil").unwrap();
+    assert_eq!(val, NilDefault(0));
+    let res: Result<NilDefault<u8>, Error> = ruby.eval("false");
+    assert!(res.is_err());
+}
@@ -0,0 +1,88 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use magnus::{nogvl, rb_assert, Ruby};
+
+#[test]
+fn it_releases_and_reacquires_the_gvl() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    // another Ruby thread can run while the GVL is released
+    let flag: magnus::Value = ruby
+        .eval("$flag = false; Thread.new { $flag = true }")
+        .unwrap();
+    let ran = ruby
+        .without_gvl(|| {
+            let start = std::time::Instant::now();
+            loop {
+                let done = nogvl::with_gvl(|ruby| {
+                    assert!(Ruby::get().is_ok());
+                    ruby.eval::<bool>("$flag").unwrap()
+                })
+                .unwrap();
+                if done || start.elapsed().as_secs() > 5 {
+                    break done;
+                }
+                assert!(Ruby::get().is_err());
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+        })
+        .unwrap();
+    assert!(ran);
+    assert!(Ruby::get().is_ok());
+    rb_assert!(ruby, "flag.join", flag);
+
+    // panics are propagated after the GVL is re-acquired
+    let res = std::panic::catch_unwind(|| {
+        Ruby::get()
+            .unwrap()
+            .without_gvl(|| nogvl::with_gvl(|_| panic!("boom")).unwrap())
+    });
+    assert!(res.is_err());
+    assert!(Ruby::get().is_ok());
+
+    // the unblock function is called when the thread is interrupted
+    let unblocked = Arc::new(AtomicBool::new(false));
+    let u = unblocked.clone();
+    let res = ruby.without_gvl_unblock(
+        || {
+            nogvl::with_gvl(|ruby| {
+                let _: magnus::Value = ruby
+                    .eval("main = Thread.current; Thread.new { sleep 0.01; main.wakeup }")
+                    .unwrap();
+            })
+            .unwrap();
+            while !u.load(Ordering::Relaxed) {
+                std::thread::yield_now();
+            }
+            "stopped"
+        },
+        || unblocked.store(true, Ordering::Relaxed),
+    );
+    assert_eq!(res.unwrap(), "stopped");
+
+    // an exception raised in the thread is returned, rather than unwinding
+    // over Rust frames
+    let unblocked = Arc::new(AtomicBool::new(false));
+    let u = unblocked.clone();
+    let res = ruby.without_gvl_unblock(
+        || {
+            nogvl::with_gvl(|ruby| {
+                let _: magnus::Value = ruby
+                    .eval("main = Thread.current; Thread.new { sleep 0.01; main.raise 'stop' }")
+                    .unwrap();
+            })
+            .unwrap();
+            while !u.load(Ordering::Relaxed) {
+                std::thread::yield_now();
+            }
+        },
+        || unblocked.store(true, Ordering::Relaxed),
+    );
+    let err = res.unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_runtime_error()));
+    assert!(err.to_string().contains("stop"));
+    assert!(Ruby::get().is_ok());
+}
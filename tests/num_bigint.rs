@@ -0,0 +1,29 @@
+use magnus::{rb_assert, TryConvert, Value};
+use num_bigint::{BigInt, BigUint};
+
+#[test]
+fn it_converts_big_integers() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    for s in [
+        "0",
+        "1",
+        "-1",
+        "4611686018427387904",
+        "-(2**200 + 7)",
+        "3**150",
+    ] {
+        let val: Value = ruby.eval(s).unwrap();
+        let big = BigInt::try_convert(val).unwrap();
+        let expected: String = ruby.eval(&format!("({}).to_s", s)).unwrap();
+        assert_eq!(big.to_string(), expected);
+        rb_assert!(ruby, "big == val", big, val);
+    }
+
+    let n: BigUint = "340282366920938463463374607431768211456".parse().unwrap();
+    rb_assert!(ruby, "n == 2**128", n = n.clone());
+    let val: Value = ruby.eval("2**128").unwrap();
+    assert_eq!(BigUint::try_convert(val).unwrap(), n);
+    let val: Value = ruby.eval("-(2**128)").unwrap();
+    assert!(BigUint::try_convert(val).is_err());
+}
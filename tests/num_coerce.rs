@@ -0,0 +1,54 @@
+use magnus::{function, rb_assert, try_convert::NumCoerce, Integer, Value};
+
+fn add(a: NumCoerce<i64>, b: NumCoerce<f64>) -> f64 {
+    a.into_inner() as f64 + b.into_inner()
+}
+
+fn int(a: NumCoerce<Integer>) -> Integer {
+    a.0
+}
+
+fn plain_add(a: i64, b: f64) -> f64 {
+    a as f64 + b
+}
+
+#[test]
+fn it_coerces_numeric_arguments() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let _: Value = ruby
+        .eval(
+            r#"
+            class Num < Numeric
+              def initialize(n)
+                @n = n
+              end
+
+              def coerce(other)
+                [other, other.is_a?(Float) ? @n.to_f : @n.to_i]
+              end
+            end
+            "#,
+        )
+        .unwrap();
+
+    ruby.define_global_function("add", function!(add, 2));
+    ruby.define_global_function("int", function!(int, 1));
+    ruby.define_global_function("plain_add", function!(plain_add, 2));
+
+    rb_assert!(ruby, "add(1, 2.5) == 3.5");
+    rb_assert!(ruby, "add(1, 2) == 3.0");
+    rb_assert!(ruby, "add(3r, 1/2r) == 3.5");
+    rb_assert!(ruby, "int(2**70) == 2**70");
+
+    // plain conversions can't handle a Numeric that only implements coerce
+    rb_assert!(ruby, "(plain_add(Num.new(2), 1.5) rescue :err) == :err");
+    rb_assert!(ruby, "(plain_add(2, Num.new(1.5)) rescue :err) == :err");
+    rb_assert!(ruby, "add(Num.new(2), Num.new(1.5)) == 3.5");
+    rb_assert!(ruby, "int(Num.new(7)) == 7");
+
+    rb_assert!(ruby, r#"(add(1, "2") rescue $!).is_a?(TypeError)"#);
+    rb_assert!(ruby, "(add(1, nil) rescue $!).is_a?(TypeError)");
+    rb_assert!(ruby, "(add(1, Time.now) rescue $!).is_a?(TypeError)");
+    rb_assert!(ruby, "(add(1, Object.new) rescue $!).is_a?(TypeError)");
+}
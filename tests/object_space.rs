@@ -0,0 +1,32 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use magnus::{Error, Value};
+
+#[test]
+fn it_reports_allocation_sources() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let untraced: Value = ruby.eval("Object.new").unwrap();
+    assert_eq!(ruby.allocation_source(untraced).unwrap(), None);
+
+    let traced = ruby
+        .trace_object_allocations(|| ruby.eval::<Value>("\n\nObject.new"))
+        .unwrap();
+    let source = ruby.allocation_source(traced).unwrap().unwrap();
+    assert_eq!(source.line, 3);
+    assert_eq!(source.class_path.as_deref(), Some("Class"));
+    assert_eq!(source.method.as_deref(), Some("new"));
+
+    let res: Result<Value, Error> = ruby.trace_object_allocations(|| ruby.eval("raise 'oops'"));
+    assert!(res.is_err());
+
+    ruby.trace_object_allocations_clear().unwrap();
+    assert_eq!(ruby.allocation_source(traced).unwrap(), None);
+
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        ruby.trace_object_allocations(|| -> Result<(), Error> { panic!("oops") })
+    }));
+    assert!(res.is_err());
+    let untraced: Value = ruby.eval("Object.new").unwrap();
+    assert_eq!(ruby.allocation_source(untraced).unwrap(), None);
+}
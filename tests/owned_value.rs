@@ -0,0 +1,49 @@
+use magnus::{
+    rb_assert,
+    value::{BigInteger, OwnedValue},
+    Value,
+};
+
+#[test]
+fn it_round_trips_owned_values() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let val: Value = ruby
+        .eval(r#"[nil, true, 1, 2 ** 80, 1.5, "héllo", "\xFF".b, :sym, {"a" => [1]}]"#)
+        .unwrap();
+    let owned = val.to_owned_tree().unwrap();
+    assert_eq!(
+        owned,
+        OwnedValue::Array(vec![
+            OwnedValue::Nil,
+            OwnedValue::Bool(true),
+            OwnedValue::Integer(1),
+            OwnedValue::BigInteger("1208925819614629174706176".parse().unwrap()),
+            OwnedValue::Float(1.5),
+            OwnedValue::String(String::from("héllo")),
+            OwnedValue::Bytes(vec![0xFF]),
+            OwnedValue::Symbol(String::from("sym")),
+            OwnedValue::Hash(vec![(
+                OwnedValue::String(String::from("a")),
+                OwnedValue::Array(vec![OwnedValue::Integer(1)]),
+            )]),
+        ])
+    );
+
+    assert!("".parse::<BigInteger>().is_err());
+    assert!("-".parse::<BigInteger>().is_err());
+    assert!("0x10".parse::<BigInteger>().is_err());
+    rb_assert!(
+        ruby,
+        "i == -(2 ** 80)",
+        i = OwnedValue::BigInteger("-1208925819614629174706176".parse().unwrap()),
+    );
+
+    let owned = std::thread::spawn(move || owned).join().unwrap();
+    rb_assert!(ruby, "val == owned", val, owned);
+
+    let recursive: Value = ruby.eval("a = []; a << a").unwrap();
+    assert!(recursive.to_owned_tree().is_err());
+    let object: Value = ruby.eval("[Object.new]").unwrap();
+    assert!(object.to_owned_tree().is_err());
+}
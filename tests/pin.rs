@@ -0,0 +1,19 @@
+use magnus::{prelude::*, value::Pinned, RString, Value};
+
+#[test]
+fn it_keeps_pinned_values_alive() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let pinned: Vec<Pinned<RString>> = (0..100)
+        .map(|i| ruby.str_new(&format!("pinned {}", i)).pin())
+        .collect();
+
+    let _: Value = ruby
+        .eval(r#"1024.times.map {|i| "garbage#{i}"}; GC.compact if GC.respond_to?(:compact)"#)
+        .unwrap();
+    ruby.gc_start();
+
+    for (i, s) in pinned.iter().enumerate() {
+        assert_eq!(s.get().to_string().unwrap(), format!("pinned {}", i));
+    }
+}
@@ -0,0 +1,44 @@
+use magnus::{prelude::*, RArray, TryConvert, Value};
+
+#[test]
+fn it_converts_arrays_modified_during_conversion() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    // an Array whose first element clears the Array when converted
+    let shrinking = r#"
+        ary = []
+        shrink = Object.new
+        shrink.define_singleton_method(:to_int) { ary.clear; 1 }
+        ary.push(shrink, 2, 3)
+    "#;
+
+    let ary: RArray = ruby.eval(shrinking).unwrap();
+    assert_eq!(ary.to_vec::<i64>().unwrap(), vec![1]);
+
+    let ary: RArray = ruby.eval(shrinking).unwrap();
+    assert_eq!(ary.to_array::<i64, 3>().unwrap(), [1, 2, 3]);
+    assert!(ary.is_empty());
+
+    let ary: RArray = ruby.eval(shrinking).unwrap();
+    assert_eq!(
+        <(i64, i64, i64)>::try_convert(ary.as_value()).unwrap(),
+        (1, 2, 3)
+    );
+    assert!(ary.is_empty());
+
+    // a Struct whose first member replaces the others when converted
+    let val: Value = ruby
+        .eval(
+            r#"
+            point = Struct.new(:x, :y, :z).new
+            shrink = Object.new
+            shrink.define_singleton_method(:to_int) { point.y = nil; point.z = nil; 1 }
+            point.x = shrink
+            point.y = 2
+            point.z = 3
+            point
+            "#,
+        )
+        .unwrap();
+    assert_eq!(<(i64, i64, i64)>::try_convert(val).unwrap(), (1, 2, 3));
+}
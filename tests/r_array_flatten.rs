@@ -0,0 +1,32 @@
+use magnus::{rb_assert, RArray};
+
+#[test]
+fn it_flattens_compacts_and_uniqs_like_ruby() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let ary: RArray = ruby
+        .eval(
+            r#"
+            o = Object.new
+            def o.to_ary
+              [3, [4]]
+            end
+            [1, [2, nil, o], [[5, 5.0]], 5]
+            "#,
+        )
+        .unwrap();
+    let res = ary.flatten(None).unwrap();
+    rb_assert!(ruby, "res == [1, 2, nil, 3, 4, 5, 5.0, 5]", res);
+    let res = ary.flatten(Some(1)).unwrap();
+    rb_assert!(ruby, "res == ary.flatten(1)", res, ary);
+    let res = ary.flatten(None).unwrap().compact().unwrap();
+    rb_assert!(ruby, "res == [1, 2, 3, 4, 5, 5.0, 5]", res);
+    let res = res.uniq().unwrap();
+    rb_assert!(ruby, "res == [1, 2, 3, 4, 5, 5.0]", res);
+
+    let recursive: RArray = ruby.eval("a = [1]; a << [a]; a").unwrap();
+    let err = recursive.flatten(None).unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_arg_error()));
+    let res = recursive.flatten(Some(2)).unwrap();
+    rb_assert!(ruby, "res == recursive.flatten(2)", res, recursive);
+}
@@ -0,0 +1,50 @@
+use std::io::{Read, Write};
+
+use magnus::{prelude::*, rb_assert, RFile, RIo, TryConvert, Value};
+
+#[test]
+fn it_streams_to_ruby_io() {
+    let ruby = unsafe { magnus::embed::init() };
+    ruby.require("stringio").unwrap();
+
+    let (r, w): (RFile, RIo) = ruby.eval("IO.pipe").unwrap();
+    assert!(w.as_r_file().is_some());
+    assert!(w.fileno().is_some());
+    let mut w = w;
+    w.write_all(b"hello pipe").unwrap();
+    w.flush().unwrap();
+    let _: Value = w.funcall("close", ()).unwrap();
+    let mut s = String::new();
+    let mut r = r;
+    r.read_to_string(&mut s).unwrap();
+    assert_eq!(s, "hello pipe");
+
+    let mut io: RIo = ruby.eval("StringIO.new").unwrap();
+    assert!(io.as_r_file().is_none());
+    assert!(io.fileno().is_none());
+    assert!(io.wait_writable(None).unwrap());
+    writeln!(io, "line {}", 1).unwrap();
+    io.flush().unwrap();
+    rb_assert!(ruby, r#"io.string == "line 1\n""#, io);
+
+    let val: Value = ruby.eval("Object.new").unwrap();
+    assert!(RIo::try_convert(val).is_err());
+
+    let val: Value = ruby
+        .eval(
+            r#"
+            Object.new.tap do |o|
+              def o.respond_to?(*)
+                raise ArgumentError, "from respond_to?"
+              end
+            end
+            "#,
+        )
+        .unwrap();
+    assert!(RIo::from_value(val).is_none());
+    let err = RIo::try_convert(val).unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_arg_error()));
+
+    let mut closed: RIo = ruby.eval("StringIO.new.tap(&:close)").unwrap();
+    assert!(closed.write_all(b"x").is_err());
+}
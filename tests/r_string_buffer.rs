@@ -0,0 +1,37 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use magnus::{prelude::*, r_string::RStringReader, rb_assert};
+
+#[test]
+fn it_uses_r_string_as_buffer() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let mut s = ruby.str_new("");
+    write!(s, "hello {}", "world").unwrap();
+
+    let mut reader = RStringReader::new(s);
+    let mut buf = [0; 5];
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+    assert_eq!(reader.position(), 5);
+    assert_eq!(reader.seek(SeekFrom::Current(1)).unwrap(), 6);
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"world");
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    assert!(reader.seek(SeekFrom::End(-100)).is_err());
+
+    s.truncate(5).unwrap();
+    rb_assert!(ruby, r#"s == "hello""#, s);
+    reader.set_position(0);
+    let mut all = String::new();
+    reader.read_to_string(&mut all).unwrap();
+    assert_eq!(all, "hello");
+
+    s.clear().unwrap();
+    rb_assert!(ruby, r#"s == """#, s);
+
+    s.freeze();
+    assert!(s.clear().is_err());
+    assert!(s.truncate(100).is_err());
+}
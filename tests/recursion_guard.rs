@@ -0,0 +1,29 @@
+use magnus::{function, prelude::*, rb_assert, Error, RArray, Ruby, Value};
+
+fn flat_len(ruby: &Ruby, val: Value) -> Result<usize, Error> {
+    ruby.stack_check()?;
+    let ary = match RArray::from_value(val) {
+        Some(ary) => ary,
+        None => return Ok(1),
+    };
+    ruby.with_recursion_guard(ary, |recursive| -> Result<usize, Error> {
+        if recursive {
+            return Err(Error::new(ruby.exception_arg_error(), "recursive array"));
+        }
+        ary.into_iter().map(|v| flat_len(ruby, v)).sum()
+    })
+}
+
+#[test]
+fn it_guards_recursion() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.define_global_function("flat_len", function!(flat_len, 1));
+
+    rb_assert!(ruby, "flat_len([1, [2, [3, 4]], 5]) == 5");
+    rb_assert!(ruby, "flat_len([[1], [1]].tap { |a| a << a[0] }) == 3");
+    rb_assert!(
+        ruby,
+        r#"((a = [1]; a << a; flat_len(a)) rescue $!.message) == "recursive array""#
+    );
+}
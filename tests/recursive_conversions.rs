@@ -0,0 +1,41 @@
+use magnus::{msgpack::Options, Value};
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum Tree {
+    Leaf(i64),
+    Node(Vec<Tree>),
+}
+
+#[test]
+fn it_detects_cycles_in_conversions() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let val: Value = ruby.eval("[1, [2, [3]]]").unwrap();
+    let tree: Tree = magnus::serde::deserialize(&ruby, val).unwrap();
+    assert_eq!(
+        tree,
+        Tree::Node(vec![
+            Tree::Leaf(1),
+            Tree::Node(vec![Tree::Leaf(2), Tree::Node(vec![Tree::Leaf(3)])]),
+        ])
+    );
+    assert!(magnus::serde::deserialize_with_max_depth::<Tree>(&ruby, val, 2).is_err());
+    assert!(ruby.msgpack_pack(val).is_ok());
+    assert!(Options::new().max_depth(2).pack(val).is_err());
+
+    let val: Value = ruby.eval("a = [1]; a << [a]; a").unwrap();
+    let err = magnus::serde::deserialize::<Tree>(&ruby, val).unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_arg_error()));
+    let err = ruby.msgpack_pack(val).unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_arg_error()));
+
+    let val: Value = ruby.eval("h = {}; h[:a] = h; h").unwrap();
+    assert!(ruby.msgpack_pack(val).is_err());
+
+    // the same object appearing twice isn't a cycle
+    let val: Value = ruby.eval("a = [1]; [a, a]").unwrap();
+    assert!(magnus::serde::deserialize::<Tree>(&ruby, val).is_ok());
+    assert!(ruby.msgpack_pack(val).is_ok());
+}
@@ -0,0 +1,52 @@
+use magnus::{function, method, prelude::*, rb_assert, RArray, RString, Ruby, Value};
+
+fn upcase(ruby: &Ruby, rb_self: RString) -> RString {
+    ruby.str_new(&rb_self.to_string().unwrap().to_uppercase())
+}
+
+fn join(ruby: &Ruby, rb_self: RString, other: RString) -> RString {
+    let res = ruby.str_buf_new(0);
+    res.buf_append(rb_self).unwrap();
+    res.buf_append(other).unwrap();
+    res
+}
+
+fn count_c_ary(ruby: &Ruby, _rb_self: Value, args: &[Value]) -> Value {
+    ruby.integer_from_i64(args.len() as i64).as_value()
+}
+
+fn count_rb_ary(ruby: &Ruby, _rb_self: Value, args: RArray) -> Value {
+    ruby.integer_from_i64(args.len() as i64).as_value()
+}
+
+fn nil(ruby: &Ruby) -> Value {
+    ruby.qnil().as_value()
+}
+
+fn len_c_ary(ruby: &Ruby, args: &[Value]) -> Value {
+    ruby.integer_from_i64(args.len() as i64).as_value()
+}
+
+#[test]
+fn it_passes_ruby_handle_to_methods() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = ruby.class_string();
+    class.define_method("up", method!(upcase, 0)).unwrap();
+    class.define_method("join", method!(join, 1)).unwrap();
+    class
+        .define_method("count_c_ary", method!(count_c_ary, -1))
+        .unwrap();
+    class
+        .define_method("count_rb_ary", method!(count_rb_ary, -2))
+        .unwrap();
+    ruby.define_global_function("nil_from_rust", function!(nil, 0));
+    ruby.define_global_function("len_c_ary", function!(len_c_ary, -1));
+
+    rb_assert!(ruby, r#""foo".up == "FOO""#);
+    rb_assert!(ruby, r#""foo".join("bar") == "foobar""#);
+    rb_assert!(ruby, r#""foo".count_c_ary(1, 2) == 2"#);
+    rb_assert!(ruby, r#""foo".count_rb_ary(1, 2, 3) == 3"#);
+    rb_assert!(ruby, "nil_from_rust.nil?");
+    rb_assert!(ruby, "len_c_ary(1) == 1");
+}
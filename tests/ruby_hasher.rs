@@ -0,0 +1,42 @@
+use std::hash::{Hash, Hasher};
+
+use magnus::{function, method, prelude::*, rb_assert, typed_data, Ruby};
+
+#[magnus::wrap(class = "Key", free_immediately)]
+#[derive(PartialEq, Eq)]
+struct Key(String, u32);
+
+impl Key {
+    fn new(name: String, n: u32) -> Self {
+        Self(name, n)
+    }
+
+    fn hash(ruby: &Ruby, rb_self: &Self) -> i64 {
+        let mut hasher = ruby.hasher();
+        rb_self.0.hash(&mut hasher);
+        rb_self.1.hash(&mut hasher);
+        hasher.finish_i64()
+    }
+}
+
+#[test]
+fn it_hashes_wrapped_objects() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = ruby.define_class("Key", ruby.class_object()).unwrap();
+    class
+        .define_singleton_method("new", function!(Key::new, 2))
+        .unwrap();
+    class.define_method("hash", method!(Key::hash, 0)).unwrap();
+    class
+        .define_method("eql?", method!(<Key as typed_data::IsEql>::is_eql, 1))
+        .unwrap();
+
+    rb_assert!(ruby, r#"Key.new("a", 1).hash == Key.new("a", 1).hash"#);
+    rb_assert!(ruby, r#"Key.new("a", 1).hash != Key.new("a", 2).hash"#);
+    rb_assert!(ruby, r#"{Key.new("a", 1) => true}[Key.new("a", 1)]"#);
+    rb_assert!(ruby, r#"{Key.new("a", 1) => true}[Key.new("b", 1)].nil?"#);
+
+    assert_eq!(ruby.memhash(b"abc"), ruby.memhash(b"abc"));
+    assert_ne!(ruby.memhash(b"abc"), ruby.memhash(b"abd"));
+}
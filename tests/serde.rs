@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use magnus::{rb_assert, Value};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Event {
+    Start,
+    Move(i32, i32),
+    Rename(String),
+    Resize { width: u32, height: u32 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Log {
+    id: u64,
+    score: f64,
+    #[serde(with = "serde_bytes_compat")]
+    raw: Vec<u8>,
+    events: Vec<Event>,
+    meta: BTreeMap<String, Option<i64>>,
+}
+
+mod serde_bytes_compat {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_bytes(v)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        struct V;
+        impl<'de> serde::de::Visitor<'de> for V {
+            type Value = Vec<u8>;
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("bytes")
+            }
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+                Ok(v)
+            }
+        }
+        d.deserialize_byte_buf(V)
+    }
+}
+
+#[test]
+fn it_round_trips_serde() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let mut meta = BTreeMap::new();
+    meta.insert(String::from("a"), Some(1));
+    meta.insert(String::from("b"), None);
+    let log = Log {
+        id: u64::MAX,
+        score: 0.5,
+        raw: vec![0xff, 0x00],
+        events: vec![
+            Event::Start,
+            Event::Move(1, -2),
+            Event::Rename(String::from("x")),
+            Event::Resize {
+                width: 3,
+                height: 4,
+            },
+        ],
+        meta,
+    };
+
+    let val = magnus::serde::serialize(&ruby, &log).unwrap();
+    rb_assert!(
+        ruby,
+        r#"val == {
+            id: 2**64 - 1,
+            score: 0.5,
+            raw: "\xFF\x00".b,
+            events: [:Start, {Move: [1, -2]}, {Rename: "x"}, {Resize: {width: 3, height: 4}}],
+            meta: {"a" => 1, "b" => nil},
+        }"#,
+        val
+    );
+    rb_assert!(ruby, "val[:raw].encoding == Encoding::BINARY", val);
+
+    let res: Log = magnus::serde::deserialize(&ruby, val).unwrap();
+    assert_eq!(res, log);
+
+    let val: Value = ruby
+        .eval(
+            r#"{
+                "id" => 1,
+                "score" => 1.0,
+                "raw" => "",
+                "events" => ["Start", {"Rename" => "y"}],
+                "meta" => {},
+            }"#,
+        )
+        .unwrap();
+    let res: Log = magnus::serde::deserialize(&ruby, val).unwrap();
+    assert_eq!(res.events, [Event::Start, Event::Rename(String::from("y"))]);
+
+    let val: Value = ruby.eval("{id: 1}").unwrap();
+    let err = magnus::serde::deserialize::<Log>(&ruby, val).unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_type_error()));
+
+    let val: Value = ruby.eval("Object.new").unwrap();
+    assert!(magnus::serde::deserialize::<u8>(&ruby, val).is_err());
+}
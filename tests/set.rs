@@ -0,0 +1,23 @@
+use std::collections::{BTreeSet, HashSet};
+
+use magnus::rb_assert;
+
+#[test]
+fn it_converts_sets() {
+    let ruby = unsafe { magnus::embed::init() };
+    ruby.require("set").unwrap();
+
+    let set: HashSet<i64> = ruby.eval("Set[1, 2, 3]").unwrap();
+    assert_eq!(set, HashSet::from([1, 2, 3]));
+
+    let set: BTreeSet<String> = ruby.eval(r#"Set["b", "a"]"#).unwrap();
+    assert_eq!(set, BTreeSet::from(["a".to_owned(), "b".to_owned()]));
+
+    assert!(ruby.eval::<HashSet<i64>>("[1, 2]").is_err());
+
+    let val = ruby.set_from_iter(BTreeSet::from([1, 2])).unwrap();
+    rb_assert!(ruby, "val == Set[1, 2]", val);
+
+    let val = ruby.set_from_iter(HashSet::from(["a", "b"])).unwrap();
+    rb_assert!(ruby, r#"val == Set["a", "b"]"#, val);
+}
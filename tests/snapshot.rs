@@ -0,0 +1,51 @@
+use magnus::{testing, Value};
+
+#[test]
+fn it_snapshots_nested_objects() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let val: Value = ruby
+        .eval(
+            r#"
+            Point = Struct.new(:x, :y)
+            class Shape
+              def initialize
+                @points = [Point.new(1, 2)]
+                @name = "triangle"
+                @meta = {z: nil, a: 1.5}
+              end
+            end
+            shape = Shape.new
+            shape.instance_variable_set(:@self, shape)
+            shape
+            "#,
+        )
+        .unwrap();
+
+    let expected = r#"#<Shape
+  @meta = {
+    :a => 1.5,
+    :z => nil,
+  },
+  @name = "triangle",
+  @points = [
+    #<struct Point
+      x = 1,
+      y = 2,
+    >,
+  ],
+  @self = #<Shape ...>,
+>"#;
+    let actual = testing::snapshot(val).unwrap();
+    assert_eq!(testing::diff(expected, &actual), None);
+
+    let val: Value = ruby.eval("[Class.new.new, Struct.new(:a).new(1)]").unwrap();
+    let expected = r#"[
+  #<#<Class:anonymous>>,
+  #<struct #<Class:anonymous>
+    a = 1,
+  >,
+]"#;
+    let actual = testing::snapshot(val).unwrap();
+    assert_eq!(testing::diff(expected, &actual), None);
+}
@@ -0,0 +1,22 @@
+#[cfg(unix)]
+#[test]
+fn it_converts_sockets() {
+    use std::{io::Write, os::unix::net::UnixStream};
+
+    use magnus::{rb_assert, TryConvert, Value};
+
+    let ruby = unsafe { magnus::embed::init() };
+
+    let (a, _b) = UnixStream::pair().unwrap();
+    rb_assert!(ruby, "sock.is_a?(UNIXSocket)", sock = a);
+
+    let val: Value = ruby.eval("UNIXSocket.pair").unwrap();
+    let (rb_a, rb_b) = <(Value, Value)>::try_convert(val).unwrap();
+    let mut stream = UnixStream::try_convert(rb_a).unwrap();
+    stream.write_all(b"hello").unwrap();
+    rb_assert!(ruby, r#"sock.read(5) == "hello""#, sock = rb_b);
+
+    let closed: Value = ruby.eval("UNIXSocket.pair.first.tap(&:close)").unwrap();
+    let err = UnixStream::try_convert(closed).unwrap_err();
+    assert!(err.is_kind_of(ruby.exception_io_error()));
+}
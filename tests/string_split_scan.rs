@@ -0,0 +1,32 @@
+use magnus::{r_regexp::Opts, Error, RString};
+
+fn strings<I: Iterator<Item = RString>>(iter: I) -> Vec<String> {
+    iter.map(|s| s.to_string())
+        .collect::<Result<_, Error>>()
+        .unwrap()
+}
+
+#[test]
+fn it_splits_and_scans_like_ruby() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let s = ruby.str_new(" a  b c ");
+    assert_eq!(strings(s.split_pattern(" ", 0).unwrap()), ["a", "b", "c"]);
+    assert_eq!(
+        strings(s.split_pattern(ruby.qnil(), -1).unwrap()),
+        ["a", "b", "c", ""]
+    );
+
+    let re = ruby.reg_new("(,)", Opts::new()).unwrap();
+    let s = ruby.str_new("1,2");
+    assert_eq!(strings(s.split_pattern(re, 0).unwrap()), ["1", ",", "2"]);
+    assert_eq!(strings(s.scan(re).unwrap()), [","]);
+
+    let re = ruby.reg_new("b*", Opts::new()).unwrap();
+    assert_eq!(
+        strings(ruby.str_new("abc").scan(re).unwrap()),
+        ["", "b", "", ""]
+    );
+
+    assert!(ruby.str_new("abc").scan(1).is_err());
+}
@@ -0,0 +1,18 @@
+use magnus::{prelude::*, RStruct, TryConvert};
+
+#[test]
+fn it_converts_structs_to_tuples() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let point = ruby.define_struct(Some("Point"), ("x", "y")).unwrap();
+    let p = point.new_instance((1, 2)).unwrap();
+
+    let (x, y) = <(i64, i64)>::try_convert(p).unwrap();
+    assert_eq!((x, y), (1, 2));
+    assert!(<(i64, i64, i64)>::try_convert(p).is_err());
+
+    let p = RStruct::from_value(p).unwrap();
+    p.aset("y", 5).unwrap();
+    assert_eq!(p.aref::<_, i64>(1).unwrap(), 5);
+    assert_eq!(p.size(), 2);
+}
@@ -0,0 +1,30 @@
+use magnus::{prelude::*, Error, RFile, Value};
+
+#[test]
+fn it_creates_and_cleans_up_tempfiles() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let path = ruby
+        .tempfile_create("magnus", |ruby, file| {
+            let path: String = file.funcall("path", ())?;
+            let other: RFile = ruby.class_file().funcall("open", (&*path,))?;
+
+            file.lock_exclusive()?;
+            assert!(!other.try_lock_shared()?);
+            file.unlock()?;
+            assert!(other.try_lock_shared()?);
+
+            let _: Value = other.funcall("close", ())?;
+            Ok(path)
+        })
+        .unwrap();
+    assert!(!std::path::Path::new(&path).exists());
+
+    let mut tempfile_path = None;
+    let res: Result<(), Error> = ruby.tempfile_create("magnus", |ruby, file| {
+        tempfile_path = Some(file.funcall::<_, _, String>("path", ())?);
+        Err(Error::new(ruby.exception_runtime_error(), "oops"))
+    });
+    assert!(res.unwrap_err().is_kind_of(ruby.exception_runtime_error()));
+    assert!(!std::path::Path::new(&tempfile_path.unwrap()).exists());
+}
@@ -0,0 +1,71 @@
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use magnus::{prelude::*, rb_assert, Time, TryConvert, Value};
+
+#[test]
+fn it_converts_times() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let t = SystemTime::UNIX_EPOCH + Duration::new(1654013280, 123456789);
+    rb_assert!(ruby, "t == Time.at(1654013280, 123456789, :nsec)", t);
+    let val: Value = ruby.eval("Time.at(-1, 5, :nsec)").unwrap();
+    assert_eq!(
+        SystemTime::try_convert(val).unwrap(),
+        SystemTime::UNIX_EPOCH - Duration::from_nanos(999_999_995)
+    );
+
+    rb_assert!(ruby, "d == 1.5", d = Duration::from_millis(1500));
+    let val: Value = ruby.eval("3/2r").unwrap();
+    assert_eq!(
+        Duration::try_convert(val).unwrap(),
+        Duration::from_millis(1500)
+    );
+    let val: Value = ruby.eval("-1").unwrap();
+    assert!(Duration::try_convert(val).is_err());
+
+    let naive: NaiveDateTime = NaiveDate::from_ymd_opt(2022, 5, 31)
+        .unwrap()
+        .and_hms_nano_opt(9, 8, 0, 1)
+        .unwrap();
+    let utc = DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc);
+    rb_assert!(
+        ruby,
+        "t == Time.utc(2022, 5, 31, 9, 8, 0, 1/1000r) && t.utc?",
+        t = utc
+    );
+    rb_assert!(
+        ruby,
+        "t == Time.utc(2022, 5, 31, 9, 8, 0, 1/1000r)",
+        t = naive
+    );
+
+    let fixed = FixedOffset::west_opt(7 * 3600)
+        .unwrap()
+        .from_local_datetime(&naive)
+        .unwrap();
+    let val = ruby
+        .eval::<Time>(r#"Time.new(2022, 5, 31, 9, 8, 1/1_000_000_000r, "-07:00")"#)
+        .unwrap();
+    rb_assert!(ruby, "t == val && t.utc_offset == -25_200", t = fixed, val);
+    assert_eq!(
+        DateTime::<FixedOffset>::try_convert(val.as_value()).unwrap(),
+        fixed
+    );
+    assert_eq!(
+        NaiveDateTime::try_convert(val.as_value()).unwrap(),
+        fixed.naive_utc()
+    );
+    assert_eq!(
+        DateTime::<Utc>::try_convert(val.as_value()).unwrap(),
+        fixed.with_timezone(&Utc)
+    );
+
+    // round trip through a Time with a non-UTC offset
+    let val: Value = ruby
+        .eval(r#"Time.new(2022, 5, 31, 9, 8, 0, "+05:30")"#)
+        .unwrap();
+    let round_trip = NaiveDateTime::try_convert(val).unwrap();
+    assert_eq!(round_trip.to_string(), "2022-05-31 03:38:00");
+    rb_assert!(ruby, "t == val", t = round_trip, val);
+}
@@ -0,0 +1,23 @@
+use magnus::{value::Tracked, RString, Value};
+
+#[test]
+fn it_updates_tracked_values_after_compaction() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let dropped: Vec<Tracked<RString>> = (0..100)
+        .map(|i| Tracked::new(ruby.str_new(&format!("dropped {}", i))))
+        .collect();
+    let kept: Vec<Tracked<RString>> = (0..100)
+        .map(|i| Tracked::new(ruby.str_new(&format!("kept {}", i))))
+        .collect();
+    drop(dropped);
+
+    let _: Value = ruby
+        .eval(r#"1024.times.map {|i| "garbage#{i}"}; GC.compact if GC.respond_to?(:compact)"#)
+        .unwrap();
+    ruby.gc_start();
+
+    for (i, s) in kept.iter().enumerate() {
+        assert_eq!(s.get().to_string().unwrap(), format!("kept {}", i));
+    }
+}
@@ -0,0 +1,24 @@
+use magnus::{function, rb_assert, try_convert::Truthy};
+
+fn flags(a: Truthy, b: Truthy, c: Truthy, d: Truthy) -> (bool, bool, bool, bool) {
+    (a.into(), b.into(), c.into(), d.into())
+}
+
+#[test]
+fn it_converts_by_truthiness() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.define_global_function("flags", function!(flags, 4));
+
+    rb_assert!(
+        ruby,
+        r#"flags(0, "", [], Object.new) == [true, true, true, true]"#
+    );
+    rb_assert!(
+        ruby,
+        "flags(nil, false, true, :a) == [false, false, true, true]"
+    );
+
+    let val: Truthy = ruby.eval("nil").unwrap();
+    assert_eq!(val, Truthy(false));
+}
@@ -0,0 +1,47 @@
+use std::cell::RefCell;
+
+use magnus::{function, method, prelude::*, rb_assert, TypedData};
+
+#[magnus::wrap(class = "Counter", free_immediately, dup)]
+#[derive(Clone)]
+struct Counter(RefCell<Vec<i64>>);
+
+impl Counter {
+    fn new() -> Self {
+        Self(RefCell::new(Vec::new()))
+    }
+
+    fn push(&self, n: i64) {
+        self.0.borrow_mut().push(n)
+    }
+
+    fn values(&self) -> Vec<i64> {
+        self.0.borrow().clone()
+    }
+}
+
+#[test]
+fn it_dups_wrapped_objects() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = ruby.define_class("Counter", ruby.class_object()).unwrap();
+    class
+        .define_singleton_method("new", function!(Counter::new, 0))
+        .unwrap();
+    class
+        .define_method("push", method!(Counter::push, 1))
+        .unwrap();
+    class
+        .define_method("values", method!(Counter::values, 0))
+        .unwrap();
+    Counter::setup(&ruby).unwrap();
+
+    rb_assert!(
+        ruby,
+        "a = Counter.new; a.push(1); b = a.dup; b.push(2); a.values == [1] && b.values == [1, 2]"
+    );
+    rb_assert!(
+        ruby,
+        "a = Counter.new.freeze; a.clone.frozen? && !a.clone(freeze: false).frozen? && !a.dup.frozen?"
+    );
+}
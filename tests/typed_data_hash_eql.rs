@@ -0,0 +1,36 @@
+use magnus::{function, prelude::*, rb_assert, TypedData};
+
+#[magnus::wrap(class = "Point", free_immediately, hash_eql)]
+#[derive(Hash, PartialEq, Eq)]
+struct Point {
+    x: isize,
+    y: isize,
+}
+
+impl Point {
+    fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+}
+
+#[test]
+fn it_defines_hash_and_eql() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = ruby.define_class("Point", ruby.class_object()).unwrap();
+    class
+        .define_singleton_method("new", function!(Point::new, 2))
+        .unwrap();
+    Point::setup(&ruby).unwrap();
+
+    rb_assert!(ruby, "Point.new(1, 2) == Point.new(1, 2)");
+    rb_assert!(ruby, "Point.new(1, 2) != Point.new(2, 1)");
+    rb_assert!(ruby, "Point.new(1, 2).eql?(Point.new(1, 2))");
+    rb_assert!(ruby, "Point.new(1, 2) != Object.new");
+    rb_assert!(ruby, "Point.new(1, 2).hash == Point.new(1, 2).hash");
+    rb_assert!(ruby, "{Point.new(1, 2) => :a}[Point.new(1, 2)] == :a");
+    rb_assert!(
+        ruby,
+        "[Point.new(1, 2), Point.new(1, 2), Point.new(3, 4)].uniq.length == 2"
+    );
+}
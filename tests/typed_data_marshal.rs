@@ -0,0 +1,45 @@
+use magnus::{function, method, prelude::*, rb_assert, TypedData};
+use serde::{Deserialize, Serialize};
+
+#[magnus::wrap(class = "Point", free_immediately, marshal)]
+#[derive(Serialize, Deserialize)]
+struct Point {
+    x: isize,
+    y: isize,
+}
+
+impl Point {
+    fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    fn x(&self) -> isize {
+        self.x
+    }
+
+    fn y(&self) -> isize {
+        self.y
+    }
+}
+
+#[test]
+fn it_marshals_wrapped_objects() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = ruby.define_class("Point", ruby.class_object()).unwrap();
+    class
+        .define_singleton_method("new", function!(Point::new, 2))
+        .unwrap();
+    class.define_method("x", method!(Point::x, 0)).unwrap();
+    class.define_method("y", method!(Point::y, 0)).unwrap();
+    Point::setup(&ruby).unwrap();
+
+    rb_assert!(
+        ruby,
+        "Marshal.load(Marshal.dump(Point.new(1, -2))).then { |p| [p.x, p.y] } == [1, -2]"
+    );
+    rb_assert!(
+        ruby,
+        "Marshal.load(Marshal.dump([Point.new(3, 4)])).first.class == Point"
+    );
+}
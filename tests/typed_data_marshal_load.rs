@@ -0,0 +1,37 @@
+use magnus::{method, prelude::*, rb_assert, TypedData};
+use serde::{Deserialize, Serialize};
+
+#[magnus::wrap(class = "Point", free_immediately, marshal)]
+#[derive(Serialize, Deserialize)]
+struct Point {
+    x: isize,
+    y: isize,
+}
+
+impl Point {
+    fn x(&self) -> isize {
+        self.x
+    }
+
+    fn y(&self) -> isize {
+        self.y
+    }
+}
+
+#[test]
+fn it_loads_before_any_instance_is_created() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = ruby.define_class("Point", ruby.class_object()).unwrap();
+    class.define_method("x", method!(Point::x, 0)).unwrap();
+    class.define_method("y", method!(Point::y, 0)).unwrap();
+    Point::setup(&ruby).unwrap();
+
+    rb_assert!(ruby, "Point.respond_to?(:_load)");
+    // Marshal data for `Point { x: 1, y: -2 }`, as if dumped by another
+    // process.
+    rb_assert!(
+        ruby,
+        r#"Marshal.load("\x04\bu:\nPoint\b\x92\x01\xFE".b).then { |p| [p.x, p.y] } == [1, -2]"#
+    );
+}
@@ -0,0 +1,43 @@
+use magnus::{function, prelude::*, rb_assert, typed_data, IntoValue, Ruby, TypedData, Value};
+
+#[magnus::wrap(class = "Person", free_immediately, pretty_print)]
+struct Person {
+    name: String,
+    tags: Vec<String>,
+}
+
+impl Person {
+    fn new(name: String, tags: Vec<String>) -> Self {
+        Self { name, tags }
+    }
+}
+
+impl typed_data::PrettyPrint for Person {
+    fn pretty_print_fields(&self, ruby: &Ruby) -> Vec<(&'static str, Value)> {
+        vec![
+            ("name", self.name.as_str().into_value_with(ruby)),
+            ("tags", self.tags.clone().into_value_with(ruby)),
+        ]
+    }
+}
+
+#[test]
+fn it_pretty_prints_wrapped_objects() {
+    let ruby = unsafe { magnus::embed::init() };
+    ruby.require("pp").unwrap();
+
+    let class = ruby.define_class("Person", ruby.class_object()).unwrap();
+    class
+        .define_singleton_method("new", function!(Person::new, 2))
+        .unwrap();
+    Person::setup(&ruby).unwrap();
+
+    rb_assert!(
+        ruby,
+        r##"Person.new("Ann", ["a"]).pretty_inspect == "#<Person name=\"Ann\", tags=[\"a\"]>\n""##
+    );
+    rb_assert!(
+        ruby,
+        r#"Person.new("Ann", ["x" * 40, "y" * 40]).pretty_inspect.lines.length > 1"#
+    );
+}
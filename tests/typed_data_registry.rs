@@ -0,0 +1,28 @@
+use magnus::{typed_data, DataTypeFunctions, TypedData};
+
+#[derive(DataTypeFunctions, TypedData)]
+#[magnus(class = "Registered", name = "registered_type", size, free_immediately)]
+struct Registered(u8);
+
+#[test]
+fn it_lists_registered_types() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.define_class("Registered", ruby.class_object())
+        .unwrap();
+    assert!(!typed_data::registered_types()
+        .iter()
+        .any(|t| t.class == "Registered"));
+
+    let obj = ruby.obj_wrap(Registered(1));
+    assert_eq!(obj.0, 1);
+
+    let types = typed_data::registered_types();
+    let registered = types.iter().find(|t| t.class == "Registered").unwrap();
+    assert_eq!(registered.type_name, std::any::type_name::<Registered>());
+    assert_eq!(registered.data_type_name, "registered_type");
+    assert!(registered.size);
+    assert!(registered.free_immediately);
+    assert!(!registered.mark);
+    assert!(!registered.compact);
+}
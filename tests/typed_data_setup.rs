@@ -0,0 +1,47 @@
+use std::fmt;
+
+use magnus::{function, method, prelude::*, rb_assert, TypedData};
+
+#[magnus::wrap(class = "Temperature", free_immediately, inspect, to_s)]
+#[derive(Debug)]
+struct Temperature {
+    celsius: f64,
+}
+
+impl Temperature {
+    fn new(celsius: f64) -> Self {
+        Self { celsius }
+    }
+
+    fn to_s(&self) -> String {
+        format!("{} degrees", self.celsius)
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}°C", self.celsius)
+    }
+}
+
+#[test]
+fn it_does_not_clobber_existing_methods() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = ruby
+        .define_class("Temperature", ruby.class_object())
+        .unwrap();
+    class
+        .define_singleton_method("new", function!(Temperature::new, 1))
+        .unwrap();
+    class
+        .define_method("to_s", method!(Temperature::to_s, 0))
+        .unwrap();
+    Temperature::setup(&ruby).unwrap();
+
+    rb_assert!(ruby, r#"Temperature.new(21.5).to_s == "21.5 degrees""#);
+    rb_assert!(
+        ruby,
+        r##"Temperature.new(21.5).inspect == "#<Temperature Temperature { celsius: 21.5 }>""##
+    );
+}
@@ -0,0 +1,27 @@
+use magnus::{method, prelude::*, rb_assert, RArray};
+
+fn second(rb_self: RArray) -> Result<Option<i64>, magnus::Error> {
+    rb_self.entry(1)
+}
+
+#[test]
+fn it_checks_typed_self() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    ruby.class_object()
+        .define_method("second", method!(second, 0))
+        .unwrap();
+
+    rb_assert!(ruby, "[1, 2, 3].second == 2");
+    rb_assert!(
+        ruby,
+        r#"
+            begin
+              Object.new.second
+              false
+            rescue TypeError => e
+              e.message.include?("Array")
+            end
+        "#
+    );
+}
@@ -0,0 +1,45 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use magnus::{rb_assert, TryConvert, Value};
+use url::Url;
+use uuid::Uuid;
+
+#[test]
+fn it_converts_common_value_types() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let id = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    rb_assert!(ruby, r#"id == "67e55044-10b1-426f-9247-bb680e5fe0c8""#, id);
+    let val: Value = ruby
+        .eval(r#""67E55044-10B1-426F-9247-BB680E5FE0C8""#)
+        .unwrap();
+    assert_eq!(Uuid::try_convert(val).unwrap(), id);
+    let val: Value = ruby.eval(r#""not a uuid""#).unwrap();
+    assert!(Uuid::try_convert(val).is_err());
+
+    let v4 = ruby.ip_addr_new(Ipv4Addr::new(10, 0, 0, 1)).unwrap();
+    rb_assert!(ruby, r#"v4 == IPAddr.new("10.0.0.1") && v4.ipv4?"#, v4);
+    let val: Value = ruby.eval(r#"IPAddr.new("fe80::1")"#).unwrap();
+    assert_eq!(
+        IpAddr::try_convert(val).unwrap(),
+        "fe80::1".parse::<Ipv6Addr>().unwrap()
+    );
+    assert!(Ipv4Addr::try_convert(val).is_err());
+    let val: Value = ruby.eval(r#""127.0.0.1""#).unwrap();
+    assert_eq!(Ipv4Addr::try_convert(val).unwrap(), Ipv4Addr::LOCALHOST);
+    let val: Value = ruby.eval(r#"IPAddr.new("10.0.0.0/8")"#).unwrap();
+    assert!(IpAddr::try_convert(val).is_err());
+
+    let url = Url::parse("https://user@example.com:8443/a/b?c=d#e").unwrap();
+    rb_assert!(
+        ruby,
+        r#"url.is_a?(URI::HTTPS) && url.port == 8443 && url.fragment == "e""#,
+        url = ruby.uri_new(&url).unwrap()
+    );
+    let val: Value = ruby
+        .eval(r#"URI("https://user@example.com:8443/a/b?c=d#e")"#)
+        .unwrap();
+    assert_eq!(Url::try_convert(val).unwrap(), url);
+    let val: Value = ruby.eval("1").unwrap();
+    assert!(Url::try_convert(val).is_err());
+}
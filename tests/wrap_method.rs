@@ -0,0 +1,71 @@
+use magnus::{
+    prelude::*, rb_assert, scan_args::get_kwargs, typed_data::Obj, Error, RHash, Ruby, Value,
+};
+
+#[magnus::wrap(class = "Counter")]
+struct Counter {
+    start: i64,
+}
+
+impl Counter {
+    #[magnus::wrap_function]
+    fn new(start: i64) -> Self {
+        Self { start }
+    }
+
+    #[magnus::wrap_method]
+    fn start(&self) -> i64 {
+        self.start
+    }
+
+    #[magnus::wrap_method]
+    fn add(&self, a: i64, b: i64) -> i64 {
+        self.start + a + b
+    }
+}
+
+#[magnus::wrap_method]
+fn is_object(ruby: &Ruby, rb_self: Obj<Counter>) -> bool {
+    rb_self.is_kind_of(ruby.class_object())
+}
+
+#[magnus::wrap_function]
+fn count(args: &[Value]) -> usize {
+    args.len()
+}
+
+#[magnus::wrap_function]
+fn greet(name: String, #[magnus(kwargs)] kw: RHash) -> Result<String, Error> {
+    let kw = get_kwargs::<_, (), (Option<bool>,), ()>(kw, &[], &["shout"])?;
+    let (shout,) = kw.optional;
+    Ok(if shout.unwrap_or(false) {
+        format!("HELLO {}", name.to_uppercase())
+    } else {
+        format!("hello {}", name)
+    })
+}
+
+#[test]
+fn it_infers_arity() {
+    let ruby = unsafe { magnus::embed::init() };
+
+    let class = ruby.define_class("Counter", ruby.class_object()).unwrap();
+    class
+        .define_singleton_method("new", Counter::new_function())
+        .unwrap();
+    class
+        .define_method("start", Counter::start_method())
+        .unwrap();
+    class.define_method("add", Counter::add_method()).unwrap();
+    class.define_method("object?", is_object_method()).unwrap();
+    ruby.define_global_function("count", count_function());
+    ruby.define_global_function("greet", greet_function());
+
+    rb_assert!(ruby, "Counter.new(1).start == 1");
+    rb_assert!(ruby, "Counter.new(1).add(2, 3) == 6");
+    rb_assert!(ruby, "Counter.new(1).object?");
+    rb_assert!(ruby, "Counter.instance_method(:add).arity == 2");
+    rb_assert!(ruby, "count(1, 2, 3) == 3");
+    rb_assert!(ruby, r#"greet("bob") == "hello bob""#);
+    rb_assert!(ruby, r#"greet("bob", shout: true) == "HELLO BOB""#);
+}